@@ -12,9 +12,7 @@ use std::io::{self, Read};
 use std::path::PathBuf;
 use std::time::Duration;
 
-// Import du module loader
-#[path = "../plugins.rs"]
-mod plugins;
+use devit_cli::plugins;
 
 #[derive(Parser, Debug)]
 #[command(name = "devit-plugin")]
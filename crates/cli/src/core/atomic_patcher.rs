@@ -1,14 +1,48 @@
+use crate::core::config::PatchToolConfig;
 use crate::core::errors::{DevItError, DevItResult};
+use crate::core::merge3;
 use crate::core::patch_parser::{FilePatch, ParsedPatch, PatchHunk, PatchLine};
-use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use crate::core::patch_transaction::PatchTransaction;
+use crate::core::snapshot::Snapshot;
+use crate::merge_assist::FileConflicts;
 use std::path::{Path, PathBuf};
 
 pub struct AtomicPatcher {
     working_dir: PathBuf,
     dry_run: bool,
+    options: PatchOptions,
+    merge_base: Option<Snapshot>,
 }
 
+/// How far a hunk may drift from the position given by its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatchOptions {
+    /// Context lines that may be ignored at each end of a hunk (GNU `--fuzz`)
+    pub fuzz: usize,
+    /// Lines searched on each side of the expected position; `None` searches
+    /// the whole file
+    pub max_offset: Option<usize>,
+    /// Lines differing only in whitespace match (GNU `--ignore-whitespace`)
+    pub ignore_whitespace: bool,
+}
+
+impl Default for PatchOptions {
+    fn default() -> Self {
+        Self::from(&PatchToolConfig::default())
+    }
+}
+
+impl From<&PatchToolConfig> for PatchOptions {
+    fn from(config: &PatchToolConfig) -> Self {
+        Self {
+            fuzz: config.fuzz,
+            max_offset: config.max_offset,
+            ignore_whitespace: config.ignore_whitespace,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct PatchStats {
     pub files_modified: usize,
     pub hunks_applied: usize,
@@ -16,6 +50,79 @@ pub struct PatchStats {
     pub lines_removed: usize,
     pub files_created: usize,
     pub files_deleted: usize,
+    pub files_renamed: usize,
+    pub files_copied: usize,
+    /// Transaction holding the rollback journal (`None` for dry runs)
+    pub transaction_id: Option<String>,
+    /// Where each hunk was applied, in patch order
+    pub hunks: Vec<HunkPlacement>,
+    /// Files whose hunks no longer matched and were merged three-way
+    pub merged_files: Vec<PathBuf>,
+    /// Conflicts left as markers by those merges
+    pub conflicts: Vec<FileConflicts>,
+}
+
+impl PatchStats {
+    /// Hunks applied away from their header line, with fuzz or loose whitespace.
+    pub fn adjusted_hunks(&self) -> impl Iterator<Item = &HunkPlacement> {
+        self.hunks.iter().filter(|hunk| hunk.is_adjusted())
+    }
+}
+
+/// Placement of one hunk, as reported by GNU patch ("Hunk #2 succeeded at
+/// 14 with fuzz 1 (offset 3 lines)").
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkPlacement {
+    pub path: PathBuf,
+    /// 1-based index of the hunk in its file
+    pub hunk: usize,
+    /// Old-file line the hunk header should have named
+    pub line: usize,
+    /// Lines between the header position and the applied one
+    pub offset: isize,
+    /// Context lines ignored at each end of the hunk
+    pub fuzz: usize,
+    /// Some lines only matched ignoring whitespace
+    pub ignored_whitespace: bool,
+}
+
+impl HunkPlacement {
+    pub fn is_adjusted(&self) -> bool {
+        self.offset != 0 || self.fuzz > 0 || self.ignored_whitespace
+    }
+}
+
+impl std::fmt::Display for HunkPlacement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} hunk #{} at line {}",
+            self.path.display(),
+            self.hunk,
+            self.line
+        )?;
+        if self.fuzz > 0 {
+            write!(f, " with fuzz {}", self.fuzz)?;
+        }
+        if self.offset != 0 {
+            write!(f, " (offset {:+} lines)", self.offset)?;
+        }
+        if self.ignored_whitespace {
+            write!(f, " ignoring whitespace")?;
+        }
+        Ok(())
+    }
+}
+
+/// Position found for a hunk by [`AtomicPatcher::locate_hunk`]
+struct HunkMatch {
+    /// First original line matched by the (fuzzed) hunk
+    start: usize,
+    skip_leading: usize,
+    skip_trailing: usize,
+    offset: isize,
+    fuzz: usize,
+    ignored_whitespace: bool,
 }
 
 impl AtomicPatcher {
@@ -23,26 +130,38 @@ impl AtomicPatcher {
         Self {
             working_dir,
             dry_run,
+            options: PatchOptions::default(),
+            merge_base: None,
         }
     }
 
+    pub fn with_options(mut self, options: PatchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Snapshot the patch was written against. A file whose hunks no longer
+    /// match is then patched in its snapshot version and merged three-way
+    /// with its current content, conflicts being left as markers.
+    pub fn with_merge_base(mut self, snapshot: Snapshot) -> Self {
+        self.merge_base = Some(snapshot);
+        self
+    }
+
     pub fn apply_patch(&self, patch_content: &str) -> DevItResult<PatchStats> {
         let parsed = ParsedPatch::from_diff(patch_content)?;
-        let mut stats = PatchStats {
-            files_modified: 0,
-            hunks_applied: 0,
-            lines_added: 0,
-            lines_removed: 0,
-            files_created: 0,
-            files_deleted: 0,
-        };
+        let mut stats = PatchStats::default();
 
         // Security validation
         self.validate_security(&parsed)?;
 
-        // Apply each file patch
+        // Stage every file, then write them all or none
+        let mut transaction = PatchTransaction::new(self.working_dir.clone());
         for file_patch in &parsed.files {
-            self.apply_file_patch(file_patch, &mut stats)?;
+            self.apply_file_patch(file_patch, &mut transaction, &mut stats)?;
+        }
+        if !self.dry_run {
+            stats.transaction_id = transaction.commit()?;
         }
 
         Ok(stats)
@@ -101,18 +220,28 @@ impl AtomicPatcher {
         Ok(())
     }
 
-    fn apply_file_patch(&self, file_patch: &FilePatch, stats: &mut PatchStats) -> DevItResult<()> {
+    fn apply_file_patch(
+        &self,
+        file_patch: &FilePatch,
+        transaction: &mut PatchTransaction,
+        stats: &mut PatchStats,
+    ) -> DevItResult<()> {
         if file_patch.is_deleted_file {
-            self.delete_file(file_patch, stats)?;
+            self.delete_file(file_patch, transaction, stats)?;
         } else if file_patch.is_new_file {
-            self.create_file(file_patch, stats)?;
+            self.create_file(file_patch, transaction, stats)?;
         } else {
-            self.modify_file(file_patch, stats)?;
+            self.modify_file(file_patch, transaction, stats)?;
         }
         Ok(())
     }
 
-    fn delete_file(&self, file_patch: &FilePatch, stats: &mut PatchStats) -> DevItResult<()> {
+    fn delete_file(
+        &self,
+        file_patch: &FilePatch,
+        transaction: &mut PatchTransaction,
+        stats: &mut PatchStats,
+    ) -> DevItResult<()> {
         let path = file_patch
             .old_path
             .as_ref()
@@ -121,20 +250,20 @@ impl AtomicPatcher {
                 line_number: None,
             })?;
 
-        let full_path = self.working_dir.join(path);
-
-        if !self.dry_run {
-            if full_path.exists() {
-                std::fs::remove_file(&full_path)
-                    .map_err(|e| DevItError::io(Some(full_path), "delete file", e))?;
-            }
+        if transaction.exists(path) {
+            transaction.delete(path);
         }
 
         stats.files_deleted += 1;
         Ok(())
     }
 
-    fn create_file(&self, file_patch: &FilePatch, stats: &mut PatchStats) -> DevItResult<()> {
+    fn create_file(
+        &self,
+        file_patch: &FilePatch,
+        transaction: &mut PatchTransaction,
+        stats: &mut PatchStats,
+    ) -> DevItResult<()> {
         let path = file_patch
             .new_path
             .as_ref()
@@ -143,30 +272,23 @@ impl AtomicPatcher {
                 line_number: None,
             })?;
 
-        let full_path = self.working_dir.join(path);
-
-        // Create parent directories if needed
-        if let Some(parent) = full_path.parent() {
-            if !self.dry_run {
-                std::fs::create_dir_all(parent).map_err(|e| {
-                    DevItError::io(Some(parent.to_path_buf()), "create parent directories", e)
-                })?;
-            }
-        }
-
         // Build content from hunks
-        let content = self.build_new_content(&file_patch.hunks, &[])?;
-
-        if !self.dry_run {
-            self.write_file_atomically(&full_path, &content)?;
-        }
+        let content = self.build_content(path, file_patch, &[], stats)?;
+        transaction.write(path, content, None);
 
         stats.files_created += 1;
         self.update_stats_from_hunks(&file_patch.hunks, stats);
         Ok(())
     }
 
-    fn modify_file(&self, file_patch: &FilePatch, stats: &mut PatchStats) -> DevItResult<()> {
+    /// Modifies a file in place, or writes it under its new name for a
+    /// rename (the source is removed) or a copy (the source is kept).
+    fn modify_file(
+        &self,
+        file_patch: &FilePatch,
+        transaction: &mut PatchTransaction,
+        stats: &mut PatchStats,
+    ) -> DevItResult<()> {
         let path = file_patch
             .new_path
             .as_ref()
@@ -175,186 +297,358 @@ impl AtomicPatcher {
                 reason: "Modified file missing path".to_string(),
                 line_number: None,
             })?;
+        let source = file_patch.old_path.as_ref().unwrap_or(path);
+        let relocated = (file_patch.is_rename || file_patch.is_copy) && source != path;
+
+        if relocated {
+            if !transaction.exists(source) {
+                return Err(DevItError::io(
+                    Some(self.working_dir.join(source)),
+                    "read rename/copy source",
+                    std::io::Error::from(std::io::ErrorKind::NotFound),
+                ));
+            }
+            if transaction.exists(path) {
+                return Err(DevItError::VcsConflict {
+                    location: path.display().to_string(),
+                    conflict_type: "target_exists".to_string(),
+                    conflicted_files: vec![path.clone()],
+                    resolution_hint: Some(format!(
+                        "Cannot {} '{}': target already exists",
+                        if file_patch.is_rename {
+                            "rename"
+                        } else {
+                            "copy"
+                        },
+                        source.display()
+                    )),
+                });
+            }
+        }
 
-        let full_path = self.working_dir.join(path);
-
-        // Read existing file
-        let original_lines = if full_path.exists() {
-            self.read_file_lines(&full_path)?
-        } else {
-            Vec::new()
-        };
+        // Read existing file (as left by the previous files of the patch)
+        let original = transaction.read(source)?.unwrap_or_default();
 
         // Apply hunks and build new content
-        let new_content = self.build_new_content(&file_patch.hunks, &original_lines)?;
+        let new_content = match self.build_content(path, file_patch, &original, stats) {
+            Ok(content) => content,
+            Err(err) => self
+                .merge_with_base(source, path, file_patch, &original, stats)
+                .ok_or(err)?,
+        };
 
-        if !self.dry_run {
-            self.write_file_atomically(&full_path, &new_content)?;
+        if relocated {
+            transaction.write(path, new_content, transaction.permissions(source));
+            if file_patch.is_rename {
+                transaction.delete(source);
+                stats.files_renamed += 1;
+            } else {
+                stats.files_copied += 1;
+            }
+        } else {
+            transaction.write(path, new_content, None);
+            stats.files_modified += 1;
         }
-
-        stats.files_modified += 1;
         self.update_stats_from_hunks(&file_patch.hunks, stats);
         Ok(())
     }
 
-    fn read_file_lines(&self, path: &Path) -> DevItResult<Vec<String>> {
-        let file = File::open(path)
-            .map_err(|e| DevItError::io(Some(path.to_path_buf()), "open file for reading", e))?;
+    /// New content of a file: the binary payload applied to the old bytes, or
+    /// the hunks applied to the old text.
+    fn build_content(
+        &self,
+        path: &Path,
+        file_patch: &FilePatch,
+        original: &[u8],
+        stats: &mut PatchStats,
+    ) -> DevItResult<Vec<u8>> {
+        if let Some(binary) = &file_patch.binary {
+            return binary
+                .apply(original)
+                .map_err(|reason| DevItError::InvalidDiff {
+                    reason: format!("{}: {}", path.display(), reason),
+                    line_number: None,
+                });
+        }
+        // Rewriting an existing binary without its content would corrupt it
+        if file_patch.is_binary && !file_patch.is_new_file {
+            return Err(DevItError::InvalidDiff {
+                reason: format!(
+                    "Binary change to '{}' has no content; regenerate the patch with `git diff --binary`",
+                    path.display()
+                ),
+                line_number: None,
+            });
+        }
+        if file_patch.hunks.is_empty() {
+            // Pure rename, copy or mode change
+            return Ok(original.to_vec());
+        }
 
-        let reader = BufReader::new(file);
-        let lines: Result<Vec<String>, _> = reader.lines().collect();
+        let text = std::str::from_utf8(original).map_err(|e| {
+            DevItError::io(
+                Some(path.to_path_buf()),
+                "read file lines",
+                std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+            )
+        })?;
+        let original_lines: Vec<String> = text.lines().map(str::to_string).collect();
+        let mut content =
+            self.build_new_content(path, &file_patch.hunks, &original_lines, stats)?;
+
+        // `\ No newline at end of file` markers decide; otherwise the file
+        // keeps its final newline (new files get one).
+        let trailing_newline = if file_patch.hunks.iter().any(|h| h.new_missing_newline) {
+            false
+        } else if file_patch.hunks.iter().any(|h| h.old_missing_newline) {
+            true
+        } else {
+            text.is_empty() || text.ends_with('\n')
+        };
+        if trailing_newline && !content.is_empty() {
+            content.push('\n');
+        }
+        Ok(content.into_bytes())
+    }
 
-        lines.map_err(|e| DevItError::io(Some(path.to_path_buf()), "read file lines", e))
+    /// Applies the patch to the merge base version of `source` and merges the
+    /// result with `current`; `None` when the file cannot be merged.
+    fn merge_with_base(
+        &self,
+        source: &Path,
+        path: &Path,
+        file_patch: &FilePatch,
+        current: &[u8],
+        stats: &mut PatchStats,
+    ) -> Option<Vec<u8>> {
+        let snapshot = self.merge_base.as_ref()?;
+        if file_patch.is_binary || file_patch.binary.is_some() {
+            return None;
+        }
+        let base = String::from_utf8(snapshot.file_content(source).ok()??).ok()?;
+        let current = std::str::from_utf8(current).ok()?;
+        let patched = self
+            .build_content(
+                path,
+                file_patch,
+                base.as_bytes(),
+                &mut PatchStats::default(),
+            )
+            .ok()?;
+        let patched = String::from_utf8(patched).ok()?;
+
+        let merged = merge3::merge(&base, current, &patched);
+        stats.merged_files.push(path.to_path_buf());
+        if !merged.conflicts.is_empty() {
+            stats.conflicts.push(FileConflicts {
+                path: path.display().to_string(),
+                hunks: merged.conflicts,
+            });
+        }
+        Some(merged.content.into_bytes())
     }
 
     fn build_new_content(
         &self,
+        path: &Path,
         hunks: &[PatchHunk],
         original_lines: &[String],
+        stats: &mut PatchStats,
     ) -> DevItResult<String> {
-        let mut result_lines = original_lines.to_vec();
+        let mut result_lines = Vec::with_capacity(original_lines.len());
+        // Next original line not yet copied; hunks are placed in order and
+        // may not overlap.
+        let mut cursor = 0;
+        // Offset of the previous hunk, where the search for the next starts
+        let mut carried_offset = 0isize;
+
+        for (index, hunk) in hunks.iter().enumerate() {
+            let found = self.locate_hunk(original_lines, hunk, cursor, carried_offset)?;
+            result_lines.extend_from_slice(&original_lines[cursor..found.start]);
+
+            let mut old_idx = found.start;
+            for line in &hunk.lines[found.skip_leading..hunk.lines.len() - found.skip_trailing] {
+                match line {
+                    PatchLine::Context(_) => {
+                        // Keep the file's version of the line (whitespace may differ)
+                        result_lines.push(original_lines[old_idx].clone());
+                        old_idx += 1;
+                    }
+                    PatchLine::Remove(_) => old_idx += 1,
+                    PatchLine::Add(added) => result_lines.push(added.clone()),
+                }
+            }
+            cursor = old_idx;
+            carried_offset = found.offset;
 
-        // Apply hunks in reverse order to maintain line numbers
-        for hunk in hunks.iter().rev() {
-            self.apply_hunk_to_lines(&mut result_lines, hunk)?;
+            stats.hunks.push(HunkPlacement {
+                path: path.to_path_buf(),
+                hunk: index + 1,
+                line: (hunk.old_start as isize + found.offset).max(0) as usize,
+                offset: found.offset,
+                fuzz: found.fuzz,
+                ignored_whitespace: found.ignored_whitespace,
+            });
         }
+        result_lines.extend_from_slice(&original_lines[cursor..]);
 
         Ok(result_lines.join("\n"))
     }
 
-    fn apply_hunk_to_lines(&self, lines: &mut Vec<String>, hunk: &PatchHunk) -> DevItResult<()> {
-        let start_idx = if hunk.old_start > 0 {
-            hunk.old_start - 1
+    /// Finds where `hunk` applies, GNU patch style: at the expected line
+    /// first, then further and further away (alternating after/before), then
+    /// again ignoring up to `fuzz` context lines at each end of the hunk.
+    /// Fuzz never strips every old line: a hunk needs at least one line of
+    /// the file to anchor it.
+    fn locate_hunk(
+        &self,
+        lines: &[String],
+        hunk: &PatchHunk,
+        cursor: usize,
+        carried_offset: isize,
+    ) -> DevItResult<HunkMatch> {
+        // Index of the first old line; `-N,0` inserts after line N.
+        let base = if hunk.old_count == 0 {
+            hunk.old_start
         } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let leading = hunk
+            .lines
+            .iter()
+            .take_while(|line| matches!(line, PatchLine::Context(_)))
+            .count();
+        let trailing = if leading == hunk.lines.len() {
             0
+        } else {
+            hunk.lines
+                .iter()
+                .rev()
+                .take_while(|line| matches!(line, PatchLine::Context(_)))
+                .count()
         };
-        if start_idx > lines.len() {
-            return Err(DevItError::InvalidDiff {
-                reason: format!(
-                    "Patch context starts at line {} but file has only {} lines",
-                    hunk.old_start,
-                    lines.len()
-                ),
-                line_number: Some(hunk.old_start),
-            });
-        }
-        let mut old_idx = start_idx;
-        let mut patch_idx = 0;
-
-        // Validate context lines before applying
-        while patch_idx < hunk.lines.len() {
-            match &hunk.lines[patch_idx] {
-                PatchLine::Context(context_line) => {
-                    if old_idx < lines.len() && &lines[old_idx] != context_line {
-                        return Err(DevItError::VcsConflict {
-                            location: format!("line {}", old_idx + 1),
-                            conflict_type: "context_mismatch".to_string(),
-                            conflicted_files: vec![],
-                            resolution_hint: Some(format!(
-                                "Expected: '{}', Found: '{}'",
-                                context_line,
-                                lines.get(old_idx).unwrap_or(&String::new())
-                            )),
-                        });
-                    }
-                    old_idx += 1;
-                    patch_idx += 1;
+
+        for fuzz in 0..=self.options.fuzz.min(leading.max(trailing)) {
+            let skip_leading = fuzz.min(leading);
+            let skip_trailing = fuzz.min(trailing);
+            let old_lines: Vec<&str> = hunk.lines[skip_leading..hunk.lines.len() - skip_trailing]
+                .iter()
+                .filter_map(|line| match line {
+                    PatchLine::Context(text) | PatchLine::Remove(text) => Some(text.as_str()),
+                    PatchLine::Add(_) => None,
+                })
+                .collect();
+            if fuzz > 0 && old_lines.is_empty() {
+                break;
+            }
+            let Some(last_start) = lines.len().checked_sub(old_lines.len()) else {
+                continue;
+            };
+            if cursor > last_start {
+                continue;
+            }
+
+            let expected = (base + skip_leading) as isize;
+            let guess = (expected + carried_offset).clamp(cursor as isize, last_start as isize);
+            let max_distance = self
+                .options
+                .max_offset
+                .map_or(isize::MAX, |max| max as isize);
+            let mut distance = 0isize;
+            while distance <= max_distance {
+                let after = guess + distance;
+                let before = guess - distance;
+                if after > last_start as isize && before < cursor as isize {
+                    break;
                 }
-                PatchLine::Remove(remove_line) => {
-                    if old_idx < lines.len() && &lines[old_idx] != remove_line {
-                        return Err(DevItError::VcsConflict {
-                            location: format!("line {}", old_idx + 1),
-                            conflict_type: "remove_mismatch".to_string(),
-                            conflicted_files: vec![],
-                            resolution_hint: Some(format!(
-                                "Expected to remove: '{}', Found: '{}'",
-                                remove_line,
-                                lines.get(old_idx).unwrap_or(&String::new())
-                            )),
+                let candidates = std::iter::once(after).chain((distance > 0).then_some(before));
+                for candidate in candidates {
+                    if candidate < cursor as isize || candidate > last_start as isize {
+                        continue;
+                    }
+                    let start = candidate as usize;
+                    if let Some(ignored_whitespace) = self.matches_at(lines, start, &old_lines) {
+                        return Ok(HunkMatch {
+                            start,
+                            skip_leading,
+                            skip_trailing,
+                            offset: candidate - expected,
+                            fuzz,
+                            ignored_whitespace,
                         });
                     }
-                    old_idx += 1;
-                    patch_idx += 1;
-                }
-                PatchLine::Add(_) => {
-                    patch_idx += 1;
                 }
+                distance += 1;
             }
         }
 
-        // Now apply the changes
-        old_idx = start_idx;
-        patch_idx = 0;
-        let mut new_lines = Vec::new();
-
-        // Copy lines before the hunk
-        let prefix_end = start_idx.min(lines.len());
-        new_lines.extend_from_slice(&lines[..prefix_end]);
-
-        // Apply hunk changes
-        while patch_idx < hunk.lines.len() {
-            match &hunk.lines[patch_idx] {
-                PatchLine::Context(line) => {
-                    new_lines.push(line.clone());
-                    old_idx += 1;
-                    patch_idx += 1;
-                }
-                PatchLine::Remove(_) => {
-                    // Skip the removed line
-                    old_idx += 1;
-                    patch_idx += 1;
-                }
-                PatchLine::Add(line) => {
-                    new_lines.push(line.clone());
-                    patch_idx += 1;
-                }
+        Err(self.placement_error(lines, hunk, base))
+    }
+
+    /// `Some(ignored_whitespace)` when `old_lines` match the file at `start`.
+    fn matches_at(&self, lines: &[String], start: usize, old_lines: &[&str]) -> Option<bool> {
+        let mut ignored_whitespace = false;
+        for (line, expected) in lines[start..].iter().zip(old_lines) {
+            if line == expected {
+                continue;
+            }
+            if self.options.ignore_whitespace
+                && line.split_whitespace().eq(expected.split_whitespace())
+            {
+                ignored_whitespace = true;
+                continue;
             }
+            return None;
         }
+        Some(ignored_whitespace)
+    }
 
-        // Copy remaining lines
-        if old_idx > lines.len() {
-            return Err(DevItError::InvalidDiff {
+    /// Describes the first difference at the position given by the hunk header.
+    fn placement_error(&self, lines: &[String], hunk: &PatchHunk, base: usize) -> DevItError {
+        if base > lines.len() {
+            return DevItError::InvalidDiff {
                 reason: format!(
-                    "Patch consumes more lines than available (line {} in file with {} lines)",
-                    old_idx + 1,
+                    "Patch context starts at line {} but file has only {} lines",
+                    hunk.old_start,
                     lines.len()
                 ),
-                line_number: Some(old_idx + 1),
-            });
+                line_number: Some(hunk.old_start),
+            };
         }
-        new_lines.extend_from_slice(&lines[old_idx..]);
-
-        *lines = new_lines;
-        Ok(())
-    }
-
-    fn write_file_atomically(&self, path: &Path, content: &str) -> DevItResult<()> {
-        let temp_path = path.with_extension("devit.tmp");
-
-        // Write to temp file
-        let mut temp_file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&temp_path)
-            .map_err(|e| DevItError::io(Some(temp_path.clone()), "create temp file", e))?;
-
-        temp_file
-            .write_all(content.as_bytes())
-            .map_err(|e| DevItError::io(Some(temp_path.clone()), "write to temp file", e))?;
 
-        // Sync to disk
-        temp_file
-            .sync_all()
-            .map_err(|e| DevItError::io(Some(temp_path.clone()), "sync temp file", e))?;
-
-        drop(temp_file);
-
-        // Atomic rename
-        std::fs::rename(&temp_path, path)
-            .map_err(|e| DevItError::io(Some(path.to_path_buf()), "atomic rename", e))?;
+        let mut old_idx = base;
+        for line in &hunk.lines {
+            let (expected, conflict_type, label) = match line {
+                PatchLine::Context(text) => (text, "context_mismatch", "Expected"),
+                PatchLine::Remove(text) => (text, "remove_mismatch", "Expected to remove"),
+                PatchLine::Add(_) => continue,
+            };
+            match lines.get(old_idx) {
+                Some(found) if found == expected => {}
+                Some(found) => {
+                    return DevItError::VcsConflict {
+                        location: format!("line {}", old_idx + 1),
+                        conflict_type: conflict_type.to_string(),
+                        conflicted_files: vec![],
+                        resolution_hint: Some(format!(
+                            "{}: '{}', Found: '{}' (no match within offset/fuzz limits)",
+                            label, expected, found
+                        )),
+                    };
+                }
+                None => break,
+            }
+            old_idx += 1;
+        }
 
-        Ok(())
+        DevItError::InvalidDiff {
+            reason: format!(
+                "Patch consumes more lines than available (line {} in file with {} lines)",
+                old_idx + 1,
+                lines.len()
+            ),
+            line_number: Some(old_idx + 1),
+        }
     }
 
     fn update_stats_from_hunks(&self, hunks: &[PatchHunk], stats: &mut PatchStats) {
@@ -371,3 +665,294 @@ impl AtomicPatcher {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn numbered(count: usize) -> String {
+        (1..=count).map(|n| format!("line {n}\n")).collect()
+    }
+
+    fn apply(
+        content: &str,
+        diff: &str,
+        options: PatchOptions,
+    ) -> DevItResult<(String, PatchStats)> {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("file.txt"), content).unwrap();
+        let stats = AtomicPatcher::new(dir.path().to_path_buf(), false)
+            .with_options(options)
+            .apply_patch(diff)?;
+        let result = std::fs::read_to_string(dir.path().join("file.txt")).unwrap();
+        Ok((result, stats))
+    }
+
+    const HEADER: &str = "diff --git a/file.txt b/file.txt\n--- a/file.txt\n+++ b/file.txt\n";
+
+    #[test]
+    fn hunks_are_found_at_an_offset() {
+        // Both headers are 3 lines too early; the second hunk starts its
+        // search from the offset of the first.
+        let diff = format!(
+            "{HEADER}@@ -2,3 +2,3 @@\n line 5\n-line 6\n+six\n line 7\n@@ -12,3 +12,3 @@\n line 15\n-line 16\n+sixteen\n line 17\n"
+        );
+        let (result, stats) = apply(&numbered(20), &diff, PatchOptions::default()).unwrap();
+
+        assert!(result.contains("line 5\nsix\nline 7"));
+        assert!(result.contains("line 15\nsixteen\nline 17"));
+        assert_eq!(stats.hunks.len(), 2);
+        assert_eq!((stats.hunks[0].line, stats.hunks[0].offset), (5, 3));
+        assert_eq!((stats.hunks[1].hunk, stats.hunks[1].offset), (2, 3));
+        assert_eq!(stats.hunks[0].fuzz, 0);
+        assert_eq!(stats.adjusted_hunks().count(), 2);
+    }
+
+    #[test]
+    fn max_offset_bounds_the_search() {
+        let diff = format!("{HEADER}@@ -1,3 +1,3 @@\n line 10\n-line 11\n+eleven\n line 12\n");
+        let options = PatchOptions {
+            max_offset: Some(5),
+            ..PatchOptions::default()
+        };
+        let err = apply(&numbered(20), &diff, options).err().unwrap();
+        assert!(matches!(
+            err,
+            DevItError::VcsConflict { ref conflict_type, .. } if conflict_type == "context_mismatch"
+        ));
+
+        let (result, _) = apply(&numbered(20), &diff, PatchOptions::default()).unwrap();
+        assert!(result.contains("line 10\neleven\nline 12"));
+    }
+
+    #[test]
+    fn fuzz_ignores_outer_context() {
+        // The outer context lines were edited since the diff was produced.
+        let diff =
+            format!("{HEADER}@@ -4,5 +4,5 @@\n line 4\n line 5\n-line 6\n+six\n line 7\n line 8\n");
+        let content = numbered(10).replace("line 4\n", "line four\n");
+
+        let strict = PatchOptions {
+            fuzz: 0,
+            ..PatchOptions::default()
+        };
+        assert!(apply(&content, &diff, strict).is_err());
+
+        let (result, stats) = apply(&content, &diff, PatchOptions::default()).unwrap();
+        assert!(result.contains("line four\nline 5\nsix\nline 7"));
+        assert_eq!(stats.hunks[0].fuzz, 1);
+        assert_eq!(stats.hunks[0].offset, 0);
+        assert!(stats.hunks[0].is_adjusted());
+    }
+
+    #[test]
+    fn fuzz_keeps_at_least_one_old_line() {
+        // Fuzz 2 would strip both context lines and place the hunk blindly.
+        let diff = format!("{HEADER}@@ -5,2 +5,3 @@\n nothing like this\n nor this\n+INSERTED\n");
+        let err = apply(&numbered(10), &diff, PatchOptions::default())
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            DevItError::VcsConflict { ref conflict_type, .. } if conflict_type == "context_mismatch"
+        ));
+    }
+
+    #[test]
+    fn whitespace_insensitive_matching_is_opt_in() {
+        let diff =
+            format!("{HEADER}@@ -1,3 +1,3 @@\n fn main() {{\n-    old();\n+    new();\n }}\n");
+        let content = "fn main()  {\n\told();\n}\n";
+
+        let err = apply(content, &diff, PatchOptions::default())
+            .err()
+            .unwrap();
+        assert!(matches!(err, DevItError::VcsConflict { .. }));
+
+        let options = PatchOptions {
+            ignore_whitespace: true,
+            ..PatchOptions::default()
+        };
+        let (result, stats) = apply(content, &diff, options).unwrap();
+        // Context keeps the file's spelling, added lines come from the patch.
+        assert_eq!(result, "fn main()  {\n    new();\n}\n");
+        assert!(stats.hunks[0].ignored_whitespace);
+    }
+
+    #[test]
+    fn renames_copies_and_binary_patches_are_applied() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("blob.bin"), b"a\0b\x01c").unwrap();
+        std::fs::write(root.join("my notes.txt"), "one\ntwo\nthree\n").unwrap();
+        std::fs::write(root.join("old.rs"), "x\ny\n").unwrap();
+
+        let diff = concat!(
+            "diff --git a/blob.bin b/blob.bin\n",
+            "index 7cee742..7dbd418 100644\n",
+            "GIT binary patch\n",
+            "literal 6\n",
+            "NcmYdfaAHhm0ssUb0SW*B\n",
+            "\n",
+            "literal 5\n",
+            "McmYdfNMcL|00VUaC;$Ke\n",
+            "\n",
+            "diff --git a/logo.png b/logo.png\n",
+            "new file mode 100644\n",
+            "index 0000000..f584f40\n",
+            "GIT binary patch\n",
+            "literal 6\n",
+            "NcmeAS@N;Ki1ONuw0dN2S\n",
+            "\n",
+            "literal 0\n",
+            "HcmV?d00001\n",
+            "\n",
+            "diff --git a/my notes.txt b/my renamed notes.txt\n",
+            "similarity index 57%\n",
+            "rename from my notes.txt\n",
+            "rename to my renamed notes.txt\n",
+            "index 4cb29ea..2090089 100644\n",
+            "--- a/my notes.txt\t\n",
+            "+++ b/my renamed notes.txt\t\n",
+            "@@ -1,3 +1,3 @@\n",
+            " one\n",
+            " two\n",
+            "-three\n",
+            "+THREE\n",
+            "\\ No newline at end of file\n",
+            "diff --git a/old.rs b/new.rs\n",
+            "similarity index 100%\n",
+            "rename from old.rs\n",
+            "rename to new.rs\n",
+            "diff --git a/new.rs b/copy.rs\n",
+            "similarity index 100%\n",
+            "copy from new.rs\n",
+            "copy to copy.rs\n",
+        );
+        let stats = AtomicPatcher::new(root.to_path_buf(), false)
+            .apply_patch(diff)
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read(root.join("blob.bin")).unwrap(),
+            b"a\0B\x01c\x02"
+        );
+        assert_eq!(
+            std::fs::read(root.join("logo.png")).unwrap(),
+            b"\x89PNG\0\x01"
+        );
+        assert!(!root.join("my notes.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(root.join("my renamed notes.txt")).unwrap(),
+            "one\ntwo\nTHREE"
+        );
+        assert!(!root.join("old.rs").exists());
+        assert_eq!(
+            std::fs::read_to_string(root.join("new.rs")).unwrap(),
+            "x\ny\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("copy.rs")).unwrap(),
+            "x\ny\n"
+        );
+        assert_eq!(
+            (stats.files_renamed, stats.files_copied, stats.files_created),
+            (2, 1, 1)
+        );
+
+        // A rename never overwrites an existing file.
+        let diff = "diff --git a/new.rs b/copy.rs\nrename from new.rs\nrename to copy.rs\n";
+        let err = AtomicPatcher::new(root.to_path_buf(), false)
+            .apply_patch(diff)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            DevItError::VcsConflict { ref conflict_type, .. } if conflict_type == "target_exists"
+        ));
+    }
+
+    #[test]
+    fn failing_file_leaves_the_tree_untouched() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.txt"), "a\n").unwrap();
+        std::fs::write(root.join("b.txt"), "b\n").unwrap();
+        let diff = concat!(
+            "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+A\n",
+            "diff --git a/b.txt b/b.txt\n--- a/b.txt\n+++ b/b.txt\n@@ -1 +1 @@\n-x\n+X\n",
+        );
+
+        let patcher = AtomicPatcher::new(root.to_path_buf(), false);
+        assert!(patcher.apply_patch(diff).is_err());
+        assert_eq!(std::fs::read_to_string(root.join("a.txt")).unwrap(), "a\n");
+
+        let diff = diff.replace("-x\n+X", "-b\n+B");
+        let stats = patcher.apply_patch(&diff).unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("a.txt")).unwrap(), "A\n");
+        assert_eq!(std::fs::read_to_string(root.join("b.txt")).unwrap(), "B\n");
+
+        let id = stats.transaction_id.unwrap();
+        crate::core::patch_transaction::rollback(root, &id).unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("a.txt")).unwrap(), "a\n");
+        assert_eq!(std::fs::read_to_string(root.join("b.txt")).unwrap(), "b\n");
+    }
+
+    #[test]
+    fn binary_changes_need_a_payload() {
+        let diff = "diff --git a/a.bin b/a.bin\nindex 1..2 100644\nBinary files a/a.bin and b/a.bin differ\n";
+        let err = apply("", diff, PatchOptions::default()).err().unwrap();
+        assert!(
+            matches!(err, DevItError::InvalidDiff { ref reason, .. } if reason.contains("--binary"))
+        );
+    }
+
+    #[test]
+    fn stale_hunks_are_merged_with_the_snapshot_base() {
+        use crate::core::snapshot::SnapshotOptions;
+
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, numbered(10)).unwrap();
+        let snapshot = Snapshot::create(
+            dir.path().to_path_buf(),
+            "base".to_string(),
+            &SnapshotOptions::default(),
+        )
+        .unwrap();
+        let patcher = || {
+            AtomicPatcher::new(dir.path().to_path_buf(), false)
+                .with_options(PatchOptions {
+                    fuzz: 0,
+                    max_offset: Some(0),
+                    ignore_whitespace: false,
+                })
+                .with_merge_base(snapshot.clone())
+        };
+
+        // Another writer merged lines 3 and 4 since the snapshot
+        let current = numbered(10).replace("line 3\nline 4\n", "three\n");
+        std::fs::write(&file, &current).unwrap();
+        let diff = format!("{HEADER}@@ -5,3 +5,3 @@\n line 5\n-line 6\n+six\n line 7\n");
+        let stats = patcher().apply_patch(&diff).unwrap();
+        assert_eq!(stats.merged_files, vec![PathBuf::from("file.txt")]);
+        assert!(stats.conflicts.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            current.replace("line 6\n", "six\n")
+        );
+
+        // Both sides changed line 4: the conflict is written and reported
+        let diff = format!("{HEADER}@@ -3,3 +3,3 @@\n line 3\n-line 4\n+FOUR\n line 5\n");
+        let stats = patcher().apply_patch(&diff).unwrap();
+        let [conflicts] = stats.conflicts.as_slice() else {
+            panic!("expected one conflicted file");
+        };
+        assert_eq!(conflicts.path, "file.txt");
+        assert_eq!(conflicts.hunks[0].ours, "three");
+        assert_eq!(conflicts.hunks[0].theirs, "line 3\nFOUR");
+        let merged = std::fs::read_to_string(&file).unwrap();
+        assert!(merged.contains("<<<<<<< current\nthree\n=======\nline 3\nFOUR\n>>>>>>> patch\n"));
+    }
+}
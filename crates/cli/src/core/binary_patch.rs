@@ -0,0 +1,338 @@
+//! `GIT binary patch` blocks, as produced by `git diff --binary`.
+//!
+//! A block holds a forward and a reverse payload, each either the full new
+//! content (`literal`) or a git delta against the old content (`delta`),
+//! zlib-deflated and base85-encoded one line at a time. Only the forward
+//! payload is kept.
+
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use crate::core::errors::{DevItError, DevItResult};
+
+const BASE85: &[u8; 85] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryPatch {
+    /// Full content of the new file
+    Literal(Vec<u8>),
+    /// Git delta to apply to the old content
+    Delta(Vec<u8>),
+}
+
+impl BinaryPatch {
+    /// Parses the block following a `GIT binary patch` line at `start`;
+    /// returns the patch and the index of the first line after the block.
+    pub fn parse(lines: &[&str], start: usize) -> DevItResult<(Self, usize)> {
+        let (patch, mut i) = Self::parse_payload(lines, start)?;
+        // Reverse payload, only needed to revert the patch
+        if i < lines.len() && (lines[i].starts_with("literal ") || lines[i].starts_with("delta ")) {
+            i = Self::parse_payload(lines, i)?.1;
+        }
+        Ok((patch, i))
+    }
+
+    fn parse_payload(lines: &[&str], start: usize) -> DevItResult<(Self, usize)> {
+        let header = lines.get(start).copied().unwrap_or_default();
+        let (kind, size) = header
+            .split_once(' ')
+            .ok_or_else(|| DevItError::InvalidDiff {
+                reason: format!("Invalid binary patch header: '{}'", header),
+                line_number: Some(start + 1),
+            })?;
+        let size: usize = size.trim().parse().map_err(|_| DevItError::InvalidDiff {
+            reason: format!("Invalid binary patch size: '{}'", header),
+            line_number: Some(start + 1),
+        })?;
+
+        let mut deflated = Vec::new();
+        let mut i = start + 1;
+        while i < lines.len() && !lines[i].is_empty() {
+            decode_base85_line(lines[i], &mut deflated).map_err(|reason| {
+                DevItError::InvalidDiff {
+                    reason,
+                    line_number: Some(i + 1),
+                }
+            })?;
+            i += 1;
+        }
+        // Blank line closing the payload
+        if i < lines.len() {
+            i += 1;
+        }
+
+        // The announced size is untrusted: no preallocation, and inflating
+        // stops one byte past it.
+        let mut data = Vec::new();
+        ZlibDecoder::new(deflated.as_slice())
+            .take((size as u64).saturating_add(1))
+            .read_to_end(&mut data)
+            .map_err(|err| DevItError::InvalidDiff {
+                reason: format!("Corrupt binary patch data: {}", err),
+                line_number: Some(start + 1),
+            })?;
+        if data.len() != size {
+            let inflated = if data.len() > size {
+                "more than".to_string()
+            } else {
+                data.len().to_string()
+            };
+            return Err(DevItError::InvalidDiff {
+                reason: format!(
+                    "Binary patch inflates to {} bytes, header announces {}",
+                    inflated, size
+                ),
+                line_number: Some(start + 1),
+            });
+        }
+
+        let patch = match kind {
+            "literal" => BinaryPatch::Literal(data),
+            "delta" => BinaryPatch::Delta(data),
+            _ => {
+                return Err(DevItError::InvalidDiff {
+                    reason: format!("Unknown binary patch kind '{}'", kind),
+                    line_number: Some(start + 1),
+                })
+            }
+        };
+        Ok((patch, i))
+    }
+
+    /// Size of the resulting file, when known without the old content.
+    pub fn new_size(&self) -> Option<u64> {
+        match self {
+            BinaryPatch::Literal(data) => Some(data.len() as u64),
+            BinaryPatch::Delta(delta) => {
+                let mut pos = 0;
+                read_varint(delta, &mut pos)?;
+                read_varint(delta, &mut pos).map(|size| size as u64)
+            }
+        }
+    }
+
+    /// Content of the new file given the old one.
+    pub fn apply(&self, old: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            BinaryPatch::Literal(data) => Ok(data.clone()),
+            BinaryPatch::Delta(delta) => apply_delta(old, delta),
+        }
+    }
+}
+
+/// Decodes one payload line: a length character (`A`-`Z` = 1-26 bytes,
+/// `a`-`z` = 27-52) followed by groups of 5 base85 digits.
+fn decode_base85_line(line: &str, out: &mut Vec<u8>) -> Result<(), String> {
+    let bytes = line.as_bytes();
+    let len = match bytes.first() {
+        Some(c @ b'A'..=b'Z') => (c - b'A' + 1) as usize,
+        Some(c @ b'a'..=b'z') => (c - b'a' + 27) as usize,
+        _ => return Err(format!("Invalid binary patch line length in '{}'", line)),
+    };
+    let digits = &bytes[1..];
+    if !digits.len().is_multiple_of(5) || digits.len() / 5 * 4 < len {
+        return Err(format!("Truncated binary patch line '{}'", line));
+    }
+
+    let mut decoded = Vec::with_capacity(digits.len() / 5 * 4);
+    for group in digits.chunks(5) {
+        let mut value: u64 = 0;
+        for &digit in group {
+            let index = BASE85
+                .iter()
+                .position(|&c| c == digit)
+                .ok_or_else(|| format!("Invalid base85 character '{}'", digit as char))?;
+            value = value * 85 + index as u64;
+        }
+        let value = u32::try_from(value).map_err(|_| "Base85 group overflows".to_string())?;
+        decoded.extend_from_slice(&value.to_be_bytes());
+    }
+    out.extend_from_slice(&decoded[..len]);
+    Ok(())
+}
+
+/// Little-endian base-128 size; `None` when truncated or wider than `usize`.
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<usize> {
+    let mut value = 0usize;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        let bits = (byte & 0x7f) as usize;
+        if shift >= usize::BITS || bits > usize::MAX >> shift {
+            return None;
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+    }
+}
+
+/// Applies a git delta: sizes of the source and result, then copy
+/// (`1xxxxxxx`, offset and size bytes selected by the low bits) and insert
+/// (`0nnnnnnn`, n literal bytes) instructions.
+fn apply_delta(source: &[u8], delta: &[u8]) -> Result<Vec<u8>, String> {
+    let corrupt = || "Corrupt binary delta".to_string();
+    let mut pos = 0;
+    let source_size = read_varint(delta, &mut pos).ok_or_else(corrupt)?;
+    let result_size = read_varint(delta, &mut pos).ok_or_else(corrupt)?;
+    if source_size != source.len() {
+        return Err(format!(
+            "Binary delta expects a {} byte file, found {} bytes",
+            source_size,
+            source.len()
+        ));
+    }
+
+    // Grown as instructions are applied, never past the announced size
+    let mut result = Vec::new();
+    while pos < delta.len() {
+        let cmd = delta[pos];
+        pos += 1;
+        if cmd & 0x80 != 0 {
+            let mut offset = 0usize;
+            let mut size = 0usize;
+            for bit in 0..4 {
+                if cmd & (1 << bit) != 0 {
+                    offset |= (*delta.get(pos).ok_or_else(corrupt)? as usize) << (8 * bit);
+                    pos += 1;
+                }
+            }
+            for bit in 0..3 {
+                if cmd & (0x10 << bit) != 0 {
+                    size |= (*delta.get(pos).ok_or_else(corrupt)? as usize) << (8 * bit);
+                    pos += 1;
+                }
+            }
+            if size == 0 {
+                size = 0x10000;
+            }
+            let chunk = offset
+                .checked_add(size)
+                .and_then(|end| source.get(offset..end))
+                .ok_or_else(corrupt)?;
+            if result.len() + chunk.len() > result_size {
+                return Err(corrupt());
+            }
+            result.extend_from_slice(chunk);
+        } else if cmd != 0 {
+            let chunk = delta.get(pos..pos + cmd as usize).ok_or_else(corrupt)?;
+            if result.len() + chunk.len() > result_size {
+                return Err(corrupt());
+            }
+            result.extend_from_slice(chunk);
+            pos += cmd as usize;
+        } else {
+            return Err(corrupt());
+        }
+    }
+
+    if result.len() != result_size {
+        return Err(corrupt());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::Write;
+
+    fn encode_base85_line(chunk: &[u8]) -> String {
+        let mut line = String::new();
+        line.push(if chunk.len() <= 26 {
+            (b'A' + chunk.len() as u8 - 1) as char
+        } else {
+            (b'a' + chunk.len() as u8 - 27) as char
+        });
+        for group in chunk.chunks(4) {
+            let mut bytes = [0u8; 4];
+            bytes[..group.len()].copy_from_slice(group);
+            let mut value = u32::from_be_bytes(bytes) as u64;
+            let mut digits = [0u8; 5];
+            for digit in digits.iter_mut().rev() {
+                *digit = BASE85[(value % 85) as usize];
+                value /= 85;
+            }
+            line.push_str(std::str::from_utf8(&digits).unwrap());
+        }
+        line
+    }
+
+    /// Encodes `data` the way `git diff --binary` does.
+    fn encode_payload(kind: &str, data: &[u8]) -> String {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        let deflated = encoder.finish().unwrap();
+        let mut block = format!("{} {}\n", kind, data.len());
+        for chunk in deflated.chunks(52) {
+            block.push_str(&encode_base85_line(chunk));
+            block.push('\n');
+        }
+        block.push('\n');
+        block
+    }
+
+    #[test]
+    fn literal_round_trips_through_base85_and_zlib() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(300).collect();
+        let block = format!(
+            "{}{}",
+            encode_payload("literal", &data),
+            encode_payload("literal", b"")
+        );
+        let lines: Vec<&str> = block.lines().collect();
+
+        let (patch, next) = BinaryPatch::parse(&lines, 0).unwrap();
+        assert_eq!(next, lines.len());
+        assert_eq!(patch.new_size(), Some(300));
+        assert_eq!(patch.apply(b"ignored").unwrap(), data);
+    }
+
+    #[test]
+    fn delta_copies_and_inserts() {
+        let source = b"hello binary world";
+        // source 18, result 17: copy "hello " (offset 0, size 6), insert
+        // "brave", copy " world" (offset 12, size 6)
+        let mut delta = vec![18, 17, 0x90, 6, 5];
+        delta.extend_from_slice(b"brave");
+        delta.extend_from_slice(&[0x91, 12, 6]);
+        let block = encode_payload("delta", &delta);
+        let lines: Vec<&str> = block.lines().collect();
+
+        let (patch, _) = BinaryPatch::parse(&lines, 0).unwrap();
+        assert_eq!(patch.new_size(), Some(17));
+        assert_eq!(patch.apply(source).unwrap(), b"hello brave world");
+        assert!(patch.apply(b"other content").is_err());
+    }
+
+    #[test]
+    fn untrusted_sizes_are_bounded() {
+        // Inflates past the announced size
+        let block = encode_payload("literal", &[0u8; 4096]).replacen("4096", "16", 1);
+        let lines: Vec<&str> = block.lines().collect();
+        let err = BinaryPatch::parse(&lines, 0).unwrap_err();
+        assert!(err.to_string().contains("more than"), "{}", err);
+
+        // Largest announced size must not overflow the inflate limit
+        let block = encode_payload("literal", b"abc").replacen("3", &u64::MAX.to_string(), 1);
+        let lines: Vec<&str> = block.lines().collect();
+        let err = BinaryPatch::parse(&lines, 0).unwrap_err();
+        assert!(err.to_string().contains("inflates to 3 bytes"), "{}", err);
+
+        // Varint wider than 64 bits
+        let mut pos = 0;
+        assert_eq!(read_varint(&[0xff; 10], &mut pos), None);
+        let mut pos = 0;
+        assert_eq!(read_varint(&[0x80, 0x01], &mut pos), Some(128));
+
+        // Copies past the announced result size
+        let delta = [4, 2, 0x90, 4];
+        assert!(apply_delta(b"abcd", &delta).is_err());
+    }
+}
@@ -36,7 +36,10 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 use toml::Value;
 
-use devit_common::{ApprovalLevel, SandboxProfile};
+use devit_common::policy::deserialize_path_overrides;
+use devit_common::{ApprovalLevel, IsolationMode, SandboxProfile, SeccompProfileSpec};
+
+pub use devit_common::policy::{PatternType, PolicyRuleConfig};
 
 const DEFAULT_PROTECTED_PATHS: &[&str] = &[".git", "Cargo.toml", "package.json", ".env"];
 
@@ -248,6 +251,7 @@ impl<'de> Deserialize<'de> for OrchestrationSettings {
 pub struct ToolsConfig {
     pub screenshot: ScreenshotToolConfig,
     pub exec: ExecToolConfig,
+    pub patch: PatchToolConfig,
 }
 
 impl Default for ToolsConfig {
@@ -255,6 +259,7 @@ impl Default for ToolsConfig {
         Self {
             screenshot: ScreenshotToolConfig::default(),
             exec: ExecToolConfig::default(),
+            patch: PatchToolConfig::default(),
         }
     }
 }
@@ -321,8 +326,23 @@ pub struct ExecToolConfig {
     pub rlimit_cpu_secs: u64,
     /// Max lifetime per process (seconds)
     pub max_lifetime_secs: u64,
+    /// cgroup v2 CPU bandwidth per process, in percent of one core
+    pub cpu_limit_percent: Option<u32>,
+    /// cgroup v2 memory limit per process (megabytes, swap disabled)
+    pub memory_limit_mb: Option<u64>,
+    /// cgroup v2 limit on processes + threads per spawned command
+    pub pids_max: Option<u64>,
     /// Allowlist of binary paths
     pub binary_allowlist: Vec<String>,
+    /// Namespace isolation for spawned processes (off | auto | required)
+    pub isolation: IsolationMode,
+    /// Sandbox profile used to build the isolation plan
+    pub sandbox_profile: SandboxProfile,
+    /// Seccomp profile installed in spawned processes (built-in `strict` /
+    /// `permissive`, or a name from `[sandbox.seccomp_profiles]`). Opt-in:
+    /// installing a filter sets `no_new_privs`, which disables setuid
+    /// binaries such as `sudo` or `ping`.
+    pub seccomp_profile: Option<String>,
 }
 
 impl Default for ExecToolConfig {
@@ -333,7 +353,35 @@ impl Default for ExecToolConfig {
             rlimit_as_gb: 2,         // 2GB
             rlimit_cpu_secs: 300,    // 5 minutes
             max_lifetime_secs: 3600, // 1 hour
+            cpu_limit_percent: None,
+            memory_limit_mb: None,
+            pids_max: None,
             binary_allowlist: vec!["/usr/bin/*".to_string(), "/bin/*".to_string()],
+            isolation: IsolationMode::Off,
+            sandbox_profile: SandboxProfile::Strict,
+            seccomp_profile: None,
+        }
+    }
+}
+
+/// Hunk placement tolerance of `devit_patch_apply`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatchToolConfig {
+    /// Context lines that may be ignored at each end of a hunk
+    pub fuzz: usize,
+    /// Lines searched on each side of the header position (unbounded if unset)
+    pub max_offset: Option<usize>,
+    /// Match lines that differ only in whitespace
+    pub ignore_whitespace: bool,
+}
+
+impl Default for PatchToolConfig {
+    fn default() -> Self {
+        Self {
+            fuzz: 2,
+            max_offset: None,
+            ignore_whitespace: false,
         }
     }
 }
@@ -506,6 +554,14 @@ pub struct PolicyConfig {
 
     /// Whether to automatically revert patches if post-tests fail
     pub auto_revert_on_test_fail: bool,
+
+    /// Declarative rules (`[[policy.rules]]`), evaluated by descending priority
+    #[serde(default)]
+    pub rules: Vec<PolicyRuleConfig>,
+
+    /// Approval level caps by path prefix (`[policy.path_overrides]`)
+    #[serde(default, deserialize_with = "deserialize_path_overrides")]
+    pub path_overrides: HashMap<PathBuf, ApprovalLevel>,
 }
 
 impl Default for PolicyConfig {
@@ -553,6 +609,8 @@ impl PolicyConfig {
                 .collect(),
             sandbox_profile_default: SandboxProfile::Strict,
             auto_revert_on_test_fail: true, // Enable by default for safety
+            rules: Vec::new(),
+            path_overrides: HashMap::new(),
         }
     }
 
@@ -571,7 +629,12 @@ impl PolicyConfig {
 
         let mut config = Self::builtin_defaults();
         if let Some(policy_table) = root.get("policy").and_then(Value::as_table) {
-            config.apply_policy_table(policy_table);
+            config
+                .apply_policy_table(policy_table)
+                .map_err(|source| PolicyConfigError::Parse {
+                    path: path_ref.to_path_buf(),
+                    source,
+                })?;
         }
 
         config.apply_env_overrides();
@@ -632,7 +695,10 @@ impl PolicyConfig {
     }
 
     /// Applies values from a `[policy]` table onto the configuration.
-    fn apply_policy_table(&mut self, table: &toml::value::Table) {
+    ///
+    /// Malformed rules or path overrides are an error rather than being
+    /// skipped, so a typo cannot silently disable a restriction.
+    fn apply_policy_table(&mut self, table: &toml::value::Table) -> Result<(), toml::de::Error> {
         if let Some(value) = table
             .get("default_approval")
             .and_then(Value::as_str)
@@ -707,6 +773,16 @@ impl PolicyConfig {
         {
             self.auto_revert_on_test_fail = value;
         }
+
+        if let Some(rules) = table.get("rules") {
+            self.rules = rules.clone().try_into()?;
+        }
+
+        if let Some(overrides) = table.get("path_overrides") {
+            self.path_overrides = deserialize_path_overrides(overrides.clone())?;
+        }
+
+        Ok(())
     }
 
     /// Applies environment variable overrides using the `DEVIT_*` namespace.
@@ -818,13 +894,7 @@ impl PolicyConfig {
 
     /// Parses a string into an approval level.
     fn parse_approval_level(value: &str) -> Option<ApprovalLevel> {
-        match value.trim().to_ascii_lowercase().as_str() {
-            "untrusted" => Some(ApprovalLevel::Untrusted),
-            "ask" => Some(ApprovalLevel::Ask),
-            "moderate" => Some(ApprovalLevel::Moderate),
-            "trusted" => Some(ApprovalLevel::Trusted),
-            _ => None,
-        }
+        devit_common::policy::parse_approval_level(value)
     }
 
     /// Parses a string into a sandbox profile.
@@ -866,42 +936,6 @@ impl PolicyConfig {
     }
 }
 
-/// Custom policy rule definition.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PolicyRuleConfig {
-    /// Unique identifier for the rule
-    pub id: String,
-
-    /// Human-readable name for the rule
-    pub name: String,
-
-    /// Pattern to match against (glob or regex)
-    pub pattern: String,
-
-    /// Type of pattern matching
-    pub pattern_type: PatternType,
-
-    /// Required approval level for matches
-    pub required_approval: ApprovalLevel,
-
-    /// Whether this rule blocks the operation entirely
-    pub blocking: bool,
-
-    /// Optional description of the rule
-    pub description: Option<String>,
-}
-
-/// Type of pattern matching for policy rules.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum PatternType {
-    /// Glob pattern matching
-    Glob,
-    /// Regular expression matching
-    Regex,
-    /// Exact string matching
-    Exact,
-}
-
 /// Sandbox configuration for process isolation.
 ///
 /// Controls how operations are isolated and what resources
@@ -934,6 +968,11 @@ pub struct SandboxConfig {
 
     /// Additional sandbox restrictions
     pub custom_restrictions: HashMap<String, serde_json::Value>,
+
+    /// User-defined seccomp profiles, referenced by name from sandbox plans
+    /// and `[tools.exec] seccomp_profile`
+    #[serde(default)]
+    pub seccomp_profiles: HashMap<String, SeccompProfileSpec>,
 }
 
 impl Default for SandboxConfig {
@@ -953,6 +992,7 @@ impl Default for SandboxConfig {
             ],
             preserved_env_vars: vec!["PATH".to_string(), "HOME".to_string(), "USER".to_string()],
             custom_restrictions: HashMap::new(),
+            seccomp_profiles: HashMap::new(),
         }
     }
 }
@@ -1523,6 +1563,82 @@ mod tests {
         clear_policy_env();
     }
 
+    #[test]
+    fn loads_policy_rules_and_path_overrides() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        clear_policy_env();
+
+        let dir = tempdir().expect("tempdir");
+        let cfg_path = dir.path().join("devit.toml");
+
+        let toml = r#"
+            [policy]
+            default_approval = "moderate"
+
+            [[policy.rules]]
+            id = "migrations"
+            pattern = "migrations/**"
+            required_approval = "ask"
+            priority = 100
+
+            [[policy.rules]]
+            id = "no-secrets"
+            name = "Secrets"
+            pattern = "\\.pem$"
+            pattern_type = "regex"
+            blocking = true
+
+            [policy.path_overrides]
+            "infra" = "untrusted"
+        "#;
+
+        fs::write(&cfg_path, toml).expect("write config");
+
+        let policy = PolicyConfig::load_from_path(&cfg_path).expect("load policy");
+        assert_eq!(policy.rules.len(), 2);
+        assert_eq!(policy.rules[0].required_approval, ApprovalLevel::Ask);
+        assert_eq!(policy.rules[0].priority, 100);
+        assert!(matches!(policy.rules[1].pattern_type, PatternType::Regex));
+        assert!(policy.rules[1].blocking);
+        assert_eq!(
+            policy.path_overrides.get(Path::new("infra")),
+            Some(&ApprovalLevel::Untrusted)
+        );
+
+        let core: CoreConfig = toml::from_str(
+            r#"
+            [policy]
+            default_approval_level = "Moderate"
+            max_files_moderate = 10
+            max_lines_moderate = 400
+            protected_paths = []
+            small_binary_max_bytes = 1024
+            small_binary_ext_whitelist = []
+            sandbox_profile_default = "Strict"
+            auto_revert_on_test_fail = true
+
+            [[policy.rules]]
+            id = "migrations"
+            pattern = "migrations/**"
+            required_approval = "Ask"
+            "#,
+        )
+        .expect("parse core config");
+        assert_eq!(core.policy.rules[0].id, "migrations");
+
+        fs::write(
+            &cfg_path,
+            "[policy.path_overrides]\n\"infra\" = \"sometimes\"\n",
+        )
+        .expect("write config");
+        assert!(matches!(
+            PolicyConfig::load_from_path(&cfg_path),
+            Err(PolicyConfigError::Parse { .. })
+        ));
+
+        clear_policy_env();
+    }
+
     #[test]
     fn env_overrides_take_precedence() {
         let _guard = ENV_MUTEX.lock().unwrap();
@@ -0,0 +1,194 @@
+//! Line-based three-way merge.
+//!
+//! Used when a patch no longer applies to the current content of a file: the
+//! patch is applied to the version it was written against (the merge base),
+//! and the result is merged with the current content. Regions changed on only
+//! one side merge cleanly; regions changed differently on both sides are left
+//! as conflict markers and reported with the `merge_assist` structures.
+
+use crate::core::structured_edit::{diff_lines, LineOp};
+use crate::merge_assist::ConflictHunk;
+
+/// Opens the current side of a conflict
+pub const CURRENT_MARKER: &str = "<<<<<<< current";
+/// Separates the current side from the patched side
+pub const SEPARATOR_MARKER: &str = "=======";
+/// Closes the patched side of a conflict
+pub const PATCH_MARKER: &str = ">>>>>>> patch";
+
+/// Merged content and the conflicts left in it.
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    pub content: String,
+    /// Conflicting regions, numbered like `merge_assist::explain` numbers the
+    /// markers of `content`
+    pub conflicts: Vec<ConflictHunk>,
+}
+
+/// Merges `current` and `patched`, both derived from `base`.
+pub fn merge(base: &str, current: &str, patched: &str) -> MergeResult {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let current: Vec<&str> = current.split_inclusive('\n').collect();
+    let patched: Vec<&str> = patched.split_inclusive('\n').collect();
+    let to_current = matching(&base, &current);
+    let to_patched = matching(&base, &patched);
+
+    let mut output = Output::default();
+    let (mut i, mut c, mut p) = (0, 0, 0);
+    while i < base.len() || c < current.len() || p < patched.len() {
+        // Line kept unchanged on both sides
+        if i < base.len() && to_current[i] == Some(c) && to_patched[i] == Some(p) {
+            output.push(&[base[i]]);
+            i += 1;
+            c += 1;
+            p += 1;
+            continue;
+        }
+
+        // Changed region, up to the next base line kept on both sides
+        let next = (i..base.len())
+            .find_map(|k| Some((k, to_current[k]?, to_patched[k]?)))
+            .unwrap_or((base.len(), current.len(), patched.len()));
+        let (base_chunk, current_chunk, patched_chunk) =
+            (&base[i..next.0], &current[c..next.1], &patched[p..next.2]);
+        if current_chunk == base_chunk || current_chunk == patched_chunk {
+            output.push(patched_chunk);
+        } else if patched_chunk == base_chunk {
+            output.push(current_chunk);
+        } else {
+            output.conflict(base_chunk, current_chunk, patched_chunk);
+        }
+        (i, c, p) = next;
+    }
+
+    MergeResult {
+        content: output.content,
+        conflicts: output.conflicts,
+    }
+}
+
+/// For each line of `base`, the line of `other` it is kept as.
+fn matching(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+    let (mut b, mut o) = (0, 0);
+    for op in diff_lines(base, other) {
+        match op {
+            LineOp::Equal => {
+                matches[b] = Some(o);
+                b += 1;
+                o += 1;
+            }
+            LineOp::Delete => b += 1,
+            LineOp::Insert => o += 1,
+        }
+    }
+    matches
+}
+
+#[derive(Default)]
+struct Output {
+    content: String,
+    lines: usize,
+    conflicts: Vec<ConflictHunk>,
+}
+
+impl Output {
+    fn push(&mut self, lines: &[&str]) {
+        for line in lines {
+            // Only the last line of a side may lack its newline
+            if !self.content.is_empty() && !self.content.ends_with('\n') {
+                self.content.push('\n');
+            }
+            self.content.push_str(line);
+            self.lines += 1;
+        }
+    }
+
+    fn marker(&mut self, marker: &str) {
+        self.push(&[marker]);
+        self.content.push('\n');
+    }
+
+    fn conflict(&mut self, base: &[&str], current: &[&str], patched: &[&str]) {
+        self.marker(CURRENT_MARKER);
+        let start_line = self.lines;
+        self.push(current);
+        self.marker(SEPARATOR_MARKER);
+        self.push(patched);
+        let end_line = self.lines;
+        self.marker(PATCH_MARKER);
+
+        let text = |lines: &[&str]| lines.concat().lines().collect::<Vec<_>>().join("\n");
+        self.conflicts.push(ConflictHunk {
+            start_line,
+            end_line,
+            ours: text(current),
+            base: Some(text(base)),
+            theirs: text(patched),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_on_different_lines_merge_cleanly() {
+        let base = "a\nb\nc\nd\ne\n";
+        let current = "a\nB\nc\nd\ne\nf\n";
+        let patched = "a\nb\nc\nD\ne\n";
+        let result = merge(base, current, patched);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.content, "a\nB\nc\nD\ne\nf\n");
+
+        // The same change on both sides is not a conflict
+        let result = merge(base, patched, patched);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.content, patched);
+    }
+
+    #[test]
+    fn large_files_merge_cleanly() {
+        let base: String = (1..=5000).map(|n| format!("line {n}\n")).collect();
+        let current = base
+            .replace("line 3\n", "three\n")
+            .replace("line 4998\n", "four thousand nine hundred ninety-eight\n");
+        let patched = base.replace("line 2500\n", "two thousand five hundred\n");
+        let result = merge(&base, &current, &patched);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            result.content,
+            current.replace("line 2500\n", "two thousand five hundred\n")
+        );
+    }
+
+    #[test]
+    fn conflicts_match_merge_assist() {
+        let base = "keep\nvalue = 1\nend";
+        let result = merge(base, "keep\nvalue = 2\nend", "keep\nvalue = 3\nextra\nend");
+        assert_eq!(
+            result.content,
+            "keep\n<<<<<<< current\nvalue = 2\n=======\nvalue = 3\nextra\n>>>>>>> patch\nend"
+        );
+        let [hunk] = result.conflicts.as_slice() else {
+            panic!("expected one conflict: {:?}", result.conflicts);
+        };
+        assert_eq!(hunk.base.as_deref(), Some("value = 1"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        std::fs::write(&path, &result.content).unwrap();
+        let explained = crate::merge_assist::explain(&[path.display().to_string()]).unwrap();
+        let parsed = &explained[0].hunks[0];
+        assert_eq!(
+            (
+                parsed.start_line,
+                parsed.end_line,
+                &parsed.ours,
+                &parsed.theirs
+            ),
+            (hunk.start_line, hunk.end_line, &hunk.ours, &hunk.theirs)
+        );
+    }
+}
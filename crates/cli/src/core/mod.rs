@@ -38,6 +38,7 @@ use crate::core::formats::Compressible;
 
 // Module declarations
 pub mod atomic_patcher;
+pub mod binary_patch;
 pub mod config;
 pub mod errors;
 pub mod file_ops;
@@ -45,10 +46,12 @@ pub mod formats;
 pub mod fs;
 pub mod help_system;
 pub mod journal;
+pub mod merge3;
 pub mod num_compat;
 pub mod orchestration;
 pub mod patch;
 pub mod patch_parser;
+pub mod patch_transaction;
 pub mod path_security;
 pub mod policy;
 mod request_id;
@@ -58,9 +61,10 @@ pub mod schema;
 pub mod security;
 pub mod serde_api;
 pub mod snapshot;
+pub mod structured_edit;
 
 // Re-export core types and errors for convenience
-use atomic_patcher::AtomicPatcher;
+use atomic_patcher::{AtomicPatcher, PatchOptions};
 pub use config::CoreConfig;
 pub use devit_common::{ApprovalLevel, FileChangeKind, SandboxProfile, SnapshotId};
 pub use errors::{DevItError, DevItResult, ErrorSeverity};
@@ -258,8 +262,28 @@ impl CoreEngine {
         let working_dir = workspace.current_dir();
         config.runtime.working_directory = Some(working_dir.clone());
 
+        // Undo patches interrupted by a crash of a previous run
+        match patch_transaction::recover(&working_dir) {
+            Ok(recovered) => {
+                for id in recovered {
+                    warn!("Rolled back interrupted patch transaction {}", id);
+                }
+            }
+            Err(err) => warn!("Failed to recover patch transactions: {}", err),
+        }
+
         let default_approval_level = config.policy.default_approval_level.clone();
         let allow_internal_symlinks = default_approval_level != ApprovalLevel::Untrusted;
+        let mut policy_engine =
+            PolicyEngine::new(default_approval_level, devit_common::SandboxProfile::Strict);
+        policy_engine
+            .apply_rules(&config.policy.rules, &config.policy.path_overrides)
+            .map_err(|err| DevItError::Internal {
+                component: "policy_engine".to_string(),
+                message: err.to_string(),
+                cause: None,
+                correlation_id: Uuid::new_v4().to_string(),
+            })?;
         let orchestration_config = config.orchestration.clone().into();
 
         let orchestration_manager =
@@ -278,10 +302,7 @@ impl CoreEngine {
                 cache_size: 50,
             })),
             snapshot_manager: RwLock::new(SnapshotManager::new(working_dir.clone(), 10)),
-            policy_engine: RwLock::new(PolicyEngine::new(
-                default_approval_level,
-                devit_common::SandboxProfile::Strict,
-            )),
+            policy_engine: RwLock::new(policy_engine),
             journal: RwLock::new(Journal::new(
                 PathBuf::from(".devit/journal.log"),
                 b"test-secret".to_vec(),
@@ -651,6 +672,59 @@ impl CoreEngine {
         approval_level: ApprovalLevel,
         dry_run: bool,
         idempotency_key: Option<&str>,
+    ) -> DevItResult<PatchResult> {
+        self.apply_patch_with_base(
+            patch_content,
+            approval_level,
+            dry_run,
+            idempotency_key,
+            None,
+        )
+        .await
+    }
+
+    /// Applies a patch written against `base_snapshot`, merging three-way the
+    /// files it no longer applies to.
+    ///
+    /// Such a file is patched in its snapshot version, which is then merged
+    /// with the current content: changes on distinct lines combine, and
+    /// overlapping changes are written as conflict markers and listed in
+    /// [`PatchResult::conflicts`]. Patches leaving conflicts are not
+    /// auto-committed.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::patch_apply`], plus `DevItError::SnapshotNotFound` if
+    /// the snapshot does not exist.
+    pub async fn patch_apply_merge(
+        &self,
+        patch_content: &str,
+        base_snapshot: &SnapshotId,
+        approval_level: ApprovalLevel,
+        dry_run: bool,
+        idempotency_key: Option<&str>,
+    ) -> DevItResult<PatchResult> {
+        let snapshot = {
+            let manager = self.snapshot_manager.read().await;
+            manager.get_snapshot(&SnapshotId(base_snapshot.0.clone()))?
+        };
+        self.apply_patch_with_base(
+            patch_content,
+            approval_level,
+            dry_run,
+            idempotency_key,
+            Some(snapshot),
+        )
+        .await
+    }
+
+    async fn apply_patch_with_base(
+        &self,
+        patch_content: &str,
+        approval_level: ApprovalLevel,
+        dry_run: bool,
+        idempotency_key: Option<&str>,
+        merge_base: Option<snapshot::Snapshot>,
     ) -> DevItResult<PatchResult> {
         use std::process::Command;
         use std::time::Instant;
@@ -725,76 +799,17 @@ impl CoreEngine {
 
         // Step 2: policy.evaluate() - Security policy evaluation with PolicyBlock handling
         let policy_engine = self.policy_engine.read().await;
-
-        // Convert patch file changes to policy file changes format
-        let policy_file_changes: Vec<policy::FileChange> = file_changes
-            .iter()
-            .map(|change| policy::FileChange {
-                path: change.file_path.clone(),
-                kind: match change.change_type {
-                    patch::FileChangeType::Created => FileChangeKind::Create,
-                    patch::FileChangeType::Modified => FileChangeKind::Modify,
-                    patch::FileChangeType::Deleted => FileChangeKind::Delete,
-                    patch::FileChangeType::Renamed => FileChangeKind::Modify,
-                    patch::FileChangeType::Copied => FileChangeKind::Create,
-                },
-                is_binary: change.is_binary,
-                adds_exec_bit: change.adds_exec_bit,
-                lines_added: change.lines_added,
-                lines_deleted: change.lines_removed,
-                is_symlink: change.is_symlink,
-                symlink_target_abs: change.symlink_target.clone(),
-                touches_protected: false, // Will be set by policy engine
-                touches_submodule: change.is_submodule,
-                touches_gitmodules: change.touches_gitmodules,
-                file_size_bytes: Some(0), // Not available from patch analysis
-            })
-            .collect();
-
-        let policy_context = PolicyContext {
-            file_changes: policy_file_changes,
-            requested_approval_level: approval_level.clone(),
-            protected_paths: self.config.policy.protected_paths.clone(),
-            config: policy_engine.config().clone(),
-        };
-
-        let policy_decision = policy_engine
-            .evaluate_changes(&policy_context)
-            .map_err(|e| DevItError::Internal {
-                component: "policy_engine".to_string(),
-                message: format!("Policy evaluation failed: {:?}", e),
-                cause: None,
-                correlation_id: uuid::Uuid::new_v4().to_string(),
-            })?;
-
-        if !policy_decision.allow {
-            let policy_rule = Self::infer_policy_rule(&policy_decision.reason);
-            return Err(DevItError::PolicyBlock {
-                rule: policy_rule,
-                required_level: if let Some(downgraded) = policy_decision.downgraded_to {
-                    format!("{:?}", downgraded)
-                } else {
-                    "Higher".to_string()
-                },
-                current_level: format!("{:?}", approval_level),
-                context: policy_decision.reason,
-            });
-        }
-
+        let policy_decision = evaluate_patch_policy(
+            &policy_engine,
+            &file_changes,
+            &approval_level,
+            &self.config.policy.protected_paths,
+        )?;
         if policy_decision.requires_confirmation {
-            if approval_level == ApprovalLevel::Ask {
-                warnings.push(format!(
-                    "Policy requires confirmation: {}",
-                    policy_decision.reason
-                ));
-            } else {
-                return Err(DevItError::PolicyBlock {
-                    rule: "confirmation_required".to_string(),
-                    required_level: "Ask".to_string(),
-                    current_level: format!("{:?}", approval_level),
-                    context: policy_decision.reason,
-                });
-            }
+            warnings.push(format!(
+                "Policy requires confirmation: {}",
+                policy_decision.reason
+            ));
         } else {
             info_messages.push(format!("Policy evaluation: {}", policy_decision.reason));
         }
@@ -810,10 +825,30 @@ impl CoreEngine {
             .clone()
             .unwrap_or_else(|| PathBuf::from("."));
 
-        let patcher = AtomicPatcher::new(working_dir, dry_run);
+        let mut patcher = AtomicPatcher::new(working_dir, dry_run)
+            .with_options(PatchOptions::from(&self.config.tools.patch));
+        if let Some(base) = merge_base {
+            patcher = patcher.with_merge_base(base);
+        }
         let patch_stats = patcher.apply_patch(patch_content)?;
 
         info_messages.push("Patch validation and application successful".to_string());
+        for placement in patch_stats.adjusted_hunks() {
+            warnings.push(format!("Applied {}", placement));
+        }
+        for path in &patch_stats.merged_files {
+            info_messages.push(format!(
+                "Merged {} three-way with the base snapshot",
+                path.display()
+            ));
+        }
+        for file in &patch_stats.conflicts {
+            warnings.push(format!(
+                "{} merge conflict(s) left as markers in {}",
+                file.hunks.len(),
+                file.path
+            ));
+        }
 
         // Early exit for dry-run mode - guaranteed no modifications
         if dry_run {
@@ -830,9 +865,11 @@ impl CoreEngine {
                 required_elevation,
                 commit_sha: None,
                 rollback_cmd: None,
+                transaction_id: None,
                 test_results: None,
                 auto_reverted: false,
                 reverted_sha: None,
+                conflicts: patch_stats.conflicts.clone(),
             };
 
             // Cache the dry-run result if idempotency key is provided
@@ -854,7 +891,10 @@ impl CoreEngine {
         let modified_files = affected_files.clone();
         info_messages.push(format!(
             "Successfully applied patch: {} files modified, {} hunks, +{} -{} lines",
-            patch_stats.files_modified + patch_stats.files_created,
+            patch_stats.files_modified
+                + patch_stats.files_created
+                + patch_stats.files_renamed
+                + patch_stats.files_copied,
             patch_stats.hunks_applied,
             patch_stats.lines_added,
             patch_stats.lines_removed
@@ -868,7 +908,10 @@ impl CoreEngine {
         info_messages.push("Pre-commit security validation passed".to_string());
 
         // Step 5: Optional commit with conventional message handling
-        let commit_sha = if self.config.git.auto_commit {
+        let commit_sha = if !patch_stats.conflicts.is_empty() {
+            info_messages.push("Merge conflicts pending - commit skipped".to_string());
+            None
+        } else if self.config.git.auto_commit {
             info_messages.push("Creating commit for applied patch".to_string());
 
             let commit_message = format!("feat(patch): apply unified diff patch\n\nModified {} files through patch application\n\nGenerated with DevIt patch_apply", modified_files.len());
@@ -917,6 +960,9 @@ impl CoreEngine {
             "files_modified": modified_files.len(),
             "affected_files": modified_files,
             "commit_sha": commit_sha,
+            "transaction_id": patch_stats.transaction_id,
+            "merged_files": patch_stats.merged_files,
+            "conflicted_files": patch_stats.conflicts.iter().map(|file| &file.path).collect::<Vec<_>>(),
             "execution_time_ms": start_time.elapsed().as_millis()
         });
 
@@ -933,7 +979,10 @@ impl CoreEngine {
         let rollback_cmd = if let Some(ref sha) = commit_sha {
             Some(format!("git revert {}", sha))
         } else {
-            Some("git reset --hard HEAD".to_string())
+            patch_stats
+                .transaction_id
+                .as_deref()
+                .map(patch_transaction::rollback_command)
         };
 
         if let Some(ref cmd) = rollback_cmd {
@@ -952,9 +1001,11 @@ impl CoreEngine {
             required_elevation,
             commit_sha,
             rollback_cmd,
+            transaction_id: patch_stats.transaction_id.clone(),
             test_results: None,
             auto_reverted: false,
             reverted_sha: None,
+            conflicts: patch_stats.conflicts.clone(),
         };
 
         // Cache the result if idempotency key is provided
@@ -972,6 +1023,62 @@ impl CoreEngine {
         Ok(result)
     }
 
+    /// Applies structured edits (search/replace, anchored inserts, hashed line
+    /// ranges, whole-file create/delete).
+    ///
+    /// The edits are compiled into a unified diff and go through
+    /// [`Self::patch_apply`], with the same policy, snapshot and journal steps.
+    pub async fn edit_apply(
+        &self,
+        edits: &[structured_edit::EditOperation],
+        approval_level: ApprovalLevel,
+        dry_run: bool,
+        idempotency_key: Option<&str>,
+    ) -> DevItResult<PatchResult> {
+        let working_dir = self
+            .config
+            .runtime
+            .working_directory
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."));
+        let diff = structured_edit::edits_to_diff(&working_dir, edits)?;
+        self.patch_apply(&diff, approval_level, dry_run, idempotency_key)
+            .await
+    }
+
+    /// Undoes a patch applied without commit, from its transaction journal.
+    ///
+    /// # Arguments
+    /// * `transaction_id` - Id reported by `patch_apply` (`transaction_id`)
+    ///
+    /// # Returns
+    /// The restored paths, relative to the working directory.
+    ///
+    /// # Errors
+    /// * `DevItError::VcsConflict` - If a patched file changed since, or the
+    ///   transaction was already rolled back
+    /// * `DevItError::Io` - If the journal cannot be read or a file restored
+    pub async fn patch_rollback(&self, transaction_id: &str) -> DevItResult<Vec<PathBuf>> {
+        let working_dir = self
+            .config
+            .runtime
+            .working_directory
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."));
+        let restored = patch_transaction::rollback(&working_dir, transaction_id)?;
+
+        let journal_entry = serde_json::json!({
+            "operation": "patch_rollback",
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "transaction_id": transaction_id,
+            "restored_files": restored,
+        });
+        let mut journal = self.journal.write().await;
+        journal.append(journal_entry, None)?;
+
+        Ok(restored)
+    }
+
     /// Enhanced patch application with optional post-test execution and auto-revert.
     ///
     /// This method implements the full R2 workflow:
@@ -1380,32 +1487,16 @@ impl CoreEngine {
     }
 
     fn unique_paths(changes: &[patch::FileChange]) -> Vec<PathBuf> {
-        let mut paths: Vec<PathBuf> = changes.iter().map(|c| c.file_path.clone()).collect();
+        let mut paths: Vec<PathBuf> = changes
+            .iter()
+            .flat_map(|c| std::iter::once(&c.file_path).chain(c.old_path.as_ref()))
+            .cloned()
+            .collect();
         paths.sort();
         paths.dedup();
         paths
     }
 
-    fn infer_policy_rule(reason: &str) -> String {
-        let lower = reason.to_lowercase();
-        if lower.contains(".env")
-            || lower.contains("protected path")
-            || lower.contains("chemin prot")
-        {
-            "policy_protected_path".to_string()
-        } else if lower.contains("executable") || lower.contains("permission exécutable") {
-            "policy_exec_permission".to_string()
-        } else if lower.contains("binary") || lower.contains("binaire") {
-            "policy_binary_restriction".to_string()
-        } else if lower.contains("gitmodules") {
-            "policy_gitmodules".to_string()
-        } else if lower.contains("symlink") || lower.contains("lien symbolique") {
-            "policy_symlink_restriction".to_string()
-        } else {
-            "policy_evaluation".to_string()
-        }
-    }
-
     fn extract_permission_changes(patch_content: &str) -> Vec<PermissionChange> {
         let mut changes = Vec::new();
         let mut current_path: Option<PathBuf> = None;
@@ -1658,7 +1749,7 @@ impl CoreEngine {
                 ],
                 bind_rw: vec![current_dir.clone(), PathBuf::from("/tmp")],
                 net: false,
-                seccomp_profile: Some("test-strict".to_string()),
+                seccomp_profile: Some("strict".to_string()),
             },
             SandboxProfile::Permissive => SandboxPlan {
                 bind_ro: vec![
@@ -1753,19 +1844,20 @@ impl CoreEngine {
         use tokio::process::Command;
         use tokio::time::{timeout, Duration};
 
-        // Check if bwrap is available for sandboxing
-        let use_sandbox = self.check_bwrap_available().await;
-
-        let mut cmd = if use_sandbox {
-            self.build_bwrap_command(command, sandbox_plan)?
-        } else {
-            // Fallback to direct execution
-            let mut direct_cmd = Command::new(&command[0]);
-            if command.len() > 1 {
-                direct_cmd.args(&command[1..]);
-            }
-            direct_cmd
-        };
+        // Prefer the native namespace backend, then bwrap, then direct execution
+        let mut cmd =
+            if let Some(native_cmd) = self.build_native_sandbox_command(command, sandbox_plan)? {
+                native_cmd
+            } else if self.check_bwrap_available().await {
+                self.build_bwrap_command(command, sandbox_plan)?
+            } else {
+                // Fallback to direct execution
+                let mut direct_cmd = Command::new(&command[0]);
+                if command.len() > 1 {
+                    direct_cmd.args(&command[1..]);
+                }
+                direct_cmd
+            };
 
         // Set up process with stdio capture
         cmd.stdout(std::process::Stdio::piped())
@@ -1802,6 +1894,46 @@ impl CoreEngine {
         })
     }
 
+    /// Build a command confined by the in-process namespace backend.
+    ///
+    /// Returns `Ok(None)` when the backend is not compiled in or the kernel
+    /// does not allow unprivileged user namespaces.
+    fn build_native_sandbox_command(
+        &self,
+        command: &[String],
+        sandbox_plan: &sandbox::SandboxPlan,
+    ) -> DevItResult<Option<tokio::process::Command>> {
+        #[cfg(all(feature = "sandbox", target_os = "linux"))]
+        {
+            use devit_sandbox::NamespaceSandbox;
+
+            if !NamespaceSandbox::is_supported() {
+                return Ok(None);
+            }
+
+            let mut cmd = tokio::process::Command::new(&command[0]);
+            cmd.args(&command[1..]);
+            NamespaceSandbox::new(sandbox_plan.clone())
+                .with_seccomp_profiles(self.config.sandbox.seccomp_profiles.clone())
+                .confine(cmd.as_std_mut())
+                .map_err(|e| DevItError::SandboxDenied {
+                    reason: e.to_string(),
+                    active_profile: sandbox_plan
+                        .seccomp_profile
+                        .clone()
+                        .unwrap_or_else(|| "none".to_string()),
+                    attempted_operation: command.join(" "),
+                    violated_policy: Some("namespace_isolation".to_string()),
+                })?;
+            Ok(Some(cmd))
+        }
+        #[cfg(not(all(feature = "sandbox", target_os = "linux")))]
+        {
+            let _ = (command, sandbox_plan);
+            Ok(None)
+        }
+    }
+
     /// Check if bwrap (bubblewrap) is available for sandboxing
     async fn check_bwrap_available(&self) -> bool {
         tokio::process::Command::new("which")
@@ -2068,6 +2200,30 @@ impl CoreEngine {
     async fn perform_auto_revert(&self, patch_result: &PatchResult) -> DevItResult<PatchResult> {
         use std::process::Command;
 
+        // Without a commit, the transaction journal restores the files
+        if let (None, Some(id)) = (&patch_result.commit_sha, &patch_result.transaction_id) {
+            let restored = self.patch_rollback(id).await?;
+            return Ok(PatchResult {
+                success: true,
+                modified_files: restored,
+                warnings: Vec::new(),
+                info_messages: vec![format!(
+                    "Auto-revert executed: {}",
+                    patch_transaction::rollback_command(id)
+                )],
+                resulting_snapshot: None,
+                execution_time: std::time::Duration::from_millis(1),
+                required_elevation: false,
+                commit_sha: None,
+                rollback_cmd: Some(patch_transaction::rollback_command(id)),
+                transaction_id: None,
+                test_results: None,
+                auto_reverted: false,
+                reverted_sha: None,
+                conflicts: Vec::new(),
+            });
+        }
+
         if let Some(ref rollback_cmd) = patch_result.rollback_cmd {
             // Parse and execute the rollback command
             let cmd_parts: Vec<&str> = rollback_cmd.split_whitespace().collect();
@@ -2125,9 +2281,11 @@ impl CoreEngine {
                 required_elevation: false,
                 commit_sha,
                 rollback_cmd: None, // No further rollback needed
+                transaction_id: None,
                 test_results: None,
                 auto_reverted: false, // This is the revert operation itself
                 reverted_sha: None,
+                conflicts: Vec::new(),
             })
         } else {
             Err(DevItError::Internal {
@@ -2659,6 +2817,25 @@ impl CoreEngine {
             .await
     }
 
+    /// Annulation d'une tâche déléguée (le worker est arrêté côté daemon)
+    pub async fn orchestration_cancel(
+        &self,
+        task_id: String,
+        reason: Option<String>,
+    ) -> DevItResult<()> {
+        let mut journal_details = std::collections::HashMap::new();
+        journal_details.insert("task_id".to_string(), task_id.clone());
+        if let Some(reason) = reason.as_ref() {
+            journal_details.insert("reason".to_string(), reason.clone());
+        }
+
+        self.journal_append("orchestration_cancel", &journal_details, None)
+            .await?;
+
+        let orchestration = self.orchestration.read().await;
+        orchestration.cancel_task(&task_id, reason.as_deref()).await
+    }
+
     /// NOUVELLE méthode : Status de l'orchestration
     pub async fn orchestration_status(
         &self,
@@ -2765,6 +2942,109 @@ impl CoreEngine {
     }
 }
 
+/// Evaluates the changes of a patch against the security policy.
+///
+/// Shared by [`CoreEngine::patch_apply`] and the MCP patch tools so that both
+/// enforce the same rules. A denial, or a confirmation that `approval_level`
+/// cannot give, is returned as `DevItError::PolicyBlock`; with `Ask` the
+/// decision is returned and `requires_confirmation` tells the caller to warn.
+pub fn evaluate_patch_policy(
+    policy_engine: &policy::PolicyEngine,
+    file_changes: &[patch::FileChange],
+    approval_level: &ApprovalLevel,
+    protected_paths: &[PathBuf],
+) -> DevItResult<PolicyDecision> {
+    // Convert patch file changes to policy file changes format
+    let policy_file_changes: Vec<policy::FileChange> = file_changes
+        .iter()
+        .map(|change| policy::FileChange {
+            path: change.file_path.clone(),
+            old_path: change.old_path.clone(),
+            kind: match change.change_type {
+                patch::FileChangeType::Created => FileChangeKind::Create,
+                patch::FileChangeType::Modified => FileChangeKind::Modify,
+                patch::FileChangeType::Deleted => FileChangeKind::Delete,
+                patch::FileChangeType::Renamed => FileChangeKind::Rename,
+                patch::FileChangeType::Copied => FileChangeKind::Copy,
+            },
+            is_binary: change.is_binary,
+            adds_exec_bit: change.adds_exec_bit,
+            lines_added: change.lines_added,
+            lines_deleted: change.lines_removed,
+            is_symlink: change.is_symlink,
+            symlink_target_abs: change.symlink_target.clone(),
+            touches_protected: false, // Will be set by policy engine
+            touches_submodule: change.is_submodule,
+            touches_gitmodules: change.touches_gitmodules,
+            // Only known for binary patches
+            file_size_bytes: change.binary_size.or(Some(0)),
+        })
+        .collect();
+
+    let policy_context = PolicyContext {
+        file_changes: policy_file_changes,
+        requested_approval_level: approval_level.clone(),
+        protected_paths: protected_paths.to_vec(),
+        config: policy_engine.config().clone(),
+    };
+
+    let policy_decision = policy_engine
+        .evaluate_changes(&policy_context)
+        .map_err(|e| DevItError::Internal {
+            component: "policy_engine".to_string(),
+            message: format!("Policy evaluation failed: {:?}", e),
+            cause: None,
+            correlation_id: uuid::Uuid::new_v4().to_string(),
+        })?;
+
+    if !policy_decision.allow {
+        let policy_rule = policy_decision
+            .matched_rule
+            .clone()
+            .unwrap_or_else(|| infer_policy_rule(&policy_decision.reason));
+        return Err(DevItError::PolicyBlock {
+            rule: policy_rule,
+            required_level: if let Some(downgraded) = policy_decision.downgraded_to {
+                format!("{:?}", downgraded)
+            } else {
+                "Higher".to_string()
+            },
+            current_level: format!("{:?}", approval_level),
+            context: policy_decision.reason,
+        });
+    }
+
+    if policy_decision.requires_confirmation && *approval_level != ApprovalLevel::Ask {
+        return Err(DevItError::PolicyBlock {
+            rule: policy_decision
+                .matched_rule
+                .unwrap_or_else(|| "confirmation_required".to_string()),
+            required_level: "Ask".to_string(),
+            current_level: format!("{:?}", approval_level),
+            context: policy_decision.reason,
+        });
+    }
+
+    Ok(policy_decision)
+}
+
+fn infer_policy_rule(reason: &str) -> String {
+    let lower = reason.to_lowercase();
+    if lower.contains(".env") || lower.contains("protected path") || lower.contains("chemin prot") {
+        "policy_protected_path".to_string()
+    } else if lower.contains("executable") || lower.contains("permission exécutable") {
+        "policy_exec_permission".to_string()
+    } else if lower.contains("binary") || lower.contains("binaire") {
+        "policy_binary_restriction".to_string()
+    } else if lower.contains("gitmodules") {
+        "policy_gitmodules".to_string()
+    } else if lower.contains("symlink") || lower.contains("lien symbolique") {
+        "policy_symlink_restriction".to_string()
+    } else {
+        "policy_evaluation".to_string()
+    }
+}
+
 /// Detailed analysis of patch contents before application.
 ///
 /// Enables informed decision-making by showing what changes would be made
@@ -2826,6 +3106,10 @@ pub struct PatchResult {
     /// Rollback command for recovery (not executed here)
     pub rollback_cmd: Option<String>,
 
+    /// Patch transaction whose journal can undo the changes
+    #[serde(default)]
+    pub transaction_id: Option<String>,
+
     /// Test results if post-tests were executed
     pub test_results: Option<TestResults>,
 
@@ -2834,6 +3118,10 @@ pub struct PatchResult {
 
     /// SHA of the revert commit if auto-revert was performed
     pub reverted_sha: Option<String>,
+
+    /// Conflicts left as markers by a three-way merge
+    #[serde(default)]
+    pub conflicts: Vec<crate::merge_assist::FileConflicts>,
 }
 
 /// Lightweight test orchestration request types (legacy compatibility).
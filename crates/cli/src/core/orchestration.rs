@@ -78,6 +78,13 @@ impl OrchestrationManager {
             .map_err(convert_error)
     }
 
+    pub async fn cancel_task(&self, task_id: &str, reason: Option<&str>) -> DevItResult<()> {
+        self.context
+            .cancel(task_id, reason)
+            .await
+            .map_err(convert_error)
+    }
+
     pub async fn get_status(&self, filter: Option<&str>) -> DevItResult<OrchestrationStatus> {
         self.context.status(filter).await.map_err(convert_error)
     }
@@ -119,6 +126,16 @@ pub fn status_to_string(status: &OrchestrationStatus) -> String {
         }
     }
 
+    if !status.queue.is_empty() {
+        lines.push("--- File d'attente ---".to_string());
+        for queued in &status.queue {
+            lines.push(format!(
+                "• #{} {} → {} (priorité {})",
+                queued.position, queued.task_id, queued.delegated_to, queued.priority
+            ));
+        }
+    }
+
     if !status.completed_tasks.is_empty() {
         lines.push("--- Tâches terminées ---".to_string());
         for task in &status.completed_tasks {
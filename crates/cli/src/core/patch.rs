@@ -36,10 +36,14 @@ pub enum FileChangeType {
 #[derive(Debug, Clone)]
 pub struct FileChange {
     pub file_path: PathBuf,
+    /// Source of a rename or copy
+    pub old_path: Option<PathBuf>,
     pub change_type: FileChangeType,
     pub lines_added: usize,
     pub lines_removed: usize,
     pub is_binary: bool,
+    /// Size of the new content of a `GIT binary patch`, when known
+    pub binary_size: Option<u64>,
     pub adds_exec_bit: bool,
     pub is_symlink: bool,
     pub symlink_target: Option<PathBuf>,
@@ -128,9 +132,12 @@ pub fn classify_changes(diff_content: &str) -> DevItResult<Vec<FileChange>> {
             FileChangeType::Created
         } else if file.is_deleted_file {
             FileChangeType::Deleted
-        } else if file.old_path != file.new_path
-            && file.old_path.is_some()
-            && file.new_path.is_some()
+        } else if file.is_copy {
+            FileChangeType::Copied
+        } else if file.is_rename
+            || (file.old_path != file.new_path
+                && file.old_path.is_some()
+                && file.new_path.is_some())
         {
             FileChangeType::Renamed
         } else {
@@ -151,12 +158,19 @@ pub fn classify_changes(diff_content: &str) -> DevItResult<Vec<FileChange>> {
 
         let touches_gitmodules = primary_path == PathBuf::from(".gitmodules");
 
+        let old_path = match change_type {
+            FileChangeType::Renamed | FileChangeType::Copied => file.old_path.clone(),
+            _ => None,
+        };
+
         changes.push(FileChange {
             file_path: primary_path,
+            old_path,
             change_type,
             lines_added,
             lines_removed,
             is_binary: file.is_binary,
+            binary_size: file.binary.as_ref().and_then(|binary| binary.new_size()),
             adds_exec_bit: file.adds_exec_bit,
             is_symlink: false,
             symlink_target: None,
@@ -191,4 +205,16 @@ index 1234567..abcdefg
         assert_eq!(changes.len(), 1);
         assert!(changes[0].adds_exec_bit, "exec bit should be detected");
     }
+    #[test]
+    fn renames_and_copies_keep_their_source() {
+        let patch = "diff --git a/old.rs b/new.rs\nsimilarity index 100%\nrename from old.rs\nrename to new.rs\ndiff --git a/new.rs b/copy.rs\nsimilarity index 100%\ncopy from new.rs\ncopy to copy.rs\n";
+
+        let changes = classify_changes(patch).expect("classification works");
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].change_type, FileChangeType::Renamed);
+        assert_eq!(changes[0].file_path, PathBuf::from("new.rs"));
+        assert_eq!(changes[0].old_path, Some(PathBuf::from("old.rs")));
+        assert_eq!(changes[1].change_type, FileChangeType::Copied);
+        assert_eq!(changes[1].old_path, Some(PathBuf::from("new.rs")));
+    }
 }
@@ -1,3 +1,4 @@
+use crate::core::binary_patch::BinaryPatch;
 use crate::core::errors::{DevItError, DevItResult};
 use std::path::PathBuf;
 
@@ -8,6 +9,10 @@ pub struct PatchHunk {
     pub new_start: usize,
     pub new_count: usize,
     pub lines: Vec<PatchLine>,
+    /// `\ No newline at end of file` after the last old line
+    pub old_missing_newline: bool,
+    /// `\ No newline at end of file` after the last new line
+    pub new_missing_newline: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -24,10 +29,18 @@ pub struct FilePatch {
     pub hunks: Vec<PatchHunk>,
     pub is_new_file: bool,
     pub is_deleted_file: bool,
+    /// `rename from`/`rename to`: `old_path` disappears
+    pub is_rename: bool,
+    /// `copy from`/`copy to`: `old_path` is kept
+    pub is_copy: bool,
+    /// `similarity index` of a rename or copy, in percent
+    pub similarity: Option<u8>,
     pub old_mode: Option<u32>,
     pub new_mode: Option<u32>,
     pub adds_exec_bit: bool,
     pub is_binary: bool,
+    /// Content of a `GIT binary patch`; `None` for `Binary files ... differ`
+    pub binary: Option<BinaryPatch>,
 }
 
 #[derive(Debug)]
@@ -55,44 +68,75 @@ impl ParsedPatch {
     }
 
     fn parse_file_patch(lines: &[&str], start: usize) -> DevItResult<(FilePatch, usize)> {
-        let mut i = start;
+        let (header_old, header_new) =
+            parse_git_header(&lines[start]["diff --git ".len()..], start + 1)?;
+        let mut i = start + 1;
         let mut old_path = None;
         let mut new_path = None;
         let mut is_new_file = false;
         let mut is_deleted_file = false;
+        let mut is_rename = false;
+        let mut is_copy = false;
+        let mut similarity = None;
         let mut old_mode = None;
         let mut new_mode = None;
         let mut hunks = Vec::new();
         let mut is_binary = false;
+        let mut binary = None;
 
-        // Parse diff header
-        while i < lines.len() && !lines[i].starts_with("@@") {
-            if let Some(rest) = lines[i].strip_prefix("old mode ") {
+        // Parse extended header lines
+        while i < lines.len() && !lines[i].starts_with("@@") && !lines[i].starts_with("diff --git ")
+        {
+            let line = lines[i];
+            if let Some(rest) = line.strip_prefix("old mode ") {
                 old_mode = parse_mode(rest.trim(), i + 1)?;
-            } else if let Some(rest) = lines[i].strip_prefix("new mode ") {
+            } else if let Some(rest) = line.strip_prefix("new mode ") {
                 new_mode = parse_mode(rest.trim(), i + 1)?;
-            } else if lines[i].starts_with("--- ") {
-                let path_str = &lines[i][4..];
-                if path_str != "/dev/null" {
-                    old_path = Some(PathBuf::from(path_str.trim_start_matches("a/")));
-                }
-            } else if lines[i].starts_with("+++ ") {
-                let path_str = &lines[i][4..];
-                if path_str != "/dev/null" {
-                    new_path = Some(PathBuf::from(path_str.trim_start_matches("b/")));
-                }
-            } else if lines[i].contains("new file mode") {
+            } else if line.starts_with("new file mode ") {
                 is_new_file = true;
-            } else if lines[i].contains("deleted file mode") {
+            } else if line.starts_with("deleted file mode ") {
                 is_deleted_file = true;
-            } else if lines[i].starts_with("Binary files ") {
+            } else if let Some(rest) = line.strip_prefix("rename from ") {
+                is_rename = true;
+                old_path = Some(PathBuf::from(unquote(rest, i + 1)?));
+            } else if let Some(rest) = line.strip_prefix("rename to ") {
+                is_rename = true;
+                new_path = Some(PathBuf::from(unquote(rest, i + 1)?));
+            } else if let Some(rest) = line.strip_prefix("copy from ") {
+                is_copy = true;
+                old_path = Some(PathBuf::from(unquote(rest, i + 1)?));
+            } else if let Some(rest) = line.strip_prefix("copy to ") {
+                is_copy = true;
+                new_path = Some(PathBuf::from(unquote(rest, i + 1)?));
+            } else if let Some(rest) = line.strip_prefix("similarity index ") {
+                similarity = rest.trim().trim_end_matches('%').parse().ok();
+            } else if let Some(rest) = line.strip_prefix("--- ") {
+                old_path = parse_file_header(rest, "a/", i + 1)?;
+            } else if let Some(rest) = line.strip_prefix("+++ ") {
+                new_path = parse_file_header(rest, "b/", i + 1)?;
+            } else if line.starts_with("Binary files ") {
                 is_binary = true;
-                i += 1;
-                break;
+            } else if line == "GIT binary patch" {
+                is_binary = true;
+                let (patch, next_index) = BinaryPatch::parse(lines, i + 1)?;
+                binary = Some(patch);
+                i = next_index;
+                continue;
             }
             i += 1;
         }
 
+        // Without `---`/`+++` (mode change, binary, pure rename) the paths
+        // come from the `diff --git` line.
+        if old_path.is_none() && new_path.is_none() {
+            if !is_new_file {
+                old_path = header_old;
+            }
+            if !is_deleted_file {
+                new_path = header_new;
+            }
+        }
+
         // Parse hunks
         while i < lines.len() && lines[i].starts_with("@@") {
             let (hunk, next_index) = Self::parse_hunk(lines, i)?;
@@ -106,10 +150,14 @@ impl ParsedPatch {
             hunks,
             is_new_file,
             is_deleted_file,
+            is_rename,
+            is_copy,
+            similarity,
             old_mode,
             new_mode,
             adds_exec_bit: mode_adds_exec(old_mode, new_mode),
             is_binary,
+            binary,
         };
 
         Ok((file_patch, i))
@@ -134,6 +182,8 @@ impl ParsedPatch {
         let (new_start, new_count) = Self::parse_range(new_range)?;
 
         let mut hunk_lines = Vec::new();
+        let mut old_missing_newline = false;
+        let mut new_missing_newline = false;
         let mut i = start + 1;
 
         while i < lines.len() {
@@ -146,6 +196,16 @@ impl ParsedPatch {
                 Some(' ') => hunk_lines.push(PatchLine::Context(line[1..].to_string())),
                 Some('+') => hunk_lines.push(PatchLine::Add(line[1..].to_string())),
                 Some('-') => hunk_lines.push(PatchLine::Remove(line[1..].to_string())),
+                // `\ No newline at end of file` applies to the previous line
+                Some('\\') => match hunk_lines.last() {
+                    Some(PatchLine::Context(_)) => {
+                        old_missing_newline = true;
+                        new_missing_newline = true;
+                    }
+                    Some(PatchLine::Remove(_)) => old_missing_newline = true,
+                    Some(PatchLine::Add(_)) => new_missing_newline = true,
+                    None => {}
+                },
                 _ => break, // End of hunk
             }
             i += 1;
@@ -157,6 +217,8 @@ impl ParsedPatch {
             new_start,
             new_count,
             lines: hunk_lines,
+            old_missing_newline,
+            new_missing_newline,
         };
 
         Ok((hunk, i))
@@ -199,6 +261,123 @@ fn parse_mode(value: &str, line_number: usize) -> DevItResult<Option<u32>> {
         })
 }
 
+/// Path of a `---`/`+++` line; `None` for `/dev/null`. Git ends names
+/// containing spaces with a tab, other tools append a timestamp after one.
+fn parse_file_header(raw: &str, prefix: &str, line_number: usize) -> DevItResult<Option<PathBuf>> {
+    let name = if raw.starts_with('"') {
+        unquote(raw, line_number)?
+    } else {
+        raw.split('\t').next().unwrap_or(raw).to_string()
+    };
+    if name == "/dev/null" {
+        return Ok(None);
+    }
+    Ok(Some(PathBuf::from(strip_prefix(&name, prefix))))
+}
+
+/// Old and new paths of a `diff --git a/<old> b/<new>` line. Unquoted names
+/// may contain spaces: the split that gives the same name on both sides
+/// wins (renames carry their paths in `rename from`/`rename to`).
+fn parse_git_header(
+    rest: &str,
+    line_number: usize,
+) -> DevItResult<(Option<PathBuf>, Option<PathBuf>)> {
+    let rest = rest.trim_end();
+    let (old, new) = if rest.starts_with('"') {
+        let end = quoted_end(rest).ok_or_else(|| unterminated_quote(line_number))?;
+        (
+            unquote(&rest[..end], line_number)?,
+            unquote(rest[end..].trim_start(), line_number)?,
+        )
+    } else if let Some(split) = rest.rfind(" \"") {
+        (
+            rest[..split].to_string(),
+            unquote(&rest[split + 1..], line_number)?,
+        )
+    } else {
+        let splits: Vec<usize> = rest.match_indices(' ').map(|(index, _)| index).collect();
+        let same_name = splits.iter().copied().find(|&split| {
+            strip_prefix(&rest[..split], "a/") == strip_prefix(&rest[split + 1..], "b/")
+        });
+        match same_name.or_else(|| rest.find(" b/")) {
+            Some(split) => (rest[..split].to_string(), rest[split + 1..].to_string()),
+            None => return Ok((None, None)),
+        }
+    };
+    Ok((
+        Some(PathBuf::from(strip_prefix(&old, "a/"))),
+        Some(PathBuf::from(strip_prefix(&new, "b/"))),
+    ))
+}
+
+fn strip_prefix<'a>(name: &'a str, prefix: &str) -> &'a str {
+    name.strip_prefix(prefix).unwrap_or(name)
+}
+
+/// Length of the leading C-quoted string of `raw` (quotes included); `None`
+/// when the closing quote is missing.
+fn quoted_end(raw: &str) -> Option<usize> {
+    let bytes = raw.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn unterminated_quote(line_number: usize) -> DevItError {
+    DevItError::InvalidDiff {
+        reason: "Unterminated quoted file name".to_string(),
+        line_number: Some(line_number),
+    }
+}
+
+/// Decodes a name git quoted because of special characters (`"a/t\303\251te"`);
+/// other names are returned as is.
+fn unquote(raw: &str, line_number: usize) -> DevItResult<String> {
+    let raw = raw.trim_end();
+    if !raw.starts_with('"') {
+        return Ok(raw.to_string());
+    }
+    let end = quoted_end(raw).ok_or_else(|| unterminated_quote(line_number))?;
+    let inner = &raw[1..end - 1];
+    let bytes = inner.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' || i + 1 == bytes.len() {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        let escaped = bytes[i + 1];
+        i += 2;
+        match escaped {
+            b'n' => decoded.push(b'\n'),
+            b't' => decoded.push(b'\t'),
+            b'r' => decoded.push(b'\r'),
+            b'a' => decoded.push(0x07),
+            b'b' => decoded.push(0x08),
+            b'f' => decoded.push(0x0c),
+            b'v' => decoded.push(0x0b),
+            b'0'..=b'7' => {
+                let mut value = u32::from(escaped - b'0');
+                while i < bytes.len() && value < 0o40 && matches!(bytes[i], b'0'..=b'7') {
+                    value = value * 8 + u32::from(bytes[i] - b'0');
+                    i += 1;
+                }
+                decoded.push(value as u8);
+            }
+            other => decoded.push(other),
+        }
+    }
+    Ok(String::from_utf8_lossy(&decoded).into_owned())
+}
+
 fn mode_adds_exec(old_mode: Option<u32>, new_mode: Option<u32>) -> bool {
     const EXEC_MASK: u32 = 0o111;
     match (old_mode, new_mode) {
@@ -207,3 +386,80 @@ fn mode_adds_exec(old_mode: Option<u32>, new_mode: Option<u32>) -> bool {
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn git_extended_headers_are_parsed() {
+        let diff = concat!(
+            "diff --git a/src/a b.rs b/src/a b.rs\n",
+            "old mode 100644\n",
+            "new mode 100755\n",
+            "diff --git \"a/caf\\303\\251.txt\" \"b/docs/caf\\303\\251.txt\"\n",
+            "similarity index 90%\n",
+            "rename from \"caf\\303\\251.txt\"\n",
+            "rename to \"docs/caf\\303\\251.txt\"\n",
+            "--- \"a/caf\\303\\251.txt\"\n",
+            "+++ \"b/docs/caf\\303\\251.txt\"\n",
+            "@@ -1,2 +1,2 @@\n",
+            " keep\n",
+            "-old\n",
+            "\\ No newline at end of file\n",
+            "+new\n",
+            "diff --git a/lib.rs b/lib_copy.rs\n",
+            "similarity index 100%\n",
+            "copy from lib.rs\n",
+            "copy to lib_copy.rs\n",
+        );
+        let parsed = ParsedPatch::from_diff(diff).unwrap();
+        assert_eq!(parsed.files.len(), 3);
+
+        let mode_change = &parsed.files[0];
+        assert_eq!(mode_change.old_path, Some(PathBuf::from("src/a b.rs")));
+        assert_eq!(mode_change.new_path, Some(PathBuf::from("src/a b.rs")));
+        assert!(mode_change.adds_exec_bit);
+
+        let rename = &parsed.files[1];
+        assert!(rename.is_rename && !rename.is_copy);
+        assert_eq!(rename.similarity, Some(90));
+        assert_eq!(rename.old_path, Some(PathBuf::from("café.txt")));
+        assert_eq!(rename.new_path, Some(PathBuf::from("docs/café.txt")));
+        let hunk = &rename.hunks[0];
+        assert_eq!(hunk.lines.len(), 3);
+        assert!(hunk.old_missing_newline && !hunk.new_missing_newline);
+
+        let copy = &parsed.files[2];
+        assert!(copy.is_copy && copy.hunks.is_empty());
+        assert_eq!(copy.old_path, Some(PathBuf::from("lib.rs")));
+        assert_eq!(copy.new_path, Some(PathBuf::from("lib_copy.rs")));
+    }
+
+    #[test]
+    fn file_headers_drop_timestamps_and_one_prefix() {
+        assert_eq!(
+            parse_file_header("a/a/notes.txt\t2024-01-01 10:00:00", "a/", 1).unwrap(),
+            Some(PathBuf::from("a/notes.txt"))
+        );
+        assert_eq!(parse_file_header("/dev/null", "a/", 1).unwrap(), None);
+        assert_eq!(
+            unquote("\"tab\\there \\\"q\\\"\"", 1).unwrap(),
+            "tab\there \"q\""
+        );
+    }
+
+    #[test]
+    fn unterminated_quoted_names_are_rejected() {
+        let diff = "diff --git a/x b/y\nrename from \"café\nrename to y\n";
+        let err = ParsedPatch::from_diff(diff).err().unwrap();
+        assert!(matches!(
+            err,
+            DevItError::InvalidDiff {
+                line_number: Some(2),
+                ..
+            }
+        ));
+        assert!(parse_git_header("\"a/caf\u{e9} b/y", 1).is_err());
+    }
+}
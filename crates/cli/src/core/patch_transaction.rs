@@ -0,0 +1,812 @@
+//! Transactional application of multi-file patches.
+//!
+//! Changes are staged in memory while a patch is applied, then committed in
+//! one go:
+//!
+//! 1. every new content is written under `.devit/transactions/<id>/staged/`
+//!    and every file about to change is copied under `backup/` (symlinks are
+//!    recorded as links);
+//! 2. once the backups are synced, `manifest.json` is written (state
+//!    `committing`) and synced;
+//! 3. staged files are renamed over their targets and deleted files removed;
+//! 4. the manifest is marked `committed`.
+//!
+//! The process applying a transaction holds the exclusive lock file
+//! `<id>.lock` (recording its pid) until it is done. A failure during step 3
+//! restores the backups immediately. If the process dies instead, [`recover`]
+//! restores them on the next start, skipping transactions whose lock is
+//! still held by a live process. Committed
+//! transactions stay on disk so that [`rollback`] can undo them later, as long
+//! as the files were not modified since.
+
+use std::fs::{self, File, OpenOptions, Permissions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::core::errors::{DevItError, DevItResult};
+
+/// Directory holding transactions, relative to the working directory
+pub const TRANSACTIONS_DIR: &str = ".devit/transactions";
+
+/// Committed transactions kept for rollback
+const MAX_KEPT_TRANSACTIONS: usize = 20;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Command undoing a committed transaction.
+pub fn rollback_command(id: &str) -> String {
+    format!("devit rollback {}", id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionState {
+    /// Targets are being replaced; backups must be restored on recovery
+    Committing,
+    /// Every change is in place
+    Committed,
+    /// Backups were restored
+    RolledBack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    id: String,
+    created_at: DateTime<Utc>,
+    state: TransactionState,
+    entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ManifestEntry {
+    /// Target, relative to the working directory
+    path: PathBuf,
+    /// Copy of the previous content, relative to the transaction directory;
+    /// `None` when the file did not exist or was a symlink
+    backup: Option<PathBuf>,
+    /// Target of the previous symlink, restored as a link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    link: Option<PathBuf>,
+    /// SHA-256 of the new content; `None` when the file is deleted
+    new_sha256: Option<String>,
+}
+
+#[derive(Debug)]
+struct StagedChange {
+    path: PathBuf,
+    /// `None` deletes the file
+    content: Option<Vec<u8>>,
+    permissions: Option<Permissions>,
+}
+
+/// Changes of one patch, applied together by [`PatchTransaction::commit`].
+#[derive(Debug)]
+pub struct PatchTransaction {
+    working_dir: PathBuf,
+    staged: Vec<StagedChange>,
+}
+
+impl PatchTransaction {
+    pub fn new(working_dir: PathBuf) -> Self {
+        Self {
+            working_dir,
+            staged: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    fn staged(&self, path: &Path) -> Option<&StagedChange> {
+        self.staged.iter().find(|change| change.path == path)
+    }
+
+    /// Whether `path` exists once the staged changes are applied.
+    pub fn exists(&self, path: &Path) -> bool {
+        match self.staged(path) {
+            Some(change) => change.content.is_some(),
+            None => self.working_dir.join(path).exists(),
+        }
+    }
+
+    /// Content of `path` once the staged changes are applied.
+    pub fn read(&self, path: &Path) -> DevItResult<Option<Vec<u8>>> {
+        if let Some(change) = self.staged(path) {
+            return Ok(change.content.clone());
+        }
+        let full_path = self.working_dir.join(path);
+        if !full_path.exists() {
+            return Ok(None);
+        }
+        fs::read(&full_path)
+            .map(Some)
+            .map_err(|e| DevItError::io(Some(full_path), "read file", e))
+    }
+
+    /// Permissions of `path` once the staged changes are applied.
+    pub fn permissions(&self, path: &Path) -> Option<Permissions> {
+        match self.staged(path) {
+            Some(change) => change.permissions.clone(),
+            None => fs::metadata(self.working_dir.join(path))
+                .ok()
+                .map(|metadata| metadata.permissions()),
+        }
+    }
+
+    /// Stages new content for `path`; existing files keep their permissions
+    /// unless `permissions` is given.
+    pub fn write(&mut self, path: &Path, content: Vec<u8>, permissions: Option<Permissions>) {
+        self.stage(StagedChange {
+            path: path.to_path_buf(),
+            content: Some(content),
+            permissions,
+        });
+    }
+
+    /// Stages the removal of `path`.
+    pub fn delete(&mut self, path: &Path) {
+        self.stage(StagedChange {
+            path: path.to_path_buf(),
+            content: None,
+            permissions: None,
+        });
+    }
+
+    fn stage(&mut self, change: StagedChange) {
+        match self
+            .staged
+            .iter_mut()
+            .find(|staged| staged.path == change.path)
+        {
+            Some(staged) => *staged = change,
+            None => self.staged.push(change),
+        }
+    }
+
+    /// Applies every staged change; returns the transaction id, or `None`
+    /// when nothing was staged. On error the working tree is left unchanged.
+    pub fn commit(self) -> DevItResult<Option<String>> {
+        if self.staged.is_empty() {
+            return Ok(None);
+        }
+
+        let id = format!(
+            "{}-{}",
+            Utc::now().format("%Y%m%dT%H%M%S%3f"),
+            &Uuid::new_v4().simple().to_string()[..8]
+        );
+        let root = self.working_dir.join(TRANSACTIONS_DIR);
+        let dir = root.join(&id);
+        fs::create_dir_all(&root)
+            .map_err(|e| DevItError::io(Some(root.clone()), "create transaction directory", e))?;
+        // Taken before the directory exists, so recovery never sees it unlocked
+        let _lock = OwnerLock::acquire(&root, &id)?;
+        for sub in ["staged", "backup"] {
+            let path = dir.join(sub);
+            fs::create_dir_all(&path)
+                .map_err(|e| DevItError::io(Some(path), "create transaction directory", e))?;
+        }
+
+        let entries = match self.stage_files(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                let _ = fs::remove_dir_all(&dir);
+                return Err(err);
+            }
+        };
+        let mut manifest = Manifest {
+            id: id.clone(),
+            created_at: Utc::now(),
+            state: TransactionState::Committing,
+            entries,
+        };
+        if let Err(err) = write_manifest(&dir, &manifest) {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(err);
+        }
+
+        if let Err(err) = self.replace_targets(&dir) {
+            restore(&self.working_dir, &dir, &manifest)?;
+            manifest.state = TransactionState::RolledBack;
+            write_manifest(&dir, &manifest)?;
+            return Err(err);
+        }
+
+        manifest.state = TransactionState::Committed;
+        write_manifest(&dir, &manifest)?;
+        let _ = fs::remove_dir_all(dir.join("staged"));
+        prune(&root);
+        Ok(Some(id))
+    }
+
+    /// Writes the new contents and backs up the files they replace.
+    fn stage_files(&self, dir: &Path) -> DevItResult<Vec<ManifestEntry>> {
+        let mut entries = Vec::with_capacity(self.staged.len());
+        for (index, change) in self.staged.iter().enumerate() {
+            let target = self.working_dir.join(&change.path);
+            let existing = fs::symlink_metadata(&target).ok();
+
+            let (backup, link) = match &existing {
+                Some(metadata) if metadata.file_type().is_symlink() => {
+                    let link = fs::read_link(&target)
+                        .map_err(|e| DevItError::io(Some(target.clone()), "read symlink", e))?;
+                    (None, Some(link))
+                }
+                Some(_) => {
+                    let backup = PathBuf::from("backup").join(index.to_string());
+                    let backup_path = dir.join(&backup);
+                    fs::copy(&target, &backup_path)
+                        .map_err(|e| DevItError::io(Some(target.clone()), "back up file", e))?;
+                    File::open(&backup_path)
+                        .and_then(|file| file.sync_all())
+                        .map_err(|e| DevItError::io(Some(backup_path), "sync backup", e))?;
+                    (Some(backup), None)
+                }
+                None => (None, None),
+            };
+
+            let new_sha256 = match &change.content {
+                Some(content) => {
+                    let staged = dir.join("staged").join(index.to_string());
+                    write_synced(&staged, content)?;
+                    let permissions = change
+                        .permissions
+                        .clone()
+                        .or_else(|| existing.as_ref().map(|metadata| metadata.permissions()));
+                    if let Some(permissions) = permissions {
+                        fs::set_permissions(&staged, permissions).map_err(|e| {
+                            DevItError::io(Some(staged.clone()), "set permissions", e)
+                        })?;
+                    }
+                    Some(sha256(content))
+                }
+                None => None,
+            };
+
+            entries.push(ManifestEntry {
+                path: change.path.clone(),
+                backup,
+                link,
+                new_sha256,
+            });
+        }
+        // Backups must be durable before the manifest points at them
+        for sub in ["staged", "backup"] {
+            sync_dir(&dir.join(sub));
+        }
+        Ok(entries)
+    }
+
+    fn replace_targets(&self, dir: &Path) -> DevItResult<()> {
+        for (index, change) in self.staged.iter().enumerate() {
+            let target = self.working_dir.join(&change.path);
+            if change.content.is_some() {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent).map_err(|e| {
+                        DevItError::io(Some(parent.to_path_buf()), "create parent directories", e)
+                    })?;
+                }
+                let staged = dir.join("staged").join(index.to_string());
+                fs::rename(&staged, &target)
+                    .map_err(|e| DevItError::io(Some(target.clone()), "atomic rename", e))?;
+            } else if fs::symlink_metadata(&target).is_ok() {
+                fs::remove_file(&target)
+                    .map_err(|e| DevItError::io(Some(target.clone()), "delete file", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Restores the backups of transactions interrupted while committing, and
+/// drops transactions that never reached the commit step. Returns the ids of
+/// the restored transactions.
+pub fn recover(working_dir: &Path) -> DevItResult<Vec<String>> {
+    let root = working_dir.join(TRANSACTIONS_DIR);
+    let mut recovered = Vec::new();
+    for dir in transaction_dirs(&root) {
+        let Some(id) = dir.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        // Still being applied (or recovered) by a live process
+        let Some(_lock) = OwnerLock::try_acquire(&root, id)? else {
+            continue;
+        };
+        let manifest = match read_manifest(&dir) {
+            Ok(manifest) => manifest,
+            Err(_) if !dir.join(MANIFEST_FILE).exists() => {
+                // Interrupted while staging: nothing was touched
+                let _ = fs::remove_dir_all(&dir);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if manifest.state != TransactionState::Committing {
+            continue;
+        }
+
+        let mut manifest = manifest;
+        restore(working_dir, &dir, &manifest)?;
+        manifest.state = TransactionState::RolledBack;
+        write_manifest(&dir, &manifest)?;
+        let _ = fs::remove_dir_all(dir.join("staged"));
+        recovered.push(manifest.id);
+    }
+    Ok(recovered)
+}
+
+/// Undoes a committed transaction. Refuses when a file it wrote has changed
+/// since (or a file it deleted reappeared), or while another process holds
+/// the transaction's lock. Returns the restored paths.
+pub fn rollback(working_dir: &Path, id: &str) -> DevItResult<Vec<PathBuf>> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(DevItError::InvalidFormat {
+            format: id.to_string(),
+            supported: vec!["transaction id (e.g. 20250101T120000000-1a2b3c4d)".to_string()],
+        });
+    }
+
+    let root = working_dir.join(TRANSACTIONS_DIR);
+    let dir = root.join(id);
+    if !dir.is_dir() {
+        return Err(DevItError::io(
+            Some(dir),
+            "read transaction",
+            std::io::Error::from(std::io::ErrorKind::NotFound),
+        ));
+    }
+    // Excludes a commit or recovery of the same transaction
+    let _lock = OwnerLock::acquire(&root, id)?;
+    let mut manifest = read_manifest(&dir)?;
+    if manifest.state != TransactionState::Committed {
+        return Err(DevItError::VcsConflict {
+            location: id.to_string(),
+            conflict_type: "transaction_not_committed".to_string(),
+            conflicted_files: Vec::new(),
+            resolution_hint: Some(format!(
+                "Transaction is {:?}; only committed transactions can be rolled back",
+                manifest.state
+            )),
+        });
+    }
+
+    let changed: Vec<PathBuf> = manifest
+        .entries
+        .iter()
+        .filter(|entry| {
+            let current = fs::read(working_dir.join(&entry.path)).ok();
+            current.map(|content| sha256(&content)) != entry.new_sha256
+        })
+        .map(|entry| entry.path.clone())
+        .collect();
+    if !changed.is_empty() {
+        return Err(DevItError::VcsConflict {
+            location: id.to_string(),
+            conflict_type: "rollback_conflict".to_string(),
+            resolution_hint: Some(format!(
+                "Modified since the patch was applied: {}",
+                changed
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            conflicted_files: changed,
+        });
+    }
+
+    restore(working_dir, &dir, &manifest)?;
+    manifest.state = TransactionState::RolledBack;
+    write_manifest(&dir, &manifest)?;
+    Ok(manifest
+        .entries
+        .into_iter()
+        .map(|entry| entry.path)
+        .collect())
+}
+
+/// Puts every target back in its pre-transaction state. Idempotent, so an
+/// interrupted restore can simply be run again.
+fn restore(working_dir: &Path, dir: &Path, manifest: &Manifest) -> DevItResult<()> {
+    for entry in manifest.entries.iter().rev() {
+        let target = working_dir.join(&entry.path);
+        if entry.backup.is_some() || entry.link.is_some() {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| {
+                    DevItError::io(Some(parent.to_path_buf()), "create parent directories", e)
+                })?;
+            }
+        }
+        match (&entry.backup, &entry.link) {
+            (Some(backup), _) => {
+                // Copy then rename, so the backup survives an interrupted restore
+                let temp = dir.join("restore.tmp");
+                fs::copy(dir.join(backup), &temp)
+                    .map_err(|e| DevItError::io(Some(temp.clone()), "copy backup", e))?;
+                fs::rename(&temp, &target)
+                    .map_err(|e| DevItError::io(Some(target.clone()), "restore backup", e))?;
+            }
+            (None, Some(link)) => {
+                let temp = dir.join("restore.tmp");
+                let _ = fs::remove_file(&temp);
+                create_symlink(link, &temp)
+                    .map_err(|e| DevItError::io(Some(temp.clone()), "recreate symlink", e))?;
+                fs::rename(&temp, &target)
+                    .map_err(|e| DevItError::io(Some(target.clone()), "restore symlink", e))?;
+            }
+            (None, None) => {
+                if fs::symlink_metadata(&target).is_ok() {
+                    fs::remove_file(&target)
+                        .map_err(|e| DevItError::io(Some(target.clone()), "remove new file", e))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Transaction directories, oldest first (ids start with a timestamp).
+fn transaction_dirs(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Drops the oldest finished transactions beyond [`MAX_KEPT_TRANSACTIONS`].
+fn prune(root: &Path) {
+    let finished: Vec<PathBuf> = transaction_dirs(root)
+        .into_iter()
+        .filter(|dir| {
+            read_manifest(dir)
+                .map(|manifest| manifest.state != TransactionState::Committing)
+                .unwrap_or(false)
+        })
+        .collect();
+    let excess = finished.len().saturating_sub(MAX_KEPT_TRANSACTIONS);
+    for dir in &finished[..excess] {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+fn read_manifest(dir: &Path) -> DevItResult<Manifest> {
+    let path = dir.join(MANIFEST_FILE);
+    let raw =
+        fs::read(&path).map_err(|e| DevItError::io(Some(path.clone()), "read manifest", e))?;
+    serde_json::from_slice(&raw).map_err(|e| {
+        DevItError::io(
+            Some(path),
+            "parse manifest",
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        )
+    })
+}
+
+fn write_manifest(dir: &Path, manifest: &Manifest) -> DevItResult<()> {
+    let path = dir.join(MANIFEST_FILE);
+    let temp = dir.join("manifest.json.tmp");
+    let raw = serde_json::to_vec_pretty(manifest).map_err(|e| {
+        DevItError::io(
+            Some(path.clone()),
+            "serialize manifest",
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        )
+    })?;
+    write_synced(&temp, &raw)?;
+    fs::rename(&temp, &path).map_err(|e| DevItError::io(Some(path), "write manifest", e))?;
+    // Make the rename itself durable
+    sync_dir(dir);
+    Ok(())
+}
+
+/// Best effort: directories cannot be opened for syncing on every platform.
+fn sync_dir(dir: &Path) {
+    if let Ok(dir_handle) = File::open(dir) {
+        let _ = dir_handle.sync_all();
+    }
+}
+
+#[cfg(unix)]
+fn create_symlink(link: &Path, path: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(link, path)
+}
+
+#[cfg(windows)]
+fn create_symlink(link: &Path, path: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(link, path)
+}
+
+/// Exclusive lock on `<id>.lock` next to a transaction directory, recording
+/// the owner's pid. The OS releases it if the owner dies; the file is removed
+/// when the lock is dropped.
+struct OwnerLock {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl OwnerLock {
+    fn acquire(root: &Path, id: &str) -> DevItResult<Self> {
+        Self::try_acquire(root, id)?.ok_or_else(|| {
+            DevItError::io(
+                Some(root.join(format!("{}.lock", id))),
+                "lock transaction",
+                std::io::Error::new(std::io::ErrorKind::WouldBlock, "already locked"),
+            )
+        })
+    }
+
+    /// `None` when another process holds the lock.
+    fn try_acquire(root: &Path, id: &str) -> DevItResult<Option<Self>> {
+        let path = root.join(format!("{}.lock", id));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| DevItError::io(Some(path.clone()), "open transaction lock", e))?;
+        if file.try_lock_exclusive().is_err() {
+            return Ok(None);
+        }
+        file.set_len(0)
+            .and_then(|_| writeln!(file, "{}", std::process::id()))
+            .map_err(|e| DevItError::io(Some(path.clone()), "write transaction lock", e))?;
+        Ok(Some(Self {
+            path,
+            file: Some(file),
+        }))
+    }
+}
+
+impl Drop for OwnerLock {
+    fn drop(&mut self) {
+        // Closed first: an open file cannot be removed on Windows
+        drop(self.file.take());
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn write_synced(path: &Path, content: &[u8]) -> DevItResult<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .map_err(|e| DevItError::io(Some(path.to_path_buf()), "create temp file", e))?;
+    file.write_all(content)
+        .map_err(|e| DevItError::io(Some(path.to_path_buf()), "write to temp file", e))?;
+    file.sync_all()
+        .map_err(|e| DevItError::io(Some(path.to_path_buf()), "sync temp file", e))
+}
+
+fn sha256(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn transaction_dir(root: &Path, id: &str) -> PathBuf {
+        root.join(TRANSACTIONS_DIR).join(id)
+    }
+
+    #[test]
+    fn commit_applies_all_changes_and_rollback_undoes_them() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "a\n").unwrap();
+        fs::write(root.join("gone.txt"), "gone\n").unwrap();
+
+        let mut transaction = PatchTransaction::new(root.to_path_buf());
+        transaction.write(Path::new("a.txt"), b"A\n".to_vec(), None);
+        transaction.write(Path::new("sub/new.txt"), b"new\n".to_vec(), None);
+        transaction.delete(Path::new("gone.txt"));
+        assert!(!transaction.exists(Path::new("gone.txt")));
+        assert_eq!(
+            transaction.read(Path::new("sub/new.txt")).unwrap(),
+            Some(b"new\n".to_vec())
+        );
+        // Nothing touches the tree before the commit
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "a\n");
+
+        let id = transaction.commit().unwrap().unwrap();
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "A\n");
+        assert_eq!(
+            fs::read_to_string(root.join("sub/new.txt")).unwrap(),
+            "new\n"
+        );
+        assert!(!root.join("gone.txt").exists());
+        assert_eq!(
+            read_manifest(&transaction_dir(root, &id)).unwrap().state,
+            TransactionState::Committed
+        );
+
+        let restored = rollback(root, &id).unwrap();
+        assert_eq!(restored.len(), 3);
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "a\n");
+        assert_eq!(fs::read_to_string(root.join("gone.txt")).unwrap(), "gone\n");
+        assert!(!root.join("sub/new.txt").exists());
+
+        // A second rollback is refused
+        assert!(matches!(
+            rollback(root, &id),
+            Err(DevItError::VcsConflict { ref conflict_type, .. })
+                if conflict_type == "transaction_not_committed"
+        ));
+    }
+
+    #[test]
+    fn rollback_refuses_files_changed_since_commit() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "a\n").unwrap();
+
+        let mut transaction = PatchTransaction::new(root.to_path_buf());
+        transaction.write(Path::new("a.txt"), b"A\n".to_vec(), None);
+        let id = transaction.commit().unwrap().unwrap();
+        fs::write(root.join("a.txt"), "edited by hand\n").unwrap();
+
+        let err = rollback(root, &id).unwrap_err();
+        assert!(matches!(
+            err,
+            DevItError::VcsConflict { ref conflict_type, ref conflicted_files, .. }
+                if conflict_type == "rollback_conflict"
+                    && conflicted_files == &vec![PathBuf::from("a.txt")]
+        ));
+        assert_eq!(
+            fs::read_to_string(root.join("a.txt")).unwrap(),
+            "edited by hand\n"
+        );
+        assert!(rollback(root, "../etc").is_err());
+    }
+
+    #[test]
+    fn recover_restores_interrupted_commits() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "a\n").unwrap();
+
+        let mut transaction = PatchTransaction::new(root.to_path_buf());
+        transaction.write(Path::new("a.txt"), b"A\n".to_vec(), None);
+        transaction.write(Path::new("b.txt"), b"B\n".to_vec(), None);
+        let id = transaction.commit().unwrap().unwrap();
+
+        // Simulate a crash after the renames, before the commit was recorded
+        let tx_dir = transaction_dir(root, &id);
+        let mut manifest = read_manifest(&tx_dir).unwrap();
+        manifest.state = TransactionState::Committing;
+        write_manifest(&tx_dir, &manifest).unwrap();
+        // and a transaction that died while staging
+        fs::create_dir_all(transaction_dir(root, "00000000T000000000-deadbeef/staged")).unwrap();
+
+        assert_eq!(recover(root).unwrap(), vec![id.clone()]);
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "a\n");
+        assert!(!root.join("b.txt").exists());
+        assert!(!transaction_dir(root, "00000000T000000000-deadbeef").exists());
+        assert_eq!(
+            read_manifest(&tx_dir).unwrap().state,
+            TransactionState::RolledBack
+        );
+        assert!(recover(root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn recover_skips_transactions_still_locked() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "a\n").unwrap();
+
+        let mut transaction = PatchTransaction::new(root.to_path_buf());
+        transaction.write(Path::new("a.txt"), b"A\n".to_vec(), None);
+        let id = transaction.commit().unwrap().unwrap();
+        assert!(!root
+            .join(TRANSACTIONS_DIR)
+            .join(format!("{}.lock", id))
+            .exists());
+
+        // Another process is still committing it
+        let tx_dir = transaction_dir(root, &id);
+        let mut manifest = read_manifest(&tx_dir).unwrap();
+        manifest.state = TransactionState::Committing;
+        write_manifest(&tx_dir, &manifest).unwrap();
+        let lock = OwnerLock::acquire(&root.join(TRANSACTIONS_DIR), &id).unwrap();
+        let recorded =
+            fs::read_to_string(root.join(TRANSACTIONS_DIR).join(format!("{}.lock", id))).unwrap();
+        assert_eq!(recorded.trim(), std::process::id().to_string());
+
+        assert!(recover(root).unwrap().is_empty());
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "A\n");
+
+        // Once the owner is gone the transaction is recovered
+        drop(lock);
+        assert_eq!(recover(root).unwrap(), vec![id]);
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "a\n");
+    }
+
+    #[test]
+    fn rollback_refuses_transactions_still_locked() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "a\n").unwrap();
+
+        let mut transaction = PatchTransaction::new(root.to_path_buf());
+        transaction.write(Path::new("a.txt"), b"A\n".to_vec(), None);
+        let id = transaction.commit().unwrap().unwrap();
+
+        // Another process is recovering it
+        let lock = OwnerLock::acquire(&root.join(TRANSACTIONS_DIR), &id).unwrap();
+        assert!(rollback(root, &id).is_err());
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "A\n");
+        assert_eq!(
+            read_manifest(&transaction_dir(root, &id)).unwrap().state,
+            TransactionState::Committed
+        );
+
+        drop(lock);
+        assert_eq!(rollback(root, &id).unwrap(), vec![PathBuf::from("a.txt")]);
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "a\n");
+        assert!(!root
+            .join(TRANSACTIONS_DIR)
+            .join(format!("{}.lock", id))
+            .exists());
+        assert!(rollback(root, "20250101T120000000-00000000").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_restored_as_links() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "a\n").unwrap();
+        std::os::unix::fs::symlink("a.txt", root.join("link.txt")).unwrap();
+
+        let mut transaction = PatchTransaction::new(root.to_path_buf());
+        transaction.delete(Path::new("link.txt"));
+        let id = transaction.commit().unwrap().unwrap();
+        assert!(fs::symlink_metadata(root.join("link.txt")).is_err());
+
+        rollback(root, &id).unwrap();
+        let metadata = fs::symlink_metadata(root.join("link.txt")).unwrap();
+        assert!(metadata.file_type().is_symlink());
+        assert_eq!(
+            fs::read_link(root.join("link.txt")).unwrap(),
+            PathBuf::from("a.txt")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_survive_the_commit() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("run.sh"), "echo a\n").unwrap();
+        fs::set_permissions(root.join("run.sh"), Permissions::from_mode(0o755)).unwrap();
+
+        let mut transaction = PatchTransaction::new(root.to_path_buf());
+        transaction.write(Path::new("run.sh"), b"echo b\n".to_vec(), None);
+        transaction.commit().unwrap();
+
+        let mode = fs::metadata(root.join("run.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+}
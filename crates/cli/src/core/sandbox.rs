@@ -1,8 +1,10 @@
 //! Sandbox planning helpers.
 //!
 //! Defines high-level sandbox profiles and provides serializable plans that
-//! callers can feed into lower-level runtimes (bwrap, firejail, …). The
-//! implementation intentionally avoids performing any process execution.
+//! callers can feed into lower-level runtimes. The plan types live in
+//! `devit-common` so the MCP tools and the daemon can share them; execution is
+//! handled by the namespace backend in `devit-sandbox` (with `bwrap` as a
+//! fallback when user namespaces are unavailable).
 //!
 //! ## Prompt 9 Implementation
 //!
@@ -10,114 +12,14 @@
 //! - SandboxPlan with bind_ro, bind_rw, net, seccomp_profile fields
 //! - plan_for_apply: strict repo RW, everything else RO, net=false
 //! - plan_for_test: strict repo RW + tmp RW, permissive adds net=true
-//! - No bwrap execution, just plan construction
-
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
 
+pub use devit_common::sandbox::{IsolationMode, SandboxPlan, SYSTEM_RO_PATHS};
 pub use devit_common::SandboxProfile;
 
-/// Standard read-only system paths for sandbox environments
-const SYSTEM_RO_PATHS: &[&str] = &["/usr", "/bin", "/lib", "/lib64", "/etc", "/opt"];
-
-/// Serializable sandbox plan capturing bind mounts, network access, and seccomp
-/// configuration that should be applied by sandbox backends.
-///
-/// This structure follows the exact specification from Prompt 9 with fields:
-/// bind_ro, bind_rw, net, seccomp_profile.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct SandboxPlan {
-    /// Paths to mount read-only inside the sandbox
-    pub bind_ro: Vec<PathBuf>,
-    /// Paths that remain writable for the confined process
-    pub bind_rw: Vec<PathBuf>,
-    /// Whether outbound network access is permitted
-    pub net: bool,
-    /// Optional seccomp profile name for syscall filtering
-    pub seccomp_profile: Option<String>,
-}
-
-impl SandboxPlan {
-    /// Generates a sandbox plan for patch application workflows.
-    ///
-    /// **Strict profile**: Repository RW, everything else RO, net=false
-    /// - bind_ro: System paths (/usr, /bin, /lib, etc.)
-    /// - bind_rw: Repository root only
-    /// - net: false (no network access)
-    /// - seccomp_profile: "strict" for syscall filtering
-    ///
-    /// # Arguments
-    /// * `repo_root` - Path to the repository root directory
-    /// * `profile` - Sandbox profile (Strict or Permissive)
-    ///
-    /// # Returns
-    /// Configured SandboxPlan for apply operations
-    pub fn plan_for_apply(repo_root: PathBuf, profile: SandboxProfile) -> Self {
-        let mut bind_ro = SYSTEM_RO_PATHS
-            .iter()
-            .map(PathBuf::from)
-            .collect::<Vec<_>>();
-        let bind_rw = vec![repo_root];
-
-        // For strict profile, add additional RO paths for security
-        if profile == SandboxProfile::Strict {
-            bind_ro.extend([
-                PathBuf::from("/proc"),
-                PathBuf::from("/sys"),
-                PathBuf::from("/dev"),
-            ]);
-        }
-
-        Self {
-            bind_ro,
-            bind_rw,
-            net: false, // No network access for apply operations
-            seccomp_profile: match profile {
-                SandboxProfile::Strict => Some("strict".to_string()),
-                SandboxProfile::Permissive => Some("permissive".to_string()),
-            },
-        }
-    }
-
-    /// Generates a sandbox plan for test execution workflows.
-    ///
-    /// **Strict profile**: Repository RW, tmp RW, net=false
-    /// **Permissive profile**: Repository RW, tmp RW, net=true
-    ///
-    /// # Arguments
-    /// * `repo_root` - Path to the repository root directory
-    /// * `profile` - Sandbox profile determining network access and restrictions
-    ///
-    /// # Returns
-    /// Configured SandboxPlan for test operations
-    pub fn plan_for_test(repo_root: PathBuf, profile: SandboxProfile) -> Self {
-        let bind_ro = SYSTEM_RO_PATHS
-            .iter()
-            .map(PathBuf::from)
-            .collect::<Vec<_>>();
-
-        let mut bind_rw = vec![repo_root, PathBuf::from("/tmp")];
-
-        // Permissive profile gets additional writable paths
-        if profile == SandboxProfile::Permissive {
-            bind_rw.extend([PathBuf::from("/var/tmp"), PathBuf::from("/home")]);
-        }
-
-        Self {
-            bind_ro,
-            bind_rw,
-            net: profile == SandboxProfile::Permissive, // Network only for permissive
-            seccomp_profile: match profile {
-                SandboxProfile::Strict => Some("strict".to_string()),
-                SandboxProfile::Permissive => None, // No seccomp restrictions for permissive
-            },
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
 
     #[test]
     fn plan_for_apply_strict_creates_secure_environment() {
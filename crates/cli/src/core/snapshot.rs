@@ -990,46 +990,7 @@ impl Snapshot {
                     }
                 }
 
-                // Extract content from storage
-                let content = match &snapshot_file.storage {
-                    ContentStorage::Inline { content } => content.clone(),
-                    ContentStorage::Compressed { compressed_content } => {
-                        // Decompress content
-                        use flate2::read::ZlibDecoder;
-                        let mut decoder = ZlibDecoder::new(&compressed_content[..]);
-                        let mut decompressed = Vec::new();
-                        decoder.read_to_end(&mut decompressed).map_err(|e| {
-                            DevItError::io(Some(target_path.clone()), "decompress file", e)
-                        })?;
-                        decompressed
-                    }
-                    ContentStorage::External { path: ext_path } => {
-                        fs::read(ext_path).map_err(|e| {
-                            DevItError::io(Some(ext_path.clone()), "read external storage", e)
-                        })?
-                    }
-                    ContentStorage::Deduplicated { reference_hash } => {
-                        // Find file with this hash
-                        let mut found_content = None;
-                        for file in self.files.values() {
-                            if file.content_hash == *reference_hash {
-                                found_content = Some(match &file.storage {
-                                    ContentStorage::Inline { content } => content.clone(),
-                                    _ => continue,
-                                });
-                                break;
-                            }
-                        }
-                        found_content.ok_or_else(|| DevItError::SnapshotStale {
-                            snapshot_id: self.id.0.clone(),
-                            created_at: None,
-                            staleness_reason: Some(format!(
-                                "Dedup reference not found: {}",
-                                reference_hash
-                            )),
-                        })?
-                    }
-                };
+                let content = self.read_content(rel_path, snapshot_file)?;
 
                 // Write file
                 fs::write(&target_path, &content)
@@ -1057,6 +1018,57 @@ impl Snapshot {
         Ok(restored_files)
     }
 
+    /// Content of a file as stored in the snapshot, `None` if the snapshot
+    /// does not contain it.
+    pub fn file_content(&self, rel_path: &Path) -> DevItResult<Option<Vec<u8>>> {
+        match self.files.get(rel_path) {
+            Some(snapshot_file) => self.read_content(rel_path, snapshot_file).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Extracts the content of a snapshot file from its storage.
+    fn read_content(&self, rel_path: &Path, snapshot_file: &SnapshotFile) -> DevItResult<Vec<u8>> {
+        let target_path = self.root_path.join(rel_path);
+        let content = match &snapshot_file.storage {
+            ContentStorage::Inline { content } => content.clone(),
+            ContentStorage::Compressed { compressed_content } => {
+                // Decompress content
+                use flate2::read::ZlibDecoder;
+                let mut decoder = ZlibDecoder::new(&compressed_content[..]);
+                let mut decompressed = Vec::new();
+                decoder
+                    .read_to_end(&mut decompressed)
+                    .map_err(|e| DevItError::io(Some(target_path.clone()), "decompress file", e))?;
+                decompressed
+            }
+            ContentStorage::External { path: ext_path } => fs::read(ext_path)
+                .map_err(|e| DevItError::io(Some(ext_path.clone()), "read external storage", e))?,
+            ContentStorage::Deduplicated { reference_hash } => {
+                // Find file with this hash
+                let mut found_content = None;
+                for file in self.files.values() {
+                    if file.content_hash == *reference_hash {
+                        found_content = Some(match &file.storage {
+                            ContentStorage::Inline { content } => content.clone(),
+                            _ => continue,
+                        });
+                        break;
+                    }
+                }
+                found_content.ok_or_else(|| DevItError::SnapshotStale {
+                    snapshot_id: self.id.0.clone(),
+                    created_at: None,
+                    staleness_reason: Some(format!(
+                        "Dedup reference not found: {}",
+                        reference_hash
+                    )),
+                })?
+            }
+        };
+        Ok(content)
+    }
+
     /// Calculates the size of the snapshot in bytes.
     ///
     /// # Returns
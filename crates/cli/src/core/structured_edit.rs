@@ -0,0 +1,777 @@
+//! Structured edit operations, an alternative to hand-written unified diffs.
+//!
+//! Edits are resolved against the working directory and compiled into a
+//! git-style unified diff, which then goes through the regular patch
+//! pipeline: `classify_changes` gives the same `FileChange` set as for a diff,
+//! so policy evaluation, snapshots, the journal and patch transactions apply
+//! unchanged.
+
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::errors::{DevItError, DevItResult};
+
+/// Unchanged lines shown around each change of the generated diff
+const CONTEXT_LINES: usize = 3;
+
+/// Above this many line pairs a region is split before building its LCS table
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Minimum length of an `expected_sha256` prefix
+const MIN_HASH_PREFIX: usize = 8;
+
+/// One edit; edits apply in order, later edits see the result of earlier ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum EditOperation {
+    /// Replaces `search` by `replace`. `search` must occur exactly once,
+    /// unless `all` replaces every occurrence.
+    SearchReplace {
+        path: PathBuf,
+        search: String,
+        replace: String,
+        #[serde(default)]
+        all: bool,
+    },
+    /// Inserts `content` after the line holding `anchor`, which must occur
+    /// exactly once.
+    InsertAfter {
+        path: PathBuf,
+        anchor: String,
+        content: String,
+    },
+    /// Replaces lines `start_line..=end_line` (1-based). `expected_sha256`
+    /// (or a prefix of at least 8 characters) must be the SHA-256 of the
+    /// current lines, each followed by `\n` (`sed -n 'S,Ep' file | sha256sum`).
+    ReplaceLines {
+        path: PathBuf,
+        start_line: usize,
+        end_line: usize,
+        expected_sha256: String,
+        content: String,
+    },
+    /// Creates a file that does not exist yet.
+    CreateFile { path: PathBuf, content: String },
+    /// Deletes an existing file.
+    DeleteFile { path: PathBuf },
+}
+
+impl EditOperation {
+    pub fn path(&self) -> &Path {
+        match self {
+            EditOperation::SearchReplace { path, .. }
+            | EditOperation::InsertAfter { path, .. }
+            | EditOperation::ReplaceLines { path, .. }
+            | EditOperation::CreateFile { path, .. }
+            | EditOperation::DeleteFile { path } => path,
+        }
+    }
+}
+
+/// Content of one file before and after the edits.
+struct EditedFile {
+    path: PathBuf,
+    original: Option<String>,
+    current: Option<String>,
+}
+
+/// Applies `edits` in memory against `working_dir` and returns the unified
+/// diff producing the same result.
+pub fn edits_to_diff(working_dir: &Path, edits: &[EditOperation]) -> DevItResult<String> {
+    if edits.is_empty() {
+        return Err(DevItError::InvalidDiff {
+            reason: "No edits provided".to_string(),
+            line_number: None,
+        });
+    }
+
+    let mut files: Vec<EditedFile> = Vec::new();
+    for (index, edit) in edits.iter().enumerate() {
+        let path = validate_path(edit.path())?;
+        let position = match files.iter().position(|file| file.path == path) {
+            Some(position) => position,
+            None => {
+                let original = read_text(&working_dir.join(&path))?;
+                files.push(EditedFile {
+                    path,
+                    current: original.clone(),
+                    original,
+                });
+                files.len() - 1
+            }
+        };
+        let file = &mut files[position];
+        file.current = apply_edit(index + 1, edit, file.current.take())?;
+    }
+
+    let diff: String = files
+        .iter()
+        .filter(|file| file.original != file.current)
+        .map(|file| {
+            file_diff(
+                &file.path,
+                file.original.as_deref(),
+                file.current.as_deref(),
+            )
+        })
+        .collect();
+    if diff.is_empty() {
+        return Err(DevItError::InvalidDiff {
+            reason: "Edits do not change any file".to_string(),
+            line_number: None,
+        });
+    }
+    Ok(diff)
+}
+
+/// SHA-256 expected by [`EditOperation::ReplaceLines`] for `lines`.
+pub fn lines_sha256<'a>(lines: impl IntoIterator<Item = &'a str>) -> String {
+    let mut hasher = Sha256::new();
+    for line in lines {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Checks `path` and drops its `.` components, so that `./a` and `a` name
+/// the same file.
+fn validate_path(path: &Path) -> DevItResult<PathBuf> {
+    let normalized: PathBuf = path
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect();
+    let rule = if path.is_absolute() {
+        "no_absolute_paths"
+    } else if path
+        .components()
+        .any(|component| matches!(component, Component::ParentDir))
+    {
+        "no_path_traversal"
+    } else if normalized
+        .to_str()
+        .is_none_or(|name| name.is_empty() || name.contains(['\n', '\t', '"']))
+    {
+        "unsupported_file_name"
+    } else {
+        return Ok(normalized);
+    };
+    Err(DevItError::ProtectedPath {
+        path: path.to_path_buf(),
+        protection_rule: rule.to_string(),
+        attempted_operation: "edit_apply".to_string(),
+    })
+}
+
+fn read_text(path: &Path) -> DevItResult<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = std::fs::read(path)
+        .map_err(|e| DevItError::io(Some(path.to_path_buf()), "read file", e))?;
+    String::from_utf8(bytes).map(Some).map_err(|e| {
+        DevItError::io(
+            Some(path.to_path_buf()),
+            "read text file",
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        )
+    })
+}
+
+fn conflict(path: &Path, index: usize, conflict_type: &str, hint: String) -> DevItError {
+    DevItError::VcsConflict {
+        location: format!("{} (edit #{})", path.display(), index),
+        conflict_type: conflict_type.to_string(),
+        conflicted_files: vec![path.to_path_buf()],
+        resolution_hint: Some(hint),
+    }
+}
+
+/// Single occurrence of `needle` in `text`, or the conflict to report.
+fn unique_match(
+    text: &str,
+    needle: &str,
+    path: &Path,
+    index: usize,
+    what: &str,
+) -> DevItResult<usize> {
+    if needle.is_empty() {
+        return Err(DevItError::InvalidDiff {
+            reason: format!("Edit #{}: {} text is empty", index, what),
+            line_number: None,
+        });
+    }
+    let mut matches = text.match_indices(needle).map(|(position, _)| position);
+    match (matches.next(), matches.count()) {
+        (Some(position), 0) => Ok(position),
+        (None, _) => Err(conflict(
+            path,
+            index,
+            &format!("{}_not_found", what),
+            format!("{} text not found; re-read the file", what),
+        )),
+        (Some(_), others) => Err(conflict(
+            path,
+            index,
+            &format!("{}_ambiguous", what),
+            format!(
+                "{} text occurs {} times; include more surrounding lines",
+                what,
+                others + 1
+            ),
+        )),
+    }
+}
+
+fn apply_edit(
+    index: usize,
+    edit: &EditOperation,
+    current: Option<String>,
+) -> DevItResult<Option<String>> {
+    let path = edit.path();
+    let existing = |current: Option<String>| {
+        current.ok_or_else(|| {
+            conflict(
+                path,
+                index,
+                "file_missing",
+                "File does not exist; use create_file".to_string(),
+            )
+        })
+    };
+
+    match edit {
+        EditOperation::SearchReplace {
+            search,
+            replace,
+            all,
+            ..
+        } => {
+            let text = existing(current)?;
+            if *all {
+                if search.is_empty() || !text.contains(search.as_str()) {
+                    unique_match(&text, search, path, index, "search")?;
+                }
+                Ok(Some(text.replace(search.as_str(), replace)))
+            } else {
+                let position = unique_match(&text, search, path, index, "search")?;
+                let mut text = text;
+                text.replace_range(position..position + search.len(), replace);
+                Ok(Some(text))
+            }
+        }
+        EditOperation::InsertAfter {
+            anchor, content, ..
+        } => {
+            let mut text = existing(current)?;
+            let position = unique_match(&text, anchor, path, index, "anchor")?;
+            let anchor_end = position + anchor.len();
+            // After the line holding the end of the anchor
+            let line_end = if anchor.ends_with('\n') {
+                anchor_end
+            } else {
+                text[anchor_end..]
+                    .find('\n')
+                    .map_or(text.len(), |offset| anchor_end + offset + 1)
+            };
+            let mut insertion = String::new();
+            if line_end == text.len() && !text.is_empty() && !text.ends_with('\n') {
+                insertion.push('\n');
+            }
+            insertion.push_str(content);
+            if !content.is_empty() && !content.ends_with('\n') && line_end < text.len() {
+                insertion.push('\n');
+            }
+            text.insert_str(line_end, &insertion);
+            Ok(Some(text))
+        }
+        EditOperation::ReplaceLines {
+            start_line,
+            end_line,
+            expected_sha256,
+            content,
+            ..
+        } => {
+            let text = existing(current)?;
+            let lines: Vec<&str> = text.split_inclusive('\n').collect();
+            if *start_line == 0 || start_line > end_line || *end_line > lines.len() {
+                return Err(conflict(
+                    path,
+                    index,
+                    "line_range_out_of_bounds",
+                    format!(
+                        "Lines {}-{} are outside the file ({} lines)",
+                        start_line,
+                        end_line,
+                        lines.len()
+                    ),
+                ));
+            }
+
+            let range = &lines[start_line - 1..*end_line];
+            let actual = lines_sha256(range.iter().map(|line| line.trim_end_matches('\n')));
+            let expected = expected_sha256.trim().to_ascii_lowercase();
+            if expected.len() < MIN_HASH_PREFIX || !actual.starts_with(&expected) {
+                return Err(conflict(
+                    path,
+                    index,
+                    "hash_mismatch",
+                    format!(
+                        "Lines {}-{} changed since they were read (sha256 is {}); re-read them",
+                        start_line, end_line, actual
+                    ),
+                ));
+            }
+
+            let mut replaced: String = lines[..start_line - 1].concat();
+            replaced.push_str(content);
+            let at_end = *end_line == lines.len();
+            if !content.is_empty() && !content.ends_with('\n') && (!at_end || text.ends_with('\n'))
+            {
+                replaced.push('\n');
+            }
+            replaced.push_str(&lines[*end_line..].concat());
+            Ok(Some(replaced))
+        }
+        EditOperation::CreateFile { content, .. } => match current {
+            Some(_) => Err(conflict(
+                path,
+                index,
+                "target_exists",
+                "File already exists; edit it instead".to_string(),
+            )),
+            None => Ok(Some(content.clone())),
+        },
+        EditOperation::DeleteFile { .. } => {
+            existing(current)?;
+            Ok(None)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LineOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Line operations turning `old` into `new`: common prefix and suffix, and a
+/// longest common subsequence in between. Regions too large for the LCS table
+/// are first split along the middle snake of Myers' diff.
+pub(crate) fn diff_lines(old: &[&str], new: &[&str]) -> Vec<LineOp> {
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    push_diff(old, new, MAX_DIFF_CELLS, &mut ops);
+    ops
+}
+
+fn push_diff(old: &[&str], new: &[&str], max_cells: usize, ops: &mut Vec<LineOp>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    ops.extend(std::iter::repeat_n(LineOp::Equal, prefix));
+    if old_mid.is_empty() || new_mid.is_empty() {
+        ops.extend(std::iter::repeat_n(LineOp::Delete, old_mid.len()));
+        ops.extend(std::iter::repeat_n(LineOp::Insert, new_mid.len()));
+    } else if old_mid.len().saturating_mul(new_mid.len()) > max_cells {
+        let (x, y) = middle_snake(old_mid, new_mid);
+        push_diff(&old_mid[..x], &new_mid[..y], max_cells, ops);
+        push_diff(&old_mid[x..], &new_mid[y..], max_cells, ops);
+    } else {
+        // lcs[i][j]: common lines of old_mid[i..] and new_mid[j..]
+        let width = new_mid.len() + 1;
+        let mut lcs = vec![0usize; (old_mid.len() + 1) * width];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old_mid.len() || j < new_mid.len() {
+            if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+                ops.push(LineOp::Equal);
+                i += 1;
+                j += 1;
+            } else if j == new_mid.len()
+                || (i < old_mid.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                ops.push(LineOp::Delete);
+                i += 1;
+            } else {
+                ops.push(LineOp::Insert);
+                j += 1;
+            }
+        }
+    }
+    ops.extend(std::iter::repeat_n(LineOp::Equal, suffix));
+}
+
+/// Point `(x, y)` of a shortest edit script from `old` to `new` found by
+/// running Myers' search from both ends until the paths meet, in linear
+/// space. `old` and `new` must be non-empty and differ at both ends, so that
+/// the point is neither `(0, 0)` nor the end.
+fn middle_snake(old: &[&str], new: &[&str]) -> (usize, usize) {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    // Furthest x reached on each diagonal k = x - y, from the start and
+    // (counted from the end) from the end
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let furthest = |v: &[isize], d: isize, k: isize| {
+        let index = (k + offset) as usize;
+        if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+            v[index + 1]
+        } else {
+            v[index - 1] + 1
+        }
+    };
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let start = furthest(&forward, d, k);
+            let mut x = start;
+            while x < n && x - k < m && old[x as usize] == new[(x - k) as usize] {
+                x += 1;
+            }
+            forward[(k + offset) as usize] = x;
+            let back = delta - k;
+            if odd && (1 - d..d).contains(&back) && x + backward[(back + offset) as usize] >= n {
+                return (start as usize, (start - k) as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = furthest(&backward, d, k);
+            while x < n && x - k < m && old[(n - x - 1) as usize] == new[(m - x + k - 1) as usize] {
+                x += 1;
+            }
+            backward[(k + offset) as usize] = x;
+            let fwd = delta - k;
+            if !odd && (-d..=d).contains(&fwd) && x + forward[(fwd + offset) as usize] >= n {
+                return ((n - x) as usize, (m - x + k) as usize);
+            }
+        }
+    }
+    unreachable!("Myers paths always meet within (n + m + 1) / 2 steps")
+}
+
+/// `diff --git` section for one file; `None` contents mean the file is
+/// absent on that side.
+fn file_diff(path: &Path, old: Option<&str>, new: Option<&str>) -> String {
+    let name = path.to_string_lossy();
+    let mut out = format!("diff --git a/{name} b/{name}\n");
+    match (old, new) {
+        (None, _) => {
+            out.push_str("new file mode 100644\n");
+            out.push_str(&format!("--- /dev/null\n+++ b/{name}\n"));
+        }
+        (_, None) => {
+            out.push_str("deleted file mode 100644\n");
+            out.push_str(&format!("--- a/{name}\n+++ /dev/null\n"));
+        }
+        _ => out.push_str(&format!("--- a/{name}\n+++ b/{name}\n")),
+    }
+
+    let old_lines: Vec<&str> = old.unwrap_or_default().split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.unwrap_or_default().split_inclusive('\n').collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+
+    // Old and new line indexes before each operation
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for op in &ops {
+        positions.push((old_pos, new_pos));
+        match op {
+            LineOp::Equal => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            LineOp::Delete => old_pos += 1,
+            LineOp::Insert => new_pos += 1,
+        }
+    }
+    positions.push((old_pos, new_pos));
+
+    let changes: Vec<usize> = (0..ops.len())
+        .filter(|&index| ops[index] != LineOp::Equal)
+        .collect();
+    let mut next = 0;
+    while next < changes.len() {
+        // Changes separated by at most 2 * CONTEXT_LINES share a hunk
+        let mut last = next;
+        while last + 1 < changes.len() && changes[last + 1] - changes[last] <= 2 * CONTEXT_LINES + 1
+        {
+            last += 1;
+        }
+        let start = changes[next].saturating_sub(CONTEXT_LINES);
+        let end = (changes[last] + CONTEXT_LINES + 1).min(ops.len());
+        next = last + 1;
+
+        let (old_start, new_start) = positions[start];
+        let old_count = positions[end].0 - old_start;
+        let new_count = positions[end].1 - new_start;
+        let header_start = |start: usize, count: usize| if count == 0 { start } else { start + 1 };
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            header_start(old_start, old_count),
+            old_count,
+            header_start(new_start, new_count),
+            new_count
+        ));
+
+        for index in start..end {
+            let (old_index, new_index) = positions[index];
+            let (marker, line) = match ops[index] {
+                LineOp::Equal => (' ', old_lines[old_index]),
+                LineOp::Delete => ('-', old_lines[old_index]),
+                LineOp::Insert => ('+', new_lines[new_index]),
+            };
+            let text = line.strip_suffix('\n').unwrap_or(line);
+            out.push(marker);
+            out.push_str(text.strip_suffix('\r').unwrap_or(text));
+            out.push('\n');
+            if !line.ends_with('\n') {
+                out.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::atomic_patcher::AtomicPatcher;
+    use tempfile::tempdir;
+
+    fn numbered(count: usize) -> String {
+        (1..=count).map(|n| format!("line {n}\n")).collect()
+    }
+
+    /// Generates the diff, applies it and returns the resulting files.
+    fn apply(files: &[(&str, &str)], edits: &[EditOperation]) -> DevItResult<Vec<Option<String>>> {
+        let dir = tempdir().unwrap();
+        for (path, content) in files {
+            std::fs::write(dir.path().join(path), content).unwrap();
+        }
+        let diff = edits_to_diff(dir.path(), edits)?;
+        AtomicPatcher::new(dir.path().to_path_buf(), false).apply_patch(&diff)?;
+        Ok(edits
+            .iter()
+            .map(|edit| std::fs::read_to_string(dir.path().join(edit.path())).ok())
+            .collect())
+    }
+
+    fn search_replace(search: &str, replace: &str) -> EditOperation {
+        EditOperation::SearchReplace {
+            path: PathBuf::from("file.txt"),
+            search: search.to_string(),
+            replace: replace.to_string(),
+            all: false,
+        }
+    }
+
+    #[test]
+    fn search_replace_requires_a_unique_match() {
+        let content = numbered(20);
+        let result = apply(
+            &[("file.txt", &content)],
+            &[
+                search_replace("line 5\n", "five\n"),
+                search_replace("line 15", "fifteen"),
+            ],
+        )
+        .unwrap();
+        let expected = content
+            .replace("line 5\n", "five\n")
+            .replace("line 15", "fifteen");
+        assert_eq!(result[0].as_deref(), Some(expected.as_str()));
+
+        let err = apply(
+            &[("file.txt", &content)],
+            &[search_replace("line 1", "one")],
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            DevItError::VcsConflict { ref conflict_type, .. } if conflict_type == "search_ambiguous"
+        ));
+        let err = apply(&[("file.txt", &content)], &[search_replace("nope", "x")]).unwrap_err();
+        assert!(matches!(
+            err,
+            DevItError::VcsConflict { ref conflict_type, .. } if conflict_type == "search_not_found"
+        ));
+    }
+
+    #[test]
+    fn insert_after_and_replace_lines() {
+        let content = "fn main() {\n    run();\n}";
+        let hash = lines_sha256(["    run();"]);
+        let edits = [
+            EditOperation::InsertAfter {
+                path: PathBuf::from("file.txt"),
+                anchor: "fn main() {".to_string(),
+                content: "    init();".to_string(),
+            },
+            EditOperation::ReplaceLines {
+                path: PathBuf::from("file.txt"),
+                start_line: 3,
+                end_line: 3,
+                expected_sha256: hash[..12].to_string(),
+                content: "    start();\n".to_string(),
+            },
+        ];
+        let result = apply(&[("file.txt", content)], &edits).unwrap();
+        assert_eq!(
+            result[0].as_deref(),
+            Some("fn main() {\n    init();\n    start();\n}")
+        );
+
+        let stale = [EditOperation::ReplaceLines {
+            path: PathBuf::from("file.txt"),
+            start_line: 2,
+            end_line: 2,
+            expected_sha256: lines_sha256(["    old();"]),
+            content: String::new(),
+        }];
+        let err = apply(&[("file.txt", content)], &stale).unwrap_err();
+        assert!(matches!(
+            err,
+            DevItError::VcsConflict { ref conflict_type, ref resolution_hint, .. }
+                if conflict_type == "hash_mismatch"
+                    && resolution_hint.as_deref().unwrap().contains(&hash)
+        ));
+    }
+
+    #[test]
+    fn whole_file_operations_and_minimal_hunks() {
+        let edits = [
+            EditOperation::CreateFile {
+                path: PathBuf::from("new.txt"),
+                content: "hello\n".to_string(),
+            },
+            EditOperation::DeleteFile {
+                path: PathBuf::from("old.txt"),
+            },
+        ];
+        let result = apply(&[("old.txt", "bye\n")], &edits).unwrap();
+        assert_eq!(result, vec![Some("hello\n".to_string()), None]);
+
+        let err = apply(
+            &[("new.txt", "")],
+            &[EditOperation::CreateFile {
+                path: PathBuf::from("new.txt"),
+                content: String::new(),
+            }],
+        )
+        .unwrap_err();
+        assert!(matches!(err, DevItError::VcsConflict { .. }));
+
+        // Only the changed line and its context end up in the diff
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("file.txt"), numbered(100)).unwrap();
+        let diff = edits_to_diff(dir.path(), &[search_replace("line 50\n", "fifty\n")]).unwrap();
+        assert!(diff.contains("@@ -47,7 +47,7 @@\n line 47\n"));
+        assert_eq!(diff.lines().filter(|line| line.starts_with('-')).count(), 2);
+
+        let err = edits_to_diff(
+            dir.path(),
+            &[EditOperation::DeleteFile {
+                path: PathBuf::from("../outside.txt"),
+            }],
+        )
+        .unwrap_err();
+        assert!(matches!(err, DevItError::ProtectedPath { .. }));
+    }
+
+    /// Applies `ops` to `old`, checking they produce `new`, and returns the
+    /// number of lines kept.
+    fn replay(old: &[&str], new: &[&str], ops: &[LineOp]) -> usize {
+        let (mut o, mut result, mut kept) = (0, Vec::new(), 0);
+        for op in ops {
+            match op {
+                LineOp::Equal => {
+                    result.push(old[o]);
+                    o += 1;
+                    kept += 1;
+                }
+                LineOp::Delete => o += 1,
+                LineOp::Insert => result.push(new[result.len()]),
+            }
+        }
+        assert_eq!((o, result.as_slice()), (old.len(), new));
+        kept
+    }
+
+    #[test]
+    fn large_regions_are_split_with_myers() {
+        // Pseudo-random texts over a small alphabet: the Myers split must keep
+        // as many lines as the full LCS table
+        let mut seed = 42u32;
+        let mut text = |len: usize| -> Vec<&'static str> {
+            (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    ["a\n", "b\n", "c\n", "d\n"][(seed >> 16) as usize % 4]
+                })
+                .collect()
+        };
+        for round in 0..200 {
+            let (old, new) = (text(round % 23), text(round % 17));
+            let mut split = Vec::new();
+            push_diff(&old, &new, 0, &mut split);
+            assert_eq!(
+                replay(&old, &new, &split),
+                replay(&old, &new, &diff_lines(&old, &new)),
+                "{:?} -> {:?}",
+                old,
+                new
+            );
+        }
+
+        // Beyond MAX_DIFF_CELLS, distant changes stay small
+        let content = numbered(5000);
+        let old: Vec<&str> = content.split_inclusive('\n').collect();
+        let mut new = old.clone();
+        new[10] = "changed\n";
+        new.insert(2500, "inserted\n");
+        new.remove(4990);
+        let ops = diff_lines(&old, &new);
+        assert_eq!(replay(&old, &new, &ops), old.len() - 2);
+    }
+
+    #[test]
+    fn dot_components_name_the_same_file() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("file.txt"), "one\ntwo\n").unwrap();
+        let edit = |path: &str, search: &str, replace: &str| EditOperation::SearchReplace {
+            path: PathBuf::from(path),
+            search: search.to_string(),
+            replace: replace.to_string(),
+            all: false,
+        };
+        let diff = edits_to_diff(
+            dir.path(),
+            &[edit("./file.txt", "one", "1"), edit("file.txt", "two", "2")],
+        )
+        .unwrap();
+        assert_eq!(diff.matches("+++ ").count(), 1);
+        assert!(diff.contains("+++ b/file.txt\n"));
+        assert!(diff.contains("+1\n+2\n"));
+    }
+}
@@ -4,7 +4,10 @@
 
 pub mod capabilities;
 pub mod core;
+pub mod merge_assist;
 pub mod platform;
+pub mod plugins;
+pub mod recipes;
 
 // Re-export core types for convenience
 pub use core::{
@@ -16,14 +16,13 @@ use devit_sandbox as sandbox;
 use devit_tools::git;
 use std::time::Duration;
 mod commit_msg;
-mod merge_assist;
 mod precommit;
-mod recipes;
 mod report;
 mod test_runner;
 use hmac::{Hmac, Mac};
 use rand::RngCore;
-use recipes::{list_recipes, run_recipe, RecipeRunError};
+use devit_cli::merge_assist;
+use devit_cli::recipes::{list_recipes, run_recipe, RecipeRunError};
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::io::{stdin, Read, Write};
@@ -33,7 +32,7 @@ mod sbom;
 
 // Core Engine
 use devit_cli::core::formats::OutputFormat;
-use devit_cli::core::{CoreConfig, CoreEngine, DevItError};
+use devit_cli::core::{CoreConfig, CoreEngine, DevItError, SnapshotId};
 use serde_json::{json, Value};
 
 #[derive(Debug, Clone)]
@@ -116,6 +115,18 @@ enum Commands {
         /// Dry run - no side effects, only validation and preview
         #[arg(long)]
         dry_run: bool,
+        /// Snapshot the patch was written against; files the patch no longer
+        /// applies to are merged three-way, conflicts left as markers
+        #[arg(long, value_name = "SNAPSHOT_ID")]
+        base_snapshot: Option<String>,
+    },
+
+    /// Undo a patch applied without commit, using its transaction journal
+    /// (`.devit/transactions/<ID>`), as printed in `rollback_cmd`.
+    Rollback {
+        /// Transaction id reported by `devit apply`
+        #[arg(value_name = "TRANSACTION_ID")]
+        transaction_id: String,
     },
 
     /// Chain: suggest -> (approval) -> apply -> commit -> test
@@ -224,10 +235,13 @@ enum Commands {
     },
 
     /// Show details for a specific task
+    #[command(args_conflicts_with_subcommands = true)]
     Task {
         /// Task identifier to inspect
-        #[arg(value_name = "TASK_ID")]
-        task_id: String,
+        #[arg(value_name = "TASK_ID", required = true)]
+        task_id: Option<String>,
+        #[command(subcommand)]
+        action: Option<TaskCmd>,
     },
 
     /// Tools (experimental): list and call
@@ -330,6 +344,19 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum TaskCmd {
+    /// Cancel a delegated task and stop its worker
+    Cancel {
+        /// Task identifier to cancel
+        #[arg(value_name = "TASK_ID")]
+        task_id: String,
+        /// Reason recorded with the cancellation
+        #[arg(long)]
+        reason: Option<String>,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 enum ToolCmd {
     /// List available tools (JSON)
@@ -583,6 +610,7 @@ async fn main() -> Result<()> {
             approval,
             sandbox,
             dry_run,
+            base_snapshot,
         }) => {
             tracing::info!("Starting apply command for patch: {:?}", patch_file);
             tracing::debug!(
@@ -591,8 +619,19 @@ async fn main() -> Result<()> {
                 approval,
                 sandbox
             );
-            let response =
-                handle_apply(patch_file, approval, sandbox, dry_run, use_json_output).await;
+            let response = handle_apply(
+                patch_file,
+                approval,
+                sandbox,
+                dry_run,
+                base_snapshot,
+                use_json_output,
+            )
+            .await;
+            output_response(response, use_json_output);
+        }
+        Some(Commands::Rollback { transaction_id }) => {
+            let response = handle_rollback(transaction_id, use_json_output).await;
             output_response(response, use_json_output);
         }
         Some(Commands::Run { goal, use_mcp }) => {
@@ -704,7 +743,19 @@ async fn main() -> Result<()> {
                 )
             })?;
         }
-        Some(Commands::Task { task_id }) => {
+        Some(Commands::Task {
+            action: Some(TaskCmd::Cancel { task_id, reason }),
+            ..
+        }) => {
+            let payload = handle_orchestration_cancel(task_id, reason).await?;
+            print_orchestration_payload(&payload, use_json_output, |value| {
+                let task = value.get("task_id").and_then(Value::as_str).unwrap_or("-");
+                let reason = value.get("reason").and_then(Value::as_str).unwrap_or("-");
+                format!("Task cancelled.\nTask ID: {}\nReason: {}", task, reason)
+            })?;
+        }
+        Some(Commands::Task { task_id, .. }) => {
+            let task_id = task_id.context("task id required")?;
             let task = handle_orchestration_task(task_id).await?;
             print_orchestration_payload(&task, use_json_output, |value| {
                 format_task_details(value)
@@ -1885,6 +1936,7 @@ async fn handle_apply(
     approval: Option<String>,
     sandbox: Option<String>,
     dry_run: bool,
+    base_snapshot: Option<String>,
     use_json_output: bool,
 ) -> StdResponse<String> {
     use chrono::Utc;
@@ -1980,10 +2032,25 @@ async fn handle_apply(
         }
     };
 
-    let patch_result = match engine
-        .patch_apply(&patch_content, effective_approval.clone(), dry_run, None)
-        .await
-    {
+    let applied = match &base_snapshot {
+        Some(id) => {
+            engine
+                .patch_apply_merge(
+                    &patch_content,
+                    &SnapshotId(id.clone()),
+                    effective_approval.clone(),
+                    dry_run,
+                    None,
+                )
+                .await
+        }
+        None => {
+            engine
+                .patch_apply(&patch_content, effective_approval.clone(), dry_run, None)
+                .await
+        }
+    };
+    let patch_result = match applied {
         Ok(result) => result,
         Err(err) => {
             return StdResponse {
@@ -2065,6 +2132,19 @@ async fn handle_apply(
             lines.push(format!("Rollback command: {}", rollback));
         }
 
+        if !patch_result.conflicts.is_empty() {
+            lines.push(String::new());
+            lines.push("Merge conflicts:".to_string());
+            for file in &patch_result.conflicts {
+                for hunk in &file.hunks {
+                    lines.push(format!(
+                        "  ⚔️ {} lines {}-{}",
+                        file.path, hunk.start_line, hunk.end_line
+                    ));
+                }
+            }
+        }
+
         if !warnings.is_empty() {
             lines.push(String::new());
             lines.push("Warnings:".to_string());
@@ -2122,6 +2202,7 @@ async fn handle_apply(
             "test_results": test_results_value,
             "auto_reverted": patch_result.auto_reverted,
             "reverted_sha": reverted_sha,
+            "conflicts": patch_result.conflicts,
             "patch_file": patch_file.display().to_string(),
         });
         serde_json::to_string_pretty(&payload).unwrap_or_else(|_| payload.to_string())
@@ -2138,6 +2219,57 @@ async fn handle_apply(
     }
 }
 
+async fn handle_rollback(transaction_id: String, use_json_output: bool) -> StdResponse<String> {
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    let request_id = Uuid::new_v4();
+    let timestamp = Utc::now();
+
+    let restored = match CoreEngine::new(load_core_config_with_env()).await {
+        Ok(engine) => engine.patch_rollback(&transaction_id).await,
+        Err(err) => Err(err),
+    };
+    let restored = match restored {
+        Ok(restored) => restored,
+        Err(err) => {
+            return StdResponse {
+                success: false,
+                timestamp,
+                request_id: Some(request_id),
+                error: Some(std_error_from_core(err)),
+                data: None,
+            }
+        }
+    };
+
+    let restored_files: Vec<String> = restored
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let data_content = if use_json_output {
+        let payload = json!({
+            "transaction_id": transaction_id,
+            "restored_files": restored_files,
+        });
+        serde_json::to_string_pretty(&payload).unwrap_or_else(|_| payload.to_string())
+    } else {
+        let mut lines = vec![format!("↩️ Transaction {} rolled back.", transaction_id)];
+        for file in &restored_files {
+            lines.push(format!("  • {}", file));
+        }
+        lines.join("\n")
+    };
+
+    StdResponse {
+        success: true,
+        timestamp,
+        request_id: Some(request_id),
+        error: None,
+        data: Some(data_content),
+    }
+}
+
 fn parse_approval_level_cli(raw: &str) -> Option<ApprovalLevel> {
     match raw.to_ascii_lowercase().as_str() {
         "untrusted" => Some(ApprovalLevel::Untrusted),
@@ -2839,6 +2971,33 @@ async fn handle_orchestration_notify(
     }))
 }
 
+async fn handle_orchestration_cancel(task_id: String, reason: Option<String>) -> Result<Value> {
+    let config = load_core_config_with_env();
+    let engine = CoreEngine::new(config)
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to initialize orchestration core: {}", err))?;
+
+    if let Ok(socket) = std::env::var("DEVIT_DAEMON_SOCKET") {
+        if !engine.orchestration_uses_daemon().await {
+            anyhow::bail!(
+                "devitd daemon not available at {} (set DEVIT_ORCHESTRATION_MODE=local to work offline)",
+                socket
+            );
+        }
+    }
+
+    engine
+        .orchestration_cancel(task_id.clone(), reason.clone())
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to cancel task: {}", err))?;
+
+    Ok(serde_json::json!({
+        "task_id": task_id,
+        "status": "cancelled",
+        "reason": reason,
+    }))
+}
+
 async fn handle_orchestration_task(task_id: String) -> Result<Value> {
     let config = load_core_config_with_env();
     let engine = CoreEngine::new(config)
@@ -1,6 +1,6 @@
 //! Loader minimal pour plugins WASI (JSON stdin → JSON stdout) via `wasmtime` binaire.
 //! - Registry: .devit/plugins/<id>/devit-plugin.toml (override: DEVIT_PLUGINS_DIR)
-//! - Manifest TOML: id, name, wasm, version?, allowed_dirs?[], env?[]
+//! - Manifest TOML: id, name, wasm, version?, description?, allowed_dirs?[], env?[]
 //! - Sandbox: pas de `--dir` par défaut (zéro accès FS). Ajouts contrôlés via allowed_dirs.
 //! - Timeout: DEVIT_TIMEOUT_SECS (fallback 30s). Timeout → exit 124.
 use anyhow::{anyhow, Context, Result};
@@ -34,6 +34,9 @@ pub struct PluginManifest {
     pub wasm: String,
     #[serde(default)]
     pub version: Option<String>,
+    /// Description courte (reprise par le tool MCP du plugin).
+    #[serde(default)]
+    pub description: Option<String>,
     /// Pré-ouvertures de répertoires (`wasmtime run --dir=<path>`).
     #[serde(default)]
     pub allowed_dirs: Vec<String>,
@@ -47,6 +50,8 @@ pub struct PluginInfo {
     pub id: String,
     pub name: String,
     pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     pub manifest_path: String,
 }
 
@@ -99,6 +104,7 @@ pub fn discover_plugins(root: Option<&Path>) -> Result<Vec<PluginInfo>> {
             id: m.id.clone(),
             name: m.name.clone().unwrap_or_else(|| m.id.clone()),
             version: m.version.clone(),
+            description: m.description.clone(),
             manifest_path: manifest.display().to_string(),
         });
     }
@@ -233,7 +239,6 @@ pub fn invoke_by_id(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Write as _;
     use tempfile::tempdir;
 
     #[test]
@@ -14,6 +14,33 @@ struct RecipeFile {
     description: Option<String>,
     #[serde(default)]
     steps: Vec<RecipeStep>,
+    /// Arguments of the MCP prompt built from the recipe
+    #[serde(default)]
+    arguments: Vec<RecipeArgument>,
+    /// Prompt text; `{{name}}` is replaced by the argument `name`
+    #[serde(default)]
+    prompt: Option<String>,
+}
+
+#[derive(Deserialize, serde::Serialize, Debug, Clone)]
+pub struct RecipeArgument {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Recipe as exposed through MCP `prompts/*`
+#[derive(Debug, Clone)]
+pub struct RecipePrompt {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub arguments: Vec<RecipeArgument>,
+    pub prompt: Option<String>,
+    /// One line per step, e.g. `shell: echo hi`
+    pub steps: Vec<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -59,7 +86,7 @@ pub struct RecipeRunError {
 const DEFAULT_RECIPES_DIR: &str = ".devit/recipes";
 const ENV_RECIPES_DIR: &str = "DEVIT_RECIPES_DIR";
 
-fn recipes_dir() -> PathBuf {
+pub fn recipes_dir() -> PathBuf {
     if let Ok(custom) = env::var(ENV_RECIPES_DIR) {
         if !custom.is_empty() {
             return PathBuf::from(custom);
@@ -118,6 +145,27 @@ pub fn list_recipes() -> Result<Vec<RecipeSummary>> {
     Ok(recipes)
 }
 
+/// Recipes of `dir` with their prompt metadata; invalid files are skipped.
+pub fn recipe_prompts(dir: &Path) -> Vec<RecipePrompt> {
+    let mut prompts = Vec::new();
+    for path in load_recipe_files(dir) {
+        match load_recipe(&path) {
+            Ok(file) => prompts.push(RecipePrompt {
+                steps: file.steps.iter().map(RecipeStep::describe).collect(),
+                id: file.id,
+                name: file.name,
+                description: file.description,
+                arguments: file.arguments,
+                prompt: file.prompt,
+            }),
+            Err(e) => {
+                eprintln!("warn: skip recipe {} ({})", path.display(), e);
+            }
+        }
+    }
+    prompts
+}
+
 pub fn run_recipe(id: &str, dry_run: bool) -> Result<RecipeRunReport, RecipeRunError> {
     let dir = recipes_dir();
     let mut selected: Option<RecipeFile> = None;
@@ -250,6 +298,22 @@ fn execute_step(step: &RecipeStep) -> Result<(), String> {
     }
 }
 
+impl RecipeStep {
+    fn describe(&self) -> String {
+        let action = match self.kind {
+            RecipeKind::Shell => self.run.clone().unwrap_or_default(),
+            RecipeKind::Git => format!("git {}", self.args.clone().unwrap_or_default().join(" ")),
+            RecipeKind::Devit => {
+                format!("devit {}", self.args.clone().unwrap_or_default().join(" "))
+            }
+        };
+        match &self.name {
+            Some(name) => format!("{}: {} ({})", self.kind.as_str(), name, action),
+            None => format!("{}: {}", self.kind.as_str(), action),
+        }
+    }
+}
+
 impl RecipeKind {
     fn as_str(&self) -> &'static str {
         match self {
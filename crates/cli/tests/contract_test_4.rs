@@ -15,6 +15,7 @@ fn contract_approval_levels_respect_size_limits() {
 
     let large_change = FileChange {
         path: PathBuf::from("src/large_file.rs"),
+        old_path: None,
         kind: devit_cli::core::FileChangeKind::Mod,
         is_binary: false,
         adds_exec_bit: false,
@@ -27,6 +27,7 @@ fn create_simple_file_change(path: &str) -> FileChange
 {
     FileChange {
         path: PathBuf::from(path),
+        old_path: None,
         kind: FileChangeKind::Modify,
         is_binary: false,
         adds_exec_bit: false,
@@ -57,6 +57,8 @@ pub fn create_test_policy_config(approval_level: ApprovalLevel) -> PolicyConfig
             "woff2".to_string(),
         ],
         sandbox_profile_default: SandboxProfile::Strict,
+        rules: Vec::new(),
+        path_overrides: HashMap::new(),
     }
 }
 
@@ -72,6 +74,7 @@ pub fn create_test_sandbox_config() -> SandboxConfig {
         forbidden_directories: vec![PathBuf::from("/etc")],
         preserved_env_vars: vec!["PATH".to_string()],
         custom_restrictions: HashMap::new(),
+        seccomp_profiles: HashMap::new(),
     }
 }
 
@@ -155,6 +158,7 @@ pub fn create_scenario_configs() -> HashMap<String, CoreConfig> {
         forbidden_directories: vec![PathBuf::from("/etc"), PathBuf::from("/root")],
         preserved_env_vars: vec![],
         custom_restrictions: HashMap::new(),
+        seccomp_profiles: HashMap::new(),
     };
     strict.git = create_test_git_config();
     strict.testing = create_test_testing_config();
@@ -175,6 +179,8 @@ pub fn create_scenario_configs() -> HashMap<String, CoreConfig> {
         small_binary_max_bytes: 10 * 1024 * 1024,
         small_binary_ext_whitelist: vec!["png".to_string(), "jpg".to_string(), "gif".to_string()],
         sandbox_profile_default: SandboxProfile::Permissive,
+        rules: Vec::new(),
+        path_overrides: HashMap::new(),
     };
     permissive.sandbox = SandboxConfig {
         enabled: true,
@@ -186,6 +192,7 @@ pub fn create_scenario_configs() -> HashMap<String, CoreConfig> {
         forbidden_directories: vec![PathBuf::from("/etc")],
         preserved_env_vars: vec!["PATH".to_string(), "HOME".to_string()],
         custom_restrictions: HashMap::new(),
+        seccomp_profiles: HashMap::new(),
     };
     permissive.git = create_test_git_config();
     permissive.testing = create_test_testing_config();
@@ -216,6 +223,7 @@ pub fn create_scenario_configs() -> HashMap<String, CoreConfig> {
         forbidden_directories: vec![],
         preserved_env_vars: vec!["PATH".to_string(), "HOME".to_string()],
         custom_restrictions: HashMap::new(),
+        seccomp_profiles: HashMap::new(),
     };
     privileged.git = create_test_git_config();
     privileged.testing = create_test_testing_config();
@@ -99,6 +99,8 @@ pub fn create_test_config(default_approval: ApprovalLevel) -> CoreConfig {
             "ico".to_string(),
         ],
         sandbox_profile_default: SandboxProfile::Strict,
+        rules: Vec::new(),
+        path_overrides: HashMap::new(),
     };
     config.sandbox = SandboxConfig {
         enabled: true,
@@ -110,6 +112,7 @@ pub fn create_test_config(default_approval: ApprovalLevel) -> CoreConfig {
         forbidden_directories: vec![PathBuf::from("/etc"), PathBuf::from("/root")],
         preserved_env_vars: vec!["PATH".to_string(), "HOME".to_string()],
         custom_restrictions: HashMap::new(),
+        seccomp_profiles: HashMap::new(),
     };
     config.git = GitConfig {
         conventional_commits: true,
@@ -5,8 +5,10 @@ pub mod cache;
 pub mod fs;
 pub mod limits;
 pub mod orchestration;
+pub mod policy;
 pub mod process_registry;
 pub mod process_utils;
+pub mod sandbox;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -253,6 +255,8 @@ pub enum SandboxProfile {
     Permissive,
 }
 
+pub use sandbox::{IsolationMode, ResourceUsage, SandboxPlan, SeccompAction, SeccompProfileSpec};
+
 /// Unique identifier for snapshots.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SnapshotId(pub String);
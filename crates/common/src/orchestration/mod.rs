@@ -4,4 +4,5 @@ pub use types::*;
 pub mod orchestration;
 pub use orchestration::{format_status, OrchestrationContext, StatusFormat};
 
+pub use devit_orchestration::graph;
 pub use devit_orchestration::DelegateResult;
@@ -10,7 +10,8 @@ use tracing::{info, warn};
 
 use super::types::{
     default_daemon_start_timeout_ms, DelegateResult, DelegatedTask, OrchestrationConfig,
-    OrchestrationMode, OrchestrationStatus, StatusFilter, TaskStatus, DEFAULT_DAEMON_SOCKET,
+    OrchestrationMode, OrchestrationStatus, StatusFilter, TaskSpec, TaskStatus,
+    DEFAULT_DAEMON_SOCKET,
 };
 
 pub struct OrchestrationContext {
@@ -144,6 +145,14 @@ impl OrchestrationContext {
             .await
     }
 
+    /// Submit tasks linked by `depends_on`; see [`TaskSpec`].
+    pub async fn delegate_graph(
+        &self,
+        tasks: Vec<TaskSpec>,
+    ) -> Result<Vec<(String, DelegateResult)>> {
+        self.backend.delegate_graph(tasks).await
+    }
+
     pub async fn notify(
         &self,
         task_id: &str,
@@ -157,6 +166,10 @@ impl OrchestrationContext {
             .await
     }
 
+    pub async fn cancel(&self, task_id: &str, reason: Option<&str>) -> Result<()> {
+        self.backend.cancel(task_id, reason).await
+    }
+
     pub async fn status(&self, filter: Option<&str>) -> Result<OrchestrationStatus> {
         let filter = StatusFilter::from_str(filter);
         self.backend.status(filter).await
@@ -301,6 +314,16 @@ pub fn format_status(status: &OrchestrationStatus, format: StatusFormat) -> Resu
                 }
             }
 
+            if !status.queue.is_empty() {
+                lines.push("--- File d'attente ---".to_string());
+                for queued in &status.queue {
+                    lines.push(format!(
+                        "• #{} {} → {} (priorité {})",
+                        queued.position, queued.task_id, queued.delegated_to, queued.priority
+                    ));
+                }
+            }
+
             if !status.completed_tasks.is_empty() {
                 lines.push("--- Tâches terminées ---".to_string());
                 for task in &status.completed_tasks {
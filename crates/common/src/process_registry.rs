@@ -8,6 +8,8 @@ use std::fs::{File, OpenOptions};
 use std::io;
 use std::path::PathBuf;
 
+use crate::sandbox::ResourceUsage;
+
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
 
@@ -32,6 +34,12 @@ pub struct ProcessRecord {
     pub exit_code: Option<i32>,
     pub terminated_by_signal: Option<i32>,
     pub auto_kill_at: Option<DateTime<Utc>>,
+    /// cgroup holding the job's limits (Linux), removed once it is reaped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<PathBuf>,
+    /// Final cgroup counters, captured when the cgroup is removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_usage: Option<ResourceUsage>,
 }
 
 /// Process registry
@@ -220,6 +228,8 @@ mod tests {
             exit_code: None,
             terminated_by_signal: None,
             auto_kill_at: None,
+            cgroup: None,
+            resource_usage: None,
         };
 
         registry.insert(12345, record.clone());
@@ -244,6 +254,8 @@ mod tests {
             exit_code: None,
             terminated_by_signal: None,
             auto_kill_at: None,
+            cgroup: None,
+            resource_usage: None,
         };
 
         registry.insert(12345, record);
@@ -272,6 +284,8 @@ mod tests {
             exit_code: None,
             terminated_by_signal: None,
             auto_kill_at: None,
+            cgroup: None,
+            resource_usage: None,
         };
 
         registry.insert(12345, record);
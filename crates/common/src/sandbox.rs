@@ -0,0 +1,160 @@
+//! Sandbox plans shared by the CLI core, the MCP tools and the daemon.
+//!
+//! A [`SandboxPlan`] describes *what* a confined process may see (bind mounts,
+//! network, seccomp profile). Turning a plan into real isolation is the job of
+//! the backends in `devit-sandbox`.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::SandboxProfile;
+
+/// Standard read-only system paths for sandbox environments
+pub const SYSTEM_RO_PATHS: &[&str] = &["/usr", "/bin", "/lib", "/lib64", "/etc", "/opt"];
+
+/// Serializable sandbox plan capturing bind mounts, network access, and seccomp
+/// configuration that should be applied by sandbox backends.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxPlan {
+    /// Paths to mount read-only inside the sandbox
+    pub bind_ro: Vec<PathBuf>,
+    /// Paths that remain writable for the confined process
+    pub bind_rw: Vec<PathBuf>,
+    /// Whether outbound network access is permitted
+    pub net: bool,
+    /// Optional seccomp profile name for syscall filtering
+    pub seccomp_profile: Option<String>,
+}
+
+impl SandboxPlan {
+    /// Generates a sandbox plan for patch application workflows.
+    ///
+    /// **Strict profile**: Repository RW, everything else RO, net=false
+    /// - bind_ro: System paths (/usr, /bin, /lib, etc.)
+    /// - bind_rw: Repository root only
+    /// - net: false (no network access)
+    /// - seccomp_profile: "strict" for syscall filtering
+    pub fn plan_for_apply(repo_root: PathBuf, profile: SandboxProfile) -> Self {
+        let mut bind_ro = SYSTEM_RO_PATHS
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+        let bind_rw = vec![repo_root];
+
+        // For strict profile, add additional RO paths for security
+        if profile == SandboxProfile::Strict {
+            bind_ro.extend([
+                PathBuf::from("/proc"),
+                PathBuf::from("/sys"),
+                PathBuf::from("/dev"),
+            ]);
+        }
+
+        Self {
+            bind_ro,
+            bind_rw,
+            net: false, // No network access for apply operations
+            seccomp_profile: match profile {
+                SandboxProfile::Strict => Some("strict".to_string()),
+                SandboxProfile::Permissive => Some("permissive".to_string()),
+            },
+        }
+    }
+
+    /// Generates a sandbox plan for test execution workflows.
+    ///
+    /// **Strict profile**: Repository RW, tmp RW, net=false
+    /// **Permissive profile**: Repository RW, tmp RW, net=true
+    pub fn plan_for_test(repo_root: PathBuf, profile: SandboxProfile) -> Self {
+        let bind_ro = SYSTEM_RO_PATHS
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+
+        let mut bind_rw = vec![repo_root, PathBuf::from("/tmp")];
+
+        // Permissive profile gets additional writable paths
+        if profile == SandboxProfile::Permissive {
+            bind_rw.extend([PathBuf::from("/var/tmp"), PathBuf::from("/home")]);
+        }
+
+        Self {
+            bind_ro,
+            bind_rw,
+            net: profile == SandboxProfile::Permissive, // Network only for permissive
+            seccomp_profile: match profile {
+                SandboxProfile::Strict => Some("strict".to_string()),
+                SandboxProfile::Permissive => None, // No seccomp restrictions for permissive
+            },
+        }
+    }
+}
+
+/// What happens when a confined process invokes a denied syscall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SeccompAction {
+    /// The syscall fails with `EPERM`.
+    #[default]
+    Errno,
+    /// The whole process is killed (`SIGSYS`).
+    Kill,
+    /// The syscall is allowed but logged by the kernel audit subsystem.
+    Log,
+}
+
+/// User-defined seccomp profile, declared under
+/// `[sandbox.seccomp_profiles.<name>]` in `devit.core.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SeccompProfileSpec {
+    /// Built-in or custom profile whose deny list is inherited.
+    pub extends: Option<String>,
+    /// Additional syscall names to deny.
+    pub deny: Vec<String>,
+    /// Syscall names removed from the inherited deny list.
+    pub allow: Vec<String>,
+    /// Action applied to denied syscalls.
+    pub action: SeccompAction,
+}
+
+/// How strictly a caller wants namespace isolation to be enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IsolationMode {
+    /// Never isolate; spawn processes directly.
+    #[default]
+    Off,
+    /// Isolate when the kernel allows unprivileged user namespaces, otherwise
+    /// fall back to a direct spawn (with a warning).
+    Auto,
+    /// Refuse to spawn when isolation is unavailable.
+    Required,
+}
+
+/// Resource accounting for a confined process, as reported by the backend's
+/// enforcement layer (cgroup v2 event counters on Linux).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// Processes killed by the OOM killer because of the memory limit.
+    pub oom_kills: u64,
+    /// Times the memory limit was hit (reclaim forced).
+    pub memory_max_events: u64,
+    pub memory_peak_bytes: Option<u64>,
+    pub cpu_usage_usec: u64,
+    /// CPU periods in which the process was throttled by the CPU limit.
+    pub throttled_periods: u64,
+    pub throttled_usec: u64,
+    /// Forks refused because of the pids limit.
+    pub pids_max_events: u64,
+}
+
+impl ResourceUsage {
+    pub fn oom_killed(&self) -> bool {
+        self.oom_kills > 0
+    }
+
+    pub fn throttled(&self) -> bool {
+        self.throttled_periods > 0
+    }
+}
@@ -1,7 +1,13 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use serde::Serialize;
 use serde_json::Value;
 use thiserror::Error;
+use tokio::sync::Notify;
 
 pub type McpResult<T> = Result<T, McpError>;
 
@@ -62,9 +68,139 @@ impl McpError {
             data: data.into(),
         }
     }
+
+    /// Error returned by a call aborted through `notifications/cancelled`.
+    pub fn cancelled() -> Self {
+        McpError::rpc(REQUEST_CANCELLED, "request cancelled", None)
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// JSON-RPC code of a request cancelled by the client.
+pub const REQUEST_CANCELLED: i32 = -32800;
+
+/// Cooperative cancellation shared between the transport and a running tool.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancellationState>,
+}
+
+#[derive(Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the token is cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Progress of a running call, relayed as `notifications/progress`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressUpdate {
+    pub progress: f64,
+    pub total: Option<f64>,
+    pub message: Option<String>,
+}
+
+type ProgressSink = Arc<dyn Fn(ProgressUpdate) + Send + Sync>;
+
+/// Per-call context handed to [`McpTool::execute_with_context`].
+///
+/// Progress is only delivered when the client sent a `progressToken`; tools
+/// can report unconditionally.
+#[derive(Clone, Default)]
+pub struct ToolContext {
+    progress: Option<ProgressSink>,
+    cancellation: CancellationToken,
+}
+
+impl ToolContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_progress<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(ProgressUpdate) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(sink));
+        self
+    }
+
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    pub fn reports_progress(&self) -> bool {
+        self.progress.is_some()
+    }
+
+    pub fn report_progress<M: Into<Option<String>>>(
+        &self,
+        progress: f64,
+        total: Option<f64>,
+        message: M,
+    ) {
+        if let Some(sink) = &self.progress {
+            sink(ProgressUpdate {
+                progress,
+                total,
+                message: message.into(),
+            });
+        }
+    }
+
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    pub async fn cancelled(&self) {
+        self.cancellation.cancelled().await
+    }
+
+    /// Runs `future` until it completes or the call is cancelled, in which
+    /// case the future is dropped and [`McpError::cancelled`] is returned.
+    pub async fn run_cancellable<F, T>(&self, future: F) -> McpResult<T>
+    where
+        F: Future<Output = McpResult<T>>,
+    {
+        tokio::select! {
+            result = future => result,
+            _ = self.cancelled() => Err(McpError::cancelled()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ToolDescriptor {
     pub name: String,
     pub description: String,
@@ -79,6 +215,13 @@ pub trait McpTool: Send + Sync {
     async fn execute(&self, params: Value) -> McpResult<Value>;
     fn input_schema(&self) -> Value;
 
+    /// Variant of [`McpTool::execute`] receiving progress and cancellation.
+    /// The default runs `execute` and drops it on cancellation; tools owning
+    /// resources (processes, delegated tasks) override it to clean up.
+    async fn execute_with_context(&self, params: Value, ctx: &ToolContext) -> McpResult<Value> {
+        ctx.run_cancellable(self.execute(params)).await
+    }
+
     fn descriptor(&self) -> ToolDescriptor {
         ToolDescriptor {
             name: self.name().to_string(),
@@ -87,3 +230,95 @@ pub trait McpTool: Send + Sync {
         }
     }
 }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceDescriptor {
+    pub uri: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceTemplate {
+    #[serde(rename = "uriTemplate")]
+    pub uri_template: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Text content of a resource as returned by `resources/read`
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceContents {
+    pub uri: String,
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    pub text: String,
+}
+
+/// Source of the resources published through `resources/*`.
+#[async_trait]
+pub trait McpResourceProvider: Send + Sync {
+    async fn list(&self) -> McpResult<Vec<ResourceDescriptor>>;
+    fn templates(&self) -> Vec<ResourceTemplate>;
+    async fn read(&self, uri: &str) -> McpResult<Vec<ResourceContents>>;
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptArgument {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptDescriptor {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub arguments: Vec<PromptArgument>,
+}
+
+/// Rendered prompt returned by `prompts/get`; every message is a user text
+#[derive(Debug, Clone)]
+pub struct PromptMessages {
+    pub description: Option<String>,
+    pub messages: Vec<String>,
+}
+
+impl PromptMessages {
+    pub fn to_value(&self) -> Value {
+        let messages: Vec<Value> = self
+            .messages
+            .iter()
+            .map(|text| {
+                serde_json::json!({
+                    "role": "user",
+                    "content": { "type": "text", "text": text }
+                })
+            })
+            .collect();
+        let mut result = serde_json::json!({ "messages": messages });
+        if let Some(description) = &self.description {
+            result["description"] = Value::String(description.clone());
+        }
+        result
+    }
+}
+
+/// Source of the prompts published through `prompts/*`.
+#[async_trait]
+pub trait McpPromptProvider: Send + Sync {
+    async fn list(&self) -> McpResult<Vec<PromptDescriptor>>;
+    async fn get(
+        &self,
+        name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> McpResult<PromptMessages>;
+}
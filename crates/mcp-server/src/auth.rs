@@ -1,33 +1,84 @@
-use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use axum::http::HeaderValue;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+
+use crate::scope::TokenScope;
+use crate::transport::HttpAuthConfig;
 
 #[derive(Clone)]
 pub(crate) struct AuthManager {
-    tokens: HashSet<String>,
+    tokens: HashMap<String, Arc<TokenScope>>,
+}
+
+/// Outcome of checking the `Authorization` header.
+pub(crate) enum Access {
+    /// Auth enabled without any token: every request is accepted
+    Open,
+    Granted(Arc<TokenScope>),
+    Denied,
 }
 
 impl AuthManager {
-    pub(crate) fn new(tokens: HashSet<String>) -> Self {
+    pub(crate) fn new(config: HttpAuthConfig) -> Self {
+        let HttpAuthConfig { tokens, mut scopes } = config;
+        let tokens = tokens
+            .into_iter()
+            .map(|token| {
+                let scope = scopes.remove(&token).unwrap_or_else(|| {
+                    TokenScope::unrestricted(fingerprint(&token), PathBuf::new())
+                });
+                (token, Arc::new(scope))
+            })
+            .collect();
         Self { tokens }
     }
 
-    pub(crate) fn validate(&self, header: Option<&HeaderValue>) -> bool {
+    pub(crate) fn authenticate(&self, header: Option<&HeaderValue>) -> Access {
         if self.tokens.is_empty() {
-            return true;
+            return Access::Open;
         }
 
-        let raw = match header.and_then(|value| value.to_str().ok()) {
-            Some(value) => value.trim(),
-            None => return false,
+        let Some(token) = bearer(header) else {
+            return Access::Denied;
         };
 
-        let token = raw
-            .strip_prefix("Bearer ")
+        match self.tokens.get(token) {
+            Some(scope) if scope.is_expired(Utc::now()) => {
+                tracing::warn!("rejected expired MCP token '{}'", scope.name);
+                Access::Denied
+            }
+            Some(scope) => Access::Granted(scope.clone()),
+            None => Access::Denied,
+        }
+    }
+}
+
+/// Token of an `Authorization` header, with or without the `Bearer` prefix.
+fn bearer(header: Option<&HeaderValue>) -> Option<&str> {
+    let raw = header.and_then(|value| value.to_str().ok())?.trim();
+    Some(
+        raw.strip_prefix("Bearer ")
             .or_else(|| raw.strip_prefix("bearer "))
             .unwrap_or(raw)
-            .trim();
+            .trim(),
+    )
+}
 
-        self.tokens.contains(token)
-    }
+/// SHA-256 of the presented token, to tie state to it without keeping it.
+pub(crate) fn token_hash(header: Option<&HeaderValue>) -> Option<String> {
+    let digest = Sha256::digest(bearer(header)?.as_bytes());
+    Some(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// Stable identity for tokens configured without a name.
+fn fingerprint(token: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    token.hash(&mut hasher);
+    format!("token-{:08x}", hasher.finish() as u32)
 }
@@ -4,8 +4,8 @@ use anyhow::{Context, Result};
 use axum::{
     extract::Extension,
     http::{
-        header::{AUTHORIZATION, CONTENT_TYPE, HOST},
-        HeaderMap, HeaderValue, Method, StatusCode,
+        header::{AUTHORIZATION, CONTENT_TYPE, HOST, RETRY_AFTER},
+        HeaderMap, HeaderName, HeaderValue, Method, StatusCode,
     },
     response::{
         sse::{Event, KeepAlive, Sse},
@@ -28,9 +28,14 @@ use tower_http::{
 use futures_core::Stream;
 use futures_util::stream::once;
 
+mod rate_limit;
+mod streamable;
+mod tls;
+
 use crate::{
-    auth::AuthManager,
-    transport::{HttpAuthConfig, HttpCorsConfig, HttpTransportConfig},
+    auth::{Access, AuthManager},
+    scope::TokenScope,
+    transport::{HttpAuthConfig, HttpCorsConfig, HttpRateLimitConfig, HttpTransportConfig},
     McpServer, NotificationHub,
 };
 
@@ -45,16 +50,28 @@ struct HttpStateInner {
     auth: Option<AuthManager>,
     base_url_override: Option<String>,
     query_suffix: String,
+    sse_enabled: bool,
+    /// Scheme of the advertised URLs when no proxy header says otherwise
+    https: bool,
+    sessions: streamable::SessionStore,
+    limiter: Option<rate_limit::RateLimiter>,
+    /// Browser origins allowed besides loopback pages
+    allowed_origins: Vec<String>,
 }
 
 impl HttpState {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         server: Arc<McpServer>,
         auth_config: Option<HttpAuthConfig>,
         base_url_override: Option<String>,
         query_suffix: String,
+        sse_enabled: bool,
+        https: bool,
+        rate_limits: Option<HttpRateLimitConfig>,
+        cors: Option<&HttpCorsConfig>,
     ) -> Self {
-        let auth = auth_config.map(|cfg| AuthManager::new(cfg.tokens));
+        let auth = auth_config.map(AuthManager::new);
         let notifier = server.notifier();
         Self {
             inner: Arc::new(HttpStateInner {
@@ -63,20 +80,40 @@ impl HttpState {
                 auth,
                 base_url_override,
                 query_suffix,
+                sse_enabled,
+                https,
+                sessions: streamable::SessionStore::default(),
+                limiter: rate_limits.map(rate_limit::RateLimiter::new),
+                allowed_origins: cors
+                    .map(|cors| cors.allowed_origins.clone())
+                    .unwrap_or_default(),
             }),
         }
     }
 
-    fn ensure_authorized(&self, headers: &HeaderMap) -> Result<(), ApiError> {
+    /// Rejects unknown or expired tokens; returns the caller's scope when
+    /// a token was presented.
+    fn ensure_authorized(&self, headers: &HeaderMap) -> Result<Option<Arc<TokenScope>>, ApiError> {
         if let Some(auth) = &self.inner.auth {
-            if auth.validate(headers.get(AUTHORIZATION)) {
-                return Ok(());
-            }
-
-            return Err(ApiError::Unauthorized);
+            return match auth.authenticate(headers.get(AUTHORIZATION)) {
+                Access::Open => Ok(None),
+                Access::Granted(scope) => Ok(Some(scope)),
+                Access::Denied => Err(ApiError::Unauthorized),
+            };
         }
 
-        Ok(())
+        Ok(None)
+    }
+
+    fn check_rate(
+        &self,
+        scope: Option<&TokenScope>,
+        message: &Value,
+    ) -> Result<(), rate_limit::Rejection> {
+        match &self.inner.limiter {
+            Some(limiter) => limiter.check(scope, message),
+            None => Ok(()),
+        }
     }
 
     fn server(&self) -> &Arc<McpServer> {
@@ -94,11 +131,56 @@ impl HttpState {
     fn query_suffix(&self) -> &str {
         &self.inner.query_suffix
     }
+
+    fn sse_enabled(&self) -> bool {
+        self.inner.sse_enabled
+    }
+
+    fn default_scheme(&self) -> &'static str {
+        if self.inner.https {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    fn sessions(&self) -> &streamable::SessionStore {
+        &self.inner.sessions
+    }
+
+    /// `true` for loopback pages and the configured CORS origins.
+    fn origin_allowed(&self, origin: &str) -> bool {
+        let origin = origin.trim_end_matches('/');
+        is_loopback_origin(origin)
+            || self
+                .inner
+                .allowed_origins
+                .iter()
+                .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(origin))
+    }
+}
+
+fn is_loopback_origin(origin: &str) -> bool {
+    let Some((_, authority)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match authority.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => authority.split([':', '/']).next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
 }
 
 #[derive(Debug)]
 enum ApiError {
     Unauthorized,
+    Forbidden(&'static str),
+    BadRequest(String),
+    NotFound(&'static str),
+    Unavailable(&'static str),
     Internal(anyhow::Error),
 }
 
@@ -116,6 +198,20 @@ impl IntoResponse for ApiError {
                 Json(json!({ "error": "unauthorized" })),
             )
                 .into_response(),
+            ApiError::Forbidden(message) => {
+                (StatusCode::FORBIDDEN, Json(json!({ "error": message }))).into_response()
+            }
+            ApiError::BadRequest(message) => {
+                (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response()
+            }
+            ApiError::NotFound(message) => {
+                (StatusCode::NOT_FOUND, Json(json!({ "error": message }))).into_response()
+            }
+            ApiError::Unavailable(message) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({ "error": message })),
+            )
+                .into_response(),
             ApiError::Internal(err) => {
                 tracing::error!("HTTP transport error: {:#}", err);
                 (
@@ -135,15 +231,27 @@ pub async fn run_http_transport(server: Arc<McpServer>, config: HttpTransportCon
         sse_enabled,
         auth,
         cors,
+        rate_limits,
+        tls,
     } = config;
 
     let base_url_override = std::env::var("MCP_HTTP_BASE_URL").ok();
     let query_suffix = std::env::var("MCP_HTTP_URL_SUFFIX").unwrap_or_default();
 
-    let shared_state = HttpState::new(server.clone(), auth, base_url_override, query_suffix);
+    let shared_state = HttpState::new(
+        server.clone(),
+        auth,
+        base_url_override,
+        query_suffix,
+        sse_enabled,
+        tls.is_some(),
+        rate_limits,
+        cors.as_ref(),
+    );
 
     let mut router = Router::new()
         .route("/.well-known/mcp.json", get(manifest))
+        .route("/mcp", streamable::endpoint())
         .route("/message", post(handle_message))
         .route("/health", get(health));
 
@@ -160,12 +268,28 @@ pub async fn run_http_transport(server: Arc<McpServer>, config: HttpTransportCon
         .parse()
         .with_context(|| format!("Invalid bind address {host}:{port}"))?;
 
-    tracing::info!("MCP HTTP server listening on {addr}");
-
     let listener = TcpListener::bind(addr)
         .await
         .with_context(|| format!("Failed to bind MCP HTTP server to {addr}"))?;
 
+    if let Some(tls_config) = tls {
+        let tls = Arc::new(tls::ReloadableTls::load(tls_config)?);
+        #[cfg(unix)]
+        tls::reload_on_sighup(tls.clone())?;
+        tracing::info!(
+            "MCP HTTPS server listening on {addr}{}",
+            if tls.requires_client_cert() {
+                " (client certificates required)"
+            } else {
+                ""
+            }
+        );
+        tls::serve(listener, router, tls).await;
+        return Ok(());
+    }
+
+    tracing::info!("MCP HTTP server listening on {addr}");
+
     let make_service = axum::Router::into_make_service(router);
 
     axum::serve(listener, make_service)
@@ -180,11 +304,24 @@ async fn handle_message(
     headers: HeaderMap,
     Json(payload): Json<Value>,
 ) -> Result<Response, ApiError> {
-    state.ensure_authorized(&headers)?;
+    let scope = state.ensure_authorized(&headers)?;
+
+    if let Err(rejection) = state.check_rate(scope.as_deref(), &payload) {
+        let retry_after = HeaderValue::from(rejection.retry_after_secs());
+        let id = payload.get("id").cloned().unwrap_or(Value::Null);
+        tracing::warn!("rate limited /message request {}", id);
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(rejection.into_response(id)),
+        )
+            .into_response();
+        response.headers_mut().insert(RETRY_AFTER, retry_after);
+        return Ok(response);
+    }
 
     let result = state
         .server()
-        .handle_jsonrpc(payload)
+        .handle_jsonrpc_scoped(payload, scope.as_deref())
         .await
         .map_err(ApiError::internal)?;
 
@@ -233,13 +370,14 @@ async fn events(
 async fn manifest(Extension(state): Extension<HttpState>, headers: HeaderMap) -> impl IntoResponse {
     let base_url = state
         .base_url_override()
-        .or_else(|| derive_base_url(&headers))
+        .or_else(|| derive_base_url(&headers, state.default_scheme()))
         .unwrap_or_else(|| "http://localhost:3001".to_string());
 
     let suffix = normalize_suffix(state.query_suffix());
 
     let message_url = format!("{}/message{}", base_url, suffix);
     let sse_url = format!("{}/sse{}", base_url, suffix);
+    let streamable_url = format!("{}/mcp{}", base_url, suffix);
 
     let manifest = json!({
         "protocolVersion": "2025-06-18",
@@ -247,6 +385,7 @@ async fn manifest(Extension(state): Extension<HttpState>, headers: HeaderMap) ->
             "type": "http",
             "url": message_url,
             "sseUrl": sse_url,
+            "streamableHttpUrl": streamable_url,
         },
         "capabilities": {
             "tools": {},
@@ -276,8 +415,15 @@ async fn health() -> impl IntoResponse {
 
 fn build_cors_layer(config: Option<&HttpCorsConfig>) -> CorsLayer {
     let layer = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST])
-        .allow_headers([AUTHORIZATION, CONTENT_TYPE]);
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers([
+            AUTHORIZATION,
+            CONTENT_TYPE,
+            HeaderName::from_static(streamable::SESSION_HEADER),
+            HeaderName::from_static(streamable::LAST_EVENT_ID_HEADER),
+            HeaderName::from_static(streamable::PROTOCOL_VERSION_HEADER),
+        ])
+        .expose_headers([HeaderName::from_static(streamable::SESSION_HEADER)]);
 
     if let Some(cors) = config {
         if !cors.allowed_origins.is_empty() {
@@ -295,7 +441,7 @@ fn build_cors_layer(config: Option<&HttpCorsConfig>) -> CorsLayer {
     layer
 }
 
-fn derive_base_url(headers: &HeaderMap) -> Option<String> {
+fn derive_base_url(headers: &HeaderMap, default_scheme: &str) -> Option<String> {
     let host = headers
         .get(HOST)
         .and_then(|value| value.to_str().ok())
@@ -305,7 +451,7 @@ fn derive_base_url(headers: &HeaderMap) -> Option<String> {
         .get("x-forwarded-proto")
         .and_then(|value| value.to_str().ok())
         .filter(|value| !value.is_empty())
-        .unwrap_or("http");
+        .unwrap_or(default_scheme);
 
     Some(format!("{}://{}", scheme, host))
 }
@@ -0,0 +1,298 @@
+//! Rate limits and daily quotas of the HTTP transport.
+//!
+//! Every JSON-RPC request draws from the caller's token bucket; `tools/call`
+//! also draws from a per-tool bucket and counts against the caller's quota
+//! for the current UTC day. Callers are keyed by token name, anonymous ones
+//! share a single key. A refused request consumes nothing.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use devit_common::orchestration::CapabilityRateLimit;
+use serde_json::{json, Value};
+
+use crate::scope::TokenScope;
+use crate::transport::HttpRateLimitConfig;
+
+/// JSON-RPC error code of a request refused by a limit.
+pub(super) const RATE_LIMITED: i32 = -32029;
+
+const ANONYMOUS: &str = "anonymous";
+
+pub(super) struct RateLimiter {
+    config: HttpRateLimitConfig,
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Default)]
+struct LimiterState {
+    /// Buckets by caller, and tool for `tools/call` limits
+    buckets: HashMap<(String, Option<String>), Bucket>,
+    usage: HashMap<String, DailyUsage>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+struct DailyUsage {
+    day: NaiveDate,
+    used: u64,
+}
+
+/// Why a request was refused and when the caller may retry.
+#[derive(Debug)]
+pub(super) struct Rejection {
+    kind: &'static str,
+    limit: String,
+    tool: Option<String>,
+    retry_after: Duration,
+}
+
+impl RateLimiter {
+    pub(super) fn new(config: HttpRateLimitConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(LimiterState::default()),
+        }
+    }
+
+    /// Accounts for one incoming message; only requests are limited.
+    pub(super) fn check(
+        &self,
+        caller: Option<&TokenScope>,
+        message: &Value,
+    ) -> Result<(), Rejection> {
+        if message.get("method").is_none() || message.get("id").is_none() {
+            return Ok(());
+        }
+        let tool = (message.get("method").and_then(Value::as_str) == Some("tools/call"))
+            .then(|| {
+                message
+                    .get("params")
+                    .and_then(|params| params.get("name"))
+                    .and_then(Value::as_str)
+            })
+            .flatten();
+        self.check_at(caller, tool, Instant::now(), Utc::now())
+    }
+
+    fn check_at(
+        &self,
+        caller: Option<&TokenScope>,
+        tool: Option<&str>,
+        now: Instant,
+        today: DateTime<Utc>,
+    ) -> Result<(), Rejection> {
+        let key = caller.map_or(ANONYMOUS, |scope| scope.name.as_str());
+        let per_token = caller
+            .and_then(|scope| scope.rate_limit)
+            .or(self.config.per_token);
+        let quota = caller
+            .and_then(|scope| scope.daily_quota)
+            .or(self.config.daily_quota);
+        let per_tool = tool.and_then(|tool| self.config.per_tool.get(tool).map(|l| (tool, *l)));
+
+        let mut state = self.state.lock().expect("rate limiter lock poisoned");
+
+        let mut draws = Vec::new();
+        if let Some(limit) = per_token {
+            draws.push(((key.to_string(), None), limit, "token"));
+        }
+        if let Some((tool, limit)) = per_tool {
+            draws.push(((key.to_string(), Some(tool.to_string())), limit, "tool"));
+        }
+        for (bucket_key, limit, kind) in &draws {
+            let bucket = state
+                .buckets
+                .entry(bucket_key.clone())
+                .or_insert_with(|| Bucket::full(limit, now));
+            bucket.refill(limit, now);
+            if bucket.tokens < 1.0 {
+                return Err(Rejection {
+                    kind,
+                    limit: describe(limit),
+                    tool: bucket_key.1.clone(),
+                    retry_after: bucket.wait_for_one(limit),
+                });
+            }
+        }
+
+        let day = today.date_naive();
+        if let (Some(quota), Some(tool)) = (quota, tool) {
+            let usage = state
+                .usage
+                .entry(key.to_string())
+                .or_insert(DailyUsage { day, used: 0 });
+            if usage.day != day {
+                *usage = DailyUsage { day, used: 0 };
+            }
+            if usage.used >= quota {
+                let midnight = day
+                    .succ_opt()
+                    .and_then(|next| next.and_hms_opt(0, 0, 0))
+                    .map(|next| next.and_utc());
+                let retry_after = midnight
+                    .and_then(|midnight| (midnight - today).to_std().ok())
+                    .unwrap_or(Duration::from_secs(24 * 3600));
+                return Err(Rejection {
+                    kind: "daily_quota",
+                    limit: format!("{quota}/day"),
+                    tool: Some(tool.to_string()),
+                    retry_after,
+                });
+            }
+            usage.used += 1;
+        }
+
+        for (bucket_key, _, _) in &draws {
+            if let Some(bucket) = state.buckets.get_mut(bucket_key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Bucket {
+    fn full(limit: &CapabilityRateLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.max_actions as f64,
+            updated: now,
+        }
+    }
+
+    fn rate(limit: &CapabilityRateLimit) -> f64 {
+        limit.max_actions as f64 / limit.per_seconds.max(1) as f64
+    }
+
+    fn refill(&mut self, limit: &CapabilityRateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * Self::rate(limit)).min(limit.max_actions as f64);
+        self.updated = now;
+    }
+
+    fn wait_for_one(&self, limit: &CapabilityRateLimit) -> Duration {
+        let rate = Self::rate(limit);
+        if rate <= 0.0 {
+            return Duration::from_secs(limit.per_seconds.max(1));
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / rate)
+    }
+}
+
+impl Rejection {
+    /// Whole seconds to wait, rounded up.
+    pub(super) fn retry_after_secs(&self) -> u64 {
+        self.retry_after.as_secs() + u64::from(self.retry_after.subsec_nanos() > 0)
+    }
+
+    pub(super) fn into_response(self, id: Value) -> Value {
+        let retry_after = self.retry_after_secs();
+        let message = match &self.tool {
+            Some(tool) if self.kind != "token" => {
+                format!("{} limit {} reached for '{}'", self.kind, self.limit, tool)
+            }
+            _ => format!("{} limit {} reached", self.kind, self.limit),
+        };
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {
+                "code": RATE_LIMITED,
+                "message": message,
+                "data": {
+                    "limit": self.kind,
+                    "rate": self.limit,
+                    "tool": self.tool,
+                    "retryAfter": retry_after,
+                }
+            }
+        })
+    }
+}
+
+fn describe(limit: &CapabilityRateLimit) -> String {
+    match limit.per_seconds {
+        1 => format!("{}/second", limit.max_actions),
+        60 => format!("{}/minute", limit.max_actions),
+        3600 => format!("{}/hour", limit.max_actions),
+        other => format!("{}/{}s", limit.max_actions, other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+
+    fn limiter() -> RateLimiter {
+        let mut per_tool = HashMap::new();
+        per_tool.insert("devit_exec".to_string(), CapabilityRateLimit::per_minute(2));
+        RateLimiter::new(HttpRateLimitConfig {
+            per_token: Some(CapabilityRateLimit::per_minute(60)),
+            per_tool,
+            daily_quota: Some(3),
+        })
+    }
+
+    #[test]
+    fn tool_bucket_refills_over_time() {
+        let limiter = limiter();
+        let bot = TokenScope::unrestricted("bot", PathBuf::new());
+        let start = Instant::now();
+        let today = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+
+        assert!(limiter
+            .check_at(Some(&bot), Some("devit_exec"), start, today)
+            .is_ok());
+        assert!(limiter
+            .check_at(Some(&bot), Some("devit_exec"), start, today)
+            .is_ok());
+        let rejection = limiter
+            .check_at(Some(&bot), Some("devit_exec"), start, today)
+            .unwrap_err();
+        assert_eq!(rejection.kind, "tool");
+        assert_eq!(rejection.retry_after_secs(), 30);
+
+        // Other callers and other tools are not affected.
+        assert!(limiter
+            .check_at(None, Some("devit_exec"), start, today)
+            .is_ok());
+        assert!(limiter.check_at(Some(&bot), None, start, today).is_ok());
+
+        let later = start + Duration::from_secs(30);
+        assert!(limiter
+            .check_at(Some(&bot), Some("devit_exec"), later, today)
+            .is_ok());
+    }
+
+    #[test]
+    fn daily_quota_resets_at_midnight_utc() {
+        let limiter = limiter();
+        let mut bot = TokenScope::unrestricted("bot", PathBuf::new());
+        bot.daily_quota = Some(1);
+        let now = Instant::now();
+        let evening = Utc.with_ymd_and_hms(2026, 3, 1, 23, 0, 0).unwrap();
+
+        assert!(limiter
+            .check_at(Some(&bot), Some("devit_pwd"), now, evening)
+            .is_ok());
+        let rejection = limiter
+            .check_at(Some(&bot), Some("devit_pwd"), now, evening)
+            .unwrap_err();
+        let response = rejection.into_response(json!(4));
+        assert_eq!(response["error"]["code"], RATE_LIMITED);
+        assert_eq!(response["error"]["data"]["limit"], "daily_quota");
+        assert_eq!(response["error"]["data"]["retryAfter"], 3600);
+
+        let next_day = Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 1).unwrap();
+        assert!(limiter
+            .check_at(Some(&bot), Some("devit_pwd"), now, next_day)
+            .is_ok());
+    }
+}
@@ -0,0 +1,775 @@
+//! Streamable HTTP transport: a single `/mcp` endpoint.
+//!
+//! - `POST` carries one JSON-RPC message or a batch. `initialize` opens a
+//!   session whose id is returned in `Mcp-Session-Id`; every other call must
+//!   send it back. Requests are answered with plain JSON, or with an SSE stream
+//!   of their responses when the client accepts `text/event-stream`.
+//! - `GET` opens the session's standalone SSE stream of server notifications.
+//! - `DELETE` closes the session.
+//!
+//! A session belongs to the token that opened it: other tokens get 404 for
+//! its id. Requests carrying an `Origin` header must come from a loopback page
+//! or a configured CORS origin, so a DNS-rebound page cannot drive the server.
+//!
+//! Each session keeps a bounded log of the events it emitted, with ids unique
+//! within the session, so a client can reconnect with `Last-Event-ID` and get
+//! what it missed on the interrupted stream.
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::Extension,
+    http::{
+        header::{ACCEPT, AUTHORIZATION, ORIGIN},
+        HeaderMap, HeaderName, HeaderValue, StatusCode,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, MethodRouter},
+    Json,
+};
+use futures_core::Stream;
+use futures_util::stream;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+use uuid::Uuid;
+
+use super::{ApiError, HttpState};
+use crate::{
+    auth::token_hash, scope::TokenScope, Caller, ClientSession, McpServer, NotificationHub,
+    SessionSink,
+};
+
+pub(super) const SESSION_HEADER: &str = "mcp-session-id";
+pub(super) const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+pub(super) const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+/// Events kept per session for `Last-Event-ID` replay
+const EVENT_LOG_CAPACITY: usize = 512;
+/// Sessions without any request for this long are dropped
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Open sessions, all tokens together; each one runs a notification pump
+const MAX_SESSIONS: usize = 1024;
+/// Open sessions of one token; its least recently used one makes room
+const MAX_SESSIONS_PER_TOKEN: usize = 32;
+const STANDALONE_STREAM: &str = "standalone";
+
+pub(super) fn endpoint() -> MethodRouter {
+    get(open_stream).post(handle_post).delete(close_session)
+}
+
+#[derive(Clone, Debug)]
+struct StoredEvent {
+    id: u64,
+    stream: String,
+    /// `None` for priming and closing events, sent with empty data
+    payload: Option<Value>,
+    /// Closes the stream once delivered (last response of a POST)
+    last: bool,
+}
+
+#[derive(Default)]
+struct EventLog {
+    last_id: u64,
+    events: VecDeque<StoredEvent>,
+}
+
+pub(super) struct Session {
+    id: String,
+    /// Hash of the token that opened the session
+    owner: Option<String>,
+    log: Mutex<EventLog>,
+    live: broadcast::Sender<StoredEvent>,
+    last_seen: Mutex<Instant>,
+    next_stream: AtomicU64,
+    pump: Mutex<Option<JoinHandle<()>>>,
+    /// Identity given to the server; its notifications go on the standalone
+    /// stream
+    client: ClientSession,
+}
+
+impl Session {
+    fn open(notifier: &NotificationHub, owner: Option<String>) -> Arc<Self> {
+        let (live, _) = broadcast::channel(EVENT_LOG_CAPACITY);
+        let id = Uuid::new_v4().to_string();
+        let session = Arc::new_cyclic(|session: &Weak<Self>| {
+            let session = session.clone();
+            Self {
+                id: id.clone(),
+                owner,
+                log: Mutex::new(EventLog::default()),
+                live,
+                last_seen: Mutex::new(Instant::now()),
+                next_stream: AtomicU64::new(1),
+                pump: Mutex::new(None),
+                client: ClientSession {
+                    id,
+                    notify: Arc::new(move |payload| {
+                        if let Some(session) = session.upgrade() {
+                            session.record(STANDALONE_STREAM, Some(payload), false);
+                        }
+                    }),
+                },
+            }
+        });
+        let pump = tokio::spawn(pump_notifications(
+            Arc::downgrade(&session),
+            notifier.subscribe(),
+        ));
+        *session.pump.lock().expect("session pump lock poisoned") = Some(pump);
+        session
+    }
+
+    fn close(&self) {
+        if let Some(pump) = self.pump.lock().expect("session pump lock poisoned").take() {
+            pump.abort();
+        }
+    }
+
+    fn touch(&self) {
+        *self.last_seen.lock().expect("session clock lock poisoned") = Instant::now();
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_seen
+            .lock()
+            .expect("session clock lock poisoned")
+            .elapsed()
+    }
+
+    fn next_stream_key(&self) -> String {
+        format!(
+            "request-{}",
+            self.next_stream.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+
+    fn record(&self, stream: &str, payload: Option<Value>, last: bool) -> StoredEvent {
+        let mut log = self.log.lock().expect("session log lock poisoned");
+        log.last_id += 1;
+        let event = StoredEvent {
+            id: log.last_id,
+            stream: stream.to_string(),
+            payload,
+            last,
+        };
+        log.events.push_back(event.clone());
+        while log.events.len() > EVENT_LOG_CAPACITY {
+            log.events.pop_front();
+        }
+        // Sent under the log lock so live delivery follows id order.
+        let _ = self.live.send(event.clone());
+        event
+    }
+
+    fn stream_of(&self, event_id: u64) -> Option<String> {
+        let log = self.log.lock().expect("session log lock poisoned");
+        log.events
+            .iter()
+            .find(|event| event.id == event_id)
+            .map(|event| event.stream.clone())
+    }
+
+    /// Events of `stream`: the logged ones after `replay_after` (none when
+    /// `None`), then live ones, until an event marked `last`.
+    fn events(&self, stream: &str, replay_after: Option<u64>) -> impl Stream<Item = StoredEvent> {
+        let log = self.log.lock().expect("session log lock poisoned");
+        let live = self.live.subscribe();
+        let backlog: VecDeque<StoredEvent> = match replay_after {
+            Some(after) => log
+                .events
+                .iter()
+                .filter(|event| event.stream == stream && event.id > after)
+                .cloned()
+                .collect(),
+            None => VecDeque::new(),
+        };
+        let cursor = StreamCursor {
+            stream: stream.to_string(),
+            backlog,
+            live,
+            seen: log.last_id,
+            done: false,
+        };
+        drop(log);
+
+        stream::unfold(cursor, |mut cursor| async move {
+            if cursor.done {
+                return None;
+            }
+            let event = match cursor.backlog.pop_front() {
+                Some(event) => event,
+                None => loop {
+                    match cursor.live.recv().await {
+                        Ok(event) if event.stream == cursor.stream && event.id > cursor.seen => {
+                            break event
+                        }
+                        Ok(_) => continue,
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!("MCP stream subscriber lagged by {skipped} events");
+                            continue;
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                },
+            };
+            cursor.seen = cursor.seen.max(event.id);
+            cursor.done = event.last;
+            Some((event, cursor))
+        })
+    }
+}
+
+struct StreamCursor {
+    stream: String,
+    backlog: VecDeque<StoredEvent>,
+    live: broadcast::Receiver<StoredEvent>,
+    seen: u64,
+    done: bool,
+}
+
+/// Copies server notifications of the hub into the session standalone stream.
+async fn pump_notifications(session: Weak<Session>, mut receiver: broadcast::Receiver<Value>) {
+    loop {
+        let payload = match receiver.recv().await {
+            Ok(payload) => payload,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("MCP session lagged by {skipped} notifications");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        // Relayed client events are not JSON-RPC messages; only server
+        // notifications go on the wire, as on stdio.
+        if payload.get("jsonrpc").is_none() {
+            continue;
+        }
+        let Some(session) = session.upgrade() else {
+            return;
+        };
+        session.record(STANDALONE_STREAM, Some(payload), false);
+    }
+}
+
+#[derive(Default)]
+pub(super) struct SessionStore {
+    sessions: Mutex<HashMap<String, Arc<Session>>>,
+}
+
+impl SessionStore {
+    fn create(&self, server: &McpServer, owner: Option<String>) -> Result<Arc<Session>, ApiError> {
+        let mut sessions = self.sessions.lock().expect("session store lock poisoned");
+        let expired: Vec<String> = sessions
+            .values()
+            .filter(|session| session.idle_for() >= SESSION_IDLE_TIMEOUT)
+            .map(|session| session.id.clone())
+            .collect();
+        for id in expired {
+            Self::drop_session(&mut sessions, &id, server);
+        }
+
+        let owned: Vec<&Arc<Session>> = sessions
+            .values()
+            .filter(|session| session.owner == owner)
+            .collect();
+        if owned.len() >= MAX_SESSIONS_PER_TOKEN {
+            let oldest = owned
+                .into_iter()
+                .max_by_key(|session| session.idle_for())
+                .map(|session| session.id.clone());
+            if let Some(id) = oldest {
+                Self::drop_session(&mut sessions, &id, server);
+            }
+        }
+        if sessions.len() >= MAX_SESSIONS {
+            return Err(ApiError::Unavailable("too many open MCP sessions"));
+        }
+
+        let session = Session::open(&server.notifier(), owner);
+        sessions.insert(session.id.clone(), Arc::clone(&session));
+        Ok(session)
+    }
+
+    fn drop_session(sessions: &mut HashMap<String, Arc<Session>>, id: &str, server: &McpServer) {
+        if let Some(session) = sessions.remove(id) {
+            session.close();
+            server.close_session(id);
+        }
+    }
+
+    /// Session `id`, if `owner` opened it.
+    fn get(&self, id: &str, owner: Option<&str>) -> Option<Arc<Session>> {
+        let session = self
+            .sessions
+            .lock()
+            .expect("session store lock poisoned")
+            .get(id)
+            .filter(|session| session.owner.as_deref() == owner)
+            .cloned()?;
+        session.touch();
+        Some(session)
+    }
+
+    fn remove(&self, id: &str, owner: Option<&str>, server: &McpServer) -> bool {
+        let mut sessions = self.sessions.lock().expect("session store lock poisoned");
+        let owned = sessions
+            .get(id)
+            .is_some_and(|session| session.owner.as_deref() == owner);
+        if !owned {
+            return false;
+        }
+        Self::drop_session(&mut sessions, id, server);
+        true
+    }
+}
+
+fn session_id(headers: &HeaderMap) -> Result<&str, ApiError> {
+    headers
+        .get(SESSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| ApiError::BadRequest("missing Mcp-Session-Id header".into()))
+}
+
+fn existing_session(state: &HttpState, headers: &HeaderMap) -> Result<Arc<Session>, ApiError> {
+    let id = session_id(headers)?;
+    let owner = token_hash(headers.get(AUTHORIZATION));
+    state
+        .sessions()
+        .get(id, owner.as_deref())
+        .ok_or(ApiError::NotFound("unknown MCP session"))
+}
+
+/// Browsers always send `Origin`; other clients usually omit it.
+fn check_origin(state: &HttpState, headers: &HeaderMap) -> Result<(), ApiError> {
+    let Some(origin) = headers.get(ORIGIN) else {
+        return Ok(());
+    };
+    match origin.to_str() {
+        Ok(origin) if state.origin_allowed(origin) => Ok(()),
+        _ => {
+            tracing::warn!("rejected /mcp request from origin {:?}", origin);
+            Err(ApiError::Forbidden("origin not allowed"))
+        }
+    }
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map(|accept| accept.contains("text/event-stream"))
+        .unwrap_or(false)
+}
+
+fn with_session(mut response: Response, session: &Session) -> Response {
+    if let Ok(value) = HeaderValue::from_str(&session.id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(SESSION_HEADER), value);
+    }
+    response
+}
+
+fn sse_response(events: impl Stream<Item = StoredEvent> + Send + 'static) -> Response {
+    let events = events.map(|event| {
+        let data = event
+            .payload
+            .map(|payload| payload.to_string())
+            .unwrap_or_default();
+        Ok::<Event, Infallible>(Event::default().id(event.id.to_string()).data(data))
+    });
+    let keep_alive = KeepAlive::new()
+        .interval(Duration::from_secs(15))
+        .text("keep-alive");
+    Sse::new(events).keep_alive(keep_alive).into_response()
+}
+
+fn is_request(message: &Value) -> bool {
+    message.get("method").is_some() && message.get("id").is_some()
+}
+
+/// Runs one incoming message; only requests produce a response. Responses
+/// sent by the client are accepted and ignored (the server issues no request).
+/// Progress of the request goes on `stream`, the SSE stream of the POST.
+async fn dispatch(
+    state: &HttpState,
+    session: &Session,
+    message: Value,
+    scope: Option<&TokenScope>,
+    stream: Option<&SessionSink>,
+) -> Option<Value> {
+    message.get("method")?;
+    let id = message.get("id").cloned();
+    if let Err(rejection) = state.check_rate(scope, &message) {
+        tracing::warn!("rate limited /mcp message {:?}", id);
+        return id.map(|id| rejection.into_response(id));
+    }
+    let caller = Caller {
+        scope,
+        session: Some(&session.client),
+        stream,
+    };
+    match state.server().handle_jsonrpc_from(message, caller).await {
+        Ok(response) => response,
+        Err(err) => id.map(|id| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32600, "message": err.to_string() }
+            })
+        }),
+    }
+}
+
+async fn handle_post(
+    Extension(state): Extension<HttpState>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Result<Response, ApiError> {
+    let scope = state.ensure_authorized(&headers)?;
+    check_origin(&state, &headers)?;
+
+    let (messages, batch) = match body {
+        Value::Array(messages) => (messages, true),
+        message => (vec![message], false),
+    };
+    if messages.is_empty() {
+        return Err(ApiError::BadRequest("empty JSON-RPC batch".into()));
+    }
+
+    let initializing = messages
+        .iter()
+        .any(|message| message.get("method").and_then(Value::as_str) == Some("initialize"));
+    let session = if initializing {
+        state
+            .sessions()
+            .create(state.server(), token_hash(headers.get(AUTHORIZATION)))?
+    } else {
+        existing_session(&state, &headers)?
+    };
+
+    let expected = messages
+        .iter()
+        .filter(|message| is_request(message))
+        .count();
+    if expected == 0 {
+        for message in messages {
+            dispatch(&state, &session, message, scope.as_deref(), None).await;
+        }
+        return Ok(with_session(StatusCode::ACCEPTED.into_response(), &session));
+    }
+
+    if !(state.sse_enabled() && accepts_event_stream(&headers)) {
+        let mut responses = Vec::with_capacity(expected);
+        for message in messages {
+            if let Some(response) =
+                dispatch(&state, &session, message, scope.as_deref(), None).await
+            {
+                responses.push(response);
+            }
+        }
+        let body = if batch {
+            Value::Array(responses)
+        } else {
+            responses.pop().unwrap_or(Value::Null)
+        };
+        return Ok(with_session(
+            (StatusCode::OK, Json(body)).into_response(),
+            &session,
+        ));
+    }
+
+    // The priming event gives the client an id to resume from even if the
+    // connection drops before the first response.
+    let stream = session.next_stream_key();
+    let priming = session.record(&stream, None, false);
+    let events = session.events(&stream, Some(priming.id - 1));
+
+    let worker = Arc::clone(&session);
+    let progress: SessionSink = {
+        let session = Arc::downgrade(&session);
+        let stream = stream.clone();
+        Arc::new(move |payload| {
+            if let Some(session) = session.upgrade() {
+                session.record(&stream, Some(payload), false);
+            }
+        })
+    };
+    tokio::spawn(async move {
+        let mut answered = 0;
+        for message in messages {
+            let request = is_request(&message);
+            let response =
+                dispatch(&state, &worker, message, scope.as_deref(), Some(&progress)).await;
+            if !request {
+                continue;
+            }
+            answered += 1;
+            worker.record(&stream, response, answered == expected);
+        }
+    });
+
+    Ok(with_session(sse_response(events), &session))
+}
+
+async fn open_stream(
+    Extension(state): Extension<HttpState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    state.ensure_authorized(&headers)?;
+    check_origin(&state, &headers)?;
+    if !(state.sse_enabled() && accepts_event_stream(&headers)) {
+        return Ok(StatusCode::METHOD_NOT_ALLOWED.into_response());
+    }
+    let session = existing_session(&state, &headers)?;
+
+    let last_event_id = headers
+        .get(LAST_EVENT_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    let events = match last_event_id {
+        Some(after) => {
+            let stream = session
+                .stream_of(after)
+                .unwrap_or_else(|| STANDALONE_STREAM.to_string());
+            session.events(&stream, Some(after))
+        }
+        None => session.events(STANDALONE_STREAM, None),
+    };
+
+    Ok(with_session(sse_response(events), &session))
+}
+
+async fn close_session(
+    Extension(state): Extension<HttpState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    state.ensure_authorized(&headers)?;
+    check_origin(&state, &headers)?;
+    let id = session_id(&headers)?;
+    let owner = token_hash(headers.get(AUTHORIZATION));
+    if state
+        .sessions()
+        .remove(id, owner.as_deref(), state.server())
+    {
+        Ok(StatusCode::NO_CONTENT.into_response())
+    } else {
+        Err(ApiError::NotFound("unknown MCP session"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{McpServer, ToolRegistry};
+    use axum::{body::Body, http::Request, Router};
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        router_with(None)
+    }
+
+    fn router_with(auth: Option<crate::transport::HttpAuthConfig>) -> Router {
+        let server = Arc::new(McpServer::new(ToolRegistry::new(Vec::new())));
+        let cors = crate::transport::HttpCorsConfig {
+            allowed_origins: vec!["https://app.example.com".to_string()],
+        };
+        let state = HttpState::new(
+            server,
+            auth,
+            None,
+            String::new(),
+            true,
+            false,
+            None,
+            Some(&cors),
+        );
+        Router::new()
+            .route("/mcp", endpoint())
+            .layer(Extension(state))
+    }
+
+    fn post(body: Value, session: Option<&str>, accept: &str) -> Request<Body> {
+        let mut request = Request::post("/mcp")
+            .header("content-type", "application/json")
+            .header(ACCEPT, accept);
+        if let Some(id) = session {
+            request = request.header(SESSION_HEADER, id);
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn sessions_gate_requests_and_stream_responses() {
+        let app = router();
+        let init = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}});
+        let response = app
+            .clone()
+            .oneshot(post(init, None, "application/json"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let session = response.headers()[SESSION_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+        let missing = app
+            .clone()
+            .oneshot(post(list.clone(), None, "application/json"))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), StatusCode::BAD_REQUEST);
+
+        let streamed = app
+            .clone()
+            .oneshot(post(
+                list,
+                Some(&session),
+                "application/json, text/event-stream",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(streamed.status(), StatusCode::OK);
+        let body = text(streamed).await;
+        assert!(body.contains("id: 1\n"), "{body}");
+        assert!(body.contains("\"tools\""), "{body}");
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        let accepted = app
+            .clone()
+            .oneshot(post(notification, Some(&session), "application/json"))
+            .await
+            .unwrap();
+        assert_eq!(accepted.status(), StatusCode::ACCEPTED);
+
+        let delete = || {
+            Request::delete("/mcp")
+                .header(SESSION_HEADER, session.as_str())
+                .body(Body::empty())
+                .unwrap()
+        };
+        let closed = app.clone().oneshot(delete()).await.unwrap();
+        assert_eq!(closed.status(), StatusCode::NO_CONTENT);
+        let gone = app.oneshot(delete()).await.unwrap();
+        assert_eq!(gone.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn sessions_belong_to_their_token_and_origins_are_checked() {
+        let app = router_with(Some(crate::transport::HttpAuthConfig {
+            tokens: ["alpha".to_string(), "beta".to_string()].into(),
+            scopes: HashMap::new(),
+        }));
+        let request = |body: Value, token: &str, session: Option<&str>, origin: Option<&str>| {
+            let mut request = post(body, session, "application/json");
+            let headers = request.headers_mut();
+            headers.insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+            );
+            if let Some(origin) = origin {
+                headers.insert(ORIGIN, HeaderValue::from_str(origin).unwrap());
+            }
+            request
+        };
+        let init = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}});
+        let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+
+        let rebound = app
+            .clone()
+            .oneshot(request(
+                init.clone(),
+                "alpha",
+                None,
+                Some("http://evil.test"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(rebound.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(request(init, "alpha", None, Some("http://localhost:5173")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let session = response.headers()[SESSION_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let stolen = app
+            .clone()
+            .oneshot(request(list.clone(), "beta", Some(&session), None))
+            .await
+            .unwrap();
+        assert_eq!(stolen.status(), StatusCode::NOT_FOUND);
+
+        let owned = app
+            .clone()
+            .oneshot(request(
+                list,
+                "alpha",
+                Some(&session),
+                Some("https://app.example.com"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(owned.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn a_token_keeps_a_bounded_number_of_sessions() {
+        let server = McpServer::new(ToolRegistry::new(Vec::new()));
+        let store = SessionStore::default();
+        let first = store
+            .create(&server, Some("owner".to_string()))
+            .expect("session");
+        for _ in 0..MAX_SESSIONS_PER_TOKEN {
+            store
+                .create(&server, Some("owner".to_string()))
+                .expect("session");
+        }
+        let sessions = store.sessions.lock().unwrap();
+        assert_eq!(sessions.len(), MAX_SESSIONS_PER_TOKEN);
+        assert!(!sessions.contains_key(&first.id));
+        drop(sessions);
+        assert!(first.pump.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn last_event_id_replays_the_interrupted_stream() {
+        let session = Session::open(&NotificationHub::new(8), None);
+        let stream = session.next_stream_key();
+        let priming = session.record(&stream, None, false);
+        session.record(STANDALONE_STREAM, Some(json!({"other": true})), false);
+        session.record(&stream, Some(json!({"id": 7})), true);
+
+        assert_eq!(
+            session.stream_of(priming.id).as_deref(),
+            Some(stream.as_str())
+        );
+        let replayed: Vec<StoredEvent> = session.events(&stream, Some(priming.id)).collect().await;
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].payload, Some(json!({"id": 7})));
+        assert!(replayed[0].last);
+        session.close();
+    }
+}
@@ -0,0 +1,321 @@
+//! HTTPS listener of the HTTP transport, with optional client certificates.
+//!
+//! The rustls configuration is built from the PEM files of
+//! [`HttpTlsConfig`] and swapped in place on reload (SIGHUP): connections
+//! already established keep the certificate they negotiated, new handshakes
+//! use the reloaded one. A reload that fails keeps the previous certificates.
+
+use std::sync::{Arc, RwLock};
+
+use anyhow::{anyhow, Context, Result};
+use axum::Router;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto::Builder,
+    service::TowerToHyperService,
+};
+use rustls::{
+    crypto::{ring, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+use crate::transport::HttpTlsConfig;
+
+pub(super) struct ReloadableTls {
+    config: HttpTlsConfig,
+    current: RwLock<Arc<ServerConfig>>,
+}
+
+impl ReloadableTls {
+    pub(super) fn load(config: HttpTlsConfig) -> Result<Self> {
+        let server_config = build_server_config(&config)?;
+        Ok(Self {
+            config,
+            current: RwLock::new(Arc::new(server_config)),
+        })
+    }
+
+    /// Re-reads the PEM files; on error the current certificates stay in use.
+    pub(super) fn reload(&self) -> Result<()> {
+        let server_config = build_server_config(&self.config)?;
+        *self.current.write().expect("TLS config lock poisoned") = Arc::new(server_config);
+        Ok(())
+    }
+
+    pub(super) fn requires_client_cert(&self) -> bool {
+        self.config.client_ca_path.is_some()
+    }
+
+    fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(
+            self.current
+                .read()
+                .expect("TLS config lock poisoned")
+                .clone(),
+        )
+    }
+}
+
+/// Reloads the certificates whenever the process receives SIGHUP.
+#[cfg(unix)]
+pub(super) fn reload_on_sighup(tls: Arc<ReloadableTls>) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup()).context("Failed to install SIGHUP handler")?;
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match tls.reload() {
+                Ok(()) => tracing::info!(
+                    "Reloaded TLS certificate from {}",
+                    tls.config.cert_path.display()
+                ),
+                Err(err) => tracing::error!(
+                    "TLS reload failed, keeping the previous certificate: {:#}",
+                    err
+                ),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Accepts TLS connections and serves `router` over HTTP/1.1 or HTTP/2.
+pub(super) async fn serve(listener: TcpListener, router: Router, tls: Arc<ReloadableTls>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                tracing::warn!("HTTPS accept failed: {}", err);
+                continue;
+            }
+        };
+        let acceptor = tls.acceptor();
+        let service = TowerToHyperService::new(router.clone());
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    tracing::debug!("TLS handshake with {} failed: {}", peer, err);
+                    return;
+                }
+            };
+            if let Err(err) = Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!("HTTPS connection with {} ended: {}", peer, err);
+            }
+        });
+    }
+}
+
+fn build_server_config(config: &HttpTlsConfig) -> Result<ServerConfig> {
+    let provider = Arc::new(ring::default_provider());
+
+    let certs = read_certs(&config.cert_path)?;
+    let key = PrivateKeyDer::from_pem_file(&config.key_path).with_context(|| {
+        format!(
+            "Failed to read TLS private key {}",
+            config.key_path.display()
+        )
+    })?;
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .context("Unsupported TLS protocol versions")?;
+    let builder = match &config.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(ca_path)? {
+                roots.add(cert).with_context(|| {
+                    format!("Invalid client CA certificate in {}", ca_path.display())
+                })?;
+            }
+            let verifier = client_verifier(roots, provider)?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder.with_single_cert(certs, key).with_context(|| {
+        format!(
+            "TLS certificate {} does not match its key",
+            config.cert_path.display()
+        )
+    })?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server_config)
+}
+
+fn client_verifier(
+    roots: RootCertStore,
+    provider: Arc<CryptoProvider>,
+) -> Result<Arc<dyn rustls::server::danger::ClientCertVerifier>> {
+    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+        .build()
+        .context("Invalid client CA bundle")
+}
+
+fn read_certs(path: &std::path::Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read certificates from {}", path.display()))?;
+    if certs.is_empty() {
+        return Err(anyhow!("No certificate found in {}", path.display()));
+    }
+    Ok(certs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+    use rustls::{pki_types::ServerName, ClientConfig};
+    use std::net::SocketAddr;
+    use std::path::{Path, PathBuf};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio_rustls::TlsConnector;
+
+    struct Authority {
+        cert: rcgen::Certificate,
+        key: KeyPair,
+    }
+
+    impl Authority {
+        fn new() -> Self {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let cert = params.self_signed(&key).unwrap();
+            Self { cert, key }
+        }
+
+        /// Issues a certificate for `localhost`; returns (cert PEM, key PEM).
+        fn issue(&self) -> (String, String) {
+            let key = KeyPair::generate().unwrap();
+            let cert = CertificateParams::new(vec!["localhost".to_string()])
+                .unwrap()
+                .signed_by(&key, &self.cert, &self.key)
+                .unwrap();
+            (cert.pem(), key.serialize_pem())
+        }
+
+        fn roots(&self) -> RootCertStore {
+            let mut roots = RootCertStore::empty();
+            roots.add(self.cert.der().clone()).unwrap();
+            roots
+        }
+    }
+
+    fn write_pair(dir: &Path, name: &str, (cert, key): (String, String)) -> (PathBuf, PathBuf) {
+        let cert_path = dir.join(format!("{name}.pem"));
+        let key_path = dir.join(format!("{name}.key"));
+        std::fs::write(&cert_path, cert).unwrap();
+        std::fs::write(&key_path, key).unwrap();
+        (cert_path, key_path)
+    }
+
+    async fn start(config: HttpTlsConfig) -> (SocketAddr, Arc<ReloadableTls>) {
+        let tls = Arc::new(ReloadableTls::load(config).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = Router::new().route("/health", get(|| async { "ok" }));
+        tokio::spawn(serve(listener, router, tls.clone()));
+        (addr, tls)
+    }
+
+    fn client(roots: RootCertStore, identity: Option<(String, String)>) -> ClientConfig {
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+        match identity {
+            Some((cert, key)) => {
+                let certs = CertificateDer::pem_slice_iter(cert.as_bytes())
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap();
+                let key = PrivateKeyDer::from_pem_slice(key.as_bytes()).unwrap();
+                builder.with_client_auth_cert(certs, key).unwrap()
+            }
+            None => builder.with_no_client_auth(),
+        }
+    }
+
+    /// Status line of `GET /health`, or `None` when TLS fails.
+    async fn health(addr: SocketAddr, config: ClientConfig) -> Option<String> {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let connector = TlsConnector::from(Arc::new(config));
+        let server_name = ServerName::try_from("localhost").unwrap();
+        let mut stream = connector.connect(server_name, stream).await.ok()?;
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .ok()?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.ok()?;
+        let response = String::from_utf8_lossy(&response);
+        response.lines().next().map(str::to_string)
+    }
+
+    #[tokio::test]
+    async fn https_serves_and_reloads_certificates() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = Authority::new();
+        let (cert_path, key_path) = write_pair(dir.path(), "server", first.issue());
+        let (addr, tls) = start(HttpTlsConfig {
+            cert_path,
+            key_path,
+            client_ca_path: None,
+        })
+        .await;
+
+        let status = health(addr, client(first.roots(), None)).await;
+        assert_eq!(status.as_deref(), Some("HTTP/1.1 200 OK"));
+
+        // A broken file is rejected and the running certificate kept.
+        std::fs::write(dir.path().join("server.key"), "not a key").unwrap();
+        assert!(tls.reload().is_err());
+        assert!(health(addr, client(first.roots(), None)).await.is_some());
+
+        let second = Authority::new();
+        write_pair(dir.path(), "server", second.issue());
+        tls.reload().unwrap();
+        assert!(health(addr, client(first.roots(), None)).await.is_none());
+        let status = health(addr, client(second.roots(), None)).await;
+        assert_eq!(status.as_deref(), Some("HTTP/1.1 200 OK"));
+    }
+
+    #[tokio::test]
+    async fn mutual_tls_requires_a_trusted_client_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let server_ca = Authority::new();
+        let client_ca = Authority::new();
+        let (cert_path, key_path) = write_pair(dir.path(), "server", server_ca.issue());
+        let client_ca_path = dir.path().join("clients.pem");
+        std::fs::write(&client_ca_path, client_ca.cert.pem()).unwrap();
+        let (addr, tls) = start(HttpTlsConfig {
+            cert_path,
+            key_path,
+            client_ca_path: Some(client_ca_path),
+        })
+        .await;
+        assert!(tls.requires_client_cert());
+
+        assert!(health(addr, client(server_ca.roots(), None))
+            .await
+            .is_none());
+        let stranger = Authority::new().issue();
+        assert!(health(addr, client(server_ca.roots(), Some(stranger)))
+            .await
+            .is_none());
+
+        let status = health(addr, client(server_ca.roots(), Some(client_ca.issue()))).await;
+        assert_eq!(status.as_deref(), Some("HTTP/1.1 200 OK"));
+    }
+}
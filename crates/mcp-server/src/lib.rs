@@ -1,8 +1,17 @@
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
-use mcp_core::{McpError, McpTool, ToolDescriptor};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use mcp_core::{
+    CancellationToken, McpError, McpPromptProvider, McpResourceProvider, McpResult, McpTool,
+    ResourceContents, ToolContext, ToolDescriptor,
+};
+use mcp_tools::JournalContext;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
@@ -10,8 +19,15 @@ use tokio::sync::broadcast;
 
 mod auth;
 mod http_server;
+pub mod scope;
 pub mod transport;
+use crate::scope::{TokenScope, SCOPE_DENIED};
 use crate::transport::HttpTransportConfig;
+
+const RESOURCE_PAGE_SIZE: usize = 200;
+/// Period at which subscribed resources are re-read to detect changes
+const RESOURCE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 #[derive(Clone)]
 pub struct NotificationHub {
     sender: broadcast::Sender<Value>,
@@ -32,131 +48,384 @@ impl NotificationHub {
             tracing::debug!("no subscribers for notification broadcast: {}", err);
         }
     }
+
+    /// Broadcast a server-initiated JSON-RPC notification; unlike relayed
+    /// client events it carries `jsonrpc` and is forwarded to stdio clients.
+    pub fn publish_server(&self, method: &str, params: Value) {
+        self.publish(json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }));
+    }
 }
 
+/// Delivers server notifications to one client session.
+pub type SessionSink = Arc<dyn Fn(Value) + Send + Sync>;
+
+/// Transport session a message belongs to, with the sink of its
+/// server-initiated notifications.
+#[derive(Clone)]
+pub struct ClientSession {
+    pub id: String,
+    pub notify: SessionSink,
+}
+
+/// Origin of a JSON-RPC message. Messages outside any session (stdio, the
+/// legacy `/message` endpoint) share the server-wide session, whose
+/// notifications go through the [`NotificationHub`].
+#[derive(Clone, Copy, Default)]
+pub struct Caller<'a> {
+    /// Token scope restricting what the caller may reach
+    pub scope: Option<&'a TokenScope>,
+    pub session: Option<&'a ClientSession>,
+    /// Stream carrying the response, for the notifications tied to the
+    /// request (`notifications/progress`); without one they are dropped
+    pub stream: Option<&'a SessionSink>,
+}
+
+impl Caller<'_> {
+    fn session_id(&self) -> &str {
+        self.session
+            .map(|session| session.id.as_str())
+            .unwrap_or("")
+    }
+}
+
+/// A `resources/subscribe` of one session.
+struct Subscription {
+    /// Fingerprint of the last read
+    fingerprint: u64,
+    /// `None` for the server-wide session
+    notify: Option<SessionSink>,
+}
+
+/// Subscriptions by (session id, URI).
+type Subscriptions = Arc<Mutex<HashMap<(String, String), Subscription>>>;
+
+/// Registry of the tools exposed through `tools/*`.
+///
+/// Clones share the same state so tools can be added or removed at runtime
+/// (plugins, configuration toggles, late worker attachment); every change is
+/// announced to connected clients with `notifications/tools/list_changed`.
 #[derive(Clone)]
 pub struct ToolRegistry {
+    state: Arc<RwLock<RegistryState>>,
+    notifier: Arc<OnceLock<NotificationHub>>,
+}
+
+#[derive(Default)]
+struct RegistryState {
     tools: HashMap<String, Arc<dyn McpTool>>,
     order: Vec<String>,
+    /// Owning group of tools managed through [`ToolRegistry::reconcile`]
+    groups: HashMap<String, String>,
+}
+
+impl RegistryState {
+    fn insert(&mut self, tool: Arc<dyn McpTool>) -> bool {
+        let name = tool.name().to_string();
+        let added = self.tools.insert(name.clone(), tool).is_none();
+        if added {
+            self.order.push(name);
+        }
+        added
+    }
+
+    fn remove(&mut self, name: &str) -> bool {
+        self.groups.remove(name);
+        if self.tools.remove(name).is_none() {
+            return false;
+        }
+        self.order.retain(|entry| entry != name);
+        true
+    }
 }
 
 impl ToolRegistry {
     pub fn new(tools: Vec<Arc<dyn McpTool>>) -> Self {
-        let mut map = HashMap::new();
-        let mut order = Vec::new();
-
+        let mut state = RegistryState::default();
         for tool in tools {
-            let name = tool.name().to_string();
-            if map.insert(name.clone(), tool).is_none() {
-                order.push(name);
-            }
+            state.insert(tool);
         }
 
-        Self { tools: map, order }
+        Self {
+            state: Arc::new(RwLock::new(state)),
+            notifier: Arc::new(OnceLock::new()),
+        }
     }
 
     pub fn get(&self, name: &str) -> Option<Arc<dyn McpTool>> {
-        self.tools.get(name).cloned()
+        self.read().tools.get(name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.read().tools.contains_key(name)
     }
 
     pub fn descriptors(&self) -> Vec<ToolDescriptor> {
-        self.order
+        let state = self.read();
+        state
+            .order
             .iter()
-            .filter_map(|name| self.tools.get(name))
+            .filter_map(|name| state.tools.get(name))
             .map(|tool| tool.descriptor())
             .collect()
     }
+
+    /// Add a tool, replacing any tool registered under the same name.
+    pub fn register(&self, tool: Arc<dyn McpTool>) {
+        self.write().insert(tool);
+        self.notify_changed();
+    }
+
+    /// Remove a tool; returns `false` when no tool had that name.
+    pub fn unregister(&self, name: &str) -> bool {
+        let removed = self.write().remove(name);
+        if removed {
+            self.notify_changed();
+        }
+        removed
+    }
+
+    /// Make `desired` the exact set of tools owned by `group`.
+    ///
+    /// Tools of the group missing from `desired` are removed and new ones are
+    /// added. A tool already registered under the same name is kept when its
+    /// descriptor is unchanged and replaced otherwise (a statically
+    /// registered tool is adopted by the group). Clients are notified once,
+    /// and only if the list actually changed.
+    pub fn reconcile(&self, group: &str, desired: Vec<Arc<dyn McpTool>>) -> bool {
+        let mut changed = false;
+        {
+            let mut state = self.write();
+            let wanted: Vec<String> = desired.iter().map(|tool| tool.name().to_string()).collect();
+            let stale: Vec<String> = state
+                .groups
+                .iter()
+                .filter(|(name, owner)| owner.as_str() == group && !wanted.contains(name))
+                .map(|(name, _)| name.clone())
+                .collect();
+            for name in stale {
+                changed |= state.remove(&name);
+            }
+            for tool in desired {
+                let name = tool.name().to_string();
+                let unchanged = state
+                    .tools
+                    .get(&name)
+                    .is_some_and(|current| current.descriptor() == tool.descriptor());
+                if !unchanged {
+                    state.insert(tool);
+                    changed = true;
+                }
+                state.groups.insert(name, group.to_string());
+            }
+        }
+        if changed {
+            self.notify_changed();
+        }
+        changed
+    }
+
+    fn attach(&self, notifier: NotificationHub) {
+        let _ = self.notifier.set(notifier);
+    }
+
+    fn notify_changed(&self) {
+        if let Some(notifier) = self.notifier.get() {
+            notifier.publish_server("notifications/tools/list_changed", json!({}));
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, RegistryState> {
+        self.state.read().expect("tool registry lock poisoned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, RegistryState> {
+        self.state.write().expect("tool registry lock poisoned")
+    }
 }
 
 pub struct McpServer {
     registry: ToolRegistry,
     notifier: NotificationHub,
+    resources: Option<Arc<dyn McpResourceProvider>>,
+    prompts: Option<Arc<dyn McpPromptProvider>>,
+    subscriptions: Subscriptions,
+    watcher_started: AtomicBool,
+    /// Running `tools/call` requests by session id and JSON-RPC id, for
+    /// `notifications/cancelled`
+    in_flight: Mutex<HashMap<(String, String), CancellationToken>>,
+    /// Records which token ran which tool on authenticated transports
+    journal: Option<Arc<JournalContext>>,
 }
 
 impl McpServer {
     pub fn new(registry: ToolRegistry) -> Self {
+        let notifier = NotificationHub::new(128);
+        registry.attach(notifier.clone());
         Self {
             registry,
-            notifier: NotificationHub::new(128),
+            notifier,
+            resources: None,
+            prompts: None,
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            watcher_started: AtomicBool::new(false),
+            in_flight: Mutex::new(HashMap::new()),
+            journal: None,
         }
     }
 
+    /// Journal the token identity of every scoped `tools/call`.
+    pub fn with_journal(mut self, journal: Arc<JournalContext>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Publish resources through `resources/*`.
+    pub fn with_resources(mut self, resources: Arc<dyn McpResourceProvider>) -> Self {
+        self.resources = Some(resources);
+        self
+    }
+
+    /// Publish prompts through `prompts/*`.
+    pub fn with_prompts(mut self, prompts: Arc<dyn McpPromptProvider>) -> Self {
+        self.prompts = Some(prompts);
+        self
+    }
+
     pub fn notifier(&self) -> NotificationHub {
         self.notifier.clone()
     }
 
+    /// Handle on the shared tool registry, to add or remove tools at runtime.
+    pub fn registry(&self) -> ToolRegistry {
+        self.registry.clone()
+    }
+
     pub async fn serve_stdio(&self) -> Result<()> {
         eprintln!("🔍 DEBUG: Starting MCP server on STDIN/STDOUT");
         let stdin = io::stdin();
-        let mut reader = BufReader::new(stdin);
-        let mut stdout = io::stdout();
-        let mut line = String::new();
+        let mut lines = BufReader::new(stdin).lines();
+        let stdout = Arc::new(tokio::sync::Mutex::new(io::stdout()));
+        let forwarder = spawn_stdio_forwarder(self.notifier.subscribe(), Arc::clone(&stdout));
+        // Requests run concurrently so that `notifications/cancelled` is read
+        // while a long tool call is still in progress.
+        let mut pending = FuturesUnordered::new();
 
         loop {
-            line.clear();
-            match reader.read_line(&mut line).await {
-                Ok(0) => {
-                    eprintln!("🔍 DEBUG: Client disconnected (EOF)");
-                    break;
-                }
-                Ok(_) => {
-                    let trimmed = line.trim();
-                    if trimmed.is_empty() {
-                        continue;
-                    }
-                    eprintln!("🔍 DEBUG: Received line: {}", trimmed);
-
-                    match serde_json::from_str::<Value>(trimmed) {
-                        Ok(request) => {
-                            eprintln!("🔍 DEBUG: JSON parsed successfully");
-                            match self.handle_jsonrpc(request).await {
-                                Ok(Some(response)) => {
-                                    let response_line = serde_json::to_string(&response)? + "\n";
-                                    eprintln!("🔍 DEBUG: Sending: {}", response_line.trim());
-                                    stdout.write_all(response_line.as_bytes()).await?;
-                                    stdout.flush().await?;
-                                    eprintln!("🔍 DEBUG: Response sent successfully");
-                                }
-                                Ok(None) => {
-                                    eprintln!("🔍 DEBUG: No response emitted for this request");
-                                }
-                                Err(err) => {
-                                    eprintln!("🚨 ERROR in handle_jsonrpc: {}", err);
-                                    let error_response = json!({
-                                        "jsonrpc": "2.0",
-                                        "error": {"code": -32603, "message": "Internal error"}
-                                    });
-                                    let error_line = serde_json::to_string(&error_response)? + "\n";
-                                    stdout.write_all(error_line.as_bytes()).await?;
-                                    stdout.flush().await?;
-                                }
-                            }
-                        }
-                        Err(err) => {
-                            eprintln!("🚨 JSON PARSE ERROR: {}", err);
-                            eprintln!("🚨 Raw line was: '{}'", trimmed);
-                            let error_response = json!({
-                                "jsonrpc": "2.0",
-                                "error": {"code": -32700, "message": "Parse error"}
-                            });
-                            let error_line = serde_json::to_string(&error_response)? + "\n";
-                            stdout.write_all(error_line.as_bytes()).await?;
-                            stdout.flush().await?;
+            tokio::select! {
+                next = lines.next_line() => match next {
+                    Ok(Some(line)) => {
+                        let trimmed = line.trim();
+                        if trimmed.is_empty() {
+                            continue;
                         }
+                        eprintln!("🔍 DEBUG: Received line: {}", trimmed);
+                        pending.push(self.stdio_reply(trimmed.to_string()));
+                    }
+                    Ok(None) => {
+                        eprintln!("🔍 DEBUG: Client disconnected (EOF)");
+                        break;
+                    }
+                    Err(err) => {
+                        eprintln!("🚨 READ ERROR: {}", err);
+                        break;
+                    }
+                },
+                Some(reply) = pending.next(), if !pending.is_empty() => {
+                    if let Some(reply) = reply? {
+                        write_line(&stdout, &reply).await?;
                     }
-                }
-                Err(err) => {
-                    eprintln!("🚨 READ ERROR: {}", err);
-                    break;
                 }
             }
         }
 
+        // Nobody is left to read the answers: stop running calls.
+        self.cancel_in_flight();
+        while let Some(reply) = pending.next().await {
+            if let Some(reply) = reply? {
+                let _ = write_line(&stdout, &reply).await;
+            }
+        }
+
+        forwarder.abort();
         eprintln!("🔍 DEBUG: Client handler exiting");
         Ok(())
     }
 
+    /// Line to write back on stdout for one incoming line, if any.
+    async fn stdio_reply(&self, line: String) -> Result<Option<String>> {
+        match serde_json::from_str::<Value>(&line) {
+            Ok(request) => {
+                eprintln!("🔍 DEBUG: JSON parsed successfully");
+                // stdout is the only stream; the hub forwards to it.
+                let notifier = self.notifier.clone();
+                let stdout: SessionSink = Arc::new(move |payload| notifier.publish(payload));
+                let caller = Caller {
+                    stream: Some(&stdout),
+                    ..Caller::default()
+                };
+                match self.handle_jsonrpc_from(request, caller).await {
+                    Ok(Some(response)) => {
+                        let response_line = serde_json::to_string(&response)? + "\n";
+                        eprintln!("🔍 DEBUG: Sending: {}", response_line.trim());
+                        Ok(Some(response_line))
+                    }
+                    Ok(None) => {
+                        eprintln!("🔍 DEBUG: No response emitted for this request");
+                        Ok(None)
+                    }
+                    Err(err) => {
+                        eprintln!("🚨 ERROR in handle_jsonrpc: {}", err);
+                        let error_response = json!({
+                            "jsonrpc": "2.0",
+                            "error": {"code": -32603, "message": "Internal error"}
+                        });
+                        Ok(Some(serde_json::to_string(&error_response)? + "\n"))
+                    }
+                }
+            }
+            Err(err) => {
+                eprintln!("🚨 JSON PARSE ERROR: {}", err);
+                eprintln!("🚨 Raw line was: '{}'", line);
+                let error_response = json!({
+                    "jsonrpc": "2.0",
+                    "error": {"code": -32700, "message": "Parse error"}
+                });
+                Ok(Some(serde_json::to_string(&error_response)? + "\n"))
+            }
+        }
+    }
+
     pub async fn handle_jsonrpc(&self, request: Value) -> Result<Option<Value>> {
+        self.handle_jsonrpc_from(request, Caller::default()).await
+    }
+
+    /// Like [`Self::handle_jsonrpc`], restricting `tools/*` to the caller's
+    /// token scope when one is given.
+    pub async fn handle_jsonrpc_scoped(
+        &self,
+        request: Value,
+        scope: Option<&TokenScope>,
+    ) -> Result<Option<Value>> {
+        self.handle_jsonrpc_from(
+            request,
+            Caller {
+                scope,
+                ..Caller::default()
+            },
+        )
+        .await
+    }
+
+    /// Handle a message received from `caller`.
+    pub async fn handle_jsonrpc_from(
+        &self,
+        request: Value,
+        caller: Caller<'_>,
+    ) -> Result<Option<Value>> {
         let method = request
             .get("method")
             .and_then(Value::as_str)
@@ -168,6 +437,15 @@ impl McpServer {
             tracing::debug!("handling JSON-RPC method '{}'", method);
         }
 
+        if method == "notifications/cancelled" {
+            if let Some(request_id) = request
+                .get("params")
+                .and_then(|params| params.get("requestId"))
+            {
+                self.cancel_request(caller, request_id);
+            }
+        }
+
         if method.starts_with("notifications/") {
             let payload = json!({
                 "event": method,
@@ -178,7 +456,7 @@ impl McpServer {
         }
 
         let request_struct: JsonRpcRequest = serde_json::from_value(request.clone())?;
-        let response = handle_request(request_struct, &self.registry).await;
+        let response = self.handle_request(request_struct, caller).await;
         let value = serde_json::to_value(&response)?;
         tracing::debug!(
             "JSON-RPC response for '{}': {}",
@@ -191,6 +469,563 @@ impl McpServer {
     pub async fn serve_http(self: Arc<Self>, config: HttpTransportConfig) -> Result<()> {
         http_server::run_http_transport(self, config).await
     }
+
+    /// Forget what the server tracks for a closed transport session and
+    /// cancel its running calls.
+    pub fn close_session(&self, session_id: &str) {
+        self.subscriptions
+            .lock()
+            .expect("subscriptions lock poisoned")
+            .retain(|(session, _), _| session != session_id);
+        for ((session, _), token) in self
+            .in_flight
+            .lock()
+            .expect("in-flight lock poisoned")
+            .iter()
+        {
+            if session == session_id {
+                token.cancel();
+            }
+        }
+    }
+
+    async fn handle_request(&self, request: JsonRpcRequest, caller: Caller<'_>) -> JsonRpcResponse {
+        let scope = caller.scope;
+        let JsonRpcRequest {
+            jsonrpc,
+            id,
+            method,
+            params,
+        } = request;
+
+        if jsonrpc != "2.0" {
+            return JsonRpcResponse {
+                jsonrpc: "2.0",
+                id: id.unwrap_or(Value::Null),
+                result: None,
+                error: Some(JsonRpcError {
+                    code: -32600,
+                    message: format!("Unsupported JSON-RPC version: {jsonrpc}"),
+                    data: None,
+                }),
+            };
+        }
+
+        match method.as_str() {
+            "initialize" => respond_initialize(id, self.resources.is_some()),
+            "tools/list" => respond_with_tools(id, &self.registry, scope),
+            "tools/call" => self.handle_tools_call(id, params, caller).await,
+            "resources/list" => self.respond_with_resources(id, params, scope).await,
+            "resources/templates/list" => self.respond_with_resource_templates(id),
+            "resources/read" => self.handle_resources_read(id, params, scope).await,
+            "resources/subscribe" => self.handle_resources_subscribe(id, params, caller).await,
+            "resources/unsubscribe" => self.handle_resources_unsubscribe(id, params, caller),
+            "prompts/list" => self.respond_with_prompts(id).await,
+            "prompts/get" => self.handle_prompts_get(id, params, scope).await,
+            _ => JsonRpcResponse {
+                jsonrpc: "2.0",
+                id: id.unwrap_or(Value::Null),
+                result: None,
+                error: Some(JsonRpcError {
+                    code: -32601,
+                    message: format!("Unknown method: {method}"),
+                    data: None,
+                }),
+            },
+        }
+    }
+
+    async fn handle_tools_call(
+        &self,
+        id: Option<Value>,
+        params: Option<Value>,
+        caller: Caller<'_>,
+    ) -> JsonRpcResponse {
+        let id = id.unwrap_or(Value::Null);
+        let scope = caller.scope;
+
+        let params = match params {
+            Some(Value::Object(map)) => map,
+            _ => {
+                return rpc_error_response(
+                    id,
+                    McpError::InvalidRequest("Missing params object for tools/call".into()),
+                );
+            }
+        };
+
+        let name = match params.get("name").and_then(Value::as_str) {
+            Some(name) => name,
+            None => {
+                return rpc_error_response(
+                    id,
+                    McpError::InvalidRequest("Missing 'name' in params".into()),
+                );
+            }
+        };
+
+        let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+
+        let tool = match self.registry.get(name) {
+            Some(tool) => tool,
+            None => {
+                return rpc_error_response(id, McpError::ToolNotFound(name.to_string()));
+            }
+        };
+
+        if let Some(scope) = scope {
+            let decision = scope.authorize_call(name, &arguments);
+            self.journal_scoped_call(scope, name, decision.as_ref().err());
+            if let Err(reason) = decision {
+                tracing::warn!("denied tools/call '{}': {}", name, reason);
+                return scope_denied(id, reason, json!({ "tool": name, "token": scope.name }));
+            }
+        }
+
+        let token = CancellationToken::new();
+        let mut ctx = ToolContext::new().with_cancellation(token.clone());
+        if let (Some(progress_token), Some(stream)) = (
+            params
+                .get("_meta")
+                .and_then(|meta| meta.get("progressToken"))
+                .cloned(),
+            caller.stream,
+        ) {
+            let stream = Arc::clone(stream);
+            ctx = ctx.with_progress(move |update| {
+                let mut params = json!({
+                    "progressToken": progress_token,
+                    "progress": update.progress,
+                });
+                if let Some(total) = update.total {
+                    params["total"] = json!(total);
+                }
+                if let Some(message) = update.message {
+                    params["message"] = json!(message);
+                }
+                stream(json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/progress",
+                    "params": params,
+                }));
+            });
+        }
+
+        let key = (caller.session_id().to_string(), id.to_string());
+        self.in_flight
+            .lock()
+            .expect("in-flight lock poisoned")
+            .insert(key.clone(), token);
+        let result = tool.execute_with_context(arguments, &ctx).await;
+        self.in_flight
+            .lock()
+            .expect("in-flight lock poisoned")
+            .remove(&key);
+
+        match result {
+            Ok(result) => rpc_result_response(id, result),
+            Err(err) => rpc_error_response(id, err),
+        }
+    }
+
+    fn journal_scoped_call(&self, scope: &TokenScope, tool: &str, denied: Option<&String>) {
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        let mut details = HashMap::new();
+        details.insert("token".to_string(), scope.name.clone());
+        details.insert("tool".to_string(), tool.to_string());
+        details.insert(
+            "decision".to_string(),
+            if denied.is_some() {
+                "denied"
+            } else {
+                "allowed"
+            }
+            .to_string(),
+        );
+        if let Some(reason) = denied {
+            details.insert("reason".to_string(), reason.clone());
+        }
+        if let Err(err) = journal.append("mcp_tool_call", &details) {
+            tracing::warn!("failed to journal tools/call '{}': {}", tool, err);
+        }
+    }
+
+    /// Cancel the call `request_id` of the caller's session.
+    fn cancel_request(&self, caller: Caller<'_>, request_id: &Value) {
+        let key = (caller.session_id().to_string(), request_id.to_string());
+        let token = self
+            .in_flight
+            .lock()
+            .expect("in-flight lock poisoned")
+            .get(&key)
+            .cloned();
+        match token {
+            Some(token) => {
+                tracing::info!("cancelling tools/call {}", request_id);
+                token.cancel();
+            }
+            None => tracing::debug!("cancel for unknown or finished request {}", request_id),
+        }
+    }
+
+    fn cancel_in_flight(&self) {
+        for token in self
+            .in_flight
+            .lock()
+            .expect("in-flight lock poisoned")
+            .values()
+        {
+            token.cancel();
+        }
+    }
+
+    async fn respond_with_prompts(&self, id: Option<Value>) -> JsonRpcResponse {
+        let id = id.unwrap_or(Value::Null);
+        let Some(provider) = &self.prompts else {
+            return rpc_result_response(id, json!({ "prompts": [] }));
+        };
+        match provider.list().await {
+            Ok(prompts) => rpc_result_response(id, json!({ "prompts": prompts })),
+            Err(err) => rpc_error_response(id, err),
+        }
+    }
+
+    async fn handle_prompts_get(
+        &self,
+        id: Option<Value>,
+        params: Option<Value>,
+        scope: Option<&TokenScope>,
+    ) -> JsonRpcResponse {
+        let id = id.unwrap_or(Value::Null);
+        let Some(name) = params
+            .as_ref()
+            .and_then(|params| params.get("name"))
+            .and_then(Value::as_str)
+        else {
+            return rpc_error_response(
+                id,
+                McpError::InvalidRequest("Missing 'name' in params".into()),
+            );
+        };
+        let Some(provider) = &self.prompts else {
+            return rpc_error_response(
+                id,
+                McpError::rpc(-32602, format!("Unknown prompt: {name}"), None),
+            );
+        };
+
+        let arguments: HashMap<String, String> = params
+            .as_ref()
+            .and_then(|params| params.get("arguments"))
+            .and_then(Value::as_object)
+            .map(|arguments| {
+                arguments
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = match value {
+                            Value::String(text) => text.clone(),
+                            other => other.to_string(),
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if let Some(scope) = scope {
+            if let Err(reason) = scope.authorize_prompt(name, &arguments) {
+                tracing::warn!("denied prompts/get '{}': {}", name, reason);
+                return scope_denied(id, reason, json!({ "prompt": name, "token": scope.name }));
+            }
+        }
+
+        match provider.get(name, &arguments).await {
+            Ok(prompt) => rpc_result_response(id, prompt.to_value()),
+            Err(err) => rpc_error_response(id, err),
+        }
+    }
+
+    async fn respond_with_resources(
+        &self,
+        id: Option<Value>,
+        params: Option<Value>,
+        scope: Option<&TokenScope>,
+    ) -> JsonRpcResponse {
+        let id = id.unwrap_or(Value::Null);
+        let Some(provider) = &self.resources else {
+            return rpc_result_response(id, json!({ "resources": [] }));
+        };
+
+        let offset = match params
+            .as_ref()
+            .and_then(|params| params.get("cursor"))
+            .and_then(Value::as_str)
+        {
+            Some(cursor) => match cursor.parse::<usize>() {
+                Ok(offset) => offset,
+                Err(_) => {
+                    return rpc_error_response(
+                        id,
+                        McpError::rpc(-32602, format!("Invalid cursor: {cursor}"), None),
+                    )
+                }
+            },
+            None => 0,
+        };
+
+        match provider.list().await {
+            Ok(mut resources) => {
+                if let Some(scope) = scope {
+                    resources.retain(|resource| scope.authorize_resource(&resource.uri).is_ok());
+                }
+                let end = (offset + RESOURCE_PAGE_SIZE).min(resources.len());
+                let page = resources.get(offset..end).unwrap_or_default();
+                let mut result = json!({ "resources": page });
+                if end < resources.len() {
+                    result["nextCursor"] = Value::String(end.to_string());
+                }
+                rpc_result_response(id, result)
+            }
+            Err(err) => rpc_error_response(id, err),
+        }
+    }
+
+    fn respond_with_resource_templates(&self, id: Option<Value>) -> JsonRpcResponse {
+        let templates = self
+            .resources
+            .as_ref()
+            .map(|provider| provider.templates())
+            .unwrap_or_default();
+        rpc_result_response(
+            id.unwrap_or(Value::Null),
+            json!({ "resourceTemplates": templates }),
+        )
+    }
+
+    /// Provider and `uri` param of a `resources/*` request the scope allows.
+    fn resource_target(
+        &self,
+        params: Option<&Value>,
+        scope: Option<&TokenScope>,
+    ) -> McpResult<(&Arc<dyn McpResourceProvider>, String)> {
+        let uri = params
+            .and_then(|params| params.get("uri"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| McpError::InvalidRequest("Missing 'uri' in params".into()))?;
+        if let Some(scope) = scope {
+            if let Err(reason) = scope.authorize_resource(uri) {
+                tracing::warn!("denied resource '{}': {}", uri, reason);
+                return Err(McpError::Rpc {
+                    code: SCOPE_DENIED,
+                    message: reason,
+                    data: Some(json!({ "uri": uri, "token": scope.name })),
+                });
+            }
+        }
+        let provider = self.resources.as_ref().ok_or_else(|| {
+            McpError::rpc(
+                -32002,
+                format!("Resource not found: {uri}"),
+                Some(json!({ "uri": uri })),
+            )
+        })?;
+        Ok((provider, uri.to_string()))
+    }
+
+    async fn handle_resources_read(
+        &self,
+        id: Option<Value>,
+        params: Option<Value>,
+        scope: Option<&TokenScope>,
+    ) -> JsonRpcResponse {
+        let id = id.unwrap_or(Value::Null);
+        let (provider, uri) = match self.resource_target(params.as_ref(), scope) {
+            Ok(target) => target,
+            Err(err) => return rpc_error_response(id, err),
+        };
+        match provider.read(&uri).await {
+            Ok(contents) => rpc_result_response(id, json!({ "contents": contents })),
+            Err(err) => rpc_error_response(id, err),
+        }
+    }
+
+    async fn handle_resources_subscribe(
+        &self,
+        id: Option<Value>,
+        params: Option<Value>,
+        caller: Caller<'_>,
+    ) -> JsonRpcResponse {
+        let id = id.unwrap_or(Value::Null);
+        let (provider, uri) = match self.resource_target(params.as_ref(), caller.scope) {
+            Ok(target) => target,
+            Err(err) => return rpc_error_response(id, err),
+        };
+        let fingerprint = match provider.read(&uri).await {
+            Ok(contents) => fingerprint(&contents),
+            Err(err) => return rpc_error_response(id, err),
+        };
+
+        self.subscriptions
+            .lock()
+            .expect("subscriptions lock poisoned")
+            .insert(
+                (caller.session_id().to_string(), uri),
+                Subscription {
+                    fingerprint,
+                    notify: caller.session.map(|session| Arc::clone(&session.notify)),
+                },
+            );
+        if !self.watcher_started.swap(true, Ordering::SeqCst) {
+            tokio::spawn(watch_resources(
+                Arc::clone(provider),
+                Arc::clone(&self.subscriptions),
+                self.notifier.clone(),
+            ));
+        }
+        rpc_result_response(id, json!({}))
+    }
+
+    fn handle_resources_unsubscribe(
+        &self,
+        id: Option<Value>,
+        params: Option<Value>,
+        caller: Caller<'_>,
+    ) -> JsonRpcResponse {
+        let id = id.unwrap_or(Value::Null);
+        let Some(uri) = params
+            .as_ref()
+            .and_then(|params| params.get("uri"))
+            .and_then(Value::as_str)
+        else {
+            return rpc_error_response(
+                id,
+                McpError::InvalidRequest("Missing 'uri' in params".into()),
+            );
+        };
+        self.subscriptions
+            .lock()
+            .expect("subscriptions lock poisoned")
+            .remove(&(caller.session_id().to_string(), uri.to_string()));
+        rpc_result_response(id, json!({}))
+    }
+}
+
+fn scope_denied(id: Value, reason: String, data: Value) -> JsonRpcResponse {
+    rpc_error_response(
+        id,
+        McpError::Rpc {
+            code: SCOPE_DENIED,
+            message: reason,
+            data: Some(data),
+        },
+    )
+}
+
+fn fingerprint(contents: &[ResourceContents]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for content in contents {
+        content.text.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Re-read subscribed resources and send `notifications/resources/updated`
+/// to the sessions subscribed to those whose content changed.
+async fn watch_resources(
+    provider: Arc<dyn McpResourceProvider>,
+    subscriptions: Subscriptions,
+    notifier: NotificationHub,
+) {
+    let mut interval = tokio::time::interval(RESOURCE_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let watched: Vec<((String, String), u64)> = subscriptions
+            .lock()
+            .expect("subscriptions lock poisoned")
+            .iter()
+            .map(|(key, subscription)| (key.clone(), subscription.fingerprint))
+            .collect();
+
+        // Each URI is read once per round, whatever its number of subscribers.
+        let mut current_of: HashMap<String, u64> = HashMap::new();
+        for (key, previous) in watched {
+            let uri = &key.1;
+            let current = match current_of.get(uri) {
+                Some(current) => *current,
+                None => {
+                    // A resource that can no longer be read (deleted file,
+                    // purged task) is reported once as updated.
+                    let current = provider
+                        .read(uri)
+                        .await
+                        .map(|contents| fingerprint(&contents))
+                        .unwrap_or(0);
+                    current_of.insert(uri.clone(), current);
+                    current
+                }
+            };
+            if current == previous {
+                continue;
+            }
+            let notify = match subscriptions
+                .lock()
+                .expect("subscriptions lock poisoned")
+                .get_mut(&key)
+            {
+                Some(subscription) => {
+                    subscription.fingerprint = current;
+                    Some(subscription.notify.clone())
+                }
+                None => None,
+            };
+            let params = json!({ "uri": uri });
+            match notify {
+                Some(Some(notify)) => notify(json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/resources/updated",
+                    "params": params,
+                })),
+                Some(None) => notifier.publish_server("notifications/resources/updated", params),
+                None => {}
+            }
+        }
+    }
+}
+
+async fn write_line(stdout: &tokio::sync::Mutex<io::Stdout>, line: &str) -> io::Result<()> {
+    let mut stdout = stdout.lock().await;
+    stdout.write_all(line.as_bytes()).await?;
+    stdout.flush().await
+}
+
+/// Forward server-initiated notifications from the hub to the stdio client.
+fn spawn_stdio_forwarder(
+    mut receiver: broadcast::Receiver<Value>,
+    stdout: Arc<tokio::sync::Mutex<io::Stdout>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let payload = match receiver.recv().await {
+                Ok(payload) => payload,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("stdio notification forwarder lagged by {skipped} messages");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if payload.get("jsonrpc").is_none() {
+                continue;
+            }
+            let Ok(line) = serde_json::to_string(&payload) else {
+                continue;
+            };
+            if write_line(&stdout, &(line + "\n")).await.is_err() {
+                break;
+            }
+        }
+    })
 }
 
 #[derive(Debug, Deserialize)]
@@ -221,48 +1056,15 @@ struct JsonRpcError {
     data: Option<Value>,
 }
 
-async fn handle_request(request: JsonRpcRequest, registry: &ToolRegistry) -> JsonRpcResponse {
-    let JsonRpcRequest {
-        jsonrpc,
-        id,
-        method,
-        params,
-    } = request;
-
-    if jsonrpc != "2.0" {
-        return JsonRpcResponse {
-            jsonrpc: "2.0",
-            id: id.unwrap_or(Value::Null),
-            result: None,
-            error: Some(JsonRpcError {
-                code: -32600,
-                message: format!("Unsupported JSON-RPC version: {jsonrpc}"),
-                data: None,
-            }),
-        };
-    }
-
-    match method.as_str() {
-        "initialize" => respond_initialize(id),
-        "tools/list" => respond_with_tools(id, registry),
-        "tools/call" => handle_tools_call(id, params, registry).await,
-        "resources/list" => respond_with_resources(id),
-        "prompts/list" => respond_with_prompts(id),
-        _ => JsonRpcResponse {
-            jsonrpc: "2.0",
-            id: id.unwrap_or(Value::Null),
-            result: None,
-            error: Some(JsonRpcError {
-                code: -32601,
-                message: format!("Unknown method: {method}"),
-                data: None,
-            }),
-        },
+fn respond_with_tools(
+    id: Option<Value>,
+    registry: &ToolRegistry,
+    scope: Option<&TokenScope>,
+) -> JsonRpcResponse {
+    let mut tools = registry.descriptors();
+    if let Some(scope) = scope {
+        tools.retain(|tool| scope.allows_tool(&tool.name));
     }
-}
-
-fn respond_with_tools(id: Option<Value>, registry: &ToolRegistry) -> JsonRpcResponse {
-    let tools = registry.descriptors();
     JsonRpcResponse {
         jsonrpc: "2.0",
         id: id.unwrap_or(Value::Null),
@@ -271,34 +1073,16 @@ fn respond_with_tools(id: Option<Value>, registry: &ToolRegistry) -> JsonRpcResp
     }
 }
 
-fn respond_with_resources(id: Option<Value>) -> JsonRpcResponse {
-    JsonRpcResponse {
-        jsonrpc: "2.0",
-        id: id.unwrap_or(Value::Null),
-        result: Some(json!({ "resources": [] })),
-        error: None,
-    }
-}
-
-fn respond_with_prompts(id: Option<Value>) -> JsonRpcResponse {
-    JsonRpcResponse {
-        jsonrpc: "2.0",
-        id: id.unwrap_or(Value::Null),
-        result: Some(json!({ "prompts": [] })),
-        error: None,
-    }
-}
-
-fn respond_initialize(id: Option<Value>) -> JsonRpcResponse {
+fn respond_initialize(id: Option<Value>, resources: bool) -> JsonRpcResponse {
     JsonRpcResponse {
         jsonrpc: "2.0",
         id: id.unwrap_or(Value::Null),
         result: Some(json!({
             "protocolVersion": "2025-06-18",
             "capabilities": {
-                "tools": { "listChanged": false },
+                "tools": { "listChanged": true },
                 "prompts": { "listChanged": false },
-                "resources": { "listChanged": false, "subscribe": false }
+                "resources": { "listChanged": false, "subscribe": resources }
             },
             "serverInfo": {
                 "name": "mcp-server",
@@ -309,50 +1093,12 @@ fn respond_initialize(id: Option<Value>) -> JsonRpcResponse {
     }
 }
 
-async fn handle_tools_call(
-    id: Option<Value>,
-    params: Option<Value>,
-    registry: &ToolRegistry,
-) -> JsonRpcResponse {
-    let id = id.unwrap_or(Value::Null);
-
-    let params = match params {
-        Some(Value::Object(map)) => map,
-        _ => {
-            return rpc_error_response(
-                id,
-                McpError::InvalidRequest("Missing params object for tools/call".into()),
-            );
-        }
-    };
-
-    let name = match params.get("name").and_then(Value::as_str) {
-        Some(name) => name,
-        None => {
-            return rpc_error_response(
-                id,
-                McpError::InvalidRequest("Missing 'name' in params".into()),
-            );
-        }
-    };
-
-    let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
-
-    let tool = match registry.get(name) {
-        Some(tool) => tool,
-        None => {
-            return rpc_error_response(id, McpError::ToolNotFound(name.to_string()));
-        }
-    };
-
-    match tool.execute(arguments).await {
-        Ok(result) => JsonRpcResponse {
-            jsonrpc: "2.0",
-            id,
-            result: Some(result),
-            error: None,
-        },
-        Err(err) => rpc_error_response(id, err),
+fn rpc_result_response(id: Value, result: Value) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0",
+        id,
+        result: Some(result),
+        error: None,
     }
 }
 
@@ -368,3 +1114,393 @@ fn rpc_error_response(id: Value, error: McpError) -> JsonRpcResponse {
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use mcp_core::{ResourceDescriptor, ResourceTemplate};
+
+    struct Counter(Mutex<u32>);
+
+    #[async_trait]
+    impl McpResourceProvider for Counter {
+        async fn list(&self) -> McpResult<Vec<ResourceDescriptor>> {
+            Ok((0..RESOURCE_PAGE_SIZE + 1)
+                .map(|i| ResourceDescriptor {
+                    uri: format!("test://{i}"),
+                    name: i.to_string(),
+                    description: None,
+                    mime_type: None,
+                })
+                .collect())
+        }
+
+        fn templates(&self) -> Vec<ResourceTemplate> {
+            Vec::new()
+        }
+
+        async fn read(&self, uri: &str) -> McpResult<Vec<ResourceContents>> {
+            Ok(vec![ResourceContents {
+                uri: uri.to_string(),
+                mime_type: None,
+                text: self.0.lock().unwrap().to_string(),
+            }])
+        }
+    }
+
+    struct Named(&'static str);
+
+    #[async_trait]
+    impl McpTool for Named {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn description(&self) -> &str {
+            "test tool"
+        }
+
+        async fn execute(&self, _params: Value) -> McpResult<Value> {
+            Ok(json!({}))
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    /// Tool whose description changes between versions.
+    struct Versioned(&'static str, &'static str);
+
+    #[async_trait]
+    impl McpTool for Versioned {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn description(&self) -> &str {
+            self.1
+        }
+
+        async fn execute(&self, _params: Value) -> McpResult<Value> {
+            Ok(json!({}))
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    /// Reports one progress step then waits until the call is cancelled.
+    struct Slow;
+
+    #[async_trait]
+    impl McpTool for Slow {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn description(&self) -> &str {
+            "never finishes on its own"
+        }
+
+        async fn execute(&self, params: Value) -> McpResult<Value> {
+            self.execute_with_context(params, &ToolContext::default())
+                .await
+        }
+
+        async fn execute_with_context(
+            &self,
+            _params: Value,
+            ctx: &ToolContext,
+        ) -> McpResult<Value> {
+            ctx.report_progress(1.0, Some(2.0), "halfway".to_string());
+            ctx.cancelled().await;
+            Err(McpError::cancelled())
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    fn sink() -> (SessionSink, tokio::sync::mpsc::UnboundedReceiver<Value>) {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let sink: SessionSink = Arc::new(move |payload| {
+            let _ = sender.send(payload);
+        });
+        (sink, receiver)
+    }
+
+    fn client_session(id: &str) -> (ClientSession, tokio::sync::mpsc::UnboundedReceiver<Value>) {
+        let (notify, receiver) = sink();
+        let session = ClientSession {
+            id: id.to_string(),
+            notify,
+        };
+        (session, receiver)
+    }
+
+    fn request(method: &str, params: Value) -> Value {
+        json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params})
+    }
+
+    #[tokio::test]
+    async fn resources_are_paginated_and_subscriptions_notify_changes() {
+        let provider = Arc::new(Counter(Mutex::new(0)));
+        let server = McpServer::new(ToolRegistry::new(Vec::new())).with_resources(provider.clone());
+
+        let first = server
+            .handle_jsonrpc(request("resources/list", json!({})))
+            .await
+            .unwrap()
+            .unwrap();
+        let cursor = first["result"]["nextCursor"].as_str().unwrap().to_string();
+        let second = server
+            .handle_jsonrpc(request("resources/list", json!({ "cursor": cursor })))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second["result"]["resources"].as_array().unwrap().len(), 1);
+        assert!(second["result"].get("nextCursor").is_none());
+
+        let mut events = server.notifier().subscribe();
+        let subscribed = server
+            .handle_jsonrpc(request("resources/subscribe", json!({"uri": "test://0"})))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(subscribed.get("error").is_none(), "{subscribed}");
+
+        *provider.0.lock().unwrap() += 1;
+        let event = tokio::time::timeout(RESOURCE_POLL_INTERVAL * 3, events.recv())
+            .await
+            .expect("update notification")
+            .unwrap();
+        assert_eq!(event["method"], "notifications/resources/updated");
+        assert_eq!(event["params"]["uri"], "test://0");
+    }
+
+    #[tokio::test]
+    async fn resource_updates_reach_only_subscribed_sessions() {
+        let provider = Arc::new(Counter(Mutex::new(0)));
+        let server = McpServer::new(ToolRegistry::new(Vec::new())).with_resources(provider.clone());
+        let (alice, mut alice_events) = client_session("alice");
+        let (bob, mut bob_events) = client_session("bob");
+        let mut hub_events = server.notifier().subscribe();
+
+        let subscribe = request("resources/subscribe", json!({"uri": "test://0"}));
+        let caller = Caller {
+            session: Some(&alice),
+            ..Caller::default()
+        };
+        let subscribed = server
+            .handle_jsonrpc_from(subscribe.clone(), caller)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(subscribed.get("error").is_none(), "{subscribed}");
+
+        *provider.0.lock().unwrap() += 1;
+        let event = tokio::time::timeout(RESOURCE_POLL_INTERVAL * 3, alice_events.recv())
+            .await
+            .expect("update notification")
+            .unwrap();
+        assert_eq!(event["method"], "notifications/resources/updated");
+        assert!(bob_events.try_recv().is_err());
+        assert!(hub_events.try_recv().is_err());
+
+        // A closed session stops receiving updates
+        let caller = Caller {
+            session: Some(&bob),
+            ..Caller::default()
+        };
+        server.handle_jsonrpc_from(subscribe, caller).await.unwrap();
+        server.close_session("alice");
+        *provider.0.lock().unwrap() += 1;
+        tokio::time::timeout(RESOURCE_POLL_INTERVAL * 3, bob_events.recv())
+            .await
+            .expect("update notification")
+            .unwrap();
+        assert!(alice_events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn reconciled_tools_are_listed_and_announced() {
+        let server = McpServer::new(ToolRegistry::new(vec![Arc::new(Named("static"))]));
+        let registry = server.registry();
+        let mut events = server.notifier().subscribe();
+
+        assert!(registry.reconcile("plugins", vec![Arc::new(Named("plugin_a"))]));
+        let event = events.try_recv().expect("list_changed notification");
+        assert_eq!(event["method"], "notifications/tools/list_changed");
+
+        let listed = server
+            .handle_jsonrpc(request("tools/list", json!({})))
+            .await
+            .unwrap()
+            .unwrap();
+        let names: Vec<&str> = listed["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|tool| tool["name"].as_str())
+            .collect();
+        assert_eq!(names, vec!["static", "plugin_a"]);
+
+        // Same set again: nothing to announce.
+        assert!(!registry.reconcile("plugins", vec![Arc::new(Named("plugin_a"))]));
+        assert!(events.try_recv().is_err());
+
+        // Same name, new descriptor: the tool is replaced and announced.
+        assert!(registry.reconcile("plugins", vec![Arc::new(Versioned("plugin_a", "upgraded"))]));
+        assert!(events.try_recv().is_ok());
+        assert_eq!(registry.get("plugin_a").unwrap().description(), "upgraded");
+
+        // Only tools owned by the group are dropped.
+        assert!(registry.reconcile("plugins", Vec::new()));
+        assert!(!registry.contains("plugin_a"));
+        assert!(registry.contains("static"));
+        assert!(events.try_recv().is_ok());
+    }
+
+    #[tokio::test]
+    async fn tool_calls_report_progress_and_honour_cancellation() {
+        let server = Arc::new(McpServer::new(ToolRegistry::new(vec![Arc::new(Slow)])));
+        let mut hub_events = server.notifier().subscribe();
+        let (alice, _) = client_session("alice");
+        let (bob, _) = client_session("bob");
+        let (stream, mut stream_events) = sink();
+
+        let call = tokio::spawn({
+            let server = server.clone();
+            let alice = alice.clone();
+            async move {
+                let caller = Caller {
+                    session: Some(&alice),
+                    stream: Some(&stream),
+                    ..Caller::default()
+                };
+                let call = json!({
+                    "jsonrpc": "2.0",
+                    "id": 7,
+                    "method": "tools/call",
+                    "params": {
+                        "name": "slow",
+                        "arguments": {},
+                        "_meta": {"progressToken": "tok"}
+                    }
+                });
+                server.handle_jsonrpc_from(call, caller).await
+            }
+        });
+
+        let progress = tokio::time::timeout(Duration::from_secs(5), stream_events.recv())
+            .await
+            .expect("progress notification")
+            .unwrap();
+        assert_eq!(progress["method"], "notifications/progress");
+        assert_eq!(progress["params"]["progressToken"], "tok");
+        assert_eq!(progress["params"]["total"], 2.0);
+        assert_eq!(progress["params"]["message"], "halfway");
+        assert!(hub_events.try_recv().is_err());
+
+        let cancel = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": {"requestId": 7, "reason": "user abort"}
+        });
+        // The same JSON-RPC id in another session is another request.
+        let other = Caller {
+            session: Some(&bob),
+            ..Caller::default()
+        };
+        server
+            .handle_jsonrpc_from(cancel.clone(), other)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!call.is_finished());
+
+        let owner = Caller {
+            session: Some(&alice),
+            ..Caller::default()
+        };
+        let ack = server.handle_jsonrpc_from(cancel, owner).await.unwrap();
+        assert!(ack.is_none());
+
+        let response = tokio::time::timeout(Duration::from_secs(5), call)
+            .await
+            .expect("cancelled call returns")
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(response["error"]["code"], mcp_core::REQUEST_CANCELLED);
+        assert!(server.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn token_scopes_filter_listing_and_calls() {
+        let server = McpServer::new(ToolRegistry::new(vec![
+            Arc::new(Named("devit_file_read")),
+            Arc::new(Named("devit_exec")),
+        ]));
+        let mut scope = TokenScope::unrestricted("review-bot", std::path::PathBuf::from("/work"));
+        scope.max_approval = Some(devit_common::ApprovalLevel::Untrusted);
+
+        let listed = server
+            .handle_jsonrpc_scoped(request("tools/list", json!({})), Some(&scope))
+            .await
+            .unwrap()
+            .unwrap();
+        let names: Vec<&str> = listed["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|tool| tool["name"].as_str())
+            .collect();
+        assert_eq!(names, vec!["devit_file_read"]);
+
+        let denied = server
+            .handle_jsonrpc_scoped(
+                request("tools/call", json!({"name": "devit_exec", "arguments": {}})),
+                Some(&scope),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(denied["error"]["code"], SCOPE_DENIED);
+        assert_eq!(denied["error"]["data"]["token"], "review-bot");
+
+        let allowed = server
+            .handle_jsonrpc(request(
+                "tools/call",
+                json!({"name": "devit_exec", "arguments": {}}),
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(allowed.get("error").is_none(), "{allowed}");
+
+        // Resources are checked like the reads they stand for.
+        let server = server.with_resources(Arc::new(Counter(Mutex::new(0))));
+        scope.roots = vec![std::path::PathBuf::from("/work/src")];
+        let listed = server
+            .handle_jsonrpc_scoped(request("resources/list", json!({})), Some(&scope))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(listed["result"]["resources"], json!([]));
+        let denied = server
+            .handle_jsonrpc_scoped(
+                request("resources/read", json!({"uri": "test://0"})),
+                Some(&scope),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(denied["error"]["code"], SCOPE_DENIED);
+        assert_eq!(denied["error"]["data"]["uri"], "test://0");
+    }
+}
@@ -2,6 +2,7 @@ use std::{
     env, fs, io,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{Context, Result};
@@ -12,9 +13,18 @@ use mcp_server::{
     transport::{self, CliTransportOptions, Transport},
     McpServer, ToolRegistry,
 };
-use mcp_tools::{default_tools_with_options, ToolOptions, WorkerBridge};
+use mcp_tools::{
+    default_tools_and_resources, worker_tools, DynamicTools, FileSystemContext, JournalContext,
+    ToolOptions, WorkerBridge, WorkspacePrompts,
+};
 use serde_json::{json, Value};
 const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Registry group owning plugin and config-toggled tools
+const DYNAMIC_TOOLS_GROUP: &str = "dynamic";
+/// Period at which plugins and capability toggles are re-scanned
+const DYNAMIC_TOOLS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Delay between attempts to reach devitd when worker mode starts without it
+const WORKER_ATTACH_RETRY_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Parser, Debug)]
 #[command(name = "devit-mcp-server", version = env!("CARGO_PKG_VERSION"))]
@@ -63,6 +73,18 @@ struct Args {
     #[arg(long = "cors-origin", value_name = "ORIGIN")]
     cors_origins: Vec<String>,
 
+    /// PEM certificate chain served over HTTPS (reloaded on SIGHUP)
+    #[arg(long = "tls-cert", value_name = "FILE")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key matching --tls-cert
+    #[arg(long = "tls-key", value_name = "FILE")]
+    tls_key: Option<PathBuf>,
+
+    /// PEM CA bundle required to sign client certificates (mutual TLS)
+    #[arg(long = "tls-client-ca", value_name = "FILE")]
+    tls_client_ca: Option<PathBuf>,
+
     /// Run the MCP server in worker mode (connect to devitd as a worker)
     #[arg(long = "worker-mode")]
     worker_mode: bool,
@@ -117,6 +139,7 @@ async fn main() -> Result<()> {
     env::set_var("DEVIT_FORCE_ROOT", &working_dir);
     let core_config_path = env::var("DEVIT_CORE_CONFIG").ok().map(PathBuf::from);
 
+    let mut pending_worker = None;
     let worker_bridge = if args.worker_mode {
         let worker_id = args
             .worker_id
@@ -134,15 +157,27 @@ async fn main() -> Result<()> {
             env::set_var("DEVIT_SECRET", secret);
         }
 
-        let bridge = WorkerBridge::connect(
+        match WorkerBridge::connect(
             working_dir.clone(),
             &socket,
             worker_id.clone(),
             args.secret.clone(),
         )
         .await
-        .with_context(|| format!("Failed to initialize worker bridge for {}", worker_id))?;
-        Some(bridge)
+        {
+            Ok(bridge) => Some(bridge),
+            Err(err) => {
+                // Serve the other tools now; the worker ones are added once
+                // the daemon answers.
+                tracing::warn!(
+                    "Failed to initialize worker bridge for {}: {:#}; retrying in the background",
+                    worker_id,
+                    err
+                );
+                pending_worker = Some((socket, worker_id, args.secret.clone()));
+                None
+            }
+        }
     } else {
         None
     };
@@ -152,7 +187,7 @@ async fn main() -> Result<()> {
         tool_options.worker_bridge = Some(bridge);
     }
 
-    let mut tools = default_tools_with_options(working_dir.clone(), tool_options)
+    let (mut tools, resources) = default_tools_and_resources(working_dir.clone(), tool_options)
         .await
         .map_err(|err| anyhow::anyhow!(err.to_string()))?;
 
@@ -185,7 +220,30 @@ async fn main() -> Result<()> {
     }
 
     let registry = ToolRegistry::new(tools);
-    let server = Arc::new(McpServer::new(registry));
+    let dynamic_tools = DynamicTools::new(working_dir.clone());
+    registry.reconcile(DYNAMIC_TOOLS_GROUP, dynamic_tools.scan());
+    tokio::spawn(watch_dynamic_tools(registry.clone(), dynamic_tools));
+    if let Some((socket, worker_id, secret)) = pending_worker {
+        tokio::spawn(attach_worker(
+            registry.clone(),
+            working_dir.clone(),
+            socket,
+            worker_id,
+            secret,
+        ));
+    }
+    let workspace = Arc::new(
+        FileSystemContext::new(working_dir.clone())
+            .map_err(|err| anyhow::anyhow!(err.to_string()))?,
+    );
+    let prompts = WorkspacePrompts::new(workspace.clone());
+    let journal = JournalContext::new(workspace).map_err(|err| anyhow::anyhow!(err.to_string()))?;
+    let server = Arc::new(
+        McpServer::new(registry)
+            .with_resources(resources)
+            .with_prompts(Arc::new(prompts))
+            .with_journal(Arc::new(journal)),
+    );
 
     let cli_transport = CliTransportOptions {
         transport: args.transport.clone(),
@@ -201,6 +259,9 @@ async fn main() -> Result<()> {
         tokens: args.auth_tokens.clone(),
         tokens_file: args.tokens_file.clone(),
         cors_origins: args.cors_origins.clone(),
+        tls_cert: args.tls_cert.clone(),
+        tls_key: args.tls_key.clone(),
+        tls_client_ca: args.tls_client_ca.clone(),
     };
 
     let file_transport = transport::load_file_config(core_config_path.as_deref())?;
@@ -212,13 +273,74 @@ async fn main() -> Result<()> {
             server.serve_stdio().await?;
         }
         Transport::Http(http_cfg) => {
-            server.clone().serve_http(http_cfg).await?;
+            server.clone().serve_http(*http_cfg).await?;
         }
     }
 
     Ok(())
 }
 
+async fn watch_dynamic_tools(registry: ToolRegistry, dynamic_tools: DynamicTools) {
+    let dynamic_tools = Arc::new(dynamic_tools);
+    let mut ticker = tokio::time::interval(DYNAMIC_TOOLS_POLL_INTERVAL);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let scanner = Arc::clone(&dynamic_tools);
+        let desired = match tokio::task::spawn_blocking(move || scanner.scan()).await {
+            Ok(tools) => tools,
+            Err(err) => {
+                tracing::warn!("dynamic tools scan failed: {}", err);
+                continue;
+            }
+        };
+        if registry.reconcile(DYNAMIC_TOOLS_GROUP, desired) {
+            tracing::info!("tool list changed; notified connected clients");
+        }
+    }
+}
+
+/// Connect to devitd until it answers, then register the worker-mode tools.
+async fn attach_worker(
+    registry: ToolRegistry,
+    working_dir: PathBuf,
+    socket: PathBuf,
+    worker_id: String,
+    secret: Option<String>,
+) {
+    loop {
+        tokio::time::sleep(WORKER_ATTACH_RETRY_INTERVAL).await;
+        let bridge = match WorkerBridge::connect(
+            working_dir.clone(),
+            &socket,
+            worker_id.clone(),
+            secret.clone(),
+        )
+        .await
+        {
+            Ok(bridge) => bridge,
+            Err(err) => {
+                tracing::debug!(
+                    "worker bridge for {} still unavailable: {:#}",
+                    worker_id,
+                    err
+                );
+                continue;
+            }
+        };
+        match worker_tools(working_dir.clone(), bridge).await {
+            Ok(tools) => {
+                for tool in tools {
+                    registry.register(tool);
+                }
+                tracing::info!("worker {} attached; worker tools registered", worker_id);
+                return;
+            }
+            Err(err) => tracing::warn!("cannot build worker tools for {}: {}", worker_id, err),
+        }
+    }
+}
+
 fn init_tracing(args: &Args) {
     if let Some(level) = &args.log_level {
         env::set_var("RUST_LOG", level);
@@ -0,0 +1,490 @@
+//! Per-token authorization scopes for the HTTP transport.
+//!
+//! A scope restricts which tools a bearer token may list and call (name
+//! globs plus a maximum approval level) and which workspace sub-roots the
+//! call arguments may point at. Scopes are built from the tokens file by
+//! [`crate::transport`] and enforced by the server on `tools/*`,
+//! `resources/*` and `prompts/get`, the latter two being checked as the tool
+//! call that would read the same data.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use devit_cli::core::patch_parser::ParsedPatch;
+use devit_common::{orchestration::CapabilityRateLimit, ApprovalLevel};
+use serde_json::{json, Value};
+use url::Url;
+
+/// JSON-RPC error code returned when a token is not allowed to run a call.
+pub const SCOPE_DENIED: i32 = -32003;
+
+/// Arguments interpreted as workspace paths when checking sub-roots.
+const PATH_ARGUMENTS: &[&str] = &["path", "paths", "working_dir", "output_path"];
+
+/// Read tools that work on the workspace root when no path is given.
+const ROOT_DEFAULT_TOOLS: &[&str] = &[
+    "devit_file_list*",
+    "devit_file_search*",
+    "devit_directory_list",
+    "devit_project_structure*",
+    "devit_git_*",
+];
+
+#[derive(Debug, Clone)]
+pub struct TokenScope {
+    /// Identity written to logs and the journal; never the token itself
+    pub name: String,
+    /// Tool name globs (`*`, `?`); empty means every tool
+    pub allowed_tools: Vec<String>,
+    /// Highest approval level the token may exercise; `None` means no cap
+    pub max_approval: Option<ApprovalLevel>,
+    /// Absolute sub-roots the arguments must stay in; empty means the workspace
+    pub roots: Vec<PathBuf>,
+    /// Workspace root relative arguments are resolved against
+    pub workspace: PathBuf,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Overrides the transport-wide per-token rate limit
+    pub rate_limit: Option<CapabilityRateLimit>,
+    /// Overrides the transport-wide daily `tools/call` quota
+    pub daily_quota: Option<u64>,
+}
+
+impl TokenScope {
+    /// Scope of a token configured without restrictions.
+    pub fn unrestricted(name: impl Into<String>, workspace: PathBuf) -> Self {
+        Self {
+            name: name.into(),
+            allowed_tools: Vec::new(),
+            max_approval: None,
+            roots: Vec::new(),
+            workspace,
+            expires_at: None,
+            rate_limit: None,
+            daily_quota: None,
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expiry| expiry <= now)
+    }
+
+    /// Whether the tool is visible to this token in `tools/list`.
+    pub fn allows_tool(&self, tool: &str) -> bool {
+        let listed = self.allowed_tools.is_empty()
+            || self
+                .allowed_tools
+                .iter()
+                .any(|pattern| glob_match(pattern, tool));
+        let within_level = self
+            .max_approval
+            .as_ref()
+            .is_none_or(|max| max.satisfies(&required_approval(tool)));
+        listed && within_level
+    }
+
+    /// Checks a `tools/call`; the error explains the refusal.
+    pub fn authorize_call(&self, tool: &str, arguments: &Value) -> Result<(), String> {
+        if !self.allows_tool(tool) {
+            return Err(format!(
+                "tool '{tool}' is not allowed for token '{}'",
+                self.name
+            ));
+        }
+        if self.roots.is_empty() {
+            return Ok(());
+        }
+
+        let mut targets = argument_paths(tool, arguments)?;
+        // Execution and tree-wide reads default to the workspace root when no
+        // path is given.
+        let root_default = required_approval(tool) >= ApprovalLevel::Trusted
+            || ROOT_DEFAULT_TOOLS
+                .iter()
+                .any(|pattern| glob_match(pattern, tool));
+        if targets.is_empty() && root_default {
+            targets.push(".".to_string());
+        }
+        let roots: Vec<PathBuf> = self.roots.iter().filter_map(|root| resolve(root)).collect();
+        for target in targets {
+            let resolved = resolve(&self.workspace.join(&target));
+            let inside = resolved
+                .as_deref()
+                .is_some_and(|path| roots.iter().any(|root| path.starts_with(root)));
+            if !inside {
+                return Err(format!(
+                    "path '{target}' is outside the roots allowed for token '{}'",
+                    self.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks a `resources/read` or `resources/subscribe`. Files are read like
+    /// `devit_file_read`; the journal and snapshot manifests describe the
+    /// whole workspace, tasks are the orchestration status.
+    pub fn authorize_resource(&self, uri: &str) -> Result<(), String> {
+        let (tool, arguments) = if uri.starts_with("file://") {
+            let path = Url::parse(uri)
+                .ok()
+                .and_then(|url| url.to_file_path().ok())
+                .ok_or_else(|| format!("invalid file URI '{uri}'"))?;
+            ("devit_file_read", json!({ "path": path }))
+        } else if uri.starts_with("devit://tasks") {
+            ("devit_orchestration_status", json!({}))
+        } else {
+            ("devit_file_read", json!({ "path": "." }))
+        };
+        self.authorize_call(tool, &arguments)
+            .map_err(|reason| format!("resource '{uri}': {reason}"))
+    }
+
+    /// Checks a `prompts/get`. Built-in prompts read the git state or the
+    /// files they are given; recipes only fill in their template.
+    pub fn authorize_prompt(
+        &self,
+        name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Result<(), String> {
+        let argument = |key: &str| {
+            arguments
+                .get(key)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let (tool, arguments) = match name {
+            _ if name.starts_with("recipe:") => return Ok(()),
+            "review_patch" if argument("patch").is_some() => return Ok(()),
+            "explain_merge_conflict" if argument("paths").is_some() => {
+                let paths: Vec<&str> = argument("paths")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|path| !path.is_empty())
+                    .collect();
+                ("devit_file_read", json!({ "paths": paths }))
+            }
+            _ => ("devit_git_diff", json!({})),
+        };
+        self.authorize_call(tool, &arguments)
+            .map_err(|reason| format!("prompt '{name}': {reason}"))
+    }
+}
+
+/// Approval level a tool requires: reads are untrusted, network and screen
+/// reads ask, workspace writes are moderate, anything executing code or
+/// driving the desktop (and unknown tools) is trusted.
+pub fn required_approval(tool: &str) -> ApprovalLevel {
+    const READ: &[&str] = &[
+        "devit_file_read*",
+        "devit_file_list*",
+        "devit_file_search*",
+        "devit_directory_list",
+        "devit_project_structure*",
+        "devit_pwd",
+        "devit_ps",
+        "devit_git_*",
+        "devit_help*",
+        "devit_mcp_tools",
+        "devit_orchestration_status",
+        "devit_task_result",
+    ];
+    const ASK: &[&str] = &[
+        "devit_fetch_url",
+        "devit_search_web",
+        "devit_screenshot",
+        "devit_ocr",
+        "devit_ocr_alerts",
+    ];
+    const WRITE: &[&str] = &[
+        "devit_file_write",
+        "devit_patch_apply",
+        "devit_edit_apply",
+        "devit_snapshot",
+        "devit_journal_append",
+        "devit_notify",
+        "devit_poll_tasks",
+        "devit_task_cancel",
+    ];
+
+    let matches = |patterns: &[&str]| patterns.iter().any(|pattern| glob_match(pattern, tool));
+    if matches(READ) {
+        ApprovalLevel::Untrusted
+    } else if matches(ASK) {
+        ApprovalLevel::Ask
+    } else if matches(WRITE) {
+        ApprovalLevel::Moderate
+    } else {
+        ApprovalLevel::Trusted
+    }
+}
+
+/// Workspace paths referenced by the arguments, including the files touched
+/// by a unified diff or by structured edits. A diff is read with the parser
+/// the patcher uses, so rename and copy sources and binary patches count.
+fn argument_paths(tool: &str, arguments: &Value) -> Result<Vec<String>, String> {
+    let mut paths = Vec::new();
+    for key in PATH_ARGUMENTS {
+        match arguments.get(*key) {
+            Some(Value::String(path)) => paths.push(path.clone()),
+            Some(Value::Array(items)) => {
+                paths.extend(items.iter().filter_map(Value::as_str).map(str::to_string))
+            }
+            _ => {}
+        }
+    }
+    if tool == "devit_patch_apply" {
+        if let Some(diff) = arguments.get("diff").and_then(Value::as_str) {
+            let parsed =
+                ParsedPatch::from_diff(diff).map_err(|err| format!("invalid diff: {err}"))?;
+            for file in parsed.files {
+                paths.extend(
+                    [file.old_path, file.new_path]
+                        .into_iter()
+                        .flatten()
+                        .map(|path| path.to_string_lossy().into_owned()),
+                );
+            }
+        }
+    }
+    if tool == "devit_edit_apply" {
+        if let Some(edits) = arguments.get("edits").and_then(Value::as_array) {
+            paths.extend(
+                edits
+                    .iter()
+                    .filter_map(|edit| edit.get("path").and_then(Value::as_str))
+                    .map(str::to_string),
+            );
+        }
+    }
+    Ok(paths)
+}
+
+/// Resolves `path` component by component, following the symlinks of the
+/// part that exists and normalizing the rest lexically. `None` when `..`
+/// climbs above the filesystem root or a symlink dangles.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
+            }
+            other => {
+                resolved.push(other);
+                match resolved.canonicalize() {
+                    Ok(real) => resolved = real,
+                    Err(_) if resolved.is_symlink() => return None,
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+    Some(resolved)
+}
+
+/// Matches `*` (any run of characters) and `?` (one character).
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn review_bot() -> TokenScope {
+        TokenScope {
+            name: "review-bot".to_string(),
+            allowed_tools: vec!["devit_file_*".to_string(), "devit_git_*".to_string()],
+            max_approval: Some(ApprovalLevel::Untrusted),
+            roots: vec![PathBuf::from("/work/src")],
+            workspace: PathBuf::from("/work"),
+            expires_at: None,
+            rate_limit: None,
+            daily_quota: None,
+        }
+    }
+
+    #[test]
+    fn globs_and_approval_cap_filter_tools() {
+        let scope = review_bot();
+        assert!(scope.allows_tool("devit_file_read"));
+        assert!(scope.allows_tool("devit_git_log"));
+        // Matches the glob but exceeds the approval cap.
+        assert!(!scope.allows_tool("devit_file_write"));
+        assert!(!scope.allows_tool("devit_exec"));
+
+        let editor = TokenScope::unrestricted("editor", PathBuf::from("/work"));
+        assert!(editor.allows_tool("devit_exec"));
+    }
+
+    #[test]
+    fn calls_must_stay_within_roots() {
+        let scope = review_bot();
+        assert!(scope
+            .authorize_call("devit_file_read", &json!({"path": "src/lib.rs"}))
+            .is_ok());
+        assert!(scope
+            .authorize_call("devit_file_read", &json!({"path": "src/../Cargo.toml"}))
+            .is_err());
+        assert!(scope
+            .authorize_call("devit_file_read", &json!({"path": "/etc/passwd"}))
+            .is_err());
+
+        let mut writer = review_bot();
+        writer.allowed_tools.clear();
+        writer.max_approval = None;
+        let patch = |diff: &str| writer.authorize_call("devit_patch_apply", &json!({"diff": diff}));
+        assert!(patch(
+            "diff --git a/src/main.rs b/src/main.rs\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1 +1 @@\n-a\n+b\n"
+        )
+        .is_ok());
+        assert!(patch(
+            "diff --git a/build.rs b/build.rs\n--- a/build.rs\n+++ b/build.rs\n@@ -1 +1 @@\n-a\n+b\n"
+        )
+        .is_err());
+        // Quoted names are compared without their quotes and prefix.
+        assert!(patch(
+            "diff --git \"a/src/x y\" \"b/src/x y\"\n--- \"a/src/x y\"\n+++ \"b/src/x y\"\n@@ -1 +1 @@\n-a\n+b\n"
+        )
+        .is_ok());
+        // Renames and copies out of another directory, and binary patches,
+        // carry their paths outside `---`/`+++` lines.
+        assert!(patch(
+            "diff --git a/secrets/key b/src/key\nsimilarity index 100%\nrename from secrets/key\nrename to src/key\n"
+        )
+        .is_err());
+        assert!(patch(
+            "diff --git a/secrets/key b/src/key\nsimilarity index 100%\ncopy from secrets/key\ncopy to src/key\n"
+        )
+        .is_err());
+        assert!(patch(
+            "diff --git a/src/old.rs b/src/new.rs\nsimilarity index 100%\nrename from src/old.rs\nrename to src/new.rs\n"
+        )
+        .is_ok());
+        let binary = "diff --git a/build.rs b/build.rs\nnew file mode 100644\nGIT binary patch\nliteral 2\nJcmZQz1ONa700IC2\n\nliteral 0\nHcmV?d00001\n\n";
+        assert!(patch(binary).is_err());
+        assert!(patch(&binary.replace("build.rs", "src/blob.bin")).is_ok());
+        assert!(patch("diff --git a/src/x b/src/x\nrename from \"src/x\n").is_err());
+        let edits = json!({"edits": [
+            {"op": "create_file", "path": "src/new.rs", "content": ""},
+            {"op": "delete_file", "path": "build.rs"}
+        ]});
+        assert!(writer.authorize_call("devit_edit_apply", &edits).is_err());
+        // Execution without a working_dir runs at the workspace root.
+        assert!(writer
+            .authorize_call("devit_exec", &json!({"binary": "ls", "args": []}))
+            .is_err());
+    }
+
+    #[test]
+    fn default_paths_and_symlinks_are_checked() {
+        let scope = review_bot();
+        // Tree-wide reads without a path run at the workspace root.
+        assert!(scope
+            .authorize_call("devit_file_search", &json!({"pattern": "TODO"}))
+            .is_err());
+        assert!(scope.authorize_call("devit_git_log", &json!({})).is_err());
+        assert!(scope
+            .authorize_call("devit_git_log", &json!({"path": "src"}))
+            .is_ok());
+
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("work");
+        std::fs::create_dir_all(workspace.join("src")).unwrap();
+        std::fs::write(workspace.join("secret.txt"), "token").unwrap();
+        let mut scope = review_bot();
+        scope.workspace = workspace.clone();
+        scope.roots = vec![workspace.join("src")];
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&workspace, workspace.join("src/escape")).unwrap();
+            std::os::unix::fs::symlink("missing", workspace.join("src/dangling")).unwrap();
+            assert!(scope
+                .authorize_call("devit_file_read", &json!({"path": "src/escape/secret.txt"}))
+                .is_err());
+            assert!(scope
+                .authorize_call("devit_file_read", &json!({"path": "src/dangling"}))
+                .is_err());
+        }
+        assert!(scope
+            .authorize_call("devit_file_read", &json!({"path": "src/new/file.rs"}))
+            .is_ok());
+    }
+
+    #[test]
+    fn resources_and_prompts_are_checked_like_tools() {
+        let scope = review_bot();
+        assert!(scope.authorize_resource("file:///work/src/lib.rs").is_ok());
+        assert!(scope.authorize_resource("file:///work/Cargo.toml").is_err());
+        assert!(scope.authorize_resource("devit://journal?tail=5").is_err());
+        // Not in the token's tool globs.
+        assert!(scope.authorize_resource("devit://tasks").is_err());
+
+        let arguments = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+        assert!(scope
+            .authorize_prompt("write_commit_message", &arguments(&[]))
+            .is_err());
+        assert!(scope
+            .authorize_prompt("review_patch", &arguments(&[("patch", "+a")]))
+            .is_ok());
+        assert!(scope
+            .authorize_prompt(
+                "explain_merge_conflict",
+                &arguments(&[("paths", "src/a.rs, src/b.rs")])
+            )
+            .is_ok());
+        assert!(scope
+            .authorize_prompt(
+                "explain_merge_conflict",
+                &arguments(&[("paths", "build.rs")])
+            )
+            .is_err());
+        assert!(scope
+            .authorize_prompt("recipe:fmt", &arguments(&[]))
+            .is_ok());
+    }
+
+    #[test]
+    fn expiry_is_inclusive() {
+        let mut scope = review_bot();
+        let now = Utc::now();
+        assert!(!scope.is_expired(now));
+        scope.expires_at = Some(now);
+        assert!(scope.is_expired(now));
+    }
+}
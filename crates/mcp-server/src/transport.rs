@@ -1,16 +1,20 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use devit_common::{orchestration::CapabilityRateLimit, ApprovalLevel};
 use serde::Deserialize;
 
+use crate::scope::TokenScope;
+
 #[derive(Debug, Clone)]
 pub enum Transport {
     Stdio,
-    Http(HttpTransportConfig),
+    Http(Box<HttpTransportConfig>),
 }
 
 #[derive(Debug, Clone)]
@@ -20,11 +24,25 @@ pub struct HttpTransportConfig {
     pub sse_enabled: bool,
     pub auth: Option<HttpAuthConfig>,
     pub cors: Option<HttpCorsConfig>,
+    pub rate_limits: Option<HttpRateLimitConfig>,
+    /// Serve HTTPS directly when set
+    pub tls: Option<HttpTlsConfig>,
+}
+
+/// PEM files of the HTTPS listener; they are read again on SIGHUP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpTlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// CA bundle required to sign client certificates (mutual TLS)
+    pub client_ca_path: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
 pub struct HttpAuthConfig {
     pub tokens: HashSet<String>,
+    /// Restrictions of the tokens-file entries that declare any
+    pub scopes: HashMap<String, TokenScope>,
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +50,18 @@ pub struct HttpCorsConfig {
     pub allowed_origins: Vec<String>,
 }
 
+/// Limits applied per token (or to anonymous callers as a whole); token
+/// entries may override `per_token` and `daily_quota`.
+#[derive(Debug, Clone, Default)]
+pub struct HttpRateLimitConfig {
+    /// JSON-RPC requests per token
+    pub per_token: Option<CapabilityRateLimit>,
+    /// `tools/call` per token and tool
+    pub per_tool: HashMap<String, CapabilityRateLimit>,
+    /// `tools/call` per token and UTC day
+    pub daily_quota: Option<u64>,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct CliTransportOptions {
     pub transport: Option<String>,
@@ -41,6 +71,9 @@ pub struct CliTransportOptions {
     pub tokens: Vec<String>,
     pub tokens_file: Option<PathBuf>,
     pub cors_origins: Vec<String>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub tls_client_ca: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +84,8 @@ pub struct FileTransportConfig {
     pub sse_enabled: Option<bool>,
     pub auth: Option<FileAuthConfig>,
     pub cors: Option<FileCorsConfig>,
+    pub rate_limits: Option<HttpRateLimitConfig>,
+    pub tls: Option<FileTlsConfig>,
 }
 
 #[derive(Debug, Clone)]
@@ -66,6 +101,13 @@ pub struct FileCorsConfig {
     pub allowed_origins: Vec<String>,
 }
 
+#[derive(Debug, Clone)]
+pub struct FileTlsConfig {
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    pub client_ca: Option<PathBuf>,
+}
+
 #[derive(Deserialize)]
 struct RootConfig {
     #[serde(default)]
@@ -80,6 +122,8 @@ struct RawFileTransportConfig {
     sse_enabled: Option<bool>,
     auth: Option<RawFileAuthConfig>,
     cors: Option<RawFileCorsConfig>,
+    rate_limits: Option<RawRateLimitConfig>,
+    tls: Option<RawFileTlsConfig>,
 }
 
 #[derive(Deserialize, Default)]
@@ -96,6 +140,21 @@ struct RawFileCorsConfig {
     allowed_origins: Option<Vec<String>>,
 }
 
+#[derive(Deserialize, Default)]
+struct RawFileTlsConfig {
+    cert: Option<String>,
+    key: Option<String>,
+    client_ca: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawRateLimitConfig {
+    per_token: Option<String>,
+    daily_quota: Option<u64>,
+    #[serde(default)]
+    tools: HashMap<String, String>,
+}
+
 pub fn load_file_config(path: Option<&Path>) -> Result<Option<FileTransportConfig>> {
     let Some(path) = path else {
         return Ok(None);
@@ -110,9 +169,11 @@ pub fn load_file_config(path: Option<&Path>) -> Result<Option<FileTransportConfi
     let parsed: RootConfig = toml::from_str(&contents)
         .with_context(|| format!("Failed to parse TOML config {}", path.display()))?;
 
-    Ok(parsed
+    parsed
         .mcp_server
-        .map(|raw| raw.into_runtime_config(path.parent().unwrap_or(Path::new(".")))))
+        .map(|raw| raw.into_runtime_config(path.parent().unwrap_or(Path::new("."))))
+        .transpose()
+        .with_context(|| format!("Invalid MCP config {}", path.display()))
 }
 
 pub fn determine_transport(
@@ -129,7 +190,7 @@ pub fn determine_transport(
 
     match transport_choice.as_str() {
         "stdio" => Ok(Transport::Stdio),
-        "http" => {
+        "http" | "https" => {
             let host = cli
                 .host
                 .clone()
@@ -146,18 +207,24 @@ pub fn determine_transport(
 
             let auth_config = build_auth_config(cli, file_cfg, working_dir)?;
             let cors_config = build_cors_config(cli, file_cfg);
+            let rate_limits = file_cfg.and_then(|cfg| cfg.rate_limits.clone());
+            let tls = build_tls_config(cli, file_cfg, working_dir)?;
+            if transport_choice == "https" && tls.is_none() {
+                return Err(anyhow!(
+                    "HTTPS transport requires a certificate and key (--tls-cert/--tls-key or [mcp_server.tls])."
+                ));
+            }
 
-            Ok(Transport::Http(HttpTransportConfig {
+            Ok(Transport::Http(Box::new(HttpTransportConfig {
                 host,
                 port,
                 sse_enabled,
                 auth: auth_config,
                 cors: cors_config,
-            }))
+                rate_limits,
+                tls,
+            })))
         }
-        "https" => Err(anyhow!(
-            "HTTPS transport is not implemented yet. Please use HTTP or stdio."
-        )),
         other => Err(anyhow!("Unsupported transport '{}'", other)),
     }
 }
@@ -192,6 +259,8 @@ mod tests {
             cors: Some(FileCorsConfig {
                 allowed_origins: vec!["https://example.com".to_string()],
             }),
+            rate_limits: None,
+            tls: None,
         };
 
         let cli = CliTransportOptions::default();
@@ -258,6 +327,109 @@ mod tests {
             other => panic!("expected HTTP transport, got {other:?}"),
         }
     }
+
+    #[test]
+    fn https_requires_certificate_and_key() {
+        let mut cli = CliTransportOptions {
+            transport: Some("https".to_string()),
+            ..Default::default()
+        };
+        assert!(determine_transport(&cli, None, Path::new("/srv")).is_err());
+
+        cli.tls_cert = Some(PathBuf::from("certs/server.pem"));
+        assert!(determine_transport(&cli, None, Path::new("/srv")).is_err());
+
+        cli.tls_key = Some(PathBuf::from("/etc/devit/server.key"));
+        cli.tls_client_ca = Some(PathBuf::from("certs/clients.pem"));
+        let Transport::Http(http) =
+            determine_transport(&cli, None, Path::new("/srv")).expect("https transport")
+        else {
+            panic!("expected HTTP transport");
+        };
+        assert_eq!(
+            http.tls,
+            Some(HttpTlsConfig {
+                cert_path: PathBuf::from("/srv/certs/server.pem"),
+                key_path: PathBuf::from("/etc/devit/server.key"),
+                client_ca_path: Some(PathBuf::from("/srv/certs/clients.pem")),
+            })
+        );
+    }
+
+    #[test]
+    fn rate_limits_are_read_from_file_config() {
+        let dir = tempdir().expect("tempdir");
+        let config_path = dir.path().join("devit.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+[mcp_server]
+transport = "http"
+
+[mcp_server.rate_limits]
+per_token = "120/minute"
+daily_quota = 5000
+
+[mcp_server.rate_limits.tools]
+devit_exec = "10/minute"
+"#,
+        )
+        .expect("write config");
+
+        let file_cfg = load_file_config(Some(&config_path))
+            .expect("load config")
+            .expect("mcp_server section");
+        let limits = file_cfg.rate_limits.expect("rate limits");
+        assert_eq!(limits.per_token, Some(CapabilityRateLimit::per_minute(120)));
+        assert_eq!(
+            limits.per_tool["devit_exec"],
+            CapabilityRateLimit::per_minute(10)
+        );
+        assert_eq!(limits.daily_quota, Some(5000));
+
+        std::fs::write(
+            &config_path,
+            "[mcp_server.rate_limits]\nper_token = \"lots\"\n",
+        )
+        .expect("rewrite config");
+        assert!(load_file_config(Some(&config_path)).is_err());
+    }
+
+    #[test]
+    fn token_entries_carry_scopes() {
+        let dir = tempdir().expect("tempdir");
+        let tokens_path = dir.path().join("tokens.json");
+        std::fs::write(
+            &tokens_path,
+            r#"{"tokens":[
+                {"token":"editor-token"},
+                {"token":"bot-token","name":"review-bot","allowed_tools":["devit_git_*"],
+                 "max_approval":"untrusted","roots":["src"],"expires_at":"2030-01-01T00:00:00Z"}
+            ]}"#,
+        )
+        .expect("write tokens file");
+
+        let cli = CliTransportOptions {
+            transport: Some("http".to_string()),
+            tokens_file: Some(tokens_path),
+            ..Default::default()
+        };
+
+        let transport =
+            determine_transport(&cli, None, dir.path()).expect("determine http transport");
+        let Transport::Http(http) = transport else {
+            panic!("expected HTTP transport");
+        };
+        let auth = http.auth.expect("auth config");
+        assert!(auth.tokens.contains("editor-token"));
+        assert!(!auth.scopes.contains_key("editor-token"));
+
+        let scope = &auth.scopes["bot-token"];
+        assert_eq!(scope.name, "review-bot");
+        assert_eq!(scope.max_approval, Some(ApprovalLevel::Untrusted));
+        assert_eq!(scope.roots, vec![dir.path().join("src")]);
+        assert!(scope.expires_at.is_some());
+    }
 }
 
 fn build_auth_config(
@@ -266,6 +438,7 @@ fn build_auth_config(
     working_dir: &Path,
 ) -> Result<Option<HttpAuthConfig>> {
     let mut tokens: HashSet<String> = HashSet::new();
+    let mut scopes: HashMap<String, TokenScope> = HashMap::new();
     let mut auth_enabled = false;
 
     if let Some(cfg) = file_cfg.and_then(|cfg| cfg.auth.as_ref()) {
@@ -288,7 +461,7 @@ fn build_auth_config(
         }
 
         if let Some(path) = cfg.tokens_file.as_ref() {
-            let loaded = load_tokens_from_file(path)?;
+            let loaded = load_tokens_from_file(path, working_dir, &mut scopes)?;
             if !loaded.is_empty() {
                 auth_enabled = true;
             }
@@ -298,7 +471,7 @@ fn build_auth_config(
 
     if let Some(path) = cli.tokens_file.as_ref() {
         let resolved = resolve_relative(working_dir, path);
-        let loaded = load_tokens_from_file(&resolved)?;
+        let loaded = load_tokens_from_file(&resolved, working_dir, &mut scopes)?;
         if !loaded.is_empty() {
             auth_enabled = true;
         }
@@ -315,12 +488,44 @@ fn build_auth_config(
     }
 
     if auth_enabled {
-        Ok(Some(HttpAuthConfig { tokens }))
+        Ok(Some(HttpAuthConfig { tokens, scopes }))
     } else {
         Ok(None)
     }
 }
 
+fn build_tls_config(
+    cli: &CliTransportOptions,
+    file_cfg: Option<&FileTransportConfig>,
+    working_dir: &Path,
+) -> Result<Option<HttpTlsConfig>> {
+    let file_tls = file_cfg.and_then(|cfg| cfg.tls.as_ref());
+    let pick = |cli_path: &Option<PathBuf>, file_path: Option<&PathBuf>| {
+        cli_path
+            .as_ref()
+            .map(|path| resolve_relative(working_dir, path))
+            .or_else(|| file_path.cloned())
+    };
+    let cert = pick(&cli.tls_cert, file_tls.and_then(|tls| tls.cert.as_ref()));
+    let key = pick(&cli.tls_key, file_tls.and_then(|tls| tls.key.as_ref()));
+    let client_ca = pick(
+        &cli.tls_client_ca,
+        file_tls.and_then(|tls| tls.client_ca.as_ref()),
+    );
+
+    match (cert, key) {
+        (Some(cert_path), Some(key_path)) => Ok(Some(HttpTlsConfig {
+            cert_path,
+            key_path,
+            client_ca_path: client_ca,
+        })),
+        (None, None) if client_ca.is_none() => Ok(None),
+        _ => Err(anyhow!(
+            "TLS needs both a certificate and a private key (client CA alone is not enough)."
+        )),
+    }
+}
+
 fn build_cors_config(
     cli: &CliTransportOptions,
     file_cfg: Option<&FileTransportConfig>,
@@ -345,12 +550,51 @@ fn build_cors_config(
     })
 }
 
-fn load_tokens_from_file(path: &Path) -> Result<Vec<String>> {
+/// Loads the tokens of a tokens file; entries declaring a name or any
+/// restriction also get a scope, with roots resolved against the workspace.
+fn load_tokens_from_file(
+    path: &Path,
+    workspace: &Path,
+    scopes: &mut HashMap<String, TokenScope>,
+) -> Result<Vec<String>> {
     let contents = fs::read_to_string(path)
         .with_context(|| format!("Failed to read tokens file {}", path.display()))?;
     let parsed: TokenFile = serde_json::from_str(&contents)
         .with_context(|| format!("Failed to parse tokens file {}", path.display()))?;
-    Ok(parsed.tokens.into_iter().map(|entry| entry.token).collect())
+
+    let mut tokens = Vec::with_capacity(parsed.tokens.len());
+    for entry in parsed.tokens {
+        if let Some(scope) = entry
+            .scope(workspace)
+            .with_context(|| format!("Invalid token entry in {}", path.display()))?
+        {
+            scopes.insert(entry.token.clone(), scope);
+        }
+        tokens.push(entry.token);
+    }
+    Ok(tokens)
+}
+
+fn parse_rate_limit(value: &str) -> Result<CapabilityRateLimit> {
+    CapabilityRateLimit::parse_str(value).ok_or_else(|| {
+        anyhow!(
+            "Invalid rate limit '{}'. Expected e.g. \"10/minute\" or \"100/hour\".",
+            value
+        )
+    })
+}
+
+fn parse_approval_level(value: &str) -> Result<ApprovalLevel> {
+    match value.to_ascii_lowercase().as_str() {
+        "untrusted" => Ok(ApprovalLevel::Untrusted),
+        "ask" => Ok(ApprovalLevel::Ask),
+        "moderate" => Ok(ApprovalLevel::Moderate),
+        "trusted" => Ok(ApprovalLevel::Trusted),
+        other => Err(anyhow!(
+            "Unsupported max_approval '{}'. Expected untrusted, ask, moderate or trusted.",
+            other
+        )),
+    }
 }
 
 fn resolve_relative(base: &Path, path: &Path) -> PathBuf {
@@ -362,18 +606,54 @@ fn resolve_relative(base: &Path, path: &Path) -> PathBuf {
 }
 
 impl RawFileTransportConfig {
-    fn into_runtime_config(self, base: &Path) -> FileTransportConfig {
-        FileTransportConfig {
+    fn into_runtime_config(self, base: &Path) -> Result<FileTransportConfig> {
+        Ok(FileTransportConfig {
             transport: self.transport,
             host: self.host,
             port: self.port,
             sse_enabled: self.sse_enabled,
             auth: self.auth.map(|raw| raw.into_runtime_config(base)),
             cors: self.cors.map(|raw| raw.into_runtime_config()),
+            rate_limits: self
+                .rate_limits
+                .map(RawRateLimitConfig::into_runtime_config)
+                .transpose()?,
+            tls: self.tls.map(|raw| raw.into_runtime_config(base)),
+        })
+    }
+}
+
+impl RawFileTlsConfig {
+    fn into_runtime_config(self, base: &Path) -> FileTlsConfig {
+        let resolve =
+            |value: Option<String>| value.map(|path| resolve_relative(base, Path::new(&path)));
+        FileTlsConfig {
+            cert: resolve(self.cert),
+            key: resolve(self.key),
+            client_ca: resolve(self.client_ca),
         }
     }
 }
 
+impl RawRateLimitConfig {
+    fn into_runtime_config(self) -> Result<HttpRateLimitConfig> {
+        let per_tool = self
+            .tools
+            .iter()
+            .map(|(tool, limit)| Ok((tool.clone(), parse_rate_limit(limit)?)))
+            .collect::<Result<_>>()?;
+        Ok(HttpRateLimitConfig {
+            per_token: self
+                .per_token
+                .as_deref()
+                .map(parse_rate_limit)
+                .transpose()?,
+            per_tool,
+            daily_quota: self.daily_quota,
+        })
+    }
+}
+
 impl RawFileAuthConfig {
     fn into_runtime_config(self, base: &Path) -> FileAuthConfig {
         let tokens_file = self
@@ -405,4 +685,63 @@ struct TokenFile {
 #[derive(Deserialize)]
 struct TokenEntry {
     token: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    allowed_tools: Vec<String>,
+    #[serde(default)]
+    max_approval: Option<String>,
+    #[serde(default)]
+    roots: Vec<String>,
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    rate_limit: Option<String>,
+    #[serde(default)]
+    daily_quota: Option<u64>,
+}
+
+impl TokenEntry {
+    fn scope(&self, workspace: &Path) -> Result<Option<TokenScope>> {
+        let restricted = !self.allowed_tools.is_empty()
+            || self.max_approval.is_some()
+            || !self.roots.is_empty()
+            || self.expires_at.is_some()
+            || self.rate_limit.is_some()
+            || self.daily_quota.is_some();
+        let Some(name) = self
+            .name
+            .clone()
+            .or_else(|| restricted.then(|| "unnamed".to_string()))
+        else {
+            return Ok(None);
+        };
+
+        let max_approval = self
+            .max_approval
+            .as_deref()
+            .map(parse_approval_level)
+            .transpose()?;
+        let rate_limit = self
+            .rate_limit
+            .as_deref()
+            .map(parse_rate_limit)
+            .transpose()?;
+        let roots = self
+            .roots
+            .iter()
+            .map(|root| resolve_relative(workspace, Path::new(root)))
+            .collect();
+
+        Ok(Some(TokenScope {
+            name,
+            allowed_tools: self.allowed_tools.clone(),
+            max_approval,
+            roots,
+            workspace: workspace.to_path_buf(),
+            expires_at: self.expires_at,
+            rate_limit,
+            daily_quota: self.daily_quota,
+        }))
+    }
 }
@@ -2,10 +2,11 @@ use std::fmt;
 use std::path::{Path, PathBuf};
 
 use devit_cli::core::atomic_patcher::{
-    AtomicPatcher as CoreAtomicPatcher, PatchStats as CorePatchStats,
+    AtomicPatcher as CoreAtomicPatcher, PatchOptions, PatchStats as CorePatchStats,
 };
 use devit_cli::core::errors::DevItError;
 use devit_cli::core::patch_parser::{FilePatch, ParsedPatch, PatchLine};
+use devit_cli::core::snapshot::Snapshot;
 use mcp_core::{McpError, McpResult};
 
 use crate::errors::{
@@ -19,17 +20,27 @@ pub type PatchStats = CorePatchStats;
 pub(crate) struct AtomicPatcher {
     working_dir: PathBuf,
     dry_run: bool,
+    options: PatchOptions,
+    merge_base: Option<Snapshot>,
 }
 
 impl AtomicPatcher {
-    pub fn new(working_dir: PathBuf, dry_run: bool) -> Self {
+    pub fn new(working_dir: PathBuf, dry_run: bool, options: PatchOptions) -> Self {
         Self {
             working_dir,
             dry_run,
+            options,
+            merge_base: None,
         }
     }
 
-    pub fn apply_patch(&self, diff: &str) -> McpResult<(PatchStats, Vec<FileChangeSummary>)> {
+    /// Merges three-way with `snapshot` the files the patch no longer applies to.
+    pub fn with_merge_base(mut self, snapshot: Option<Snapshot>) -> Self {
+        self.merge_base = snapshot;
+        self
+    }
+
+    pub fn apply_patch(self, diff: &str) -> McpResult<(PatchStats, Vec<FileChangeSummary>)> {
         let parsed = ParsedPatch::from_diff(diff).map_err(map_core_error)?;
         if parsed.files.is_empty() {
             return Err(invalid_diff_error("No file changes detected", None));
@@ -39,7 +50,11 @@ impl AtomicPatcher {
             return Err(err);
         }
 
-        let patcher = CoreAtomicPatcher::new(self.working_dir.clone(), self.dry_run);
+        let mut patcher = CoreAtomicPatcher::new(self.working_dir.clone(), self.dry_run)
+            .with_options(self.options);
+        if let Some(base) = self.merge_base {
+            patcher = patcher.with_merge_base(base);
+        }
         let stats = match patcher.apply_patch(diff) {
             Ok(stats) => stats,
             Err(err) => return Err(map_core_error(err)),
@@ -55,6 +70,8 @@ pub enum FileAction {
     Created,
     Modified,
     Deleted,
+    Renamed,
+    Copied,
 }
 
 impl fmt::Display for FileAction {
@@ -63,6 +80,8 @@ impl fmt::Display for FileAction {
             FileAction::Created => write!(f, "created"),
             FileAction::Modified => write!(f, "modified"),
             FileAction::Deleted => write!(f, "deleted"),
+            FileAction::Renamed => write!(f, "renamed"),
+            FileAction::Copied => write!(f, "copied"),
         }
     }
 }
@@ -109,6 +128,23 @@ fn determine_action(file_patch: &FilePatch) -> (String, FileAction) {
             .map(|p| p.to_string_lossy().to_string())
             .unwrap_or_else(|| "<unknown>".to_string());
         (path, FileAction::Deleted)
+    } else if file_patch.is_rename || file_patch.is_copy {
+        let display = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| "<unknown>".to_string())
+        };
+        let path = format!(
+            "{} → {}",
+            display(&file_patch.old_path),
+            display(&file_patch.new_path)
+        );
+        let action = if file_patch.is_rename {
+            FileAction::Renamed
+        } else {
+            FileAction::Copied
+        };
+        (path, action)
     } else {
         let path = file_patch
             .new_path
@@ -167,7 +203,7 @@ fn validate_workspace_state(
     None
 }
 
-fn map_core_error(err: DevItError) -> McpError {
+pub(crate) fn map_core_error(err: DevItError) -> McpError {
     match err {
         DevItError::InvalidDiff {
             reason,
@@ -0,0 +1,193 @@
+//! Tools dont la disponibilité varie pendant la vie du serveur : plugins WASI
+//! installés sous `.devit/plugins` et capacités activées dans la config core.
+//!
+//! `DynamicTools::scan` renvoie l'ensemble courant ; le serveur le réconcilie
+//! périodiquement avec son registre et notifie les clients des changements.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use devit_cli::plugins::{self, PluginInfo, TimeoutErr};
+use mcp_core::{McpResult, McpTool};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::errors::{internal_error, validation_error};
+use crate::screenshot::ScreenshotTool;
+use crate::{apply_orchestration_env_overrides, load_core_config};
+
+/// Préfixe des tools adossés à un plugin WASI.
+pub const PLUGIN_TOOL_PREFIX: &str = "devit_plugin_";
+
+pub struct DynamicTools {
+    root: PathBuf,
+}
+
+impl DynamicTools {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Registry des plugins : `$DEVIT_PLUGINS_DIR` ou `<root>/.devit/plugins`.
+    pub fn plugins_dir(&self) -> PathBuf {
+        env::var("DEVIT_PLUGINS_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| self.root.join(".devit").join("plugins"))
+    }
+
+    /// Tools actuellement disponibles d'après la config et le registry.
+    pub fn scan(&self) -> Vec<Arc<dyn McpTool>> {
+        let mut tools: Vec<Arc<dyn McpTool>> = Vec::new();
+
+        match load_core_config(&self.root) {
+            Ok(mut config) => {
+                apply_orchestration_env_overrides(&mut config.orchestration.base);
+                if let Ok(Some(tool)) = ScreenshotTool::from_config(
+                    &config.tools.screenshot,
+                    &config.orchestration.base,
+                ) {
+                    tools.push(Arc::new(tool));
+                }
+            }
+            Err(err) => warn!("Cannot reload core config: {}", err),
+        }
+
+        match plugins::discover_plugins(Some(&self.plugins_dir())) {
+            Ok(found) => {
+                for info in found {
+                    tools.push(Arc::new(PluginTool::new(info)));
+                }
+            }
+            Err(err) => warn!("Cannot scan plugins registry: {}", err),
+        }
+
+        tools
+    }
+}
+
+/// Expose un plugin WASI comme tool MCP : les arguments sont transmis en JSON
+/// sur stdin du plugin, sa sortie JSON est renvoyée telle quelle.
+pub struct PluginTool {
+    name: String,
+    description: String,
+    manifest_path: PathBuf,
+}
+
+impl PluginTool {
+    pub fn new(info: PluginInfo) -> Self {
+        let id: String = info
+            .id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let description = info
+            .description
+            .unwrap_or_else(|| format!("WASI plugin {} (JSON stdin → JSON stdout)", info.name));
+        Self {
+            name: format!("{PLUGIN_TOOL_PREFIX}{id}"),
+            description,
+            manifest_path: PathBuf::from(info.manifest_path),
+        }
+    }
+
+    pub fn manifest_path(&self) -> &Path {
+        &self.manifest_path
+    }
+}
+
+#[async_trait]
+impl McpTool for PluginTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    async fn execute(&self, params: Value) -> McpResult<Value> {
+        let input = match params {
+            Value::Null => json!({}),
+            Value::Object(_) => params,
+            _ => {
+                return Err(validation_error(
+                    "Plugin arguments must be a JSON object (or omitted).",
+                ))
+            }
+        };
+        let manifest = self.manifest_path.clone();
+        let payload = input.to_string();
+        let output = tokio::task::spawn_blocking(move || {
+            plugins::invoke_manifest(&manifest, &payload, None)
+        })
+        .await
+        .map_err(|err| internal_error(format!("Plugin task failed: {err}")))?
+        .map_err(|err| {
+            if err.downcast_ref::<TimeoutErr>().is_some() {
+                internal_error(format!("Plugin {} timed out", self.name))
+            } else {
+                internal_error(format!("Plugin {} failed: {err:#}", self.name))
+            }
+        })?;
+
+        let text = serde_json::to_string_pretty(&output).unwrap_or_else(|_| output.to_string());
+        Ok(json!({
+            "content": [{ "type": "text", "text": text }],
+            "structuredContent": output,
+            "metadata": { "manifest": self.manifest_path.to_string_lossy() }
+        }))
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "description": "JSON object forwarded to the plugin on stdin",
+            "additionalProperties": true
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn scan_exposes_installed_plugins() {
+        if env::var("DEVIT_PLUGINS_DIR").is_ok() {
+            return;
+        }
+        let dir = tempdir().unwrap();
+        let plugin = dir.path().join(".devit/plugins/echo");
+        fs::create_dir_all(&plugin).unwrap();
+        fs::write(
+            plugin.join("devit-plugin.toml"),
+            "id = \"echo.v1\"\nwasm = \"echo.wasm\"\ndescription = \"Echo input\"\n",
+        )
+        .unwrap();
+
+        let dynamic = DynamicTools::new(dir.path().to_path_buf());
+        let tools = dynamic.scan();
+        let tool = tools
+            .iter()
+            .find(|tool| tool.name().starts_with(PLUGIN_TOOL_PREFIX))
+            .expect("plugin tool");
+        assert_eq!(tool.name(), "devit_plugin_echo_v1");
+        assert_eq!(tool.description(), "Echo input");
+
+        fs::remove_dir_all(&plugin).unwrap();
+        assert!(dynamic
+            .scan()
+            .iter()
+            .all(|tool| !tool.name().starts_with(PLUGIN_TOOL_PREFIX)));
+    }
+}
@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mcp_core::{McpResult, McpTool};
+use serde_json::{json, Value};
+
+use crate::atomic_patcher::map_core_error;
+use crate::errors::validation_error;
+use crate::patch_apply::{build_response, PatchContext};
+use devit_cli::core::structured_edit::{edits_to_diff, EditOperation};
+
+pub struct EditApplyTool {
+    context: Arc<PatchContext>,
+}
+
+impl EditApplyTool {
+    pub fn new(context: Arc<PatchContext>) -> Self {
+        Self { context }
+    }
+}
+
+#[async_trait]
+impl McpTool for EditApplyTool {
+    fn name(&self) -> &str {
+        "devit_edit_apply"
+    }
+
+    fn description(&self) -> &str {
+        "Apply structured edits without writing a diff. Edits run in order and are applied atomically.
+
+Operations (field `op`):
+- search_replace {path, search, replace, all?}: `search` must occur exactly once (or set `all`)
+- insert_after {path, anchor, content}: insert after the line holding the unique `anchor`
+- replace_lines {path, start_line, end_line, expected_sha256, content}: 1-based inclusive range, guarded by the sha256 of the current lines (each followed by \\n, 8+ hex chars)
+- create_file {path, content} / delete_file {path}
+
+Example:
+{\"edits\": [{\"op\": \"search_replace\", \"path\": \"src/lib.rs\", \"search\": \"a + b\", \"replace\": \"a.wrapping_add(b)\"}]}"
+    }
+
+    async fn execute(&self, params: Value) -> McpResult<Value> {
+        let edits = params
+            .get("edits")
+            .cloned()
+            .ok_or_else(|| validation_error("Parameter 'edits' is required"))?;
+        let edits: Vec<EditOperation> = serde_json::from_value(edits)
+            .map_err(|err| validation_error(&format!("Invalid 'edits': {}", err)))?;
+
+        let dry_run = params
+            .get("dry_run")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let diff = edits_to_diff(self.context.root_path(), &edits).map_err(map_core_error)?;
+        let result = self.context.apply_patch(&diff, dry_run)?;
+
+        let mut response = build_response(dry_run, &result);
+        response["structuredContent"]["patch"]["diff"] = Value::String(diff);
+        Ok(response)
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "edits": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "op": {
+                                "type": "string",
+                                "enum": ["search_replace", "insert_after", "replace_lines", "create_file", "delete_file"]
+                            },
+                            "path": {"type": "string"},
+                            "search": {"type": "string"},
+                            "replace": {"type": "string"},
+                            "all": {"type": "boolean"},
+                            "anchor": {"type": "string"},
+                            "start_line": {"type": "integer", "minimum": 1},
+                            "end_line": {"type": "integer", "minimum": 1},
+                            "expected_sha256": {"type": "string"},
+                            "content": {"type": "string"}
+                        },
+                        "required": ["op", "path"]
+                    }
+                },
+                "dry_run": {"type": "boolean"}
+            },
+            "required": ["edits"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use devit_cli::core::config::PolicyConfig;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn edits_are_applied_and_diff_is_returned() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("hello.txt"), "one\ntwo\nthree\n").unwrap();
+        let context = Arc::new(PatchContext::new(temp.path().to_path_buf()).unwrap());
+        let tool = EditApplyTool::new(context);
+
+        let response = tool
+            .execute(json!({
+                "edits": [
+                    {"op": "search_replace", "path": "hello.txt", "search": "two", "replace": "2"},
+                    {"op": "create_file", "path": "new.txt", "content": "fresh\n"}
+                ]
+            }))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(temp.path().join("hello.txt")).unwrap(),
+            "one\n2\nthree\n"
+        );
+        assert_eq!(
+            fs::read_to_string(temp.path().join("new.txt")).unwrap(),
+            "fresh\n"
+        );
+        let patch = &response["structuredContent"]["patch"];
+        assert_eq!(patch["summary"]["files_created"], 1);
+        assert!(patch["diff"].as_str().unwrap().contains("-two\n+2\n"));
+
+        let err = tool
+            .execute(json!({
+                "edits": [{"op": "search_replace", "path": "hello.txt", "search": "two", "replace": "2"}]
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("search_not_found"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn edits_go_through_the_policy() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("hello.txt"), "one\n").unwrap();
+        let context = PatchContext::new(temp.path().to_path_buf())
+            .unwrap()
+            .with_policy(&PolicyConfig::default())
+            .unwrap();
+        let tool = EditApplyTool::new(Arc::new(context));
+
+        tool.execute(json!({
+            "edits": [{"op": "search_replace", "path": "./hello.txt", "search": "one", "replace": "1"}]
+        }))
+        .await
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(temp.path().join("hello.txt")).unwrap(),
+            "1\n"
+        );
+
+        let err = tool
+            .execute(json!({
+                "edits": [{"op": "create_file", "path": ".gitmodules", "content": "[submodule]\n"}]
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("gitmodules"), "{}", err);
+        assert!(!temp.path().join(".gitmodules").exists());
+    }
+}
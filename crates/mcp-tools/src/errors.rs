@@ -186,6 +186,17 @@ pub fn file_not_found_error(path: impl AsRef<std::path::Path>) -> McpError {
     )
 }
 
+pub fn resource_not_found_error(uri: &str) -> McpError {
+    build_rpc_error(
+        -32002,
+        "E_RESOURCE_NOT_FOUND",
+        format!("Resource not found: {}", uri),
+        "Listez les ressources disponibles avec resources/list.",
+        true,
+        Some(json!({ "uri": uri })),
+    )
+}
+
 fn current_timestamp() -> String {
     Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
 }
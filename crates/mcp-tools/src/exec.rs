@@ -20,6 +20,7 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use devit_cli::core::config::ExecToolConfig;
 use devit_common::process_utils::read_proc_stat;
+use devit_common::{IsolationMode, ResourceUsage, SandboxPlan, SeccompProfileSpec};
 #[cfg(windows)]
 use devit_sandbox::{backend::windows::WindowsSandbox, ProcessHandle, SandboxBackend};
 use serde::{Deserialize, Serialize};
@@ -36,9 +37,20 @@ use std::thread;
 use tokio::sync::Mutex;
 #[cfg(windows)]
 use tokio::time::sleep;
-use tracing::info;
+use tracing::{info, warn};
 
-use mcp_core::{McpError, McpResult, McpTool};
+use mcp_core::{McpError, McpResult, McpTool, ToolContext};
+
+/// Period of the progress heartbeat sent while a foreground process runs
+#[cfg(target_family = "unix")]
+const FOREGROUND_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Why a foreground process was killed before it exited
+#[cfg(target_family = "unix")]
+enum ForegroundAbort {
+    Timeout,
+    Cancelled,
+}
 
 /// Stdin mode for devit_exec
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,11 +105,106 @@ pub struct BackgroundProcess {
     pub started_at: DateTime<Utc>,
 }
 
+/// cgroup enforcing the limits of a process started by `spawn_process`
+#[cfg(target_family = "unix")]
+struct ProcessCgroup {
+    #[cfg(target_os = "linux")]
+    cgroup: Option<devit_sandbox::Cgroup>,
+}
+
+#[cfg(target_family = "unix")]
+impl ProcessCgroup {
+    /// Leave the cgroup in place for a detached job and return its path
+    fn persist(&mut self) -> Option<PathBuf> {
+        #[cfg(target_os = "linux")]
+        {
+            self.cgroup.take().map(devit_sandbox::Cgroup::persist)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
+    }
+
+    fn resource_usage(&self) -> Option<ResourceUsage> {
+        #[cfg(target_os = "linux")]
+        {
+            self.cgroup.as_ref().and_then(|cgroup| cgroup.usage().ok())
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
+    }
+}
+
+pub(crate) fn resource_usage_json(usage: &ResourceUsage) -> Value {
+    json!({
+        "oom_killed": usage.oom_killed(),
+        "oom_kills": usage.oom_kills,
+        "memory_max_events": usage.memory_max_events,
+        "memory_peak_bytes": usage.memory_peak_bytes,
+        "cpu_usage_usec": usage.cpu_usage_usec,
+        "throttled_periods": usage.throttled_periods,
+        "throttled_usec": usage.throttled_usec,
+        "pids_max_events": usage.pids_max_events
+    })
+}
+
+/// Counters of a background job: live from its cgroup while it exists, or
+/// the ones captured when it was released.
+pub(crate) fn job_resource_usage(
+    record: &devit_common::process_registry::ProcessRecord,
+) -> Option<ResourceUsage> {
+    #[cfg(target_os = "linux")]
+    if let Some(usage) = record
+        .cgroup
+        .as_deref()
+        .and_then(|path| devit_sandbox::Cgroup::usage_of(path).ok())
+    {
+        return Some(usage);
+    }
+    record.resource_usage
+}
+
+/// Removes the cgroup of an exited background job, keeping its final
+/// counters in the record. Returns whether the record changed; a cgroup still
+/// holding processes is left for a later attempt.
+#[cfg(target_family = "unix")]
+pub(crate) fn release_job_cgroup(
+    record: &mut devit_common::process_registry::ProcessRecord,
+) -> bool {
+    let Some(path) = record.cgroup.clone() else {
+        return false;
+    };
+    #[cfg(target_os = "linux")]
+    {
+        match devit_sandbox::Cgroup::release(&path) {
+            Ok(usage) => record.resource_usage = Some(usage),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                tracing::debug!(
+                    target: "devit_mcp_tools",
+                    "cgroup {} not released yet: {}",
+                    path.display(),
+                    err
+                );
+                return false;
+            }
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = path;
+    record.cgroup = None;
+    true
+}
+
 /// DevIt Exec tool
 pub struct DevitExec {
     registry: Arc<Mutex<devit_common::process_registry::Registry>>,
     config: ExecToolConfig,
     sandbox_root: PathBuf,
+    seccomp_profiles: HashMap<String, SeccompProfileSpec>,
 }
 
 impl DevitExec {
@@ -113,9 +220,17 @@ impl DevitExec {
             registry: Arc::new(Mutex::new(registry)),
             config,
             sandbox_root: canonical_root,
+            seccomp_profiles: HashMap::new(),
         })
     }
 
+    /// Custom seccomp profiles (`[sandbox.seccomp_profiles]`) that
+    /// `seccomp_profile` may refer to.
+    pub fn with_seccomp_profiles(mut self, profiles: HashMap<String, SeccompProfileSpec>) -> Self {
+        self.seccomp_profiles = profiles;
+        self
+    }
+
     #[cfg(windows)]
     async fn execute_foreground_windows(&self, config: &ExecConfig) -> McpResult<Value> {
         use std::io::Read;
@@ -411,6 +526,8 @@ impl DevitExec {
             auto_kill_at: Some(
                 started_at + chrono::Duration::seconds(self.config.max_lifetime_secs as i64),
             ),
+            cgroup: None,
+            resource_usage: None,
         };
 
         let mut registry = self.registry.lock().await;
@@ -481,12 +598,13 @@ impl DevitExec {
 
     #[cfg(target_family = "unix")]
     async fn execute_background_unix(&self, config: &ExecConfig) -> McpResult<Value> {
-        let child = self
+        let (child, mut cgroup) = self
             .spawn_process(config)
             .await
             .map_err(|e| McpError::ExecutionFailed(e.to_string()))?;
 
         let pid = child.id();
+        let cgroup_path = cgroup.persist();
         drop(child);
 
         let proc_stat = read_proc_stat(pid).map_err(|e| {
@@ -509,6 +627,8 @@ impl DevitExec {
             auto_kill_at: Some(
                 started_at + chrono::Duration::seconds(self.config.max_lifetime_secs as i64),
             ),
+            cgroup: cgroup_path.clone(),
+            resource_usage: None,
         };
 
         let mut registry = self.registry.lock().await;
@@ -566,6 +686,7 @@ impl DevitExec {
                 "start_ticks": proc_stat.starttime,
                 "started_at": started_at,
                 "auto_kill_at": auto_kill_at_value,
+                "cgroup": cgroup_path,
                 "record": record_json,
                 "process": process_json
             }
@@ -677,7 +798,10 @@ impl DevitExec {
 
     /// Spawn process with security hardening
     #[cfg(target_family = "unix")]
-    async fn spawn_process(&self, config: &ExecConfig) -> io::Result<std::process::Child> {
+    async fn spawn_process(
+        &self,
+        config: &ExecConfig,
+    ) -> io::Result<(std::process::Child, ProcessCgroup)> {
         let working_dir = if let Some(ref wd) = config.working_dir {
             self.resolve_in_sandbox(Path::new(wd))?
         } else {
@@ -702,30 +826,128 @@ impl DevitExec {
 
         let limits = self.config.clone();
 
-        let child = unsafe {
-            Command::new(&binary_path)
-                .args(&config.args)
-                .current_dir(&working_dir)
-                .env_clear()
-                .envs(&safe_env)
-                .stdin(match config.stdin {
-                    StdinMode::Null => Stdio::null(),
-                    StdinMode::Pipe => Stdio::piped(),
-                })
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .pre_exec(move || {
-                    if libc::setpgid(0, 0) != 0 {
-                        return Err(io::Error::last_os_error());
-                    }
+        let mut command = Command::new(&binary_path);
+        command
+            .args(&config.args)
+            .current_dir(&working_dir)
+            .env_clear()
+            .envs(&safe_env)
+            .stdin(match config.stdin {
+                StdinMode::Null => Stdio::null(),
+                StdinMode::Pipe => Stdio::piped(),
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        unsafe {
+            command.pre_exec(move || {
+                if libc::setpgid(0, 0) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
 
-                    Self::apply_resource_limits(&limits)?;
-                    Ok(())
-                })
-                .spawn()?
+                Self::apply_resource_limits(&limits)?;
+                Ok(())
+            });
+        }
+        let cgroup = ProcessCgroup {
+            #[cfg(target_os = "linux")]
+            cgroup: self.create_cgroup(&mut command)?,
+        };
+        self.apply_isolation(&mut command, &binary_path)?;
+
+        Ok((command.spawn()?, cgroup))
+    }
+
+    /// Create the per-process cgroup enforcing `cpu_limit_percent`,
+    /// `memory_limit_mb` and `pids_max`, and move the child into it before exec.
+    #[cfg(target_os = "linux")]
+    fn create_cgroup(&self, command: &mut Command) -> io::Result<Option<devit_sandbox::Cgroup>> {
+        let limits = devit_sandbox::CgroupLimits {
+            cpu_percent: self.config.cpu_limit_percent,
+            memory_bytes: self
+                .config
+                .memory_limit_mb
+                .map(|mb| mb.saturating_mul(1024 * 1024)),
+            pids_max: self.config.pids_max,
+        };
+        if limits.is_empty() {
+            return Ok(None);
+        }
+        let cgroup = devit_sandbox::Cgroup::create(&limits)
+            .map_err(|err| io::Error::new(io::ErrorKind::PermissionDenied, format!("{err:#}")))?;
+        cgroup.attach(command);
+        Ok(Some(cgroup))
+    }
+
+    /// Confine the command in user/mount/network namespaces according to
+    /// `isolation` and `sandbox_profile`, then install the seccomp filter.
+    #[cfg(target_family = "unix")]
+    fn apply_isolation(&self, command: &mut Command, binary_path: &Path) -> io::Result<()> {
+        if self.config.isolation == IsolationMode::Off {
+            return self.apply_seccomp(command);
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            use devit_sandbox::NamespaceSandbox;
+
+            if NamespaceSandbox::is_supported() {
+                let mut plan = SandboxPlan::plan_for_test(
+                    self.sandbox_root.clone(),
+                    self.config.sandbox_profile.clone(),
+                );
+                if let Some(bin_dir) = binary_path.parent() {
+                    plan.bind_ro.push(bin_dir.to_path_buf());
+                }
+                plan.seccomp_profile = self.config.seccomp_profile.clone();
+                return NamespaceSandbox::new(plan)
+                    .with_seccomp_profiles(self.seccomp_profiles.clone())
+                    .confine(command)
+                    .map_err(|err| {
+                        io::Error::new(io::ErrorKind::PermissionDenied, err.to_string())
+                    });
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = binary_path;
+
+        match self.config.isolation {
+            IsolationMode::Required => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Namespace isolation required but unavailable on this host",
+            )),
+            _ => {
+                warn!(
+                    target: "devit_mcp_tools",
+                    "tool devit_exec isolation unavailable, spawning without namespaces"
+                );
+                self.apply_seccomp(command)
+            }
+        }
+    }
+
+    /// Install the `seccomp_profile` filter as the last step before exec.
+    #[cfg(target_family = "unix")]
+    fn apply_seccomp(&self, command: &mut Command) -> io::Result<()> {
+        let Some(profile) = self.config.seccomp_profile.as_deref() else {
+            return Ok(());
         };
 
-        Ok(child)
+        #[cfg(target_os = "linux")]
+        {
+            let filter = devit_sandbox::SeccompFilter::resolve(profile, &self.seccomp_profiles)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err.to_string()))?;
+            filter.confine(command);
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = command;
+            warn!(
+                target: "devit_mcp_tools",
+                "tool devit_exec seccomp profile '{}' ignored: not supported on this platform",
+                profile
+            );
+        }
+        Ok(())
     }
 
     /// Apply resource limits (called in before_exec)
@@ -790,15 +1012,17 @@ impl DevitExec {
         Ok(())
     }
 
-    /// Execute foreground (with timeout)
-    async fn execute_foreground(&self, config: &ExecConfig) -> McpResult<Value> {
+    /// Execute foreground (with timeout, progress heartbeat and cancellation)
+    async fn execute_foreground(&self, config: &ExecConfig, ctx: &ToolContext) -> McpResult<Value> {
         #[cfg(target_family = "unix")]
         {
-            return self.execute_foreground_unix(config).await;
+            return self.execute_foreground_unix(config, ctx).await;
         }
         #[cfg(windows)]
         {
-            return self.execute_foreground_windows(config).await;
+            return ctx
+                .run_cancellable(self.execute_foreground_windows(config))
+                .await;
         }
         #[allow(unreachable_code)]
         Err(McpError::ExecutionFailed(
@@ -807,25 +1031,45 @@ impl DevitExec {
     }
 
     #[cfg(target_family = "unix")]
-    async fn execute_foreground_unix(&self, config: &ExecConfig) -> McpResult<Value> {
+    async fn execute_foreground_unix(
+        &self,
+        config: &ExecConfig,
+        ctx: &ToolContext,
+    ) -> McpResult<Value> {
         let timeout_secs = config
             .foreground_timeout_secs
             .unwrap_or(self.config.rlimit_cpu_secs);
         let timeout = std::time::Duration::from_secs(timeout_secs);
 
         let start = std::time::Instant::now();
-        let child = self
+        let (child, cgroup) = self
             .spawn_process(config)
             .await
             .map_err(|e| McpError::ExecutionFailed(e.to_string()))?;
 
         let pid = child.id();
 
-        let output_result = tokio::time::timeout(
-            timeout,
-            tokio::task::spawn_blocking(move || child.wait_with_output()),
-        )
-        .await;
+        let wait = tokio::task::spawn_blocking(move || child.wait_with_output());
+        tokio::pin!(wait);
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+        let mut heartbeat = tokio::time::interval(FOREGROUND_PROGRESS_INTERVAL);
+        heartbeat.tick().await;
+        let output_result = loop {
+            tokio::select! {
+                joined = &mut wait => break Ok(joined),
+                _ = &mut deadline => break Err(ForegroundAbort::Timeout),
+                _ = ctx.cancelled() => break Err(ForegroundAbort::Cancelled),
+                _ = heartbeat.tick() => {
+                    let elapsed = start.elapsed().as_secs();
+                    ctx.report_progress(
+                        elapsed as f64,
+                        Some(timeout_secs as f64),
+                        format!("`{}` running for {}s", config.binary, elapsed),
+                    );
+                }
+            }
+        };
 
         let duration_ms = start.elapsed().as_millis() as u64;
 
@@ -862,10 +1106,21 @@ impl DevitExec {
                     (None, None) => "completed".to_string(),
                 };
 
-                let summary = format!(
+                let resources = cgroup.resource_usage();
+                let mut summary = format!(
                     "✅ `{}` terminé — {} ({} ms)",
                     config.binary, exit_summary, duration_ms
                 );
+                if let Some(usage) = resources.as_ref() {
+                    if usage.oom_killed() {
+                        summary.push_str(" — ⚠️ tué par l'OOM killer (memory_limit_mb)");
+                    } else if usage.throttled() {
+                        summary.push_str(&format!(
+                            " — CPU bridé {} ms (cpu_limit_percent)",
+                            usage.throttled_usec / 1000
+                        ));
+                    }
+                }
 
                 info!(
                     target: "devit_mcp_tools",
@@ -913,7 +1168,8 @@ impl DevitExec {
                         "exit_code": exit_code,
                         "terminated_by_signal": terminated_by_signal,
                         "stdout_tail": stdout_tail,
-                        "stderr_tail": stderr_tail
+                        "stderr_tail": stderr_tail,
+                        "resources": resources.as_ref().map(resource_usage_json)
                     }
                 });
 
@@ -930,12 +1186,23 @@ impl DevitExec {
                 "Blocking task failed: {}",
                 e
             ))),
-            Err(_timeout) => {
+            Err(ForegroundAbort::Timeout) => {
                 unsafe { libc::kill(-(pid as i32), libc::SIGKILL) };
                 Err(McpError::ExecutionFailed(
                     "Foreground execution timeout".into(),
                 ))
             }
+            Err(ForegroundAbort::Cancelled) => {
+                unsafe { libc::kill(-(pid as i32), libc::SIGKILL) };
+                info!(
+                    target: "devit_mcp_tools",
+                    "tool devit_exec foreground cancelled | binary={} pid={} duration_ms={}",
+                    config.binary,
+                    pid,
+                    duration_ms
+                );
+                Err(McpError::cancelled())
+            }
         }
     }
 
@@ -1007,6 +1274,11 @@ impl McpTool for DevitExec {
     }
 
     async fn execute(&self, params: Value) -> McpResult<Value> {
+        self.execute_with_context(params, &ToolContext::default())
+            .await
+    }
+
+    async fn execute_with_context(&self, params: Value, ctx: &ToolContext) -> McpResult<Value> {
         let config: ExecConfig =
             serde_json::from_value(params).map_err(|e| McpError::InvalidRequest(e.to_string()))?;
 
@@ -1017,7 +1289,7 @@ impl McpTool for DevitExec {
         );
 
         match config.mode {
-            ExecutionMode::Foreground => self.execute_foreground(&config).await,
+            ExecutionMode::Foreground => self.execute_foreground(&config, ctx).await,
             ExecutionMode::Background => self.execute_background(&config).await,
         }
     }
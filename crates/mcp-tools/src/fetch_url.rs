@@ -12,7 +12,7 @@ use async_trait::async_trait;
 use chrono::Utc;
 use devit_common::cache::cache_key;
 use devit_common::limits::{resolve_fetch_limits, EffectiveLimits, LimitSources};
-use mcp_core::{McpError, McpResult, McpTool};
+use mcp_core::{McpError, McpResult, McpTool, ToolContext};
 use reqwest::header::{ACCEPT, ACCEPT_LANGUAGE, CACHE_CONTROL, PRAGMA, USER_AGENT};
 use reqwest::redirect::Policy as RedirectPolicy;
 use reqwest::Client;
@@ -21,6 +21,9 @@ use tracing::info;
 use url::Url;
 use uuid::Uuid;
 
+/// Downloaded bytes between two progress notifications
+const PROGRESS_STEP_BYTES: usize = 64 * 1024;
+
 /// MCP tool: devit_fetch_url — safe HTML/text fetch with robots + sanitizer
 pub struct FetchUrlTool;
 
@@ -108,6 +111,11 @@ impl McpTool for FetchUrlTool {
     }
 
     async fn execute(&self, params: Value) -> McpResult<Value> {
+        self.execute_with_context(params, &ToolContext::default())
+            .await
+    }
+
+    async fn execute_with_context(&self, params: Value, ctx: &ToolContext) -> McpResult<Value> {
         let url_raw = params
             .get("url")
             .and_then(Value::as_str)
@@ -166,7 +174,11 @@ impl McpTool for FetchUrlTool {
         let mut robots_policy_str = "unknown".to_string();
         if respect_robots {
             if let Some(host) = url.host_str() {
-                let robots_txt = Self::fetch_robots(&client, &url, timeout_ms).await;
+                let robots_txt = ctx
+                    .run_cancellable(async {
+                        Ok(Self::fetch_robots(&client, &url, timeout_ms).await)
+                    })
+                    .await?;
                 if let Some(text) = robots_txt.as_deref() {
                     let policy = robots_policy_for(url.path(), text);
                     robots_policy_str = match policy {
@@ -236,7 +248,8 @@ impl McpTool for FetchUrlTool {
         let accept_hdr = "text/html, text/plain;q=0.9, */*;q=0.1";
         let cache_key_val = cache_key(url.as_str(), accept_hdr, &agent, _safe_mode, true);
 
-        let resp = req.send().await;
+        ctx.report_progress(0.0, None, format!("fetching {}", url));
+        let resp = ctx.run_cancellable(async { Ok(req.send().await) }).await?;
         let trace_id = Uuid::new_v4().to_string();
         match resp {
             Ok(mut r) => {
@@ -344,8 +357,21 @@ impl McpTool for FetchUrlTool {
 
                 // Stream body with limit
                 let mut bytes: Vec<u8> = Vec::new();
-                while let Ok(Some(chunk)) = r.chunk().await {
+                let expected_bytes = r.content_length().map(|len| len as f64);
+                let mut reported = 0usize;
+                while let Ok(Some(chunk)) = ctx
+                    .run_cancellable(async { Ok(r.chunk().await) })
+                    .await?
+                {
                     bytes.extend_from_slice(&chunk);
+                    if bytes.len() - reported >= PROGRESS_STEP_BYTES {
+                        reported = bytes.len();
+                        ctx.report_progress(
+                            reported as f64,
+                            expected_bytes,
+                            format!("{} bytes downloaded", reported),
+                        );
+                    }
                     if (bytes.len() as u64) > max_bytes {
                         info!(target: "mcp.fetch", %trace_id, op="fetch", url=%final_url, status=%status, downloaded=%bytes.len(), cache_key=%cache_key_val, "stream too large");
                         let meta = json!({
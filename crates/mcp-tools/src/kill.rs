@@ -157,11 +157,14 @@ impl McpTool for DevitKill {
                 still_running
             );
 
-            // Update registry entry
+            // Update registry entry; the cgroup goes once the job is gone
             if let Some(entry) = registry.get_mut(pid) {
                 entry.status = ProcessStatus::Exited;
                 entry.terminated_by_signal = Some(sig);
                 entry.exit_code = None;
+                if !still_running {
+                    crate::exec::release_job_cgroup(entry);
+                }
             }
 
             if let Err(e) = save_registry(&registry) {
@@ -188,7 +191,11 @@ impl McpTool for DevitKill {
                 "status": updated_record.status.clone(),
                 "exit_code": updated_record.exit_code,
                 "terminated_by_signal": updated_record.terminated_by_signal,
-                "auto_kill_at": updated_record.auto_kill_at.clone()
+                "auto_kill_at": updated_record.auto_kill_at.clone(),
+                "cgroup": updated_record.cgroup.clone(),
+                "resources": crate::exec::job_resource_usage(&updated_record)
+                    .as_ref()
+                    .map(crate::exec::resource_usage_json)
             });
 
             info!(
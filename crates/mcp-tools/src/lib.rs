@@ -9,6 +9,8 @@ use tracing::warn;
 
 mod atomic_patcher;
 mod directory_list;
+mod dynamic;
+mod edit_apply;
 mod errors;
 mod exec;
 mod fetch_url;
@@ -29,14 +31,17 @@ mod ocr;
 mod ocr_alerts;
 mod orchestration;
 mod patch_apply;
+mod prompts;
 mod ps;
 mod pwd;
+mod resources;
 mod screenshot;
 mod search_web;
 mod snapshot;
 mod test_run;
 mod worker;
 
+use devit_cli::core::atomic_patcher::PatchOptions;
 use devit_cli::core::config::CoreConfig;
 use exec::DevitExec;
 #[cfg(target_os = "linux")]
@@ -50,6 +55,8 @@ pub use devit_common::orchestration::{
     format_status, OrchestrationConfig, OrchestrationContext, OrchestrationMode, StatusFormat,
 };
 pub use directory_list::DirectoryListTool;
+pub use dynamic::{DynamicTools, PluginTool, PLUGIN_TOOL_PREFIX};
+pub use edit_apply::EditApplyTool;
 pub use errors::{
     desktop_env_error, internal_error, invalid_diff_error, io_error, policy_block_error,
     validation_error,
@@ -63,9 +70,14 @@ pub use file_write::FileWriteTool;
 pub use git::{GitBlameTool, GitDiffTool, GitLogTool, GitSearchTool, GitShowTool};
 pub use help::HelpTool;
 pub use journal::{JournalAppendResult, JournalAppendTool, JournalContext};
-pub use orchestration::{DelegateTool, NotifyTool, OrchestrationStatusTool, TaskResultTool};
+pub use orchestration::{
+    DelegateGraphTool, DelegateTool, NotifyTool, OrchestrationStatusTool, TaskCancelTool,
+    TaskResultTool,
+};
 pub use patch_apply::{PatchApplyTool, PatchContext};
+pub use prompts::WorkspacePrompts;
 pub use pwd::PwdTool;
+pub use resources::WorkspaceResources;
 pub use screenshot::ScreenshotTool;
 pub use snapshot::{SnapshotContext, SnapshotTool};
 pub use test_run::{TestRunContext, TestRunTool};
@@ -90,6 +102,37 @@ pub async fn default_tools_with_options(
     root_path: PathBuf,
     options: ToolOptions,
 ) -> McpResult<Vec<Arc<dyn McpTool>>> {
+    let (tools, _) = default_tools_and_resources(root_path, options).await?;
+    Ok(tools)
+}
+
+/// Tools du worker-mode pour un `WorkerBridge` connecté après le démarrage :
+/// `devit_notify` relié au worker (remplace la version sans worker) et
+/// `devit_poll_tasks`.
+pub async fn worker_tools(
+    root_path: PathBuf,
+    worker: Arc<WorkerBridge>,
+) -> McpResult<Vec<Arc<dyn McpTool>>> {
+    let mut core_config =
+        load_core_config(&root_path).map_err(|err| internal_error(err.to_string()))?;
+    apply_orchestration_env_overrides(&mut core_config.orchestration.base);
+    let orchestration_context = Arc::new(
+        OrchestrationContext::new(core_config.orchestration.base)
+            .await
+            .map_err(|err| internal_error(err.to_string()))?,
+    );
+    Ok(vec![
+        Arc::new(NotifyTool::with_worker(orchestration_context, Arc::clone(&worker))),
+        Arc::new(PollTasksTool::new(worker)),
+    ])
+}
+
+/// Construit les tools et les ressources MCP en partageant leurs contextes
+/// (fichiers, orchestration).
+pub async fn default_tools_and_resources(
+    root_path: PathBuf,
+    options: ToolOptions,
+) -> McpResult<(Vec<Arc<dyn McpTool>>, Arc<WorkspaceResources>)> {
     let ToolOptions {
         worker_bridge,
         exec_config: provided_exec_config,
@@ -98,9 +141,8 @@ pub async fn default_tools_with_options(
 
     let file_context = Arc::new(FileSystemContext::new(root_path.clone())?);
     let dir_context = Arc::clone(&file_context);
-    let patch_context = Arc::new(PatchContext::new(root_path.clone())?);
     let test_context = Arc::new(TestRunContext::new(root_path.clone())?);
-    let snapshot_context = Arc::new(SnapshotContext::new(root_path)?);
+    let snapshot_context = Arc::new(SnapshotContext::new(root_path.clone())?);
     let journal_context = Arc::new(JournalContext::new(Arc::clone(&file_context))?);
     let mut core_config =
         load_core_config(file_context.root()).map_err(|err| internal_error(err.to_string()))?;
@@ -115,6 +157,12 @@ pub async fn default_tools_with_options(
     let file_tool_ext = FileReadTool::new_extended(Arc::clone(&file_context));
     let explorer = Arc::new(FileExplorer::new(Arc::clone(&file_context))?);
     let file_write_tool = FileWriteTool::new(Arc::clone(&file_context))?;
+    let patch_context = Arc::new(
+        PatchContext::new(root_path)?
+            .with_options(PatchOptions::from(&core_config.tools.patch))
+            .with_policy(&core_config.policy)?,
+    );
+    let edit_tool = EditApplyTool::new(Arc::clone(&patch_context));
     let patch_tool = PatchApplyTool::new(patch_context);
     let test_tool = TestRunTool::new(test_context);
     let snapshot_tool = SnapshotTool::new(snapshot_context);
@@ -123,6 +171,10 @@ pub async fn default_tools_with_options(
         Arc::clone(&orchestration_context),
         Arc::clone(&file_context),
     );
+    let delegate_graph_tool = DelegateGraphTool::new(
+        Arc::clone(&orchestration_context),
+        Arc::clone(&file_context),
+    );
 
     let notify_tool: Arc<dyn McpTool> = if let Some(worker) = worker_bridge.as_ref() {
         Arc::new(NotifyTool::with_worker(
@@ -134,6 +186,7 @@ pub async fn default_tools_with_options(
     };
     let status_tool = OrchestrationStatusTool::new(Arc::clone(&orchestration_context));
     let task_result_tool = TaskResultTool::new(Arc::clone(&orchestration_context));
+    let task_cancel_tool = TaskCancelTool::new(Arc::clone(&orchestration_context));
     let git_log = GitLogTool::new(Arc::clone(&file_context));
     let git_blame = GitBlameTool::new(Arc::clone(&file_context));
     let git_show = GitShowTool::new(Arc::clone(&file_context));
@@ -148,7 +201,10 @@ pub async fn default_tools_with_options(
     let fetch_url_tool: Arc<dyn McpTool> = fetch_url::FetchUrlTool::new();
     let exec_config = provided_exec_config.unwrap_or_else(|| core_config.tools.exec.clone());
     let sandbox_root = provided_sandbox_root.unwrap_or_else(|| file_context.root().to_path_buf());
-    let exec_tool: Arc<dyn McpTool> = Arc::new(DevitExec::with_config(exec_config, sandbox_root)?);
+    let exec_tool: Arc<dyn McpTool> = Arc::new(
+        DevitExec::with_config(exec_config, sandbox_root)?
+            .with_seccomp_profiles(core_config.sandbox.seccomp_profiles.clone()),
+    );
     let ps_tool: Arc<dyn McpTool> = Arc::new(DevitPs::new());
     let kill_tool: Arc<dyn McpTool> = Arc::new(DevitKill::new());
 
@@ -165,13 +221,16 @@ pub async fn default_tools_with_options(
         Arc::new(HelpTool::new(Arc::clone(&file_context))),
         Arc::new(file_write_tool),
         Arc::new(patch_tool),
+        Arc::new(edit_tool),
         Arc::new(test_tool),
         Arc::new(snapshot_tool),
         Arc::new(journal_tool),
         Arc::new(delegate_tool),
+        Arc::new(delegate_graph_tool),
         notify_tool,
         Arc::new(status_tool),
         Arc::new(task_result_tool),
+        Arc::new(task_cancel_tool),
         Arc::new(PwdTool::new(Arc::clone(&file_context))),
         Arc::new(git_log),
         Arc::new(git_blame),
@@ -204,7 +263,12 @@ pub async fn default_tools_with_options(
         tools.push(Arc::new(KeyboardTool::new()));
     }
 
-    Ok(tools)
+    let resources = Arc::new(WorkspaceResources::new(
+        Arc::clone(&file_context),
+        orchestration_context,
+    ));
+
+    Ok((tools, resources))
 }
 
 fn load_core_config(root_path: &Path) -> Result<CoreConfig, String> {
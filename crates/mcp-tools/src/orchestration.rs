@@ -1,13 +1,14 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Error;
 use async_trait::async_trait;
 use devit_common::orchestration::{
-    format_status, DelegatedTask, OrchestrationContext, StatusFormat, TaskNotification, TaskStatus,
+    format_status, DelegatedTask, OrchestrationContext, StatusFormat, TaskNotification, TaskSpec,
+    TaskStatus,
 };
-use mcp_core::{McpError, McpResult, McpTool};
+use mcp_core::{McpError, McpResult, McpTool, ToolContext};
 use serde_json::{json, Value};
 use tokio::time::sleep;
 
@@ -15,6 +16,11 @@ use crate::errors::{internal_error, io_error, validation_error};
 use crate::file_read::FileSystemContext;
 use crate::worker::WorkerBridge;
 
+/// Intervalle de suivi d'une tâche déléguée avec `wait`
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Marge au-delà du timeout de la tâche avant d'abandonner l'attente
+const WAIT_GRACE: Duration = Duration::from_secs(60);
+
 pub struct DelegateTool {
     context: Arc<OrchestrationContext>,
     fs: Arc<FileSystemContext>,
@@ -24,6 +30,63 @@ impl DelegateTool {
     pub fn new(context: Arc<OrchestrationContext>, fs: Arc<FileSystemContext>) -> Self {
         Self { context, fs }
     }
+
+    /// Suit la tâche jusqu'à un état terminal en relayant ses notifications
+    /// comme progression ; si le client annule l'appel, la tâche est annulée.
+    async fn wait_for_completion(
+        &self,
+        task_id: &str,
+        timeout_secs: u64,
+        ctx: &ToolContext,
+    ) -> McpResult<DelegatedTask> {
+        let started = Instant::now();
+        let deadline = Duration::from_secs(timeout_secs) + WAIT_GRACE;
+        loop {
+            tokio::select! {
+                _ = sleep(WAIT_POLL_INTERVAL) => {}
+                _ = ctx.cancelled() => {
+                    if let Err(err) = self
+                        .context
+                        .cancel(task_id, Some("cancelled by MCP client"))
+                        .await
+                    {
+                        tracing::warn!("cancel of delegated task {} failed: {}", task_id, err);
+                    }
+                    return Err(McpError::cancelled());
+                }
+            }
+
+            // Force a daemon refresh so we observe newly completed tasks.
+            let _ = self.context.status(None).await.map_err(map_error)?;
+            if let Some(task) = self.context.task(task_id).await.map_err(map_error)? {
+                if matches!(
+                    task.status,
+                    TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled
+                ) {
+                    return Ok(task);
+                }
+                let message = task
+                    .notifications
+                    .last()
+                    .map(|note| note.summary.clone())
+                    .unwrap_or_else(|| {
+                        format!("Tâche {} : {}", task_id, task_status_label(&task.status))
+                    });
+                ctx.report_progress(
+                    started.elapsed().as_secs() as f64,
+                    Some(timeout_secs as f64),
+                    message,
+                );
+            }
+
+            if started.elapsed() > deadline {
+                return Err(internal_error(format!(
+                    "La tâche '{}' n'a pas terminé dans le délai imparti ({}s)",
+                    task_id, timeout_secs
+                )));
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -37,6 +100,11 @@ impl McpTool for DelegateTool {
     }
 
     async fn execute(&self, params: Value) -> McpResult<Value> {
+        self.execute_with_context(params, &ToolContext::default())
+            .await
+    }
+
+    async fn execute_with_context(&self, params: Value, ctx: &ToolContext) -> McpResult<Value> {
         let goal = params
             .get("goal")
             .and_then(Value::as_str)
@@ -96,6 +164,8 @@ impl McpTool for DelegateTool {
             None => None,
         };
 
+        let wait = params.get("wait").and_then(Value::as_bool).unwrap_or(false);
+
         let result = self
             .context
             .delegate(
@@ -119,6 +189,34 @@ impl McpTool for DelegateTool {
         let mode_label = response_format.unwrap_or_else(|| "default".to_string());
         let model_label = model.clone().unwrap_or_else(|| "<default>".to_string());
 
+        if wait {
+            let task = self
+                .wait_for_completion(&result.task_id, result.timeout_secs, ctx)
+                .await?;
+            let status_label = task_status_label(&task.status);
+            let summary = find_result_notification(&task)
+                .map(|note| note.summary.clone())
+                .unwrap_or_else(|| "Aucun compte rendu n'a été enregistré".to_string());
+            return Ok(json!({
+                "content": [{
+                    "type": "text",
+                    "text": format!(
+                        "🎯 **Delegated Task Finished**\n\n**Task ID**: {}\n**Goal**: {}\n**Delegated to**: {}\n**Status**: {}\n**Summary**: {}\n\n➡️ Détails complets via `devit_task_result`",
+                        task.id,
+                        goal,
+                        delegated_to,
+                        status_label,
+                        summary
+                    )
+                }],
+                "structuredContent": {
+                    "task_id": task.id,
+                    "status": status_label,
+                    "summary": summary
+                }
+            }));
+        }
+
         Ok(json!({
             "content": [{
                 "type": "text",
@@ -169,6 +267,11 @@ impl McpTool for DelegateTool {
                     "type": "string",
                     "enum": ["default", "compact"],
                     "default": "default"
+                },
+                "wait": {
+                    "type": "boolean",
+                    "description": "Attendre la fin de la tâche (progression relayée, annulation propagée)",
+                    "default": false
                 }
             },
             "required": ["goal"],
@@ -177,6 +280,177 @@ impl McpTool for DelegateTool {
     }
 }
 
+pub struct DelegateGraphTool {
+    context: Arc<OrchestrationContext>,
+    fs: Arc<FileSystemContext>,
+}
+
+impl DelegateGraphTool {
+    pub fn new(context: Arc<OrchestrationContext>, fs: Arc<FileSystemContext>) -> Self {
+        Self { context, fs }
+    }
+}
+
+#[async_trait]
+impl McpTool for DelegateGraphTool {
+    fn name(&self) -> &str {
+        "devit_delegate_graph"
+    }
+
+    fn description(&self) -> &str {
+        "Déléguer un graphe de tâches liées par depends_on (chaque tâche attend la fin de ses dépendances)"
+    }
+
+    async fn execute(&self, params: Value) -> McpResult<Value> {
+        let tasks = params
+            .get("tasks")
+            .and_then(Value::as_array)
+            .filter(|tasks| !tasks.is_empty())
+            .ok_or_else(|| {
+                validation_error("Le paramètre 'tasks' est requis et ne peut pas être vide")
+            })?;
+
+        let mut specs = Vec::with_capacity(tasks.len());
+        for (index, raw) in tasks.iter().enumerate() {
+            let mut raw = raw.clone();
+            // Same spelling as devit_delegate
+            if let Some(object) = raw.as_object_mut() {
+                if let Some(timeout) = object.remove("timeout") {
+                    object.entry("timeout_secs").or_insert(timeout);
+                }
+                if let Some(format) = object.remove("format") {
+                    if format.as_str() != Some("default") {
+                        object.entry("response_format").or_insert(format);
+                    }
+                }
+            }
+            let mut spec: TaskSpec = serde_json::from_value(raw).map_err(|err| {
+                validation_error(&format!("Tâche #{} invalide: {}", index + 1, err))
+            })?;
+            if spec.key.trim().is_empty() || spec.goal.trim().is_empty() {
+                return Err(validation_error(&format!(
+                    "Tâche #{}: 'key' et 'goal' ne peuvent pas être vides",
+                    index + 1
+                )));
+            }
+            if let Some(format) = spec.response_format.as_deref() {
+                if format != "compact" {
+                    return Err(validation_error(&format!(
+                        "Format invalide '{}'. Formats supportés: compact, default",
+                        format
+                    )));
+                }
+            }
+            spec.working_dir = match spec.working_dir.take() {
+                Some(dir) => parse_working_dir(
+                    Some(&Value::String(dir.to_string_lossy().into_owned())),
+                    &self.fs,
+                )?,
+                None => None,
+            };
+            specs.push(spec);
+        }
+
+        let results = self
+            .context
+            .delegate_graph(specs)
+            .await
+            .map_err(map_error)?;
+
+        let lines: Vec<String> = results
+            .iter()
+            .map(|(key, result)| {
+                format!(
+                    "- **{}** → `{}` (timeout {}s)",
+                    key, result.task_id, result.timeout_secs
+                )
+            })
+            .collect();
+        let tasks: Vec<Value> = results
+            .iter()
+            .map(|(key, result)| {
+                json!({
+                    "key": key,
+                    "task_id": result.task_id,
+                    "timeout_secs": result.timeout_secs
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "content": [{
+                "type": "text",
+                "text": format!(
+                    "🎯 **Task Graph Delegated** ({} tâches)\n\n{}\n\n⏳ Chaque tâche démarre quand ses dépendances sont terminées",
+                    results.len(),
+                    lines.join("\n")
+                )
+            }],
+            "structuredContent": {
+                "tasks": tasks
+            }
+        }))
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tasks": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "key": {
+                                "type": "string",
+                                "description": "Nom de la tâche dans le graphe, référencé par depends_on"
+                            },
+                            "goal": {"type": "string"},
+                            "delegated_to": {
+                                "type": "string",
+                                "description": "Identifiant du worker (ex: claude_code, codex, ...)"
+                            },
+                            "depends_on": {
+                                "type": "array",
+                                "items": {"type": "string"},
+                                "description": "Clés d'autres tâches du graphe, ou identifiants de tâches déjà déléguées"
+                            },
+                            "on_dependency_failure": {
+                                "type": "string",
+                                "enum": ["fail", "skip"],
+                                "default": "fail"
+                            },
+                            "priority": {"type": "integer", "default": 0},
+                            "timeout": {
+                                "type": "integer",
+                                "description": "Timeout en secondes"
+                            },
+                            "model": {"type": "string"},
+                            "watch_patterns": {
+                                "type": "array",
+                                "items": {"type": "string"}
+                            },
+                            "context": {"type": "object"},
+                            "working_dir": {
+                                "type": "string",
+                                "description": "Répertoire de travail relatif au sandbox"
+                            },
+                            "format": {
+                                "type": "string",
+                                "enum": ["default", "compact"],
+                                "default": "default"
+                            }
+                        },
+                        "required": ["key", "goal", "delegated_to"]
+                    }
+                }
+            },
+            "required": ["tasks"]
+        })
+    }
+}
+
 fn parse_working_dir(raw: Option<&Value>, fs: &FileSystemContext) -> McpResult<Option<PathBuf>> {
     let Some(Value::String(dir)) = raw else {
         return Ok(None);
@@ -572,6 +846,69 @@ impl McpTool for TaskResultTool {
     }
 }
 
+pub struct TaskCancelTool {
+    context: Arc<OrchestrationContext>,
+}
+
+impl TaskCancelTool {
+    pub fn new(context: Arc<OrchestrationContext>) -> Self {
+        Self { context }
+    }
+}
+
+#[async_trait]
+impl McpTool for TaskCancelTool {
+    fn name(&self) -> &str {
+        "devit_task_cancel"
+    }
+
+    fn description(&self) -> &str {
+        "Annuler une tâche déléguée et arrêter le worker qui l'exécute"
+    }
+
+    async fn execute(&self, params: Value) -> McpResult<Value> {
+        let task_id = params
+            .get("task_id")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| validation_error("Le paramètre 'task_id' est requis"))?;
+        let reason = params
+            .get("reason")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+
+        self.context
+            .cancel(task_id, reason)
+            .await
+            .map_err(map_error)?;
+
+        Ok(json!({
+            "content": [{
+                "type": "text",
+                "text": format!(
+                    "🛑 **Task Cancelled**\n\n**Task**: {}\n**Reason**: {}\n\n✅ Worker arrêté et tâche marquée annulée",
+                    task_id,
+                    reason.unwrap_or("-")
+                )
+            }]
+        }))
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "task_id": {"type": "string"},
+                "reason": {"type": "string", "description": "Motif enregistré avec l'annulation"}
+            },
+            "required": ["task_id"],
+            "additionalProperties": false
+        })
+    }
+}
+
 fn find_result_notification(task: &DelegatedTask) -> Option<&TaskNotification> {
     if task.notifications.is_empty() {
         return None;
@@ -1,15 +1,26 @@
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use mcp_core::{McpResult, McpTool};
 use serde_json::{json, Value};
 
+use crate::atomic_patcher::map_core_error;
 use crate::atomic_patcher::{AtomicPatcher, FileChangeSummary, PatchStats};
 use crate::errors::{
     empty_patch_error, internal_error, invalid_diff_error, unsupported_format_error,
+    validation_error,
 };
 use chrono::{SecondsFormat, Utc};
+use devit_cli::core::atomic_patcher::PatchOptions;
+use devit_cli::core::config::PolicyConfig;
+use devit_cli::core::policy::PolicyEngine;
+use devit_cli::core::snapshot::{Snapshot, SnapshotManager, SnapshotOptions};
+use devit_cli::core::{
+    evaluate_patch_policy, patch, patch_transaction, DevItResult, PathSecurityContext,
+};
+use devit_common::{ApprovalLevel, SandboxProfile, SnapshotId};
+use tracing::warn;
 
 const MAX_PATCH_SIZE: usize = 1024 * 1024; // 1 MB
 
@@ -57,7 +68,25 @@ Tip: generate patches with `git diff` to ensure proper headers."
 
         ensure_supported_format(diff)?;
 
-        match self.context.apply_patch(diff, dry_run) {
+        let mut options = self.context.options();
+        if let Some(fuzz) = params.get("fuzz").and_then(Value::as_u64) {
+            options.fuzz = fuzz as usize;
+        }
+        if let Some(max_offset) = params.get("max_offset").and_then(Value::as_u64) {
+            options.max_offset = Some(max_offset as usize);
+        }
+        if let Some(ignore) = params.get("ignore_whitespace").and_then(Value::as_bool) {
+            options.ignore_whitespace = ignore;
+        }
+
+        let applied = match params.get("snapshot_id").and_then(Value::as_str) {
+            Some(snapshot_id) => {
+                self.context
+                    .apply_patch_merge(diff, dry_run, options, snapshot_id)
+            }
+            None => self.context.apply_patch_with(diff, dry_run, options),
+        };
+        match applied {
             Ok(result) => Ok(build_response(dry_run, &result)),
             Err(err) => Err(err),
         }
@@ -68,7 +97,25 @@ Tip: generate patches with `git diff` to ensure proper headers."
             "type": "object",
             "properties": {
                 "diff": {"type": "string"},
-                "dry_run": {"type": "boolean"}
+                "dry_run": {"type": "boolean"},
+                "fuzz": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Context lines that may be ignored at each end of a hunk (default from [tools.patch], 2)"
+                },
+                "max_offset": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Lines searched around the line named by each hunk header (default: whole file)"
+                },
+                "ignore_whitespace": {
+                    "type": "boolean",
+                    "description": "Match lines that differ only in whitespace"
+                },
+                "snapshot_id": {
+                    "type": "string",
+                    "description": "Snapshot the diff was written against (devit_snapshot or devit snapshot); files it no longer applies to are merged three-way, conflicts left as markers"
+                }
             },
             "required": ["diff"]
         })
@@ -77,6 +124,16 @@ Tip: generate patches with `git diff` to ensure proper headers."
 
 pub struct PatchContext {
     root_path: PathBuf,
+    options: PatchOptions,
+    policy: Option<PatchPolicy>,
+}
+
+/// Checks run before a patch is written, the same as `CoreEngine::patch_apply`.
+struct PatchPolicy {
+    engine: PolicyEngine,
+    approval_level: ApprovalLevel,
+    protected_paths: Vec<PathBuf>,
+    path_security: PathSecurityContext,
 }
 
 pub struct PatchExecutionResult {
@@ -89,12 +146,115 @@ impl PatchContext {
         let canonical = root_path.canonicalize().map_err(|err| {
             internal_error(format!("Impossible de résoudre le répertoire: {}", err))
         })?;
+        // Undo patches interrupted by a crash of a previous server
+        match patch_transaction::recover(&canonical) {
+            Ok(recovered) => {
+                for id in recovered {
+                    warn!("Rolled back interrupted patch transaction {}", id);
+                }
+            }
+            Err(err) => warn!("Failed to recover patch transactions: {}", err),
+        }
         Ok(Self {
             root_path: canonical,
+            options: PatchOptions::default(),
+            policy: None,
         })
     }
 
+    /// Enforces `[policy]` on every patch, at its default approval level.
+    pub fn with_policy(mut self, config: &PolicyConfig) -> McpResult<Self> {
+        let approval_level = config.default_approval_level.clone();
+        let mut engine = PolicyEngine::new(approval_level.clone(), SandboxProfile::Strict);
+        engine
+            .apply_rules(&config.rules, &config.path_overrides)
+            .map_err(|err| internal_error(err.to_string()))?;
+        let path_security =
+            PathSecurityContext::new(&self.root_path, approval_level != ApprovalLevel::Untrusted)
+                .map_err(map_core_error)?;
+        self.policy = Some(PatchPolicy {
+            engine,
+            approval_level,
+            protected_paths: config.protected_paths.clone(),
+            path_security,
+        });
+        Ok(self)
+    }
+
+    /// Default hunk placement tolerance, overridable per call.
+    pub fn with_options(mut self, options: PatchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn options(&self) -> PatchOptions {
+        self.options
+    }
+
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
     pub fn apply_patch(&self, diff: &str, dry_run: bool) -> McpResult<PatchExecutionResult> {
+        self.apply_patch_with(diff, dry_run, self.options)
+    }
+
+    pub fn apply_patch_with(
+        &self,
+        diff: &str,
+        dry_run: bool,
+        options: PatchOptions,
+    ) -> McpResult<PatchExecutionResult> {
+        self.apply(diff, dry_run, options, None)
+    }
+
+    /// Applies a patch written against `snapshot_id`, merging three-way the
+    /// files it no longer applies to, like `devit apply --base-snapshot`.
+    pub fn apply_patch_merge(
+        &self,
+        diff: &str,
+        dry_run: bool,
+        options: PatchOptions,
+        snapshot_id: &str,
+    ) -> McpResult<PatchExecutionResult> {
+        let base = self.load_snapshot(snapshot_id)?;
+        self.apply(diff, dry_run, options, Some(base))
+    }
+
+    /// A snapshot copied by `devit_snapshot` (`.devit/snapshots/<id>/`) or
+    /// stored by `devit snapshot` (`.devit/snapshots/<id>.json`).
+    fn load_snapshot(&self, snapshot_id: &str) -> McpResult<Snapshot> {
+        let mut components = Path::new(snapshot_id).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(validation_error("Invalid 'snapshot_id'"));
+        }
+
+        let store = self.root_path.join(".devit").join("snapshots");
+        let copy = store.join(snapshot_id);
+        if copy.is_dir() {
+            let options = SnapshotOptions {
+                exclude_patterns: Vec::new(),
+                include_git_info: false,
+                ..SnapshotOptions::default()
+            };
+            return Snapshot::create(copy, snapshot_id.to_string(), &options)
+                .map_err(map_core_error);
+        }
+        SnapshotManager::new(store, 0)
+            .get_snapshot(&SnapshotId(snapshot_id.to_string()))
+            .map_err(|_| validation_error(&format!("Unknown snapshot '{}'", snapshot_id)))
+    }
+
+    fn apply(
+        &self,
+        diff: &str,
+        dry_run: bool,
+        options: PatchOptions,
+        merge_base: Option<Snapshot>,
+    ) -> McpResult<PatchExecutionResult> {
         if diff.trim().is_empty() {
             return Err(empty_patch_error());
         }
@@ -109,7 +269,12 @@ impl PatchContext {
             ));
         }
 
-        let patcher = AtomicPatcher::new(self.root_path.clone(), dry_run);
+        if let Some(policy) = &self.policy {
+            policy.check(diff).map_err(map_core_error)?;
+        }
+
+        let patcher = AtomicPatcher::new(self.root_path.clone(), dry_run, options)
+            .with_merge_base(merge_base);
         let (stats, summaries) = patcher.apply_patch(diff)?;
 
         Ok(PatchExecutionResult {
@@ -119,7 +284,29 @@ impl PatchContext {
     }
 }
 
-fn build_response(dry_run: bool, result: &PatchExecutionResult) -> Value {
+impl PatchPolicy {
+    fn check(&self, diff: &str) -> DevItResult<()> {
+        let changes = patch::classify_changes(diff)?;
+        for change in &changes {
+            self.path_security.validate_patch_path(&change.file_path)?;
+            if change.is_symlink {
+                if let Some(target) = &change.symlink_target {
+                    self.path_security
+                        .validate_symlink(&change.file_path, target)?;
+                }
+            }
+        }
+        evaluate_patch_policy(
+            &self.engine,
+            &changes,
+            &self.approval_level,
+            &self.protected_paths,
+        )?;
+        Ok(())
+    }
+}
+
+pub(crate) fn build_response(dry_run: bool, result: &PatchExecutionResult) -> Value {
     let stats = &result.stats;
     let status_icon = if dry_run { "🔍" } else { "✅" };
     let action_text = if dry_run { "Preview" } else { "Applied" };
@@ -145,20 +332,72 @@ fn build_response(dry_run: bool, result: &PatchExecutionResult) -> Value {
         }
     }
 
+    let adjusted: Vec<_> = stats.adjusted_hunks().collect();
+    if !adjusted.is_empty() {
+        lines.push(String::new());
+        lines.push("Adjusted hunks:".to_string());
+        for placement in &adjusted {
+            lines.push(format!("- {}", placement));
+        }
+    }
+
+    if !stats.merged_files.is_empty() {
+        lines.push(String::new());
+        lines.push("Merged three-way with the base snapshot:".to_string());
+        for path in &stats.merged_files {
+            lines.push(format!("- {}", path.display()));
+        }
+    }
+    for file in &stats.conflicts {
+        lines.push(format!(
+            "⚠️ {} merge conflict(s) left as markers in {}",
+            file.hunks.len(),
+            file.path
+        ));
+    }
+
+    let rollback_cmd = stats
+        .transaction_id
+        .as_deref()
+        .map(patch_transaction::rollback_command);
+    if let Some(cmd) = &rollback_cmd {
+        lines.push(String::new());
+        lines.push(format!("Rollback: {}", cmd));
+    }
+
     let structured = json!({
         "patch": {
             "success": true,
             "dryRun": dry_run,
+            "transaction_id": stats.transaction_id,
+            "rollback_cmd": rollback_cmd,
             "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
             "summary": {
                 "files": result.files.len(),
                 "files_modified": stats.files_modified,
                 "files_created": stats.files_created,
                 "files_deleted": stats.files_deleted,
+                "files_renamed": stats.files_renamed,
+                "files_copied": stats.files_copied,
                 "hunks": stats.hunks_applied,
                 "lines_added": stats.lines_added,
-                "lines_removed": stats.lines_removed
+                "lines_removed": stats.lines_removed,
+                "hunks_adjusted": adjusted.len(),
+                "files_merged": stats.merged_files.len(),
+                "conflicts": stats.conflicts.iter().map(|file| file.hunks.len()).sum::<usize>()
             },
+            "hunks": stats.hunks.iter().map(|placement| {
+                json!({
+                    "path": placement.path.to_string_lossy(),
+                    "hunk": placement.hunk,
+                    "line": placement.line,
+                    "offset": placement.offset,
+                    "fuzz": placement.fuzz,
+                    "ignored_whitespace": placement.ignored_whitespace
+                })
+            }).collect::<Vec<_>>(),
+            "merged_files": stats.merged_files.iter().map(|path| path.to_string_lossy()).collect::<Vec<_>>(),
+            "conflicts": stats.conflicts,
             "files": result.files.iter().map(|file| {
                 json!({
                     "path": file.path,
@@ -339,4 +578,84 @@ index e69de29..4b825dc 100644
             msg
         );
     }
+
+    #[tokio::test]
+    async fn stale_patches_merge_with_their_snapshot() {
+        let temp = tempdir().unwrap();
+        let file_path = temp.path().join("hello.txt");
+        let base: String = (1..=6).map(|n| format!("line {n}\n")).collect();
+        fs::write(&file_path, &base).unwrap();
+        let snapshot = crate::SnapshotContext::new(temp.path().to_path_buf())
+            .unwrap()
+            .create_snapshot(&["hello.txt".to_string()])
+            .unwrap();
+        let tool = PatchApplyTool::new(Arc::new(
+            PatchContext::new(temp.path().to_path_buf()).unwrap(),
+        ));
+        let params = |snapshot_id: &str| {
+            json!({
+                "diff": "diff --git a/hello.txt b/hello.txt\n--- a/hello.txt\n+++ b/hello.txt\n@@ -2,5 +2,5 @@\n line 2\n line 3\n-line 4\n+LINE 4\n line 5\n line 6\n",
+                "fuzz": 0,
+                "snapshot_id": snapshot_id
+            })
+        };
+
+        // The context lines were edited since the snapshot
+        let current = base
+            .replace("line 2\n", "two\n")
+            .replace("line 6\n", "six\n");
+        fs::write(&file_path, &current).unwrap();
+        let mut without_base = params("");
+        without_base.as_object_mut().unwrap().remove("snapshot_id");
+        let err = tool.execute(without_base).await.unwrap_err();
+        assert!(err.to_string().contains("VCS conflict"), "{}", err);
+        let response = tool.execute(params(&snapshot.id)).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            current.replace("line 4\n", "LINE 4\n")
+        );
+        let patch = &response["structuredContent"]["patch"];
+        assert_eq!(patch["merged_files"], json!(["hello.txt"]));
+        assert_eq!(patch["summary"]["conflicts"], 0);
+
+        // The changed line itself was edited: conflict markers
+        fs::write(&file_path, base.replace("line 4\n", "four\n")).unwrap();
+        let response = tool.execute(params(&snapshot.id)).await.unwrap();
+        let patch = &response["structuredContent"]["patch"];
+        assert_eq!(patch["conflicts"][0]["path"], "hello.txt");
+        assert_eq!(patch["conflicts"][0]["hunks"][0]["ours"], "four");
+        assert!(fs::read_to_string(&file_path)
+            .unwrap()
+            .contains("<<<<<<< current\nfour\n=======\nLINE 4\n>>>>>>> patch\n"));
+
+        for unknown in ["snap-missing", "../hello.txt"] {
+            let err = tool.execute(params(unknown)).await.unwrap_err();
+            assert!(format!("{:?}", err).contains("snapshot"), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn adjusted_hunks_are_reported() {
+        let temp = tempdir().unwrap();
+        let file_path = temp.path().join("hello.txt");
+        fs::write(&file_path, "intro\nmore\nold\n").unwrap();
+        let context = PatchContext::new(temp.path().to_path_buf()).unwrap();
+
+        let result = context.apply_patch(sample_diff(), false).unwrap();
+        let content = fs::read_to_string(&file_path).unwrap();
+        assert_eq!(content, "intro\nmore\nnew\n");
+
+        let response = build_response(false, &result);
+        let text = response["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("hello.txt hunk #1 at line 3 (offset +2 lines)"));
+        let patch = &response["structuredContent"]["patch"];
+        assert_eq!(patch["summary"]["hunks_adjusted"], 1);
+        assert_eq!(patch["hunks"][0]["offset"], 2);
+        assert_eq!(patch["hunks"][0]["fuzz"], 0);
+        let transaction = patch["transaction_id"].as_str().unwrap();
+        assert_eq!(
+            patch["rollback_cmd"],
+            format!("devit rollback {}", transaction)
+        );
+    }
 }
@@ -0,0 +1,432 @@
+//! Prompts MCP (`prompts/*`) : modèles intégrés (revue de patch, conflit de
+//! fusion, message de commit) et recettes de `.devit/recipes`.
+//!
+//! Une recette devient le prompt `recipe:<id>`. Ses `arguments` déclarent ceux
+//! du prompt ; son champ `prompt` (optionnel) est le texte envoyé, où
+//! `{{nom}}` est remplacé par la valeur de l'argument.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use devit_cli::merge_assist;
+use devit_cli::recipes::{recipe_prompts, recipes_dir, RecipePrompt};
+use mcp_core::{McpPromptProvider, McpResult, PromptArgument, PromptDescriptor, PromptMessages};
+use tokio::process::Command;
+
+use crate::errors::{internal_error, validation_error};
+use crate::file_read::FileSystemContext;
+
+const REVIEW_PATCH: &str = "review_patch";
+const EXPLAIN_MERGE_CONFLICT: &str = "explain_merge_conflict";
+const WRITE_COMMIT_MESSAGE: &str = "write_commit_message";
+const RECIPE_PREFIX: &str = "recipe:";
+/// Taille maximale d'un diff embarqué dans un prompt
+const MAX_DIFF_CHARS: usize = 60_000;
+
+pub struct WorkspacePrompts {
+    files: Arc<FileSystemContext>,
+    recipes_dir: PathBuf,
+}
+
+impl WorkspacePrompts {
+    pub fn new(files: Arc<FileSystemContext>) -> Self {
+        let dir = recipes_dir();
+        let recipes_dir = if dir.is_absolute() {
+            dir
+        } else {
+            files.root().join(dir)
+        };
+        Self { files, recipes_dir }
+    }
+
+    async fn git(&self, args: &[&str]) -> McpResult<String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(self.files.root())
+            .output()
+            .await
+            .map_err(|err| internal_error(format!("git {} (spawn failed): {err}", args[0])))?;
+        if !output.status.success() {
+            return Err(internal_error(format!(
+                "git {} failed (code {:?}): {}",
+                args.join(" "),
+                output.status.code(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    async fn review_patch(&self, arguments: &HashMap<String, String>) -> McpResult<PromptMessages> {
+        let patch = match non_empty(arguments, "patch") {
+            Some(patch) => patch.to_string(),
+            None => self.git(&["diff", "HEAD"]).await?,
+        };
+        if patch.trim().is_empty() {
+            return Err(validation_error(
+                "Aucun patch fourni et aucune modification locale",
+            ));
+        }
+        let mut text = String::from(
+            "Relis ce patch comme un reviewer exigeant : bugs, cas limites oubliés, \
+             régressions, tests manquants et lisibilité. Classe les remarques par \
+             gravité et cite les lignes concernées.\n",
+        );
+        if let Some(focus) = non_empty(arguments, "focus") {
+            text.push_str(&format!("Attention particulière à : {}\n", focus));
+        }
+        text.push_str(&format!("\n```diff\n{}\n```", clip(&patch)));
+        Ok(PromptMessages {
+            description: Some("Revue du patch".to_string()),
+            messages: vec![text],
+        })
+    }
+
+    async fn explain_merge_conflict(
+        &self,
+        arguments: &HashMap<String, String>,
+    ) -> McpResult<PromptMessages> {
+        let raw_paths: Vec<String> = match non_empty(arguments, "paths") {
+            Some(paths) => paths
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(String::from)
+                .collect(),
+            None => self
+                .git(&["diff", "--name-only", "--diff-filter=U"])
+                .await?
+                .lines()
+                .map(String::from)
+                .collect(),
+        };
+        let mut paths = Vec::new();
+        for raw in &raw_paths {
+            let resolved = self.files.resolve_path(raw)?;
+            paths.push(resolved.to_string_lossy().to_string());
+        }
+        if paths.is_empty() {
+            return Err(validation_error("Aucun fichier en conflit"));
+        }
+
+        let conflicts = merge_assist::explain(&paths)
+            .map_err(|err| validation_error(&format!("Conflits illisibles: {err}")))?;
+        if conflicts.is_empty() {
+            return Err(validation_error(
+                "Aucun marqueur de conflit dans ces fichiers",
+            ));
+        }
+
+        let mut text = String::from(
+            "Explique chacun de ces conflits de fusion : ce que chaque côté cherchait à \
+             faire, pourquoi ils se chevauchent, puis propose une résolution \
+             (ours, theirs ou fusion manuelle) avec le code final.\n",
+        );
+        for file in &conflicts {
+            let display = file
+                .path
+                .strip_prefix(&format!("{}/", self.files.root().display()))
+                .unwrap_or(&file.path);
+            for (index, hunk) in file.hunks.iter().enumerate() {
+                text.push_str(&format!(
+                    "\n## {} — conflit {} (lignes {}-{})\n### ours\n```\n{}\n```\n### theirs\n```\n{}\n```\n",
+                    display,
+                    index + 1,
+                    hunk.start_line,
+                    hunk.end_line,
+                    hunk.ours,
+                    hunk.theirs
+                ));
+            }
+        }
+        Ok(PromptMessages {
+            description: Some("Explication des conflits de fusion".to_string()),
+            messages: vec![clip(&text)],
+        })
+    }
+
+    async fn write_commit_message(
+        &self,
+        arguments: &HashMap<String, String>,
+    ) -> McpResult<PromptMessages> {
+        let mut diff = self.git(&["diff", "--cached"]).await?;
+        let mut source = "indexées";
+        if diff.trim().is_empty() {
+            diff = self.git(&["diff"]).await?;
+            source = "non indexées";
+        }
+        if diff.trim().is_empty() {
+            return Err(validation_error("Aucune modification à décrire"));
+        }
+
+        let mut text = format!(
+            "Rédige un message de commit pour les modifications {} ci-dessous : un titre \
+             impératif de 72 caractères au plus, une ligne vide, puis un corps expliquant \
+             le pourquoi du changement.\n",
+            source
+        );
+        if let Some(style) = non_empty(arguments, "style") {
+            text.push_str(&format!("Convention à suivre : {}\n", style));
+        }
+        text.push_str(&format!("\n```diff\n{}\n```", clip(&diff)));
+        Ok(PromptMessages {
+            description: Some("Message de commit".to_string()),
+            messages: vec![text],
+        })
+    }
+
+    fn recipe(
+        &self,
+        recipe: &RecipePrompt,
+        arguments: &HashMap<String, String>,
+    ) -> McpResult<PromptMessages> {
+        for argument in &recipe.arguments {
+            if argument.required && non_empty(arguments, &argument.name).is_none() {
+                return Err(validation_error(&format!(
+                    "Argument requis manquant: {}",
+                    argument.name
+                )));
+            }
+        }
+
+        let text = match &recipe.prompt {
+            Some(template) => recipe
+                .arguments
+                .iter()
+                .fold(template.clone(), |text, argument| {
+                    text.replace(
+                        &format!("{{{{{}}}}}", argument.name),
+                        arguments
+                            .get(&argument.name)
+                            .map(String::as_str)
+                            .unwrap_or(""),
+                    )
+                }),
+            None => {
+                let mut text = format!("Applique la recette « {} »", recipe.name);
+                if let Some(description) = &recipe.description {
+                    text.push_str(&format!(" : {}", description));
+                }
+                text.push('\n');
+                if !recipe.steps.is_empty() {
+                    text.push_str("\nÉtapes :\n");
+                    for (index, step) in recipe.steps.iter().enumerate() {
+                        text.push_str(&format!("{}. {}\n", index + 1, step));
+                    }
+                }
+                let mut provided: Vec<_> = recipe
+                    .arguments
+                    .iter()
+                    .filter_map(|argument| {
+                        non_empty(arguments, &argument.name)
+                            .map(|value| format!("- {} = {}", argument.name, value))
+                    })
+                    .collect();
+                if !provided.is_empty() {
+                    provided.insert(0, "\nParamètres :".to_string());
+                    text.push_str(&provided.join("\n"));
+                    text.push('\n');
+                }
+                text
+            }
+        };
+
+        Ok(PromptMessages {
+            description: recipe.description.clone().or(Some(recipe.name.clone())),
+            messages: vec![text],
+        })
+    }
+}
+
+#[async_trait]
+impl McpPromptProvider for WorkspacePrompts {
+    async fn list(&self) -> McpResult<Vec<PromptDescriptor>> {
+        let mut prompts = vec![
+            PromptDescriptor {
+                name: REVIEW_PATCH.to_string(),
+                description: Some("Relire un patch (par défaut les modifications locales)".into()),
+                arguments: vec![
+                    argument(
+                        "patch",
+                        "Diff unifié à relire (défaut : git diff HEAD)",
+                        false,
+                    ),
+                    argument("focus", "Aspect à examiner en priorité", false),
+                ],
+            },
+            PromptDescriptor {
+                name: EXPLAIN_MERGE_CONFLICT.to_string(),
+                description: Some("Expliquer les conflits de fusion en cours".to_string()),
+                arguments: vec![argument(
+                    "paths",
+                    "Fichiers séparés par des virgules (défaut : fichiers non fusionnés)",
+                    false,
+                )],
+            },
+            PromptDescriptor {
+                name: WRITE_COMMIT_MESSAGE.to_string(),
+                description: Some("Rédiger le message de commit des modifications".into()),
+                arguments: vec![argument(
+                    "style",
+                    "Convention (ex : Conventional Commits)",
+                    false,
+                )],
+            },
+        ];
+
+        for recipe in recipe_prompts(&self.recipes_dir) {
+            prompts.push(PromptDescriptor {
+                name: format!("{}{}", RECIPE_PREFIX, recipe.id),
+                description: recipe.description.or(Some(recipe.name)),
+                arguments: recipe
+                    .arguments
+                    .into_iter()
+                    .map(|argument| PromptArgument {
+                        name: argument.name,
+                        description: argument.description,
+                        required: argument.required,
+                    })
+                    .collect(),
+            });
+        }
+        Ok(prompts)
+    }
+
+    async fn get(
+        &self,
+        name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> McpResult<PromptMessages> {
+        match name {
+            REVIEW_PATCH => self.review_patch(arguments).await,
+            EXPLAIN_MERGE_CONFLICT => self.explain_merge_conflict(arguments).await,
+            WRITE_COMMIT_MESSAGE => self.write_commit_message(arguments).await,
+            _ => {
+                let recipe = name.strip_prefix(RECIPE_PREFIX).and_then(|id| {
+                    recipe_prompts(&self.recipes_dir)
+                        .into_iter()
+                        .find(|recipe| recipe.id == id)
+                });
+                match recipe {
+                    Some(recipe) => self.recipe(&recipe, arguments),
+                    None => Err(validation_error(&format!("Prompt inconnu: {}", name))),
+                }
+            }
+        }
+    }
+}
+
+fn argument(name: &str, description: &str, required: bool) -> PromptArgument {
+    PromptArgument {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        required,
+    }
+}
+
+fn non_empty<'a>(arguments: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    arguments
+        .get(name)
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+}
+
+fn clip(text: &str) -> String {
+    match text.char_indices().nth(MAX_DIFF_CHARS) {
+        Some((end, _)) => format!("{}\n… [tronqué]", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_prompts() -> (WorkspacePrompts, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let files = Arc::new(FileSystemContext::new(tmp.path().to_path_buf()).expect("context"));
+        let mut prompts = WorkspacePrompts::new(files);
+        prompts.recipes_dir = tmp.path().join("recipes");
+        (prompts, tmp)
+    }
+
+    #[tokio::test]
+    async fn recipes_become_prompts_with_declared_arguments() {
+        let (prompts, tmp) = build_prompts();
+        std::fs::create_dir_all(tmp.path().join("recipes")).expect("recipes dir");
+        std::fs::write(
+            tmp.path().join("recipes/bump.yaml"),
+            r#"
+id: bump-dep
+name: "Bump dependency"
+description: "Upgrade one crate"
+arguments:
+  - name: crate
+    description: "Crate to upgrade"
+    required: true
+prompt: "Upgrade {{crate}} and fix the build."
+"#,
+        )
+        .expect("recipe");
+
+        let listed = prompts.list().await.expect("list");
+        let recipe = listed
+            .iter()
+            .find(|prompt| prompt.name == "recipe:bump-dep")
+            .expect("recipe prompt");
+        assert_eq!(recipe.arguments[0].name, "crate");
+        assert!(recipe.arguments[0].required);
+        assert!(listed.iter().any(|prompt| prompt.name == REVIEW_PATCH));
+
+        assert!(prompts
+            .get("recipe:bump-dep", &HashMap::new())
+            .await
+            .is_err());
+        let arguments = HashMap::from([("crate".to_string(), "serde".to_string())]);
+        let rendered = prompts
+            .get("recipe:bump-dep", &arguments)
+            .await
+            .expect("rendered");
+        assert_eq!(rendered.messages, vec!["Upgrade serde and fix the build."]);
+    }
+
+    #[tokio::test]
+    async fn merge_conflict_prompt_lists_both_sides() {
+        let (prompts, tmp) = build_prompts();
+        std::fs::write(
+            tmp.path().join("lib.rs"),
+            "fn a() {}\n<<<<<<< HEAD\nlet x = 1;\n=======\nlet x = 2;\n>>>>>>> feature\n",
+        )
+        .expect("conflicted file");
+
+        let arguments = HashMap::from([("paths".to_string(), "lib.rs".to_string())]);
+        let rendered = prompts
+            .get(EXPLAIN_MERGE_CONFLICT, &arguments)
+            .await
+            .expect("rendered");
+        let text = &rendered.messages[0];
+        assert!(text.contains("## lib.rs — conflit 1"));
+        assert!(text.contains("let x = 1;"));
+        assert!(text.contains("let x = 2;"));
+
+        let patch = HashMap::from([("patch".to_string(), "+new line".to_string())]);
+        let review = prompts.get(REVIEW_PATCH, &patch).await.expect("review");
+        assert!(review.messages[0].contains("+new line"));
+    }
+
+    #[tokio::test]
+    async fn git_errors_are_reported() {
+        let (prompts, _tmp) = build_prompts();
+        // The temporary workspace is not a git repository
+        let err = prompts
+            .get(WRITE_COMMIT_MESSAGE, &HashMap::new())
+            .await
+            .expect_err("git must fail");
+        assert!(
+            err.to_string().contains("git diff --cached failed"),
+            "{err}"
+        );
+    }
+}
@@ -5,6 +5,7 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+use crate::exec::{job_resource_usage, resource_usage_json};
 use mcp_core::{McpError, McpResult, McpTool};
 use tracing::info;
 
@@ -94,9 +95,21 @@ impl McpTool for DevitPs {
                     .map(|ts| format!(" — auto-kill {}", ts.to_rfc3339()))
                     .unwrap_or_default();
 
+                let usage = job_resource_usage(record);
+                let mut limits = String::new();
+                if let Some(usage) = usage.as_ref() {
+                    if usage.oom_killed() {
+                        limits.push_str(&format!(" · OOM kill ×{}", usage.oom_kills));
+                    }
+                    if usage.throttled() {
+                        limits
+                            .push_str(&format!(" · CPU bridé {} ms", usage.throttled_usec / 1000));
+                    }
+                }
+
                 lines.push(format!(
-                    "- pid {} (pgid {}) · {}{} · status: {}{}",
-                    pid, record.pgid, record.command, args_preview, status, auto_kill
+                    "- pid {} (pgid {}) · {}{} · status: {}{}{}",
+                    pid, record.pgid, record.command, args_preview, status, auto_kill, limits
                 ));
 
                 processes.push(json!({
@@ -110,6 +123,8 @@ impl McpTool for DevitPs {
                     "exit_code": record.exit_code,
                     "terminated_by_signal": record.terminated_by_signal,
                     "auto_kill_at": record.auto_kill_at.clone(),
+                    "cgroup": record.cgroup.clone(),
+                    "resources": usage.as_ref().map(resource_usage_json),
                 }));
             }
         }
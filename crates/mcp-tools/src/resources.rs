@@ -0,0 +1,439 @@
+//! Ressources MCP (`resources/*`) : fichiers du workspace, fin du journal,
+//! manifestes de snapshots et tâches d'orchestration.
+//!
+//! URIs publiées :
+//! - `file://<chemin absolu>` pour les fichiers texte du workspace (chemin
+//!   encodé en pourcentage, comme `Url::from_file_path`),
+//! - `devit://journal` (paramètre optionnel `?tail=N`),
+//! - `devit://snapshots` et `devit://snapshots/{id}`,
+//! - `devit://tasks` et `devit://tasks/{id}`.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use devit_cli::core::snapshot::SnapshotManager;
+use devit_common::orchestration::OrchestrationContext;
+use devit_common::SnapshotId;
+use mcp_core::{
+    McpResourceProvider, McpResult, ResourceContents, ResourceDescriptor, ResourceTemplate,
+};
+use serde_json::{json, Value};
+use url::Url;
+use walkdir::WalkDir;
+
+use crate::errors::{internal_error, io_error, resource_not_found_error, validation_error};
+use crate::file_read::FileSystemContext;
+
+const JOURNAL_URI: &str = "devit://journal";
+const SNAPSHOTS_URI: &str = "devit://snapshots";
+const TASKS_URI: &str = "devit://tasks";
+const DEFAULT_JOURNAL_TAIL: usize = 100;
+const MAX_JOURNAL_TAIL: usize = 1000;
+/// Au-delà, les fichiers restent lisibles via le template `file://`
+const MAX_LISTED_FILES: usize = 1000;
+const IGNORED_DIRS: [&str; 5] = [".git", ".devit", "target", "node_modules", "__pycache__"];
+
+pub struct WorkspaceResources {
+    files: Arc<FileSystemContext>,
+    journal_path: PathBuf,
+    snapshots: SnapshotManager,
+    orchestration: Arc<OrchestrationContext>,
+}
+
+impl WorkspaceResources {
+    pub fn new(files: Arc<FileSystemContext>, orchestration: Arc<OrchestrationContext>) -> Self {
+        let root = files.root().to_path_buf();
+        Self {
+            journal_path: root.join(".devit").join("journal.jsonl"),
+            snapshots: SnapshotManager::new(root, 0),
+            files,
+            orchestration,
+        }
+    }
+
+    fn file_uri(&self, path: &Path) -> String {
+        Url::from_file_path(path)
+            .map(String::from)
+            .unwrap_or_else(|_| format!("file://{}", path.display()))
+    }
+
+    fn workspace_files(&self) -> Vec<ResourceDescriptor> {
+        let root = self.files.root();
+        WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0
+                    || !entry.file_type().is_dir()
+                    || entry
+                        .file_name()
+                        .to_str()
+                        .map(|name| !IGNORED_DIRS.contains(&name))
+                        .unwrap_or(true)
+            })
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .take(MAX_LISTED_FILES)
+            .map(|entry| {
+                let relative = entry
+                    .path()
+                    .strip_prefix(root)
+                    .unwrap_or(entry.path())
+                    .to_string_lossy()
+                    .to_string();
+                ResourceDescriptor {
+                    uri: self.file_uri(entry.path()),
+                    name: relative,
+                    description: None,
+                    mime_type: Some(mime_type_for(entry.path()).to_string()),
+                }
+            })
+            .collect()
+    }
+
+    fn read_file(&self, uri: &str) -> McpResult<ResourceContents> {
+        let raw_path = Url::parse(uri)
+            .ok()
+            .and_then(|url| url.to_file_path().ok())
+            .ok_or_else(|| resource_not_found_error(uri))?;
+        let path = self.files.resolve_path(&raw_path.to_string_lossy())?;
+        if !path.is_file() {
+            return Err(resource_not_found_error(uri));
+        }
+        let content = self.files.read_file(&path, false, None, None)?;
+        Ok(ResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some(mime_type_for(&path).to_string()),
+            text: content.content,
+        })
+    }
+
+    fn read_journal(&self, uri: &str, query: Option<&str>) -> McpResult<ResourceContents> {
+        let tail = match query.and_then(|q| q.strip_prefix("tail=")) {
+            Some(raw) => raw
+                .parse::<usize>()
+                .map_err(|_| validation_error("tail doit être un entier positif"))?
+                .clamp(1, MAX_JOURNAL_TAIL),
+            None if query.is_some() => {
+                return Err(validation_error("Seul le paramètre tail est supporté"))
+            }
+            None => DEFAULT_JOURNAL_TAIL,
+        };
+
+        let lines: Vec<String> = match File::open(&self.journal_path) {
+            Ok(file) => BufReader::new(file)
+                .lines()
+                .collect::<Result<_, _>>()
+                .map_err(|err| {
+                    io_error("read journal", Some(&self.journal_path), err.to_string())
+                })?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(io_error(
+                    "open journal",
+                    Some(&self.journal_path),
+                    err.to_string(),
+                ))
+            }
+        };
+        let start = lines.len().saturating_sub(tail);
+        let mut text = lines[start..].join("\n");
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        Ok(ResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some("application/x-ndjson".to_string()),
+            text,
+        })
+    }
+
+    fn snapshot_list(&self) -> McpResult<Vec<Value>> {
+        let mut infos = self
+            .snapshots
+            .list_snapshots()
+            .map_err(|err| internal_error(err.to_string()))?;
+        infos.sort_by_key(|info| std::cmp::Reverse(info.created_at));
+        Ok(infos
+            .into_iter()
+            .map(|info| {
+                json!({
+                    "id": info.id.0,
+                    "uri": format!("{}/{}", SNAPSHOTS_URI, info.id.0),
+                    "created_at": rfc3339(info.created_at),
+                    "description": info.description,
+                    "file_count": info.file_count,
+                    "size_bytes": info.size_bytes,
+                    "root_path": info.root_path,
+                })
+            })
+            .collect())
+    }
+
+    fn snapshot_manifest(&self, uri: &str, id: &str) -> McpResult<Value> {
+        let snapshot = self
+            .snapshots
+            .get_snapshot(&SnapshotId(id.to_string()))
+            .map_err(|_| resource_not_found_error(uri))?;
+        let mut files: Vec<Value> = snapshot
+            .files
+            .values()
+            .map(|file| {
+                json!({
+                    "path": file.path,
+                    "size": file.size,
+                    "content_hash": file.content_hash,
+                    "is_binary": file.is_binary,
+                })
+            })
+            .collect();
+        files.sort_by(|a, b| a["path"].as_str().cmp(&b["path"].as_str()));
+
+        Ok(json!({
+            "id": snapshot.id.0,
+            "created_at": rfc3339(snapshot.created_at),
+            "description": snapshot.description,
+            "root_path": snapshot.root_path,
+            "total_size": snapshot.total_size,
+            "integrity_hash": snapshot.integrity_hash,
+            "parent_snapshot": snapshot.parent_snapshot.map(|parent| parent.0),
+            "git": snapshot.git_info,
+            "files": files,
+        }))
+    }
+}
+
+#[async_trait]
+impl McpResourceProvider for WorkspaceResources {
+    async fn list(&self) -> McpResult<Vec<ResourceDescriptor>> {
+        let mut resources = vec![
+            ResourceDescriptor {
+                uri: JOURNAL_URI.to_string(),
+                name: "Journal DevIt".to_string(),
+                description: Some(format!(
+                    "Dernières entrées de .devit/journal.jsonl ({} par défaut, ?tail=N)",
+                    DEFAULT_JOURNAL_TAIL
+                )),
+                mime_type: Some("application/x-ndjson".to_string()),
+            },
+            ResourceDescriptor {
+                uri: SNAPSHOTS_URI.to_string(),
+                name: "Snapshots".to_string(),
+                description: Some("Snapshots disponibles, du plus récent au plus ancien".into()),
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceDescriptor {
+                uri: TASKS_URI.to_string(),
+                name: "Tâches d'orchestration".to_string(),
+                description: Some("État des tâches déléguées".to_string()),
+                mime_type: Some("application/json".to_string()),
+            },
+        ];
+
+        for snapshot in self.snapshot_list()? {
+            let id = snapshot["id"].as_str().unwrap_or_default();
+            resources.push(ResourceDescriptor {
+                uri: format!("{}/{}", SNAPSHOTS_URI, id),
+                name: format!("snapshot {}", id),
+                description: snapshot["description"].as_str().map(String::from),
+                mime_type: Some("application/json".to_string()),
+            });
+        }
+
+        // Daemon injoignable : les tâches manquent à la liste, pas le reste
+        match self.orchestration.status(None).await {
+            Ok(status) => {
+                for task in status.active_tasks.iter().chain(&status.completed_tasks) {
+                    resources.push(ResourceDescriptor {
+                        uri: format!("{}/{}", TASKS_URI, task.id),
+                        name: format!("task {}", task.id),
+                        description: Some(task.goal.clone()),
+                        mime_type: Some("application/json".to_string()),
+                    });
+                }
+            }
+            Err(err) => tracing::warn!("tâches absentes de resources/list : {}", err),
+        }
+
+        resources.extend(self.workspace_files());
+        Ok(resources)
+    }
+
+    fn templates(&self) -> Vec<ResourceTemplate> {
+        vec![
+            ResourceTemplate {
+                uri_template: match Url::from_directory_path(self.files.root()) {
+                    Ok(root) => format!("{}{{path}}", root),
+                    Err(_) => format!("file://{}/{{path}}", self.files.root().display()),
+                },
+                name: "Fichier du workspace".to_string(),
+                description: Some("Fichier texte, chemin relatif à la racine".to_string()),
+                mime_type: None,
+            },
+            ResourceTemplate {
+                uri_template: format!("{}?tail={{count}}", JOURNAL_URI),
+                name: "Fin du journal".to_string(),
+                description: Some(format!("Au plus {} entrées", MAX_JOURNAL_TAIL)),
+                mime_type: Some("application/x-ndjson".to_string()),
+            },
+            ResourceTemplate {
+                uri_template: format!("{}/{{id}}", SNAPSHOTS_URI),
+                name: "Manifeste de snapshot".to_string(),
+                description: None,
+                mime_type: Some("application/json".to_string()),
+            },
+            ResourceTemplate {
+                uri_template: format!("{}/{{task_id}}", TASKS_URI),
+                name: "Tâche déléguée".to_string(),
+                description: None,
+                mime_type: Some("application/json".to_string()),
+            },
+        ]
+    }
+
+    async fn read(&self, uri: &str) -> McpResult<Vec<ResourceContents>> {
+        if uri.starts_with("file://") {
+            return Ok(vec![self.read_file(uri)?]);
+        }
+
+        let (base, query) = match uri.split_once('?') {
+            Some((base, query)) => (base, Some(query)),
+            None => (uri, None),
+        };
+        let value = match base {
+            JOURNAL_URI => return Ok(vec![self.read_journal(uri, query)?]),
+            SNAPSHOTS_URI => Value::Array(self.snapshot_list()?),
+            TASKS_URI => {
+                let status = self
+                    .orchestration
+                    .status(None)
+                    .await
+                    .map_err(|err| internal_error(err.to_string()))?;
+                serde_json::to_value(status).map_err(|err| internal_error(err.to_string()))?
+            }
+            _ => {
+                if let Some(id) = base.strip_prefix("devit://snapshots/") {
+                    self.snapshot_manifest(uri, id)?
+                } else if let Some(id) = base.strip_prefix("devit://tasks/") {
+                    let task = self
+                        .orchestration
+                        .task(id)
+                        .await
+                        .map_err(|err| internal_error(err.to_string()))?
+                        .ok_or_else(|| resource_not_found_error(uri))?;
+                    serde_json::to_value(task).map_err(|err| internal_error(err.to_string()))?
+                } else {
+                    return Err(resource_not_found_error(uri));
+                }
+            }
+        };
+
+        let text =
+            serde_json::to_string_pretty(&value).map_err(|err| internal_error(err.to_string()))?;
+        Ok(vec![ResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some("application/json".to_string()),
+            text,
+        }])
+    }
+}
+
+fn rfc3339(time: std::time::SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn mime_type_for(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("rs") => "text/x-rust",
+        Some("md") => "text/markdown",
+        Some("json") => "application/json",
+        Some("jsonl") => "application/x-ndjson",
+        Some("toml") => "application/toml",
+        Some("yaml") | Some("yml") => "application/yaml",
+        Some("py") => "text/x-python",
+        Some("js") => "text/javascript",
+        Some("ts") => "text/x-typescript",
+        Some("html") => "text/html",
+        Some("sh") => "text/x-shellscript",
+        _ => "text/plain",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use devit_common::orchestration::{OrchestrationConfig, OrchestrationMode};
+
+    async fn build_resources() -> (WorkspaceResources, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let files = Arc::new(FileSystemContext::new(tmp.path().to_path_buf()).expect("context"));
+        let config = OrchestrationConfig {
+            mode: OrchestrationMode::Local,
+            ..OrchestrationConfig::default()
+        };
+        let orchestration = Arc::new(OrchestrationContext::new(config).await.expect("context"));
+        (WorkspaceResources::new(files, orchestration), tmp)
+    }
+
+    #[tokio::test]
+    async fn lists_and_reads_workspace_files_and_journal() {
+        let (resources, tmp) = build_resources().await;
+        std::fs::create_dir_all(tmp.path().join("src")).expect("src");
+        std::fs::write(tmp.path().join("src/main.rs"), "fn main() {}\n").expect("file");
+        std::fs::write(tmp.path().join("notes #1 100%.md"), "# notes\n").expect("file");
+        std::fs::create_dir_all(tmp.path().join(".devit")).expect(".devit");
+        let journal: String = (0..5).map(|i| format!("{{\"n\":{}}}\n", i)).collect();
+        std::fs::write(tmp.path().join(".devit/journal.jsonl"), journal).expect("journal");
+
+        let listed = resources.list().await.expect("list");
+        let main = listed
+            .iter()
+            .find(|resource| resource.name == "src/main.rs")
+            .expect("main.rs listed");
+        assert_eq!(main.mime_type.as_deref(), Some("text/x-rust"));
+        assert!(listed
+            .iter()
+            .all(|resource| !resource.name.starts_with(".devit")));
+
+        let contents = resources.read(&main.uri).await.expect("read file");
+        assert_eq!(contents[0].text, "fn main() {}\n");
+
+        let notes = listed
+            .iter()
+            .find(|resource| resource.name == "notes #1 100%.md")
+            .expect("notes listed");
+        assert!(
+            notes.uri.ends_with("/notes%20%231%20100%25.md"),
+            "{}",
+            notes.uri
+        );
+        let contents = resources.read(&notes.uri).await.expect("read encoded");
+        assert_eq!(contents[0].text, "# notes\n");
+
+        let tail = resources
+            .read("devit://journal?tail=2")
+            .await
+            .expect("journal tail");
+        assert_eq!(tail[0].text, "{\"n\":3}\n{\"n\":4}\n");
+
+        let tasks = resources.read(TASKS_URI).await.expect("tasks");
+        assert!(tasks[0].text.contains("active_tasks"));
+    }
+
+    #[tokio::test]
+    async fn unknown_resources_are_rejected() {
+        let (resources, tmp) = build_resources().await;
+        let missing = format!("file://{}/absent.txt", tmp.path().display());
+        assert!(resources.read(&missing).await.is_err());
+        let err = resources
+            .read("devit://tasks/unknown")
+            .await
+            .expect_err("unknown task");
+        assert_eq!(err.code(), -32002);
+        assert!(resources.read("file:///etc/passwd").await.is_err());
+    }
+}
@@ -5,7 +5,7 @@ use anyhow::Result;
 use async_trait::async_trait;
 use serde_json::Value;
 
-use crate::types::{DelegateResult, DelegatedTask, OrchestrationStatus, StatusFilter};
+use crate::types::{DelegateResult, DelegatedTask, OrchestrationStatus, StatusFilter, TaskSpec};
 
 #[async_trait]
 pub trait OrchestrationBackend: Send + Sync {
@@ -21,6 +21,11 @@ pub trait OrchestrationBackend: Send + Sync {
         response_format: Option<String>,
     ) -> Result<DelegateResult>;
 
+    /// Submit a task graph. Each task stays pending until its `depends_on`
+    /// are completed; results are returned as `(key, result)` in submission
+    /// order.
+    async fn delegate_graph(&self, tasks: Vec<TaskSpec>) -> Result<Vec<(String, DelegateResult)>>;
+
     async fn notify(
         &self,
         task_id: &str,
@@ -30,6 +35,9 @@ pub trait OrchestrationBackend: Send + Sync {
         evidence: Option<Value>,
     ) -> Result<()>;
 
+    /// Stop a delegated task and mark it cancelled.
+    async fn cancel(&self, task_id: &str, reason: Option<&str>) -> Result<()>;
+
     async fn status(&self, filter: StatusFilter) -> Result<OrchestrationStatus>;
 
     async fn cleanup_expired(&self) -> Result<()>;
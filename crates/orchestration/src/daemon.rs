@@ -18,10 +18,11 @@ use tokio::time::{sleep, timeout, Duration as TokioDuration, Instant as TokioIns
 use tracing::{debug, info, warn};
 
 use crate::backend::OrchestrationBackend;
+use crate::graph;
 use crate::types::{
-    default_daemon_start_timeout_ms, DelegateResult, DelegatedTask, OrchestrationConfig,
-    OrchestrationMode, OrchestrationStatus, OrchestrationSummary, StatusFilter, TaskNotification,
-    TaskStatus,
+    default_daemon_start_timeout_ms, DelegateResult, DelegatedTask, DependencyFailurePolicy,
+    OrchestrationConfig, OrchestrationMode, OrchestrationStatus, OrchestrationSummary, QueuedTask,
+    StatusFilter, TaskNotification, TaskSpec, TaskStatus,
 };
 
 #[cfg(not(unix))]
@@ -36,6 +37,7 @@ const DEFAULT_SECRET: &str = "change-me-in-production";
 struct DaemonState {
     active: HashMap<String, DelegatedTask>,
     completed: HashMap<String, DelegatedTask>,
+    queue: Vec<QueuedTask>,
     last_cleanup: Option<Instant>,
 }
 
@@ -44,6 +46,7 @@ impl Default for DaemonState {
         Self {
             active: HashMap::new(),
             completed: HashMap::new(),
+            queue: Vec::new(),
             last_cleanup: None,
         }
     }
@@ -461,6 +464,7 @@ impl DaemonBackend {
                     response_format: None,
                     model: None,
                     model_resolved: None,
+                    depends_on: Vec::new(),
                 }),
         };
 
@@ -578,6 +582,7 @@ impl DaemonBackend {
             .into_iter()
             .map(|task| (task.id.clone(), task))
             .collect();
+        state.queue = status.queue;
 
         Ok(())
     }
@@ -644,58 +649,50 @@ impl OrchestrationBackend for DaemonBackend {
         working_dir: Option<PathBuf>,
         response_format: Option<String>,
     ) -> Result<DelegateResult> {
-        let timeout_secs = timeout
-            .map(|d| d.as_secs())
-            .unwrap_or(self.config.default_timeout_secs);
-        let watch_patterns =
-            watch_patterns.unwrap_or_else(|| self.config.default_watch_patterns.clone());
-
-        let task_payload = assemble_task_payload(
-            &goal,
-            timeout_secs,
-            &watch_patterns,
-            model.clone(),
-            context.clone(),
-            working_dir.as_ref().map(|p| p.as_path()),
-            response_format.as_deref(),
-        );
-        let delegated_to_for_daemon = delegated_to.clone();
-
-        let task_id = self
-            .with_client(move |client| {
-                let payload = task_payload;
-                async move {
-                    client
-                        .delegate(&delegated_to_for_daemon, payload, client.ident())
-                        .await
-                }
-            })
-            .await?;
-
-        let now = Utc::now();
-        let task = DelegatedTask {
-            id: task_id.clone(),
+        let spec = TaskSpec {
+            key: String::new(),
             goal,
             delegated_to,
-            created_at: now,
-            timeout_secs,
-            status: TaskStatus::Pending,
-            context,
+            model,
+            timeout_secs: timeout.map(|d| d.as_secs()),
             watch_patterns,
-            last_activity: now,
-            notifications: Vec::new(),
+            context,
             working_dir,
             response_format,
-            model,
-            model_resolved: None,
+            depends_on: Vec::new(),
+            on_dependency_failure: DependencyFailurePolicy::default(),
+            priority: 0,
         };
+        self.submit_task(spec, Vec::new()).await
+    }
 
-        self.record_task(task).await;
+    async fn delegate_graph(&self, tasks: Vec<TaskSpec>) -> Result<Vec<(String, DelegateResult)>> {
+        let order = graph::topological_order(&tasks)?;
+        for dep in graph::external_dependencies(&tasks) {
+            if self.get_task(&dep).await?.is_none() {
+                bail!("Dépendance inconnue: {dep}");
+            }
+        }
 
-        Ok(DelegateResult {
-            task_id,
-            timeout_secs,
-        })
+        let mut slots: Vec<Option<TaskSpec>> = tasks.into_iter().map(Some).collect();
+        let mut ids: HashMap<String, String> = HashMap::new();
+        let mut results = Vec::with_capacity(order.len());
+        for index in order {
+            let Some(spec) = slots[index].take() else {
+                continue;
+            };
+            let depends_on = spec
+                .depends_on
+                .iter()
+                .map(|dep| ids.get(dep).cloned().unwrap_or_else(|| dep.clone()))
+                .collect();
+            let key = spec.key.clone();
+            let result = self.submit_task(spec, depends_on).await?;
+            ids.insert(key.clone(), result.task_id.clone());
+            results.push((key, result));
+        }
+
+        Ok(results)
     }
 
     async fn notify(
@@ -751,6 +748,26 @@ impl OrchestrationBackend for DaemonBackend {
             .await
     }
 
+    async fn cancel(&self, task_id: &str, reason: Option<&str>) -> Result<()> {
+        let task_id_owned = task_id.to_string();
+        let reason_owned = reason.map(str::to_string);
+        let response = self
+            .with_client(move |client| async move {
+                client.cancel(&task_id_owned, reason_owned.as_deref()).await
+            })
+            .await?;
+
+        info!(
+            task_id = %task_id,
+            worker = %response.worker,
+            delivery = %response.delivery,
+            "Task cancelled via daemon"
+        );
+        let summary = format!("Task cancelled: {}", reason.unwrap_or("requested by user"));
+        self.record_notification(task_id, "cancelled", &summary, None, None)
+            .await
+    }
+
     async fn status(&self, filter: StatusFilter) -> Result<OrchestrationStatus> {
         if let Err(err) = self.refresh_notifications().await {
             warn!("Failed to refresh notifications from daemon: {}", err);
@@ -804,6 +821,7 @@ impl OrchestrationBackend for DaemonBackend {
             active_tasks: active,
             completed_tasks: completed,
             summary,
+            queue: state.queue.clone(),
         })
     }
 
@@ -901,6 +919,78 @@ fn build_artifacts(summary: &str, details: Option<Value>, evidence: Option<Value
 }
 
 impl DaemonBackend {
+    /// Send one DELEGATE; devitd holds it until `depends_on` are completed.
+    async fn submit_task(&self, spec: TaskSpec, depends_on: Vec<String>) -> Result<DelegateResult> {
+        let timeout_secs = spec
+            .timeout_secs
+            .unwrap_or(self.config.default_timeout_secs);
+        let watch_patterns = spec
+            .watch_patterns
+            .unwrap_or_else(|| self.config.default_watch_patterns.clone());
+
+        let mut task_payload = assemble_task_payload(
+            &spec.goal,
+            timeout_secs,
+            &watch_patterns,
+            spec.model.clone(),
+            spec.context.clone(),
+            spec.working_dir.as_deref(),
+            spec.response_format.as_deref(),
+        );
+        if !depends_on.is_empty() {
+            if let Some(map) = task_payload.as_object_mut() {
+                map.insert("depends_on".into(), serde_json::json!(depends_on));
+                map.insert(
+                    "on_dependency_failure".into(),
+                    Value::String(spec.on_dependency_failure.as_str().to_string()),
+                );
+            }
+        }
+        if spec.priority != 0 {
+            if let Some(map) = task_payload.as_object_mut() {
+                map.insert("priority".into(), serde_json::json!(spec.priority));
+            }
+        }
+        let delegated_to_for_daemon = spec.delegated_to.clone();
+
+        let task_id = self
+            .with_client(move |client| {
+                let payload = task_payload;
+                async move {
+                    client
+                        .delegate(&delegated_to_for_daemon, payload, client.ident())
+                        .await
+                }
+            })
+            .await?;
+
+        let now = Utc::now();
+        let task = DelegatedTask {
+            id: task_id.clone(),
+            goal: spec.goal,
+            delegated_to: spec.delegated_to,
+            created_at: now,
+            timeout_secs,
+            status: TaskStatus::Pending,
+            context: spec.context,
+            watch_patterns,
+            last_activity: now,
+            notifications: Vec::new(),
+            working_dir: spec.working_dir,
+            response_format: spec.response_format,
+            model: spec.model,
+            model_resolved: None,
+            depends_on,
+        };
+
+        self.record_task(task).await;
+
+        Ok(DelegateResult {
+            task_id,
+            timeout_secs,
+        })
+    }
+
     pub fn mode(&self) -> OrchestrationMode {
         self.config.mode
     }
@@ -0,0 +1,147 @@
+//! Task dependency graphs
+//!
+//! A graph is a list of [`TaskSpec`] linked by `depends_on`. Backends submit
+//! the nodes in [`topological_order`]; a node is held as pending until every
+//! dependency is completed, then receives the upstream results in its
+//! context (see [`attach_upstream`]).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+use crate::types::{DelegatedTask, TaskSpec, TaskStatus};
+
+/// Indexes of `specs` ordered so that every node comes after the nodes it
+/// depends on. Ties keep the submission order.
+///
+/// Dependencies that do not name a key of the graph are left alone: they
+/// refer to tasks delegated earlier (see [`external_dependencies`]).
+pub fn topological_order(specs: &[TaskSpec]) -> Result<Vec<usize>> {
+    let mut index_by_key = HashMap::new();
+    for (index, spec) in specs.iter().enumerate() {
+        if spec.key.trim().is_empty() {
+            bail!("Tâche #{} sans clé dans le graphe", index);
+        }
+        if index_by_key.insert(spec.key.as_str(), index).is_some() {
+            bail!("Clé dupliquée dans le graphe: {}", spec.key);
+        }
+    }
+
+    let mut indegree = vec![0usize; specs.len()];
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); specs.len()];
+    for (index, spec) in specs.iter().enumerate() {
+        let mut seen = HashSet::new();
+        for dep in &spec.depends_on {
+            if dep == &spec.key {
+                bail!("La tâche {} dépend d'elle-même", spec.key);
+            }
+            if !seen.insert(dep.as_str()) {
+                continue;
+            }
+            if let Some(&upstream) = index_by_key.get(dep.as_str()) {
+                indegree[index] += 1;
+                dependents[upstream].push(index);
+            }
+        }
+    }
+
+    let mut ready: VecDeque<usize> = (0..specs.len()).filter(|&i| indegree[i] == 0).collect();
+    let mut order = Vec::with_capacity(specs.len());
+    while let Some(index) = ready.pop_front() {
+        order.push(index);
+        let mut released = Vec::new();
+        for &dependent in &dependents[index] {
+            indegree[dependent] -= 1;
+            if indegree[dependent] == 0 {
+                released.push(dependent);
+            }
+        }
+        released.sort_unstable();
+        ready.extend(released);
+    }
+
+    if order.len() != specs.len() {
+        let mut cycle: Vec<&str> = (0..specs.len())
+            .filter(|&i| indegree[i] > 0)
+            .map(|i| specs[i].key.as_str())
+            .collect();
+        cycle.sort_unstable();
+        bail!("Cycle de dépendances entre: {}", cycle.join(", "));
+    }
+
+    Ok(order)
+}
+
+/// Dependencies naming a task outside of the graph, in submission order.
+pub fn external_dependencies(specs: &[TaskSpec]) -> Vec<String> {
+    let keys: HashSet<&str> = specs.iter().map(|spec| spec.key.as_str()).collect();
+    let mut seen = HashSet::new();
+    specs
+        .iter()
+        .flat_map(|spec| spec.depends_on.iter())
+        .filter(|dep| !keys.contains(dep.as_str()) && seen.insert(dep.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// `true` once every task of `depends_on` completed successfully.
+pub fn dependencies_completed<'a>(
+    depends_on: &[String],
+    mut lookup: impl FnMut(&str) -> Option<&'a DelegatedTask>,
+) -> bool {
+    depends_on.iter().all(|dep| {
+        lookup(dep)
+            .map(|task| task.status == TaskStatus::Completed)
+            .unwrap_or(false)
+    })
+}
+
+/// Result of a completed task as handed to its dependents.
+pub fn upstream_entry(task: &DelegatedTask) -> Value {
+    let note = task
+        .notifications
+        .iter()
+        .rev()
+        .find(|note| note.status.eq_ignore_ascii_case("completed"))
+        .or_else(|| task.notifications.last());
+
+    let mut entry = Map::new();
+    entry.insert("goal".into(), Value::String(task.goal.clone()));
+    entry.insert(
+        "delegated_to".into(),
+        Value::String(task.delegated_to.clone()),
+    );
+    entry.insert(
+        "summary".into(),
+        note.map(|n| Value::String(n.summary.clone()))
+            .unwrap_or(Value::Null),
+    );
+    entry.insert(
+        "details".into(),
+        note.and_then(|n| n.details.clone()).unwrap_or(Value::Null),
+    );
+    entry.insert(
+        "evidence".into(),
+        note.and_then(|n| n.evidence.clone()).unwrap_or(Value::Null),
+    );
+    Value::Object(entry)
+}
+
+/// Add `upstream` (task id -> [`upstream_entry`]) to a task context.
+///
+/// Object contexts gain an `upstream` key; any other value is kept under
+/// `input`.
+pub fn attach_upstream(context: Option<Value>, upstream: Map<String, Value>) -> Value {
+    let mut map = match context {
+        Some(Value::Object(map)) => map,
+        None | Some(Value::Null) => Map::new(),
+        Some(other) => {
+            let mut map = Map::new();
+            map.insert("input".into(), other);
+            map
+        }
+    };
+    map.insert("upstream".into(), Value::Object(upstream));
+    Value::Object(map)
+}
@@ -1,6 +1,7 @@
 pub mod backend;
 #[cfg(feature = "daemon")]
 pub mod daemon;
+pub mod graph;
 pub mod local;
 pub mod types;
 
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
@@ -10,15 +10,19 @@ use serde_json::Value;
 use uuid::Uuid;
 
 use crate::backend::OrchestrationBackend;
+use crate::graph;
 use crate::types::{
-    DelegateResult, DelegatedTask, OrchestrationConfig, OrchestrationStatus, OrchestrationSummary,
-    StatusFilter, TaskNotification, TaskStatus,
+    DelegateResult, DelegatedTask, DependencyFailurePolicy, OrchestrationConfig,
+    OrchestrationStatus, OrchestrationSummary, StatusFilter, TaskNotification, TaskSpec,
+    TaskStatus,
 };
 
 #[derive(Default)]
 struct OrchestrationState {
     active_tasks: HashMap<String, DelegatedTask>,
     completed_tasks: HashMap<String, DelegatedTask>,
+    // Policy of tasks still waiting on their dependencies
+    dependency_policies: HashMap<String, DependencyFailurePolicy>,
     last_cleanup: Option<Instant>,
 }
 
@@ -48,9 +52,153 @@ impl LocalBackend {
             .checked_sub_signed(chrono::Duration::seconds(2 * 60 * 60))
             .unwrap_or_else(Utc::now);
 
+        // Dependencies of waiting tasks stay until their dependents settle.
+        let pinned: HashSet<String> = state
+            .active_tasks
+            .values()
+            .flat_map(|task| task.depends_on.iter().cloned())
+            .collect();
         state
             .completed_tasks
-            .retain(|_, task| task.last_activity > threshold);
+            .retain(|id, task| task.last_activity > threshold || pinned.contains(id));
+    }
+
+    fn create_task(
+        &self,
+        state: &mut OrchestrationState,
+        spec: TaskSpec,
+        depends_on: Vec<String>,
+    ) -> DelegateResult {
+        let task_id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let timeout_secs = spec
+            .timeout_secs
+            .unwrap_or(self.config.default_timeout_secs);
+
+        let task = DelegatedTask {
+            id: task_id.clone(),
+            goal: spec.goal,
+            delegated_to: spec.delegated_to,
+            created_at: now,
+            timeout_secs,
+            status: TaskStatus::Pending,
+            context: spec.context,
+            watch_patterns: spec
+                .watch_patterns
+                .unwrap_or_else(|| self.config.default_watch_patterns.clone()),
+            last_activity: now,
+            notifications: Vec::new(),
+            working_dir: spec.working_dir,
+            response_format: spec.response_format,
+            model: spec.model,
+            model_resolved: None,
+            depends_on,
+        };
+
+        if !task.depends_on.is_empty() {
+            state
+                .dependency_policies
+                .insert(task_id.clone(), spec.on_dependency_failure);
+        }
+        state.active_tasks.insert(task_id.clone(), task);
+
+        DelegateResult {
+            task_id,
+            timeout_secs,
+        }
+    }
+
+    /// Re-evaluate the tasks waiting on `finished_id`.
+    fn release_dependents(state: &mut OrchestrationState, finished_id: &str) {
+        let dependents: Vec<String> = state
+            .active_tasks
+            .values()
+            .filter(|task| task.depends_on.iter().any(|dep| dep == finished_id))
+            .map(|task| task.id.clone())
+            .collect();
+        for task_id in dependents {
+            Self::settle_dependencies(state, &task_id);
+        }
+    }
+
+    /// Fail (or skip) `task_id` when a dependency did not complete, or hand it
+    /// the upstream results once they all did.
+    fn settle_dependencies(state: &mut OrchestrationState, task_id: &str) {
+        let Some(depends_on) = state
+            .active_tasks
+            .get(task_id)
+            .map(|task| task.depends_on.clone())
+        else {
+            return;
+        };
+
+        let broken = depends_on.iter().find_map(|dep| {
+            state
+                .completed_tasks
+                .get(dep)
+                .filter(|task| task.status != TaskStatus::Completed)
+                .map(|task| (dep.clone(), task.status.clone()))
+        });
+
+        if let Some((dependency, dependency_status)) = broken {
+            let policy = state
+                .dependency_policies
+                .remove(task_id)
+                .unwrap_or_default();
+            let Some(mut task) = state.active_tasks.remove(task_id) else {
+                return;
+            };
+            let now = Utc::now();
+            let status = policy.outcome();
+            task.notifications.push(TaskNotification {
+                received_at: now,
+                status: status.as_str().to_string(),
+                summary: format!(
+                    "Dependency {} ended {}; task {}",
+                    dependency,
+                    dependency_status.as_str(),
+                    if policy == DependencyFailurePolicy::Skip {
+                        "skipped"
+                    } else {
+                        "failed"
+                    }
+                ),
+                details: None,
+                evidence: None,
+                auto_generated: true,
+                metadata: Some(serde_json::json!({
+                    "dependency": {
+                        "task_id": dependency,
+                        "status": dependency_status.as_str(),
+                        "policy": policy.as_str(),
+                    }
+                })),
+            });
+            task.status = status;
+            task.last_activity = now;
+            state.completed_tasks.insert(task_id.to_string(), task);
+            Self::release_dependents(state, task_id);
+            return;
+        }
+
+        if !graph::dependencies_completed(&depends_on, |dep| state.completed_tasks.get(dep)) {
+            return;
+        }
+
+        let upstream = depends_on
+            .iter()
+            .filter_map(|dep| {
+                state
+                    .completed_tasks
+                    .get(dep)
+                    .map(|task| (dep.clone(), graph::upstream_entry(task)))
+            })
+            .collect();
+        state.dependency_policies.remove(task_id);
+        if let Some(task) = state.active_tasks.get_mut(task_id) {
+            task.context = Some(graph::attach_upstream(task.context.take(), upstream));
+            task.last_activity = Utc::now();
+        }
     }
 }
 
@@ -78,36 +226,64 @@ impl OrchestrationBackend for LocalBackend {
             );
         }
 
-        let task_id = Uuid::new_v4().to_string();
-        let now = Utc::now();
-        let timeout_secs = timeout
-            .map(|d| d.as_secs())
-            .unwrap_or(self.config.default_timeout_secs);
-
-        let task = DelegatedTask {
-            id: task_id.clone(),
+        let spec = TaskSpec {
+            key: String::new(),
             goal,
             delegated_to,
-            created_at: now,
-            timeout_secs,
-            status: TaskStatus::Pending,
+            model,
+            timeout_secs: timeout.map(|d| d.as_secs()),
+            watch_patterns,
             context,
-            watch_patterns: watch_patterns
-                .unwrap_or_else(|| self.config.default_watch_patterns.clone()),
-            last_activity: now,
-            notifications: Vec::new(),
             working_dir,
             response_format,
-            model,
-            model_resolved: None,
+            depends_on: Vec::new(),
+            on_dependency_failure: DependencyFailurePolicy::default(),
+            priority: 0,
         };
 
-        state.active_tasks.insert(task_id.clone(), task);
+        Ok(self.create_task(&mut state, spec, Vec::new()))
+    }
 
-        Ok(DelegateResult {
-            task_id,
-            timeout_secs,
-        })
+    async fn delegate_graph(&self, tasks: Vec<TaskSpec>) -> Result<Vec<(String, DelegateResult)>> {
+        let order = graph::topological_order(&tasks)?;
+
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("orchestration context poisoned"))?;
+
+        for dep in graph::external_dependencies(&tasks) {
+            if !state.active_tasks.contains_key(&dep) && !state.completed_tasks.contains_key(&dep) {
+                bail!("Dépendance inconnue: {dep}");
+            }
+        }
+        if state.active_tasks.len() + tasks.len() > self.config.max_concurrent_tasks {
+            bail!(
+                "Nombre maximum de tâches déléguées atteint, veuillez en clôturer avant d'en créer de nouvelles"
+            );
+        }
+
+        let mut slots: Vec<Option<TaskSpec>> = tasks.into_iter().map(Some).collect();
+        let mut ids: HashMap<String, String> = HashMap::new();
+        let mut results = Vec::with_capacity(order.len());
+        for index in order {
+            let Some(spec) = slots[index].take() else {
+                continue;
+            };
+            let depends_on = spec
+                .depends_on
+                .iter()
+                .map(|dep| ids.get(dep).cloned().unwrap_or_else(|| dep.clone()))
+                .collect();
+            let key = spec.key.clone();
+            let result = self.create_task(&mut state, spec, depends_on);
+            ids.insert(key.clone(), result.task_id.clone());
+            // Dependencies delegated earlier may already be settled.
+            Self::settle_dependencies(&mut state, &result.task_id);
+            results.push((key, result));
+        }
+
+        Ok(results)
     }
 
     async fn notify(
@@ -127,6 +303,19 @@ impl OrchestrationBackend for LocalBackend {
             .state
             .lock()
             .map_err(|_| anyhow!("orchestration context poisoned"))?;
+        let waiting = state
+            .active_tasks
+            .get(task_id)
+            .map(|task| {
+                !graph::dependencies_completed(&task.depends_on, |dep| {
+                    state.completed_tasks.get(dep)
+                })
+            })
+            .unwrap_or(false);
+        if waiting {
+            bail!("Tâche en attente de ses dépendances: {task_id}");
+        }
+
         let mut task = state
             .active_tasks
             .remove(task_id)
@@ -157,6 +346,7 @@ impl OrchestrationBackend for LocalBackend {
             TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled
         ) {
             state.completed_tasks.insert(task_id.to_string(), task);
+            Self::release_dependents(&mut state, task_id);
         } else {
             state.active_tasks.insert(task_id.to_string(), task);
         }
@@ -164,6 +354,38 @@ impl OrchestrationBackend for LocalBackend {
         Ok(())
     }
 
+    async fn cancel(&self, task_id: &str, reason: Option<&str>) -> Result<()> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("orchestration context poisoned"))?;
+        if state.completed_tasks.contains_key(task_id) {
+            bail!("Tâche déjà terminée: {task_id}");
+        }
+        let mut task = state
+            .active_tasks
+            .remove(task_id)
+            .ok_or_else(|| anyhow!("Tâche inconnue: {task_id}"))?;
+
+        let now = Utc::now();
+        task.notifications.push(TaskNotification {
+            received_at: now,
+            status: "cancelled".to_string(),
+            summary: format!("Task cancelled: {}", reason.unwrap_or("requested by user")),
+            details: None,
+            evidence: None,
+            auto_generated: true,
+            metadata: None,
+        });
+        task.status = TaskStatus::Cancelled;
+        task.last_activity = now;
+        state.dependency_policies.remove(task_id);
+        state.completed_tasks.insert(task_id.to_string(), task);
+        Self::release_dependents(&mut state, task_id);
+
+        Ok(())
+    }
+
     async fn status(&self, filter: StatusFilter) -> Result<OrchestrationStatus> {
         let state = self
             .state
@@ -218,6 +440,7 @@ impl OrchestrationBackend for LocalBackend {
             active_tasks,
             completed_tasks,
             summary,
+            queue: Vec::new(),
         })
     }
 
@@ -247,3 +470,70 @@ impl OrchestrationBackend for LocalBackend {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(key: &str, depends_on: &[&str]) -> TaskSpec {
+        TaskSpec {
+            key: key.to_string(),
+            goal: format!("goal {key}"),
+            delegated_to: "codex".to_string(),
+            model: None,
+            timeout_secs: None,
+            watch_patterns: None,
+            context: None,
+            working_dir: None,
+            response_format: None,
+            depends_on: depends_on.iter().map(|dep| dep.to_string()).collect(),
+            on_dependency_failure: DependencyFailurePolicy::default(),
+            priority: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn cleanup_keeps_dependencies_of_waiting_tasks() {
+        let backend = LocalBackend::new(OrchestrationConfig::default());
+        let ids: HashMap<String, String> = backend
+            .delegate_graph(vec![
+                spec("build", &[]),
+                spec("lint", &[]),
+                spec("test", &["build", "lint"]),
+            ])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(key, result)| (key, result.task_id))
+            .collect();
+        backend
+            .notify(&ids["build"], "completed", "built", None, None)
+            .await
+            .unwrap();
+
+        // The build finished long ago while the test still waits on lint.
+        let old = Utc::now() - chrono::Duration::hours(3);
+        {
+            let mut state = backend.state.lock().unwrap();
+            state
+                .completed_tasks
+                .get_mut(&ids["build"])
+                .unwrap()
+                .last_activity = old;
+            let mut stale = state.completed_tasks[&ids["build"]].clone();
+            stale.id = "stale".to_string();
+            state.completed_tasks.insert("stale".to_string(), stale);
+        }
+        backend.cleanup_expired().await.unwrap();
+        assert!(backend.get_task("stale").await.unwrap().is_none());
+        assert!(backend.get_task(&ids["build"]).await.unwrap().is_some());
+
+        backend
+            .notify(&ids["lint"], "completed", "clean", None, None)
+            .await
+            .unwrap();
+        let test = backend.get_task(&ids["test"]).await.unwrap().unwrap();
+        let upstream = test.context.expect("upstream results attached");
+        assert!(upstream.to_string().contains("built"), "{upstream}");
+    }
+}
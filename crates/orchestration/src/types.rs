@@ -27,6 +27,9 @@ pub struct DelegatedTask {
     pub model: Option<String>,
     #[serde(default)]
     pub model_resolved: Option<String>,
+    /// Tasks that must complete before this one is dispatched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -39,6 +42,18 @@ pub enum TaskStatus {
     Cancelled,
 }
 
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Pending => "pending",
+            TaskStatus::InProgress => "in_progress",
+            TaskStatus::Completed => "completed",
+            TaskStatus::Failed => "failed",
+            TaskStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TaskNotification {
     pub received_at: DateTime<Utc>,
@@ -56,6 +71,20 @@ pub struct OrchestrationStatus {
     pub active_tasks: Vec<DelegatedTask>,
     pub completed_tasks: Vec<DelegatedTask>,
     pub summary: OrchestrationSummary,
+    /// Tasks waiting for a worker slot, in dispatch order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub queue: Vec<QueuedTask>,
+}
+
+/// A delegated task waiting for a free worker slot
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QueuedTask {
+    pub task_id: String,
+    pub delegated_to: String,
+    pub priority: i32,
+    /// 1-based rank in the dispatch order
+    pub position: usize,
+    pub queued_at: DateTime<Utc>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -118,6 +147,73 @@ pub fn default_daemon_start_timeout_ms() -> u64 {
     DEFAULT_DAEMON_START_TIMEOUT_MS
 }
 
+/// One node of a task graph submitted through `delegate_graph`.
+///
+/// `depends_on` lists the `key` of other nodes of the same graph, or the id
+/// of a task that was delegated earlier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSpec {
+    pub key: String,
+    pub goal: String,
+    pub delegated_to: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub watch_patterns: Option<Vec<String>>,
+    #[serde(default)]
+    pub context: Option<Value>,
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub response_format: Option<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub on_dependency_failure: DependencyFailurePolicy,
+    /// Higher runs first when the worker is saturated
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// What happens to a task when one of its dependencies does not complete
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyFailurePolicy {
+    /// The task is marked failed
+    #[default]
+    Fail,
+    /// The task is marked cancelled
+    Skip,
+}
+
+impl DependencyFailurePolicy {
+    /// Policy named by an optional `on_dependency_failure` value; anything
+    /// but `skip` fails the task.
+    pub fn parse_opt(value: Option<&str>) -> Self {
+        match value {
+            Some("skip") => DependencyFailurePolicy::Skip,
+            _ => DependencyFailurePolicy::Fail,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencyFailurePolicy::Fail => "fail",
+            DependencyFailurePolicy::Skip => "skip",
+        }
+    }
+
+    /// Terminal status given to a task whose dependency failed
+    pub fn outcome(&self) -> TaskStatus {
+        match self {
+            DependencyFailurePolicy::Fail => TaskStatus::Failed,
+            DependencyFailurePolicy::Skip => TaskStatus::Cancelled,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DelegateResult {
     pub task_id: String,
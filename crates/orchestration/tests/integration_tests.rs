@@ -2,7 +2,8 @@ use anyhow::Result;
 use devit_common::orchestration::OrchestrationContext;
 use devit_orchestration::backend::OrchestrationBackend;
 use devit_orchestration::types::{
-    OrchestrationConfig, OrchestrationMode, StatusFilter, TaskStatus,
+    DependencyFailurePolicy, OrchestrationConfig, OrchestrationMode, StatusFilter, TaskSpec,
+    TaskStatus,
 };
 use once_cell::sync::Lazy;
 use std::{env, path::PathBuf, process::Command, time::Duration};
@@ -248,6 +249,101 @@ mod local_tests {
 
         Ok(())
     }
+
+    fn spec(key: &str, depends_on: &[&str]) -> TaskSpec {
+        TaskSpec {
+            key: key.into(),
+            goal: format!("{} step", key),
+            delegated_to: "worker".into(),
+            model: None,
+            timeout_secs: None,
+            watch_patterns: None,
+            context: None,
+            working_dir: None,
+            response_format: None,
+            depends_on: depends_on.iter().map(|dep| dep.to_string()).collect(),
+            on_dependency_failure: DependencyFailurePolicy::Fail,
+            priority: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_graph_passes_upstream_results() -> Result<()> {
+        let backend = LocalBackend::new(OrchestrationConfig::default());
+        // Submitted out of order on purpose.
+        let ids: std::collections::HashMap<String, String> = backend
+            .delegate_graph(vec![
+                spec("test", &["refactor"]),
+                spec("analyse", &[]),
+                spec("refactor", &["analyse"]),
+            ])
+            .await?
+            .into_iter()
+            .map(|(key, result)| (key, result.task_id))
+            .collect();
+
+        assert!(backend
+            .notify(&ids["refactor"], "completed", "too early", None, None)
+            .await
+            .is_err());
+
+        backend
+            .notify(
+                &ids["analyse"],
+                "completed",
+                "analysis done",
+                Some(serde_json::json!({"hotspots": ["parser.rs"]})),
+                None,
+            )
+            .await?;
+
+        let refactor = backend.get_task(&ids["refactor"]).await?.unwrap();
+        assert_eq!(refactor.status, TaskStatus::Pending);
+        assert_eq!(refactor.depends_on, vec![ids["analyse"].clone()]);
+        let upstream = &refactor.context.unwrap()["upstream"][&ids["analyse"]];
+        assert_eq!(upstream["summary"], "analysis done");
+        assert_eq!(upstream["details"]["hotspots"][0], "parser.rs");
+
+        let test = backend.get_task(&ids["test"]).await?.unwrap();
+        assert!(test.context.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_local_graph_failure_propagates() -> Result<()> {
+        let backend = LocalBackend::new(OrchestrationConfig::default());
+        let mut review = spec("review", &["test"]);
+        review.on_dependency_failure = DependencyFailurePolicy::Skip;
+        let ids: std::collections::HashMap<String, String> = backend
+            .delegate_graph(vec![spec("build", &[]), spec("test", &["build"]), review])
+            .await?
+            .into_iter()
+            .map(|(key, result)| (key, result.task_id))
+            .collect();
+
+        backend
+            .notify(&ids["build"], "failed", "does not compile", None, None)
+            .await?;
+
+        let test = backend.get_task(&ids["test"]).await?.unwrap();
+        assert_eq!(test.status, TaskStatus::Failed);
+        let review = backend.get_task(&ids["review"]).await?.unwrap();
+        assert_eq!(review.status, TaskStatus::Cancelled);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_local_graph_rejects_cycles() {
+        let backend = LocalBackend::new(OrchestrationConfig::default());
+        let err = backend
+            .delegate_graph(vec![spec("a", &["b"]), spec("b", &["a"])])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Cycle"), "{}", err);
+
+        let status = backend.status(StatusFilter::All).await.unwrap();
+        assert!(status.active_tasks.is_empty());
+    }
 }
 
 #[tokio::test]
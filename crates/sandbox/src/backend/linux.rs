@@ -0,0 +1,638 @@
+//! Linux namespace backend.
+//!
+//! Executes a [`SandboxPlan`] with unprivileged user namespaces:
+//! - `CLONE_NEWUSER` maps the caller's uid/gid 1:1 so no privilege is gained;
+//! - `CLONE_NEWNS` builds a fresh tmpfs root populated only with the plan's
+//!   bind mounts (read-only or read-write) and pivots into it;
+//! - `CLONE_NEWNET` (when `net = false`) leaves the child in an empty network
+//!   namespace with only a loopback interface;
+//! - the plan's `seccomp_profile`, if any, is installed last, once the mount
+//!   setup no longer needs `mount`/`pivot_root`.
+//!
+//! All path and map strings are prepared before `fork`; the `pre_exec` hook
+//! only issues raw syscalls so it stays async-signal-safe.
+
+use super::unix::UnixChild;
+use super::SandboxBackend;
+use crate::cgroup::{Cgroup, CgroupLimits};
+use crate::seccomp::SeccompFilter;
+use anyhow::{anyhow, Context, Result};
+use devit_common::{SandboxPlan, SeccompProfileSpec};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Paths every sandbox needs to run ordinary binaries (`/dev/null`,
+/// `/proc/self/exe`, …). Added read-only when the plan does not mention them.
+const IMPLICIT_RO_PATHS: &[&str] = &["/dev", "/proc"];
+
+/// Name of the directory (inside the new root) where the old root is parked
+/// during `pivot_root` before being detached.
+const OLD_ROOT: &str = ".devit-oldroot";
+
+/// Sandbox backend confining children with Linux namespaces.
+#[derive(Debug, Clone)]
+pub struct NamespaceSandbox {
+    plan: SandboxPlan,
+    seccomp_profiles: HashMap<String, SeccompProfileSpec>,
+    cpu_limit_percent: Option<u32>,
+    memory_limit_bytes: Option<u64>,
+    pids_limit: Option<u64>,
+}
+
+impl NamespaceSandbox {
+    pub fn new(plan: SandboxPlan) -> Self {
+        Self {
+            plan,
+            seccomp_profiles: HashMap::new(),
+            cpu_limit_percent: None,
+            memory_limit_bytes: None,
+            pids_limit: None,
+        }
+    }
+
+    /// Custom seccomp profiles the plan's `seccomp_profile` may refer to, in
+    /// addition to the built-in `strict` and `permissive`.
+    pub fn with_seccomp_profiles(mut self, profiles: HashMap<String, SeccompProfileSpec>) -> Self {
+        self.seccomp_profiles = profiles;
+        self
+    }
+
+    pub fn plan(&self) -> &SandboxPlan {
+        &self.plan
+    }
+
+    /// Caps the number of tasks (processes + threads) of each spawned child.
+    pub fn set_pids_limit(&mut self, max: u64) -> Result<()> {
+        self.pids_limit = Some(max);
+        Ok(())
+    }
+
+    /// Returns whether unprivileged user namespaces can be created on this
+    /// host. The probe runs once per process and is cached.
+    pub fn is_supported() -> bool {
+        static SUPPORTED: OnceLock<bool> = OnceLock::new();
+        *SUPPORTED.get_or_init(|| {
+            let mut probe = Command::new("/bin/true");
+            probe
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null());
+            unsafe {
+                probe.pre_exec(|| {
+                    if libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNS) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+            probe.status().map(|s| s.success()).unwrap_or(false)
+        })
+    }
+
+    /// Installs the namespace setup on `cmd` without spawning it. Useful for
+    /// callers that drive their own `Command` (e.g. `tokio::process::Command`
+    /// through `as_std_mut`).
+    ///
+    /// The working directory of `cmd` (or the current directory) must be
+    /// reachable through one of the plan's bind mounts.
+    pub fn confine(&self, cmd: &mut Command) -> Result<()> {
+        let cwd = match cmd.get_current_dir() {
+            Some(dir) => dir.to_path_buf(),
+            None => std::env::current_dir().context("sandbox: cannot resolve current dir")?,
+        };
+        let setup = NamespaceSetup::prepare(&self.plan, &cwd)?;
+        let filter = self
+            .plan
+            .seccomp_profile
+            .as_deref()
+            .map(|name| SeccompFilter::resolve(name, &self.seccomp_profiles))
+            .transpose()?;
+        unsafe {
+            cmd.pre_exec(move || {
+                setup.enter()?;
+                if let Some(filter) = &filter {
+                    filter.install()?;
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+}
+
+impl SandboxBackend for NamespaceSandbox {
+    type Child = UnixChild;
+
+    fn spawn(&mut self, mut cmd: Command) -> Result<Self::Child> {
+        let limits = CgroupLimits {
+            cpu_percent: self.cpu_limit_percent,
+            memory_bytes: self.memory_limit_bytes,
+            pids_max: self.pids_limit,
+        };
+        // The child must join its cgroup before unsharing its namespaces.
+        let cgroup = if limits.is_empty() {
+            None
+        } else {
+            let cgroup = Cgroup::create(&limits)?;
+            cgroup.attach(&mut cmd);
+            Some(cgroup)
+        };
+        self.confine(&mut cmd)?;
+        let child = cmd
+            .spawn()
+            .context("sandbox: failed to spawn confined process")?;
+        Ok(UnixChild { child, cgroup })
+    }
+
+    fn set_cpu_limit(&mut self, percent: u32) -> Result<()> {
+        self.cpu_limit_percent = Some(percent);
+        Ok(())
+    }
+
+    fn set_memory_limit(&mut self, bytes: u64) -> Result<()> {
+        self.memory_limit_bytes = Some(bytes);
+        Ok(())
+    }
+}
+
+/// One entry of the new root, fully resolved before fork.
+#[derive(Debug)]
+struct BindStep {
+    target: CString,
+    /// Directories to create (in order) so that `target` can be created.
+    parents: Vec<CString>,
+    kind: BindKind,
+}
+
+#[derive(Debug)]
+enum BindKind {
+    /// Bind mount of a host directory or file.
+    Mount {
+        source: CString,
+        is_dir: bool,
+        read_only: bool,
+        /// Flags locked on the source mount that must be preserved when
+        /// remounting read-only from inside a user namespace.
+        locked_flags: libc::c_ulong,
+    },
+    /// Host symlink recreated verbatim (merged-usr layouts such as
+    /// `/lib64 -> usr/lib64`).
+    Symlink { link: CString },
+}
+
+/// Everything the child needs to enter the sandbox, as raw C strings.
+#[derive(Debug)]
+struct NamespaceSetup {
+    unshare_flags: libc::c_int,
+    uid_map: CString,
+    gid_map: CString,
+    new_root: CString,
+    old_root: CString,
+    old_root_after_pivot: CString,
+    binds: Vec<BindStep>,
+    cwd: CString,
+    isolate_net: bool,
+}
+
+impl NamespaceSetup {
+    fn prepare(plan: &SandboxPlan, cwd: &Path) -> Result<Self> {
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+
+        // The mount point itself is never populated on the host: the tmpfs is
+        // mounted inside the child's private mount namespace only.
+        let new_root = std::env::temp_dir().join(format!(".devit-sandbox-{uid}"));
+        std::fs::create_dir_all(&new_root)
+            .with_context(|| format!("sandbox: cannot create {}", new_root.display()))?;
+
+        let mut binds = Vec::new();
+        let mut seen: Vec<PathBuf> = Vec::new();
+        let requested = plan
+            .bind_rw
+            .iter()
+            .map(|p| (p.clone(), false))
+            .chain(plan.bind_ro.iter().map(|p| (p.clone(), true)))
+            .chain(IMPLICIT_RO_PATHS.iter().map(|p| (PathBuf::from(p), true)));
+
+        for (path, read_only) in requested {
+            if !path.is_absolute() || seen.contains(&path) {
+                continue;
+            }
+            // Plans are host-agnostic (e.g. /lib64 on distros without it).
+            if std::fs::symlink_metadata(&path).is_err() {
+                continue;
+            }
+            seen.push(path.clone());
+            binds.push(BindStep::prepare(&new_root, &path, read_only)?);
+        }
+
+        // Mount parents before children so nested binds are not shadowed.
+        binds.sort_by_key(|b| b.target.as_bytes().len());
+
+        let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf());
+        let visible = seen
+            .iter()
+            .filter_map(|p| p.canonicalize().ok())
+            .any(|root| cwd.starts_with(root));
+        if !visible {
+            return Err(anyhow!(
+                "sandbox: working directory {} is outside the sandbox plan",
+                cwd.display()
+            ));
+        }
+
+        let mut unshare_flags = libc::CLONE_NEWUSER | libc::CLONE_NEWNS;
+        if !plan.net {
+            unshare_flags |= libc::CLONE_NEWNET;
+        }
+
+        Ok(Self {
+            unshare_flags,
+            uid_map: CString::new(format!("{uid} {uid} 1\n"))?,
+            gid_map: CString::new(format!("{gid} {gid} 1\n"))?,
+            old_root: path_cstring(&new_root.join(OLD_ROOT))?,
+            new_root: path_cstring(&new_root)?,
+            old_root_after_pivot: CString::new(format!("/{OLD_ROOT}"))?,
+            binds,
+            cwd: path_cstring(&cwd)?,
+            isolate_net: !plan.net,
+        })
+    }
+
+    /// Runs in the forked child, between `fork` and `exec`.
+    fn enter(&self) -> io::Result<()> {
+        unsafe {
+            check(libc::unshare(self.unshare_flags))?;
+
+            // setgroups does not exist on kernels older than 3.19; that is fine.
+            write_proc_file(c"/proc/self/setgroups", c"deny", true)?;
+            write_proc_file(c"/proc/self/uid_map", &self.uid_map, false)?;
+            write_proc_file(c"/proc/self/gid_map", &self.gid_map, false)?;
+
+            // Stop mount events from propagating back to the host.
+            check(libc::mount(
+                std::ptr::null(),
+                c"/".as_ptr(),
+                std::ptr::null(),
+                libc::MS_REC | libc::MS_PRIVATE,
+                std::ptr::null(),
+            ))?;
+
+            check(libc::mount(
+                c"tmpfs".as_ptr(),
+                self.new_root.as_ptr(),
+                c"tmpfs".as_ptr(),
+                libc::MS_NOSUID | libc::MS_NODEV,
+                c"mode=0755".as_ptr().cast(),
+            ))?;
+            // Recursive binds of an ancestor (typically /tmp) must not copy
+            // the new root into itself.
+            check(libc::mount(
+                std::ptr::null(),
+                self.new_root.as_ptr(),
+                std::ptr::null(),
+                libc::MS_UNBINDABLE,
+                std::ptr::null(),
+            ))?;
+
+            for bind in &self.binds {
+                bind.apply()?;
+            }
+
+            mkdir_if_missing(&self.old_root)?;
+            check(libc::syscall(
+                libc::SYS_pivot_root,
+                self.new_root.as_ptr(),
+                self.old_root.as_ptr(),
+            ) as libc::c_int)?;
+            check(libc::chdir(c"/".as_ptr()))?;
+            check(libc::umount2(
+                self.old_root_after_pivot.as_ptr(),
+                libc::MNT_DETACH,
+            ))?;
+            libc::rmdir(self.old_root_after_pivot.as_ptr());
+
+            // Freeze the skeleton root; only explicit rw binds stay writable.
+            check(libc::mount(
+                std::ptr::null(),
+                c"/".as_ptr(),
+                std::ptr::null(),
+                libc::MS_REMOUNT
+                    | libc::MS_BIND
+                    | libc::MS_RDONLY
+                    | libc::MS_NOSUID
+                    | libc::MS_NODEV,
+                std::ptr::null(),
+            ))?;
+
+            if self.isolate_net {
+                bring_loopback_up();
+            }
+
+            check(libc::chdir(self.cwd.as_ptr()))?;
+        }
+        Ok(())
+    }
+}
+
+impl BindStep {
+    fn prepare(new_root: &Path, path: &Path, read_only: bool) -> Result<Self> {
+        let link_meta = std::fs::symlink_metadata(path)
+            .with_context(|| format!("sandbox: cannot stat {}", path.display()))?;
+
+        // Symlinks are recreated as-is; everything else is mounted at its
+        // canonical location so the working directory resolves identically.
+        let resolved = if link_meta.file_type().is_symlink() {
+            path.to_path_buf()
+        } else {
+            path.canonicalize()
+                .with_context(|| format!("sandbox: cannot resolve {}", path.display()))?
+        };
+        let relative = resolved.strip_prefix("/").unwrap_or(&resolved);
+        let components: Vec<Component> = relative.components().collect();
+
+        let mut parents = Vec::new();
+        let mut current = new_root.to_path_buf();
+        for component in components.iter().take(components.len().saturating_sub(1)) {
+            current.push(component);
+            parents.push(path_cstring(&current)?);
+        }
+
+        let kind = if link_meta.file_type().is_symlink() {
+            let link = std::fs::read_link(path)
+                .with_context(|| format!("sandbox: cannot read link {}", path.display()))?;
+            BindKind::Symlink {
+                link: path_cstring(&link)?,
+            }
+        } else {
+            BindKind::Mount {
+                source: path_cstring(&resolved)?,
+                is_dir: link_meta.is_dir(),
+                read_only,
+                locked_flags: if read_only {
+                    locked_mount_flags(&resolved)
+                } else {
+                    0
+                },
+            }
+        };
+
+        Ok(Self {
+            target: path_cstring(&new_root.join(relative))?,
+            parents,
+            kind,
+        })
+    }
+
+    unsafe fn apply(&self) -> io::Result<()> {
+        for dir in &self.parents {
+            mkdir_if_missing(dir)?;
+        }
+
+        let (source, read_only, locked_flags) = match &self.kind {
+            BindKind::Symlink { link } => {
+                if libc::symlink(link.as_ptr(), self.target.as_ptr()) != 0 {
+                    let err = io::Error::last_os_error();
+                    if err.raw_os_error() != Some(libc::EEXIST) {
+                        return Err(err);
+                    }
+                }
+                return Ok(());
+            }
+            BindKind::Mount {
+                source,
+                is_dir,
+                read_only,
+                locked_flags,
+            } => {
+                if *is_dir {
+                    mkdir_if_missing(&self.target)?;
+                } else {
+                    let fd = libc::open(
+                        self.target.as_ptr(),
+                        libc::O_CREAT | libc::O_RDONLY | libc::O_CLOEXEC,
+                        0o644,
+                    );
+                    check(fd)?;
+                    libc::close(fd);
+                }
+                (source, *read_only, *locked_flags)
+            }
+        };
+
+        check(libc::mount(
+            source.as_ptr(),
+            self.target.as_ptr(),
+            std::ptr::null(),
+            libc::MS_BIND | libc::MS_REC,
+            std::ptr::null(),
+        ))?;
+
+        if read_only {
+            check(libc::mount(
+                std::ptr::null(),
+                self.target.as_ptr(),
+                std::ptr::null(),
+                libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | locked_flags,
+                std::ptr::null(),
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the flags of the mount backing `path` that the kernel locks for
+/// less privileged namespaces (nosuid, nodev, noexec, atime settings).
+fn locked_mount_flags(path: &Path) -> libc::c_ulong {
+    let Ok(c_path) = path_cstring(path) else {
+        return 0;
+    };
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return 0;
+    }
+    let mapping = [
+        (libc::ST_NOSUID, libc::MS_NOSUID),
+        (libc::ST_NODEV, libc::MS_NODEV),
+        (libc::ST_NOEXEC, libc::MS_NOEXEC),
+        (libc::ST_NOATIME, libc::MS_NOATIME),
+        (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+        (libc::ST_RELATIME, libc::MS_RELATIME),
+    ];
+    mapping
+        .iter()
+        .filter(|(st, _)| stat.f_flag & st != 0)
+        .fold(0, |acc, (_, ms)| acc | ms)
+}
+
+fn path_cstring(path: &Path) -> Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| anyhow!("sandbox: path contains NUL byte: {}", path.display()))
+}
+
+fn check(ret: libc::c_int) -> io::Result<()> {
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+unsafe fn mkdir_if_missing(path: &CStr) -> io::Result<()> {
+    if libc::mkdir(path.as_ptr(), 0o755) != 0 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::EEXIST) {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Writes `contents` to a `/proc` file; `missing_ok` skips files the
+/// running kernel does not provide.
+unsafe fn write_proc_file(path: &CStr, contents: &CStr, missing_ok: bool) -> io::Result<()> {
+    let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+    if fd < 0 {
+        let err = io::Error::last_os_error();
+        if missing_ok && err.raw_os_error() == Some(libc::ENOENT) {
+            return Ok(());
+        }
+        return Err(err);
+    }
+    let bytes = contents.to_bytes();
+    let written = libc::write(fd, bytes.as_ptr().cast(), bytes.len());
+    let err = io::Error::last_os_error();
+    libc::close(fd);
+    if written < 0 {
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Best effort: a fresh network namespace starts with `lo` down, which breaks
+/// tests binding to 127.0.0.1.
+unsafe fn bring_loopback_up() {
+    let sock = libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+    if sock < 0 {
+        return;
+    }
+    let mut req: libc::ifreq = std::mem::zeroed();
+    for (dst, src) in req.ifr_name.iter_mut().zip(b"lo\0") {
+        *dst = *src as libc::c_char;
+    }
+    if libc::ioctl(sock, libc::SIOCGIFFLAGS as _, &mut req) == 0 {
+        req.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+        libc::ioctl(sock, libc::SIOCSIFFLAGS as _, &mut req);
+    }
+    libc::close(sock);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use devit_common::SandboxProfile;
+
+    fn plan_for(dir: &Path, net: bool) -> SandboxPlan {
+        let mut plan = SandboxPlan::plan_for_test(dir.to_path_buf(), SandboxProfile::Strict);
+        plan.net = net;
+        plan
+    }
+
+    fn run(plan: SandboxPlan, dir: &Path, script: &str) -> Option<std::process::Output> {
+        if !NamespaceSandbox::is_supported() {
+            eprintln!("skipping: unprivileged user namespaces unavailable");
+            return None;
+        }
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg(script).current_dir(dir);
+        NamespaceSandbox::new(plan).confine(&mut cmd).unwrap();
+        Some(cmd.output().unwrap())
+    }
+
+    #[test]
+    fn rejects_working_dir_outside_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan = SandboxPlan {
+            bind_ro: vec![PathBuf::from("/usr")],
+            bind_rw: vec![],
+            net: false,
+            seccomp_profile: None,
+        };
+        let mut cmd = Command::new("/bin/true");
+        cmd.current_dir(dir.path());
+        assert!(NamespaceSandbox::new(plan).confine(&mut cmd).is_err());
+    }
+
+    #[test]
+    fn rw_binds_are_writable_and_ro_binds_are_not() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(out) = run(
+            plan_for(dir.path(), false),
+            dir.path(),
+            "echo ok > inside.txt && touch /usr/devit-probe 2>/dev/null; echo $?",
+        ) else {
+            return;
+        };
+        assert!(out.status.success(), "{out:?}");
+        assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "1");
+        assert!(dir.path().join("inside.txt").exists());
+    }
+
+    #[test]
+    fn host_paths_outside_plan_are_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(out) = run(
+            plan_for(dir.path(), false),
+            dir.path(),
+            "ls /root >/dev/null 2>&1; echo $?",
+        ) else {
+            return;
+        };
+        assert!(out.status.success(), "{out:?}");
+        assert_ne!(String::from_utf8_lossy(&out.stdout).trim(), "0");
+    }
+
+    #[test]
+    fn no_net_plan_only_sees_loopback() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(out) = run(plan_for(dir.path(), false), dir.path(), "cat /proc/net/dev") else {
+            return;
+        };
+        assert!(out.status.success(), "{out:?}");
+        let interfaces: Vec<String> = String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .skip(2)
+            .filter_map(|l| l.split(':').next().map(|s| s.trim().to_string()))
+            .collect();
+        assert_eq!(interfaces, vec!["lo".to_string()]);
+    }
+
+    #[test]
+    fn plan_seccomp_profile_is_enforced() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(out) = run(
+            plan_for(dir.path(), false),
+            dir.path(),
+            "grep Seccomp: /proc/self/status",
+        ) else {
+            return;
+        };
+        assert!(out.status.success(), "{out:?}");
+        assert!(
+            String::from_utf8_lossy(&out.stdout).contains('2'),
+            "{out:?}"
+        );
+
+        let mut plan = plan_for(dir.path(), false);
+        plan.seccomp_profile = Some("does-not-exist".into());
+        let mut cmd = Command::new("/bin/true");
+        cmd.current_dir(dir.path());
+        assert!(NamespaceSandbox::new(plan).confine(&mut cmd).is_err());
+    }
+}
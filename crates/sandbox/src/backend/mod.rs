@@ -2,6 +2,8 @@ use anyhow::Result;
 use std::io;
 use std::process::{ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus};
 
+pub use devit_common::ResourceUsage;
+
 pub trait SandboxBackend: Send {
     type Child: ProcessHandle;
 
@@ -19,10 +21,19 @@ pub trait ProcessHandle: Send {
     fn stdout(&mut self) -> Option<&mut ChildStdout>;
     fn stderr(&mut self) -> Option<&mut ChildStderr>;
     fn stdin(&mut self) -> Option<&mut ChildStdin>;
+
+    /// Resource accounting, when the backend enforces limits. Still readable
+    /// after `wait` returned.
+    fn resource_usage(&self) -> Option<ResourceUsage> {
+        None
+    }
 }
 
 #[cfg(unix)]
 pub mod unix;
 
+#[cfg(target_os = "linux")]
+pub mod linux;
+
 #[cfg(windows)]
 pub mod windows;
@@ -1,4 +1,6 @@
-use super::{ProcessHandle, SandboxBackend};
+use super::{ProcessHandle, ResourceUsage, SandboxBackend};
+#[cfg(target_os = "linux")]
+use crate::cgroup::{Cgroup, CgroupLimits};
 use anyhow::Result;
 use std::io;
 use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus};
@@ -7,16 +9,51 @@ use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitSta
 pub struct UnixSandbox {
     cpu_limit_percent: Option<u32>,
     memory_limit_bytes: Option<u64>,
+    pids_limit: Option<u64>,
+}
+
+impl UnixSandbox {
+    /// Caps the number of tasks (processes + threads) of each spawned child.
+    pub fn set_pids_limit(&mut self, max: u64) -> Result<()> {
+        self.pids_limit = Some(max);
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn limits(&self) -> CgroupLimits {
+        CgroupLimits {
+            cpu_percent: self.cpu_limit_percent,
+            memory_bytes: self.memory_limit_bytes,
+            pids_max: self.pids_limit,
+        }
+    }
 }
 
 impl SandboxBackend for UnixSandbox {
     type Child = UnixChild;
 
     fn spawn(&mut self, mut cmd: Command) -> Result<Self::Child> {
-        // Phase 1B: no runtime resource enforcement yet; spawn directly.
         // Callers should set stdio as needed on the Command before passing it here.
-        let child = cmd.spawn()?;
-        Ok(UnixChild(child))
+        #[cfg(target_os = "linux")]
+        {
+            // One cgroup v2 per child; limits that cannot be enforced are an error.
+            let limits = self.limits();
+            let cgroup = if limits.is_empty() {
+                None
+            } else {
+                let cgroup = Cgroup::create(&limits)?;
+                cgroup.attach(&mut cmd);
+                Some(cgroup)
+            };
+            let child = cmd.spawn()?;
+            Ok(UnixChild { child, cgroup })
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            // No cgroups outside Linux: limits are recorded but not enforced.
+            let child = cmd.spawn()?;
+            Ok(UnixChild { child })
+        }
     }
 
     fn set_cpu_limit(&mut self, percent: u32) -> Result<()> {
@@ -30,34 +67,58 @@ impl SandboxBackend for UnixSandbox {
     }
 }
 
-pub struct UnixChild(pub(crate) Child);
+pub struct UnixChild {
+    pub(crate) child: Child,
+    #[cfg(target_os = "linux")]
+    pub(crate) cgroup: Option<Cgroup>,
+}
+
+impl UnixChild {
+    /// Detaches the child's cgroup so it outlives this handle (background
+    /// jobs); returns its path.
+    #[cfg(target_os = "linux")]
+    pub fn persist_cgroup(&mut self) -> Option<std::path::PathBuf> {
+        self.cgroup.take().map(Cgroup::persist)
+    }
+}
 
 impl ProcessHandle for UnixChild {
     fn id(&self) -> u32 {
-        self.0.id()
+        self.child.id()
     }
 
     fn kill(&mut self) -> io::Result<()> {
-        self.0.kill()
+        self.child.kill()
     }
 
     fn wait(&mut self) -> io::Result<ExitStatus> {
-        self.0.wait()
+        self.child.wait()
     }
 
     fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
-        self.0.try_wait()
+        self.child.try_wait()
     }
 
     fn stdout(&mut self) -> Option<&mut ChildStdout> {
-        self.0.stdout.as_mut()
+        self.child.stdout.as_mut()
     }
 
     fn stderr(&mut self) -> Option<&mut ChildStderr> {
-        self.0.stderr.as_mut()
+        self.child.stderr.as_mut()
     }
 
     fn stdin(&mut self) -> Option<&mut ChildStdin> {
-        self.0.stdin.as_mut()
+        self.child.stdin.as_mut()
+    }
+
+    fn resource_usage(&self) -> Option<ResourceUsage> {
+        #[cfg(target_os = "linux")]
+        {
+            self.cgroup.as_ref().and_then(|cgroup| cgroup.usage().ok())
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
     }
 }
@@ -0,0 +1,470 @@
+//! cgroup v2 resource enforcement.
+//!
+//! Each confined child gets its own cgroup (`devit-<pid>-<n>`) below a
+//! delegated parent, with `cpu.max`, `memory.max` and `pids.max` applied
+//! before the child is moved in. The parent is, in order:
+//! - `$DEVIT_CGROUP_PARENT` (absolute, or relative to the cgroup2 mount);
+//! - the cgroup of the current process.
+//!
+//! cgroup v2 forbids enabling controllers for children of a cgroup that
+//! still holds processes. When the parent is our own cgroup and
+//! `$DEVIT_CGROUP_SUPERVISOR=1`, the whole current process is first moved
+//! into a `devit-supervisor` leaf, which is the layout systemd expects for
+//! `Delegate=yes` units and scopes. Without the opt-in, limits are reported
+//! as unsupported rather than silently re-parenting the server.
+//!
+//! Background jobs keep their cgroup after the spawning handle is dropped
+//! ([`Cgroup::persist`]); whoever reaps the job reads the final counters and
+//! removes it with [`Cgroup::release`].
+//!
+//! The child joins its cgroup from a `pre_exec` hook (a single `write` of
+//! `0` to `cgroup.procs`), so no process ever runs outside of it.
+
+use crate::backend::ResourceUsage;
+use anyhow::{anyhow, bail, Context, Result};
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Environment variable overriding the delegated parent cgroup.
+pub const PARENT_ENV: &str = "DEVIT_CGROUP_PARENT";
+
+/// Environment variable (`1`) allowing the current process to be moved into
+/// the supervisor leaf of its own cgroup.
+pub const SUPERVISOR_ENV: &str = "DEVIT_CGROUP_SUPERVISOR";
+
+/// `cpu.max` period, in microseconds.
+const CPU_PERIOD_USEC: u64 = 100_000;
+
+const CONTROLLERS: &[&str] = &["cpu", "memory", "pids"];
+
+const SUPERVISOR_LEAF: &str = "devit-supervisor";
+
+/// Limits applied to a child cgroup. `None` leaves the kernel default (`max`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CgroupLimits {
+    /// CPU bandwidth in percent of one core (`200` = two full cores).
+    pub cpu_percent: Option<u32>,
+    pub memory_bytes: Option<u64>,
+    pub pids_max: Option<u64>,
+}
+
+impl CgroupLimits {
+    pub fn is_empty(&self) -> bool {
+        self.cpu_percent.is_none() && self.memory_bytes.is_none() && self.pids_max.is_none()
+    }
+
+    fn required_controllers(&self) -> Vec<&'static str> {
+        let mut required = Vec::new();
+        if self.cpu_percent.is_some() {
+            required.push("cpu");
+        }
+        if self.memory_bytes.is_some() {
+            required.push("memory");
+        }
+        if self.pids_max.is_some() {
+            required.push("pids");
+        }
+        required
+    }
+}
+
+/// A per-child cgroup. Removed on drop unless [`Cgroup::persist`] was called
+/// (the kernel refuses the removal while processes remain inside).
+#[derive(Debug)]
+pub struct Cgroup {
+    path: PathBuf,
+    procs: CString,
+    persist: bool,
+}
+
+impl Cgroup {
+    /// Returns whether a delegated cgroup v2 parent is usable. The probe runs
+    /// once per process and is cached; with [`SUPERVISOR_ENV`] set it may move
+    /// the current process into the supervisor leaf.
+    pub fn is_supported() -> bool {
+        delegated_parent().is_ok()
+    }
+
+    /// Creates a fresh cgroup below the delegated parent and applies `limits`.
+    pub fn create(limits: &CgroupLimits) -> Result<Self> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let parent = delegated_parent().map_err(|e| anyhow!("cgroup: {e}"))?;
+        let enabled = read_controllers(&parent.join("cgroup.subtree_control"))?;
+        for controller in limits.required_controllers() {
+            if !enabled.iter().any(|c| c == controller) {
+                bail!(
+                    "cgroup: controller '{controller}' is not delegated to {}",
+                    parent.display()
+                );
+            }
+        }
+
+        let name = format!(
+            "devit-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = parent.join(name);
+        fs::create_dir(&path)
+            .with_context(|| format!("cgroup: cannot create {}", path.display()))?;
+        let cgroup = Self {
+            procs: CString::new(path.join("cgroup.procs").as_os_str().as_bytes())?,
+            path,
+            persist: false,
+        };
+
+        if let Some(percent) = limits.cpu_percent {
+            cgroup.write("cpu.max", &cpu_max(percent))?;
+        }
+        if let Some(bytes) = limits.memory_bytes {
+            cgroup.write("memory.max", &bytes.to_string())?;
+            // Without this the limit only pushes the job into swap.
+            if cgroup.path.join("memory.swap.max").exists() {
+                cgroup.write("memory.swap.max", "0")?;
+            }
+        }
+        if let Some(max) = limits.pids_max {
+            cgroup.write("pids.max", &max.to_string())?;
+        }
+        Ok(cgroup)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Moves `cmd`'s child into this cgroup right before `exec`. Register it
+    /// before any namespace setup: the child must still see the host cgroupfs.
+    pub fn attach(&self, cmd: &mut Command) {
+        let procs = self.procs.clone();
+        unsafe {
+            cmd.pre_exec(move || join_cgroup(&procs));
+        }
+    }
+
+    /// Reads the cgroup's event counters.
+    pub fn usage(&self) -> io::Result<ResourceUsage> {
+        read_usage(&self.path)
+    }
+
+    /// Leaves the cgroup in place (e.g. for a detached background job) and
+    /// returns its path.
+    pub fn persist(mut self) -> PathBuf {
+        self.persist = true;
+        self.path.clone()
+    }
+
+    /// Reads the counters of a cgroup left in place by [`Cgroup::persist`].
+    pub fn usage_of(path: &Path) -> io::Result<ResourceUsage> {
+        check_persisted(path)?;
+        read_usage(path)
+    }
+
+    /// Removes a cgroup left in place by [`Cgroup::persist`] once its job has
+    /// exited, and returns its final counters. Fails with `EBUSY` while
+    /// processes remain inside.
+    pub fn release(path: &Path) -> io::Result<ResourceUsage> {
+        let usage = Self::usage_of(path)?;
+        fs::remove_dir(path)?;
+        Ok(usage)
+    }
+
+    fn write(&self, file: &str, value: &str) -> Result<()> {
+        let path = self.path.join(file);
+        fs::write(&path, value).with_context(|| format!("cgroup: cannot write {}", path.display()))
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        if !self.persist {
+            let _ = fs::remove_dir(&self.path);
+        }
+    }
+}
+
+/// Paths read back from the process registry must name one of our cgroups.
+fn check_persisted(path: &Path) -> io::Result<()> {
+    let ours = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("devit-") && name != SUPERVISOR_LEAF);
+    if !ours || !path.join("cgroup.procs").exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not a devit cgroup", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+fn read_usage(path: &Path) -> io::Result<ResourceUsage> {
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("cgroup {} no longer exists", path.display()),
+        ));
+    }
+    // Files of controllers that are not enabled simply read as zero.
+    let read = |file: &str| fs::read_to_string(path.join(file)).unwrap_or_default();
+    let memory_events = read("memory.events");
+    let cpu_stat = read("cpu.stat");
+    let pids_events = read("pids.events");
+
+    Ok(ResourceUsage {
+        oom_kills: flat_keyed(&memory_events, "oom_kill"),
+        memory_max_events: flat_keyed(&memory_events, "max"),
+        memory_peak_bytes: read("memory.peak").trim().parse().ok(),
+        cpu_usage_usec: flat_keyed(&cpu_stat, "usage_usec"),
+        throttled_periods: flat_keyed(&cpu_stat, "nr_throttled"),
+        throttled_usec: flat_keyed(&cpu_stat, "throttled_usec"),
+        pids_max_events: flat_keyed(&pids_events, "max"),
+    })
+}
+
+/// Async-signal-safe: writes `0` (the calling process) to `cgroup.procs`.
+fn join_cgroup(procs: &CString) -> io::Result<()> {
+    unsafe {
+        let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+        let err = io::Error::last_os_error();
+        libc::close(fd);
+        if written < 0 {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+fn delegated_parent() -> std::result::Result<&'static Path, &'static str> {
+    static PARENT: OnceLock<std::result::Result<PathBuf, String>> = OnceLock::new();
+    PARENT
+        .get_or_init(|| prepare_parent().map_err(|e| format!("{e:#}")))
+        .as_ref()
+        .map(PathBuf::as_path)
+        .map_err(String::as_str)
+}
+
+fn prepare_parent() -> Result<PathBuf> {
+    let mountinfo = fs::read_to_string("/proc/self/mountinfo")?;
+    let mount =
+        cgroup2_mount(&mountinfo).ok_or_else(|| anyhow!("no cgroup2 filesystem mounted"))?;
+
+    let (parent, own) = match std::env::var_os(PARENT_ENV) {
+        Some(dir) => {
+            let dir = PathBuf::from(dir);
+            let parent = if dir.starts_with(&mount) {
+                dir
+            } else {
+                mount.join(dir.strip_prefix("/").unwrap_or(&dir))
+            };
+            (parent, false)
+        }
+        None => {
+            let membership = fs::read_to_string("/proc/self/cgroup")?;
+            let relative = unified_cgroup(&membership)
+                .ok_or_else(|| anyhow!("process is not in a cgroup v2 hierarchy"))?;
+            (mount.join(relative.trim_start_matches('/')), true)
+        }
+    };
+
+    let available = read_controllers(&parent.join("cgroup.controllers"))?;
+    let wanted: Vec<&str> = CONTROLLERS
+        .iter()
+        .copied()
+        .filter(|c| available.iter().any(|a| a == c))
+        .collect();
+    if wanted.is_empty() {
+        bail!(
+            "no cpu/memory/pids controller available in {}",
+            parent.display()
+        );
+    }
+
+    if let Err(err) = enable_controllers(&parent, &wanted) {
+        // EBUSY: the parent still holds processes. Park ourselves in a leaf.
+        if !own || err.raw_os_error() != Some(libc::EBUSY) {
+            return Err(err)
+                .with_context(|| format!("cannot enable controllers in {}", parent.display()));
+        }
+        if std::env::var(SUPERVISOR_ENV).as_deref() != Ok("1") {
+            bail!(
+                "{} holds processes; set {PARENT_ENV} to a delegated cgroup or \
+                 {SUPERVISOR_ENV}=1 to move devit into {SUPERVISOR_LEAF}",
+                parent.display()
+            );
+        }
+        let leaf = parent.join(SUPERVISOR_LEAF);
+        if !leaf.exists() {
+            fs::create_dir(&leaf).with_context(|| format!("cannot create {}", leaf.display()))?;
+        }
+        fs::write(leaf.join("cgroup.procs"), std::process::id().to_string())
+            .with_context(|| format!("cannot move devit into {}", leaf.display()))?;
+        enable_controllers(&parent, &wanted).with_context(|| {
+            format!(
+                "{} is not a delegated subtree (other processes live in it)",
+                parent.display()
+            )
+        })?;
+    }
+    Ok(parent)
+}
+
+fn enable_controllers(parent: &Path, controllers: &[&str]) -> io::Result<()> {
+    let enabled = read_controllers(&parent.join("cgroup.subtree_control")).unwrap_or_default();
+    let missing: Vec<String> = controllers
+        .iter()
+        .filter(|c| !enabled.iter().any(|e| e == *c))
+        .map(|c| format!("+{c}"))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    fs::write(parent.join("cgroup.subtree_control"), missing.join(" "))
+}
+
+fn read_controllers(path: &Path) -> Result<Vec<String>> {
+    let raw =
+        fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    Ok(raw.split_whitespace().map(str::to_string).collect())
+}
+
+/// Mount point of the cgroup2 filesystem, from `/proc/self/mountinfo`.
+fn cgroup2_mount(mountinfo: &str) -> Option<PathBuf> {
+    mountinfo.lines().find_map(|line| {
+        let (fields, rest) = line.split_once(" - ")?;
+        if rest.split_whitespace().next()? != "cgroup2" {
+            return None;
+        }
+        fields.split_whitespace().nth(4).map(PathBuf::from)
+    })
+}
+
+/// Path of the unified (`0::`) hierarchy entry in `/proc/self/cgroup`.
+fn unified_cgroup(membership: &str) -> Option<&str> {
+    membership.lines().find_map(|line| line.strip_prefix("0::"))
+}
+
+fn cpu_max(percent: u32) -> String {
+    let quota = (CPU_PERIOD_USEC * u64::from(percent.max(1)) / 100).max(1_000);
+    format!("{quota} {CPU_PERIOD_USEC}")
+}
+
+/// Value of `key` in a flat-keyed cgroup file (`key value` per line).
+fn flat_keyed(contents: &str, key: &str) -> u64 {
+    contents
+        .lines()
+        .find_map(|line| {
+            let (k, v) = line.split_once(' ')?;
+            if k == key {
+                v.trim().parse().ok()
+            } else {
+                None
+            }
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_cgroup2_mount_in_hybrid_layout() {
+        let mountinfo = "\
+30 24 0:26 / /sys/fs/cgroup ro,nosuid - tmpfs tmpfs ro,mode=755
+31 30 0:27 / /sys/fs/cgroup/unified rw,nosuid,relatime shared:10 - cgroup2 cgroup2 rw
+32 30 0:28 / /sys/fs/cgroup/memory rw,nosuid - cgroup cgroup rw,memory";
+        assert_eq!(
+            cgroup2_mount(mountinfo),
+            Some(PathBuf::from("/sys/fs/cgroup/unified"))
+        );
+        assert_eq!(cgroup2_mount("30 24 0:26 / /sys rw - sysfs sysfs rw"), None);
+    }
+
+    #[test]
+    fn parses_unified_membership_and_counters() {
+        let membership = "4:memory:/foo\n0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(
+            unified_cgroup(membership),
+            Some("/user.slice/user-1000.slice/session-2.scope")
+        );
+
+        let events = "low 0\nhigh 0\nmax 12\noom 1\noom_kill 1\noom_group_kill 0\n";
+        assert_eq!(flat_keyed(events, "oom_kill"), 1);
+        assert_eq!(flat_keyed(events, "max"), 12);
+        assert_eq!(flat_keyed(events, "missing"), 0);
+    }
+
+    #[test]
+    fn cpu_max_scales_with_percent() {
+        assert_eq!(cpu_max(50), "50000 100000");
+        assert_eq!(cpu_max(200), "200000 100000");
+        assert_eq!(cpu_max(0), "1000 100000");
+    }
+
+    #[test]
+    fn memory_limit_triggers_oom_kill() {
+        if !Cgroup::is_supported() {
+            eprintln!("skipping: no delegated cgroup v2 hierarchy");
+            return;
+        }
+        let limits = CgroupLimits {
+            memory_bytes: Some(16 * 1024 * 1024),
+            pids_max: Some(16),
+            ..Default::default()
+        };
+        let cgroup = match Cgroup::create(&limits) {
+            Ok(cgroup) => cgroup,
+            Err(err) => {
+                eprintln!("skipping: {err:#}");
+                return;
+            }
+        };
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg("x=a; while :; do x=$x$x; done");
+        cgroup.attach(&mut cmd);
+        let status = cmd.status().unwrap();
+        assert!(!status.success());
+        assert!(cgroup.usage().unwrap().oom_killed());
+    }
+
+    #[test]
+    fn persisted_cgroup_is_released_after_exit() {
+        if !Cgroup::is_supported() {
+            eprintln!("skipping: no delegated cgroup v2 hierarchy");
+            return;
+        }
+        let limits = CgroupLimits {
+            pids_max: Some(16),
+            ..Default::default()
+        };
+        let cgroup = match Cgroup::create(&limits) {
+            Ok(cgroup) => cgroup,
+            Err(err) => {
+                eprintln!("skipping: {err:#}");
+                return;
+            }
+        };
+        let mut cmd = Command::new("/bin/true");
+        cgroup.attach(&mut cmd);
+        assert!(cmd.status().unwrap().success());
+
+        let path = cgroup.persist();
+        assert!(path.exists());
+        assert!(!Cgroup::release(&path).unwrap().oom_killed());
+        assert!(!path.exists());
+        assert!(Cgroup::release(path.parent().unwrap()).is_err());
+    }
+}
@@ -4,13 +4,28 @@
 // MVP sandboxing helpers for shell execution.
 // - Safe-list of binaries
 // - Optional "no-net" policy (best-effort)
+// - Namespace isolation backend executing `SandboxPlan`s (Linux)
+// - Seccomp-BPF syscall filters (Linux)
+// - cgroup v2 cpu/memory/pids limits per spawned child (Linux)
 
 use anyhow::{anyhow, Result};
 use devit_common::{PolicyCfg, SandboxCfg};
 use std::process::{Command, Stdio};
 
 pub mod backend;
-pub use backend::{ProcessHandle, SandboxBackend};
+pub use backend::{ProcessHandle, ResourceUsage, SandboxBackend};
+
+#[cfg(target_os = "linux")]
+pub mod cgroup;
+#[cfg(target_os = "linux")]
+pub mod seccomp;
+
+#[cfg(target_os = "linux")]
+pub use backend::linux::NamespaceSandbox;
+#[cfg(target_os = "linux")]
+pub use cgroup::{Cgroup, CgroupLimits};
+#[cfg(target_os = "linux")]
+pub use seccomp::SeccompFilter;
 
 fn tokenize_commands(cmd: &str) -> Vec<String> {
     // Split on shell operators to extract leading binaries of sub-commands
@@ -0,0 +1,594 @@
+//! Seccomp-BPF syscall filters.
+//!
+//! Profiles are deny lists: every syscall is allowed except the ones named by
+//! the profile. Two profiles are built in:
+//! - `permissive`: blocks host-level administration (module loading, kexec,
+//!   reboot, swap, mounts, `ptrace`, `bpf`, …);
+//! - `strict`: `permissive` plus namespace escapes, cross-process memory
+//!   access, keyrings, clock changes and other rarely needed syscalls.
+//!   `clone` is only refused when asked for a new namespace (`CLONE_NEW*`),
+//!   and `clone3`, whose flags live in memory the filter cannot inspect,
+//!   fails with `ENOSYS` so that libc falls back to `clone`.
+//!
+//! Custom profiles come from `[sandbox.seccomp_profiles.<name>]` in
+//! `devit.core.toml` (see [`SeccompProfileSpec`]) and may extend a built-in
+//! or another custom profile.
+//!
+//! The BPF program is compiled before `fork`; [`SeccompFilter::install`] only
+//! issues two `prctl` calls so it can run from a `pre_exec` hook.
+
+use anyhow::{anyhow, bail, Result};
+use devit_common::{SeccompAction, SeccompProfileSpec};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+/// `AUDIT_ARCH_*` values from `<linux/audit.h>` (not exported by libc).
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH_NATIVE: u32 = 0xC000_003E;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH_NATIVE: u32 = 0xC000_00B7;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const AUDIT_ARCH_NATIVE: u32 = 0;
+
+/// Syscall numbers at or above this bit belong to the x32 ABI on x86_64.
+#[cfg(target_arch = "x86_64")]
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+/// Offsets inside `struct seccomp_data`.
+const DATA_NR_OFFSET: u32 = 0;
+const DATA_ARCH_OFFSET: u32 = 4;
+/// Low 32 bits of the first argument (little-endian targets only).
+const DATA_ARG0_LOW_OFFSET: u32 = 16;
+
+/// `clone` flags creating a namespace. `CLONE_NEWTIME` is left out: for
+/// `clone` that bit belongs to the exit signal.
+const CLONE_NAMESPACE_FLAGS: u32 = (libc::CLONE_NEWNS
+    | libc::CLONE_NEWCGROUP
+    | libc::CLONE_NEWUTS
+    | libc::CLONE_NEWIPC
+    | libc::CLONE_NEWUSER
+    | libc::CLONE_NEWPID
+    | libc::CLONE_NEWNET) as u32;
+
+const PERMISSIVE_DENY: &[&str] = &[
+    "acct",
+    "bpf",
+    "delete_module",
+    "finit_module",
+    "fsconfig",
+    "fsmount",
+    "fsopen",
+    "fspick",
+    "init_module",
+    "kexec_file_load",
+    "kexec_load",
+    "mount",
+    "mount_setattr",
+    "move_mount",
+    "open_tree",
+    "pivot_root",
+    "ptrace",
+    "reboot",
+    "swapoff",
+    "swapon",
+    "umount2",
+    #[cfg(target_arch = "x86_64")]
+    "ioperm",
+    #[cfg(target_arch = "x86_64")]
+    "iopl",
+];
+
+const STRICT_EXTRA_DENY: &[&str] = &[
+    "add_key",
+    "adjtimex",
+    "chroot",
+    "clock_adjtime",
+    "clock_settime",
+    "fanotify_init",
+    "keyctl",
+    "lookup_dcookie",
+    "name_to_handle_at",
+    "open_by_handle_at",
+    "perf_event_open",
+    "personality",
+    "process_vm_readv",
+    "process_vm_writev",
+    "quotactl",
+    "request_key",
+    "setdomainname",
+    "sethostname",
+    "setns",
+    "settimeofday",
+    "syslog",
+    "unshare",
+    "userfaultfd",
+    "vhangup",
+    #[cfg(target_arch = "x86_64")]
+    "modify_ldt",
+    #[cfg(target_arch = "x86_64")]
+    "uselib",
+];
+
+/// Names of the built-in profiles.
+pub const BUILTIN_PROFILES: &[&str] = &["permissive", "strict"];
+
+/// Maximum `extends` depth, to reject cycles between custom profiles.
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+/// Compiled seccomp filter, ready to be installed in a child process.
+#[derive(Clone)]
+pub struct SeccompFilter {
+    profile: String,
+    denied: Vec<String>,
+    action: SeccompAction,
+    blocks_namespace_clone: bool,
+    program: Vec<libc::sock_filter>,
+}
+
+impl fmt::Debug for SeccompFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeccompFilter")
+            .field("profile", &self.profile)
+            .field("denied", &self.denied)
+            .field("action", &self.action)
+            .field("blocks_namespace_clone", &self.blocks_namespace_clone)
+            .field("instructions", &self.program.len())
+            .finish()
+    }
+}
+
+impl SeccompFilter {
+    /// Resolves `name` against the built-in profiles and `custom`, then
+    /// compiles the BPF program.
+    pub fn resolve(name: &str, custom: &HashMap<String, SeccompProfileSpec>) -> Result<Self> {
+        if AUDIT_ARCH_NATIVE == 0 {
+            bail!("seccomp: unsupported architecture");
+        }
+        for builtin in BUILTIN_PROFILES {
+            if custom.contains_key(*builtin) {
+                bail!("seccomp: custom profile '{builtin}' shadows a built-in profile");
+            }
+        }
+        let resolved = resolve_deny_list(name, custom, 0)?;
+        let denied: Vec<String> = resolved.denied.into_iter().collect();
+        let numbers = denied
+            .iter()
+            .map(|n| syscall_number(n).ok_or_else(|| anyhow!("seccomp: unknown syscall '{n}'")))
+            .collect::<Result<Vec<_>>>()?;
+        let program = compile(&numbers, resolved.action, resolved.blocks_namespace_clone);
+        if program.len() > libc::BPF_MAXINSNS as usize {
+            bail!("seccomp: profile '{name}' denies too many syscalls");
+        }
+        Ok(Self {
+            profile: name.to_string(),
+            denied,
+            action: resolved.action,
+            blocks_namespace_clone: resolved.blocks_namespace_clone,
+            program,
+        })
+    }
+
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    /// Sorted list of syscall names rejected by this filter.
+    pub fn denied_syscalls(&self) -> &[String] {
+        &self.denied
+    }
+
+    pub fn action(&self) -> SeccompAction {
+        self.action
+    }
+
+    /// Whether `clone` with `CLONE_NEW*` flags and `clone3` are refused.
+    pub fn blocks_namespace_clone(&self) -> bool {
+        self.blocks_namespace_clone
+    }
+
+    /// Installs the filter on the calling thread. Sets `no_new_privs` first,
+    /// as required for unprivileged callers.
+    ///
+    /// Async-signal-safe: meant to be called between `fork` and `exec`.
+    pub fn install(&self) -> io::Result<()> {
+        let prog = libc::sock_fprog {
+            len: self.program.len() as libc::c_ushort,
+            filter: self.program.as_ptr() as *mut libc::sock_filter,
+        };
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::prctl(
+                libc::PR_SET_SECCOMP,
+                libc::SECCOMP_MODE_FILTER,
+                &prog as *const libc::sock_fprog,
+            ) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Installs the filter in `cmd`'s child right before `exec`.
+    pub fn confine(&self, cmd: &mut Command) {
+        let filter = self.clone();
+        unsafe {
+            cmd.pre_exec(move || filter.install());
+        }
+    }
+}
+
+/// Profile once its `extends` chain is flattened.
+struct ResolvedProfile {
+    denied: BTreeSet<String>,
+    action: SeccompAction,
+    /// Inherited from `strict`; dropped by allowing `clone` or `clone3`
+    blocks_namespace_clone: bool,
+}
+
+fn resolve_deny_list(
+    name: &str,
+    custom: &HashMap<String, SeccompProfileSpec>,
+    depth: usize,
+) -> Result<ResolvedProfile> {
+    if depth > MAX_EXTENDS_DEPTH {
+        bail!("seccomp: profile '{name}' extends chain is too deep (cycle?)");
+    }
+    match name {
+        "permissive" => Ok(ResolvedProfile {
+            denied: PERMISSIVE_DENY.iter().map(|s| s.to_string()).collect(),
+            action: SeccompAction::default(),
+            blocks_namespace_clone: false,
+        }),
+        "strict" => Ok(ResolvedProfile {
+            denied: PERMISSIVE_DENY
+                .iter()
+                .chain(STRICT_EXTRA_DENY)
+                .map(|s| s.to_string())
+                .collect(),
+            action: SeccompAction::default(),
+            blocks_namespace_clone: true,
+        }),
+        _ => {
+            let spec = custom
+                .get(name)
+                .ok_or_else(|| anyhow!("seccomp: unknown profile '{name}'"))?;
+            let (mut denied, mut blocks_namespace_clone) = match &spec.extends {
+                Some(parent) => {
+                    let parent = resolve_deny_list(parent, custom, depth + 1)?;
+                    (parent.denied, parent.blocks_namespace_clone)
+                }
+                None => (BTreeSet::new(), false),
+            };
+            denied.extend(spec.deny.iter().cloned());
+            for allowed in &spec.allow {
+                denied.remove(allowed);
+                if allowed == "clone" || allowed == "clone3" {
+                    blocks_namespace_clone = false;
+                }
+            }
+            Ok(ResolvedProfile {
+                denied,
+                action: spec.action,
+                blocks_namespace_clone,
+            })
+        }
+    }
+}
+
+fn stmt(code: u32, k: u32) -> libc::sock_filter {
+    libc::sock_filter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    }
+}
+
+fn jump(code: u32, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter {
+        code: code as u16,
+        jt,
+        jf,
+        k,
+    }
+}
+
+fn compile(
+    numbers: &[libc::c_long],
+    action: SeccompAction,
+    blocks_namespace_clone: bool,
+) -> Vec<libc::sock_filter> {
+    let ret_denied = match action {
+        SeccompAction::Errno => {
+            libc::SECCOMP_RET_ERRNO | (libc::EPERM as u32 & libc::SECCOMP_RET_DATA)
+        }
+        SeccompAction::Kill => libc::SECCOMP_RET_KILL_PROCESS,
+        SeccompAction::Log => libc::SECCOMP_RET_LOG,
+    };
+
+    let mut program = vec![
+        // Syscall numbers are only meaningful for the native architecture.
+        stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, DATA_ARCH_OFFSET),
+        jump(
+            libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+            AUDIT_ARCH_NATIVE,
+            1,
+            0,
+        ),
+        stmt(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+        stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, DATA_NR_OFFSET),
+    ];
+    #[cfg(target_arch = "x86_64")]
+    program.extend([
+        jump(
+            libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K,
+            X32_SYSCALL_BIT,
+            0,
+            1,
+        ),
+        stmt(libc::BPF_RET | libc::BPF_K, ret_denied),
+    ]);
+    for nr in numbers {
+        program.push(jump(
+            libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+            *nr as u32,
+            0,
+            1,
+        ));
+        program.push(stmt(libc::BPF_RET | libc::BPF_K, ret_denied));
+    }
+    if blocks_namespace_clone {
+        program.extend([
+            jump(
+                libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+                libc::SYS_clone3 as u32,
+                0,
+                1,
+            ),
+            stmt(
+                libc::BPF_RET | libc::BPF_K,
+                libc::SECCOMP_RET_ERRNO | (libc::ENOSYS as u32 & libc::SECCOMP_RET_DATA),
+            ),
+            // Not `clone`: skip to the final allow
+            jump(
+                libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+                libc::SYS_clone as u32,
+                0,
+                3,
+            ),
+            stmt(
+                libc::BPF_LD | libc::BPF_W | libc::BPF_ABS,
+                DATA_ARG0_LOW_OFFSET,
+            ),
+            jump(
+                libc::BPF_JMP | libc::BPF_JSET | libc::BPF_K,
+                CLONE_NAMESPACE_FLAGS,
+                0,
+                1,
+            ),
+            stmt(libc::BPF_RET | libc::BPF_K, ret_denied),
+        ]);
+    }
+    program.push(stmt(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_ALLOW));
+    program
+}
+
+/// Maps a syscall name to its number on the native architecture.
+fn syscall_number(name: &str) -> Option<libc::c_long> {
+    let nr = match name {
+        "acct" => libc::SYS_acct,
+        "add_key" => libc::SYS_add_key,
+        "adjtimex" => libc::SYS_adjtimex,
+        "bpf" => libc::SYS_bpf,
+        "chroot" => libc::SYS_chroot,
+        "clock_adjtime" => libc::SYS_clock_adjtime,
+        "clock_settime" => libc::SYS_clock_settime,
+        "clone" => libc::SYS_clone,
+        "clone3" => libc::SYS_clone3,
+        "connect" => libc::SYS_connect,
+        "delete_module" => libc::SYS_delete_module,
+        "execve" => libc::SYS_execve,
+        "execveat" => libc::SYS_execveat,
+        "fanotify_init" => libc::SYS_fanotify_init,
+        "finit_module" => libc::SYS_finit_module,
+        "fsconfig" => libc::SYS_fsconfig,
+        "fsmount" => libc::SYS_fsmount,
+        "fsopen" => libc::SYS_fsopen,
+        "fspick" => libc::SYS_fspick,
+        "init_module" => libc::SYS_init_module,
+        "io_uring_enter" => libc::SYS_io_uring_enter,
+        "io_uring_register" => libc::SYS_io_uring_register,
+        "io_uring_setup" => libc::SYS_io_uring_setup,
+        "kexec_file_load" => libc::SYS_kexec_file_load,
+        "kexec_load" => libc::SYS_kexec_load,
+        "keyctl" => libc::SYS_keyctl,
+        "kill" => libc::SYS_kill,
+        "lookup_dcookie" => libc::SYS_lookup_dcookie,
+        "mount" => libc::SYS_mount,
+        "mount_setattr" => libc::SYS_mount_setattr,
+        "move_mount" => libc::SYS_move_mount,
+        "name_to_handle_at" => libc::SYS_name_to_handle_at,
+        "open_by_handle_at" => libc::SYS_open_by_handle_at,
+        "open_tree" => libc::SYS_open_tree,
+        "perf_event_open" => libc::SYS_perf_event_open,
+        "personality" => libc::SYS_personality,
+        "pivot_root" => libc::SYS_pivot_root,
+        "process_vm_readv" => libc::SYS_process_vm_readv,
+        "process_vm_writev" => libc::SYS_process_vm_writev,
+        "ptrace" => libc::SYS_ptrace,
+        "quotactl" => libc::SYS_quotactl,
+        "reboot" => libc::SYS_reboot,
+        "request_key" => libc::SYS_request_key,
+        "setdomainname" => libc::SYS_setdomainname,
+        "sethostname" => libc::SYS_sethostname,
+        "setns" => libc::SYS_setns,
+        "settimeofday" => libc::SYS_settimeofday,
+        "socket" => libc::SYS_socket,
+        "swapoff" => libc::SYS_swapoff,
+        "swapon" => libc::SYS_swapon,
+        "syslog" => libc::SYS_syslog,
+        "umount2" => libc::SYS_umount2,
+        "unshare" => libc::SYS_unshare,
+        "userfaultfd" => libc::SYS_userfaultfd,
+        "vhangup" => libc::SYS_vhangup,
+        #[cfg(target_arch = "x86_64")]
+        "ioperm" => libc::SYS_ioperm,
+        #[cfg(target_arch = "x86_64")]
+        "iopl" => libc::SYS_iopl,
+        #[cfg(target_arch = "x86_64")]
+        "modify_ldt" => libc::SYS_modify_ldt,
+        #[cfg(target_arch = "x86_64")]
+        "uselib" => libc::SYS_uselib,
+        _ => return None,
+    };
+    Some(nr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_under(filter: &SeccompFilter, script: &str) -> std::process::Output {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg(script);
+        filter.confine(&mut cmd);
+        cmd.output().unwrap()
+    }
+
+    #[test]
+    fn strict_extends_permissive() {
+        let none = HashMap::new();
+        let permissive = SeccompFilter::resolve("permissive", &none).unwrap();
+        let strict = SeccompFilter::resolve("strict", &none).unwrap();
+        for name in permissive.denied_syscalls() {
+            assert!(strict.denied_syscalls().contains(name), "{name}");
+        }
+        assert!(strict.denied_syscalls().contains(&"unshare".to_string()));
+        assert!(!permissive
+            .denied_syscalls()
+            .contains(&"unshare".to_string()));
+    }
+
+    #[test]
+    fn custom_profile_extends_and_allows() {
+        let mut custom = HashMap::new();
+        custom.insert(
+            "ci".to_string(),
+            SeccompProfileSpec {
+                extends: Some("strict".into()),
+                deny: vec!["io_uring_setup".into()],
+                allow: vec!["personality".into()],
+                action: SeccompAction::Kill,
+            },
+        );
+        let filter = SeccompFilter::resolve("ci", &custom).unwrap();
+        assert!(filter
+            .denied_syscalls()
+            .contains(&"io_uring_setup".to_string()));
+        assert!(!filter
+            .denied_syscalls()
+            .contains(&"personality".to_string()));
+        assert_eq!(filter.action(), SeccompAction::Kill);
+    }
+
+    #[test]
+    fn rejects_unknown_names_and_cycles() {
+        let mut custom = HashMap::new();
+        assert!(SeccompFilter::resolve("missing", &custom).is_err());
+
+        custom.insert(
+            "typo".to_string(),
+            SeccompProfileSpec {
+                deny: vec!["not_a_syscall".into()],
+                ..Default::default()
+            },
+        );
+        custom.insert(
+            "loop".to_string(),
+            SeccompProfileSpec {
+                extends: Some("loop".into()),
+                ..Default::default()
+            },
+        );
+        assert!(SeccompFilter::resolve("typo", &custom).is_err());
+        assert!(SeccompFilter::resolve("loop", &custom).is_err());
+
+        custom.clear();
+        custom.insert("strict".to_string(), SeccompProfileSpec::default());
+        assert!(SeccompFilter::resolve("permissive", &custom).is_err());
+    }
+
+    #[test]
+    fn denied_syscalls_fail_with_eperm() {
+        if !std::path::Path::new("/usr/bin/unshare").exists() {
+            eprintln!("skipping: unshare(1) not installed");
+            return;
+        }
+        let filter = SeccompFilter::resolve("strict", &HashMap::new()).unwrap();
+        let out = run_under(&filter, "/usr/bin/unshare -U /bin/true; echo $?");
+        assert!(out.status.success(), "{out:?}");
+        assert_ne!(String::from_utf8_lossy(&out.stdout).trim(), "0");
+        assert!(
+            String::from_utf8_lossy(&out.stderr).contains("Operation not permitted"),
+            "{out:?}"
+        );
+
+        let out = run_under(&filter, "echo allowed");
+        assert!(out.status.success(), "{out:?}");
+        assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "allowed");
+    }
+
+    #[test]
+    fn strict_refuses_namespace_clones_only() {
+        let filter = SeccompFilter::resolve("strict", &HashMap::new()).unwrap();
+        assert!(filter.blocks_namespace_clone());
+
+        // Plain forks keep working
+        let out = run_under(&filter, "/bin/echo forked");
+        assert!(out.status.success(), "{out:?}");
+        assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "forked");
+
+        let mut cmd = Command::new("/bin/true");
+        unsafe {
+            cmd.pre_exec(move || {
+                filter.install()?;
+                let flags = (libc::CLONE_NEWUSER | libc::SIGCHLD) as libc::c_ulong;
+                let pid = libc::syscall(libc::SYS_clone, flags, 0, 0, 0, 0);
+                if pid == 0 {
+                    libc::_exit(0);
+                }
+                if pid != -1 || io::Error::last_os_error().raw_os_error() != Some(libc::EPERM) {
+                    return Err(io::Error::other("namespace clone was allowed"));
+                }
+                let mut args = [0u64; 11];
+                let size = std::mem::size_of_val(&args);
+                if libc::syscall(libc::SYS_clone3, args.as_mut_ptr(), size) != -1
+                    || io::Error::last_os_error().raw_os_error() != Some(libc::ENOSYS)
+                {
+                    return Err(io::Error::other("clone3 was allowed"));
+                }
+                Ok(())
+            });
+        }
+        assert!(cmd.status().unwrap().success());
+
+        let mut custom = HashMap::new();
+        custom.insert(
+            "nested".to_string(),
+            SeccompProfileSpec {
+                extends: Some("strict".into()),
+                allow: vec!["clone".into()],
+                ..Default::default()
+            },
+        );
+        assert!(!SeccompFilter::resolve("nested", &custom)
+            .unwrap()
+            .blocks_namespace_clone());
+    }
+}
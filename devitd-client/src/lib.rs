@@ -509,6 +509,11 @@ impl DevitClient {
         msg.msg_type == "NOTIFY"
     }
 
+    /// Check if a message asks a worker to stop a task
+    pub fn is_cancel(msg: &Msg) -> bool {
+        msg.msg_type == "CANCEL"
+    }
+
     /// Extract task from delegate message
     pub fn extract_task(msg: &Msg) -> Option<&serde_json::Value> {
         msg.payload.get("task")
@@ -523,6 +528,15 @@ impl DevitClient {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelResponse {
+    pub task_id: String,
+    /// How the worker was stopped: `killed`, `withdrawn`, `worker_notified`
+    /// or `approval_withdrawn`
+    pub delivery: String,
+    pub worker: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScreenshotResponse {
     pub path: String,
@@ -585,6 +599,53 @@ impl DevitClient {
             None => Err(anyhow!("daemon did not return a response")),
         }
     }
+
+    /// Cancel a delegated task: the daemon kills or withdraws the worker,
+    /// closes the lease and marks the task cancelled.
+    pub async fn cancel(&self, task_id: &str, reason: Option<&str>) -> Result<CancelResponse> {
+        let mut payload = serde_json::Map::new();
+        payload.insert("task_id".into(), serde_json::json!(task_id));
+        if let Some(reason) = reason {
+            payload.insert("reason".into(), serde_json::json!(reason));
+        }
+        let msg = new_msg(
+            "CANCEL",
+            &self.ident,
+            "orchestrator",
+            &serde_json::Value::Object(payload),
+            &self.secret,
+        );
+        match self.send_message(msg).await? {
+            Some(response) => match response.msg_type.as_str() {
+                "ACK" => {
+                    let field = |name: &str| {
+                        response
+                            .payload
+                            .get(name)
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string()
+                    };
+                    debug!("Cancelled task {}", task_id);
+                    Ok(CancelResponse {
+                        task_id: task_id.to_string(),
+                        delivery: field("delivery"),
+                        worker: field("worker"),
+                    })
+                }
+                "ERR" => {
+                    let message = response
+                        .payload
+                        .get("message")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("daemon rejected cancel request");
+                    Err(anyhow!(message.to_string()))
+                }
+                other => Err(anyhow!(format!("unexpected daemon response: {other}"))),
+            },
+            None => Err(anyhow!("daemon did not return a response")),
+        }
+    }
 }
 
 // HMAC utilities
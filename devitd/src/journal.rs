@@ -1,17 +1,21 @@
 //! HMAC-chained journal for DevIt orchestration
 //!
 //! Provides append-only, verifiable audit trail for all orchestration events.
-//! Each entry is HMAC-signed and chained to prevent tampering.
+//! Each entry is HMAC-signed and chained to prevent tampering. On open, the
+//! chain resumes from the last entry of an existing file, which must verify
+//! with the current key.
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use base64::{engine::general_purpose, Engine as _};
 use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
+use std::fmt;
 use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -39,17 +43,58 @@ pub struct Journal {
 }
 
 impl Journal {
-    /// Open or create journal file
+    /// Open or create journal file, resuming the chain from its last entry.
+    ///
+    /// A last entry without its newline (a write torn by a crash) is cut off
+    /// with a warning and the chain resumes from the entry before it. Fails,
+    /// leaving the file as is, if a complete entry does not parse or the
+    /// tail does not verify with `key` (e.g. the secret changed): appending
+    /// would break the chain.
     pub fn open(path: &str, key: &[u8]) -> Result<Self> {
-        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+
+        let (seq, last_hash) = loop {
+            let Some(tail) = read_last_line(&mut file)? else {
+                break (0, String::new());
+            };
+            if !tail.terminated {
+                warn!(
+                    "journal {}: dropping torn last entry at byte {} (no newline)",
+                    path, tail.offset
+                );
+                file.set_len(tail.offset)?;
+                file.sync_all()?;
+                continue;
+            }
+            let entry = match serde_json::from_str::<JEntry>(&tail.line) {
+                Ok(entry) => entry,
+                Err(err) => bail!(
+                    "journal {} has a corrupted last entry at byte {} ({}); run `devitd journal verify --journal {}`",
+                    path,
+                    tail.offset,
+                    err,
+                    path
+                ),
+            };
+            if entry_hash(key, &entry.prev, &entry)? != entry.hash {
+                bail!(
+                    "journal {} last entry (seq {}) does not verify with the current secret",
+                    path,
+                    entry.seq
+                );
+            }
+            break (entry.seq, entry.hash);
+        };
 
-        // TODO: Read last entry to get current sequence and hash
-        // For now, start fresh
         Ok(Self {
             file: Mutex::new(file),
             key: key.to_vec(),
-            seq: Mutex::new(0),
-            last_hash: Mutex::new(String::new()),
+            seq: Mutex::new(seq),
+            last_hash: Mutex::new(last_hash),
         })
     }
 
@@ -70,22 +115,8 @@ impl Journal {
 
         let prev_hash = self.last_hash.lock().unwrap().clone();
 
-        // Create canonical string for HMAC
-        let canonical = format!(
-            "{}|{}|{}|{}|{}|{}",
-            current_seq, ts, event, msg_id, from, to
-        );
-
-        // Calculate HMAC(key, prev_hash || canonical || meta)
-        let mut mac = HmacSha256::new_from_slice(&self.key)?;
-        mac.update(prev_hash.as_bytes());
-        mac.update(canonical.as_bytes());
-        mac.update(serde_json::to_string(&meta)?.as_bytes());
-        let hash_bytes = mac.finalize().into_bytes();
-        let current_hash = general_purpose::STANDARD.encode(hash_bytes);
-
-        // Create journal entry
-        let entry = JEntry {
+        // Create journal entry, hash = HMAC(key, prev_hash || canonical || meta)
+        let mut entry = JEntry {
             seq: current_seq,
             ts,
             ev: event.to_string(),
@@ -94,8 +125,10 @@ impl Journal {
             to: to.to_string(),
             meta,
             prev: prev_hash,
-            hash: current_hash.clone(),
+            hash: String::new(),
         };
+        let current_hash = entry_hash(&self.key, &entry.prev, &entry)?;
+        entry.hash = current_hash.clone();
 
         // Write to file
         let line = serde_json::to_string(&entry)? + "\n";
@@ -124,14 +157,47 @@ impl Journal {
     }
 }
 
+/// First broken link found while walking a journal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// 1-based line number in the file
+    pub line: usize,
+    /// Sequence number of the offending entry, when it could be parsed
+    pub seq: Option<u64>,
+    pub reason: String,
+}
+
+impl fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.seq {
+            Some(seq) => write!(f, "line {} (seq {}): {}", self.line, seq, self.reason),
+            None => write!(f, "line {}: {}", self.line, self.reason),
+        }
+    }
+}
+
+/// Result of a full journal walk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Entries verified before the first broken link (or in total)
+    pub verified: u64,
+    pub last_seq: u64,
+    pub last_hash: String,
+    pub broken: Option<BrokenLink>,
+}
+
+impl VerifyReport {
+    pub fn is_valid(&self) -> bool {
+        self.broken.is_none()
+    }
+}
+
 /// Journal verification utility
-#[allow(dead_code)]
 pub struct JournalVerifier {
     key: Vec<u8>,
 }
 
 impl JournalVerifier {
-    #[allow(dead_code)]
     pub fn new(key: &[u8]) -> Self {
         Self { key: key.to_vec() }
     }
@@ -139,53 +205,145 @@ impl JournalVerifier {
     /// Verify journal integrity
     #[allow(dead_code)]
     pub fn verify_file(&self, path: &str) -> Result<bool> {
-        use std::io::{BufRead, BufReader};
+        Ok(self.verify(path)?.is_valid())
+    }
 
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
+    /// Walk the whole journal and report the first broken link. Entries are
+    /// streamed one line at a time.
+    pub fn verify(&self, path: &str) -> Result<VerifyReport> {
+        let mut reader = BufReader::new(File::open(path)?);
 
-        let mut prev_hash = String::new();
-        let mut expected_seq = 1u64;
+        let mut report = VerifyReport {
+            verified: 0,
+            last_seq: 0,
+            last_hash: String::new(),
+            broken: None,
+        };
+        let mut raw = Vec::new();
+        let mut line_no = 0;
 
-        for line in reader.lines() {
-            let line = line?;
+        loop {
+            raw.clear();
+            if reader.read_until(b'\n', &mut raw)? == 0 {
+                break;
+            }
+            line_no += 1;
+            let terminated = raw.ends_with(b"\n");
+            let line = String::from_utf8_lossy(&raw);
             if line.trim().is_empty() {
                 continue;
             }
+            let broken = |seq: Option<u64>, reason: String| BrokenLink {
+                line: line_no,
+                seq,
+                reason,
+            };
+
+            if !terminated {
+                report.broken = Some(broken(None, "truncated entry (no newline)".to_string()));
+                break;
+            }
 
-            let entry: JEntry = serde_json::from_str(&line)?;
+            let entry: JEntry = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                Err(err) => {
+                    report.broken = Some(broken(None, format!("unparsable entry: {}", err)));
+                    break;
+                }
+            };
 
-            // Check sequence
+            let expected_seq = report.last_seq + 1;
             if entry.seq != expected_seq {
-                return Ok(false);
+                report.broken = Some(broken(
+                    Some(entry.seq),
+                    format!("expected seq {}", expected_seq),
+                ));
+                break;
             }
-
-            // Check previous hash
-            if entry.prev != prev_hash {
-                return Ok(false);
+            if entry.prev != report.last_hash {
+                report.broken = Some(broken(
+                    Some(entry.seq),
+                    "prev does not match the previous entry hash".to_string(),
+                ));
+                break;
+            }
+            if entry_hash(&self.key, &report.last_hash, &entry)? != entry.hash {
+                report.broken = Some(broken(
+                    Some(entry.seq),
+                    "HMAC mismatch (entry altered or wrong secret)".to_string(),
+                ));
+                break;
             }
 
-            // Verify HMAC
-            let canonical = format!(
-                "{}|{}|{}|{}|{}|{}",
-                entry.seq, entry.ts, entry.ev, entry.msg_id, entry.from, entry.to
-            );
+            report.verified += 1;
+            report.last_seq = entry.seq;
+            report.last_hash = entry.hash;
+        }
 
-            let mut mac = HmacSha256::new_from_slice(&self.key)?;
-            mac.update(prev_hash.as_bytes());
-            mac.update(canonical.as_bytes());
-            mac.update(serde_json::to_string(&entry.meta)?.as_bytes());
-            let expected_hash = general_purpose::STANDARD.encode(mac.finalize().into_bytes());
+        Ok(report)
+    }
+}
 
-            if entry.hash != expected_hash {
-                return Ok(false);
-            }
+/// HMAC(key, prev_hash || "seq|ts|ev|msg_id|from|to" || meta), base64-encoded
+fn entry_hash(key: &[u8], prev_hash: &str, entry: &JEntry) -> Result<String> {
+    let canonical = format!(
+        "{}|{}|{}|{}|{}|{}",
+        entry.seq, entry.ts, entry.ev, entry.msg_id, entry.from, entry.to
+    );
+    let mut mac = HmacSha256::new_from_slice(key)?;
+    mac.update(prev_hash.as_bytes());
+    mac.update(canonical.as_bytes());
+    mac.update(serde_json::to_string(&entry.meta)?.as_bytes());
+    Ok(general_purpose::STANDARD.encode(mac.finalize().into_bytes()))
+}
 
-            prev_hash = entry.hash;
-            expected_seq += 1;
-        }
+/// Last non-empty line of a journal file.
+struct LastLine {
+    line: String,
+    /// Whether the line ends with a newline
+    terminated: bool,
+    /// Byte offset where the line starts
+    offset: u64,
+}
 
-        Ok(true)
+/// Last non-empty line of `file`. Reads backwards in chunks so large
+/// journals are not loaded entirely.
+fn read_last_line(file: &mut File) -> Result<Option<LastLine>> {
+    const CHUNK: u64 = 8192;
+
+    let len = file.seek(SeekFrom::End(0))?;
+    let mut buf: Vec<u8> = Vec::new();
+    let mut pos = len;
+    loop {
+        let start = pos.saturating_sub(CHUNK);
+        let mut chunk = vec![0u8; (pos - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+        pos = start;
+
+        let trimmed_len = buf
+            .iter()
+            .rposition(|b| !b.is_ascii_whitespace())
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        if trimmed_len == 0 {
+            if pos == 0 {
+                return Ok(None);
+            }
+            continue;
+        }
+        let line_start = match buf[..trimmed_len].iter().rposition(|b| *b == b'\n') {
+            Some(nl) => nl + 1,
+            None if pos == 0 => 0,
+            None => continue,
+        };
+        return Ok(Some(LastLine {
+            line: String::from_utf8_lossy(&buf[line_start..trimmed_len]).into_owned(),
+            terminated: buf[trimmed_len..].contains(&b'\n'),
+            offset: pos + line_start as u64,
+        }));
     }
 }
 
@@ -260,4 +418,106 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_journal_resumes_chain_after_reopen() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let path = temp_file.path().to_str().unwrap();
+        let key = b"test-key-123";
+
+        let last = {
+            let journal = Journal::open(path, key)?;
+            journal.append("DELEGATE", "t1", "a", "b", serde_json::json!({}))?;
+            journal.append("NOTIFY", "t1", "b", "a", serde_json::json!({"n": 1}))?
+        };
+
+        let journal = Journal::open(path, key)?;
+        assert_eq!(journal.current_seq(), 2);
+        assert_eq!(journal.last_hash(), last);
+        journal.append("ACK", "t1", "a", "b", serde_json::json!({}))?;
+
+        let report = JournalVerifier::new(key).verify(path)?;
+        assert!(report.is_valid(), "{:?}", report.broken);
+        assert_eq!(report.verified, 3);
+
+        // A different secret cannot extend the chain
+        assert!(Journal::open(path, b"other-key").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_journal_reports_first_broken_link() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let path = temp_file.path().to_str().unwrap();
+        let key = b"test-key-123";
+        {
+            let journal = Journal::open(path, key)?;
+            for i in 0..3 {
+                journal.append("NOTIFY", "t1", "a", "b", serde_json::json!({"i": i}))?;
+            }
+        }
+
+        let contents = std::fs::read_to_string(path)?;
+        std::fs::write(path, contents.replacen("{\"i\":1}", "{\"i\":42}", 1))?;
+        let report = JournalVerifier::new(key).verify(path)?;
+        let broken = report.broken.expect("tampered entry must be reported");
+        assert_eq!((broken.line, broken.seq), (2, Some(2)));
+        assert_eq!(report.verified, 1);
+
+        // Truncated tail is reported by verify
+        std::fs::write(path, &contents[..contents.len() - 10])?;
+        let report = JournalVerifier::new(key).verify(path)?;
+        assert_eq!(report.broken.map(|b| b.line), Some(3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_journal_open_drops_torn_tail() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let path = temp_file.path().to_str().unwrap();
+        let key = b"test-key-123";
+        let second = {
+            let journal = Journal::open(path, key)?;
+            journal.append("DELEGATE", "t1", "a", "b", serde_json::json!({}))?;
+            journal.append("NOTIFY", "t1", "b", "a", serde_json::json!({}))?
+        };
+        let contents = std::fs::read_to_string(path)?;
+
+        // Crash in the middle of the third write
+        std::fs::write(path, format!("{}{{\"seq\":3,\"ts\"", contents))?;
+        let journal = Journal::open(path, key)?;
+        assert_eq!((journal.current_seq(), journal.last_hash()), (2, second));
+        assert_eq!(std::fs::read_to_string(path)?, contents);
+        journal.append("ACK", "t1", "a", "b", serde_json::json!({}))?;
+        let report = JournalVerifier::new(key).verify(path)?;
+        assert!(report.is_valid(), "{:?}", report.broken);
+        assert_eq!(report.verified, 3);
+
+        // Damage before the tail is not a torn write
+        let lines: Vec<&str> = contents.lines().collect();
+        std::fs::write(path, format!("{}\ngarbage\n{{\"seq\"", lines[0]))?;
+        assert!(Journal::open(path, key).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_journal_open_keeps_corrupted_complete_entry() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let path = temp_file.path().to_str().unwrap();
+        let key = b"test-key-123";
+        {
+            let journal = Journal::open(path, key)?;
+            journal.append("DELEGATE", "t1", "a", "b", serde_json::json!({}))?;
+            journal.append("NOTIFY", "t1", "b", "a", serde_json::json!({}))?;
+        }
+
+        // The last entry is newline-terminated, so it is not a torn write
+        let contents = std::fs::read_to_string(path)?;
+        let tampered = format!("{}{{\"seq\":3,\"ts\"\n", contents);
+        std::fs::write(path, &tampered)?;
+        let err = Journal::open(path, key).err().expect("open must fail");
+        assert!(err.to_string().contains("devitd journal verify"), "{}", err);
+        assert_eq!(std::fs::read_to_string(path)?, tampered);
+        Ok(())
+    }
 }
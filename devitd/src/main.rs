@@ -9,18 +9,20 @@ mod policy;
 mod process_registry;
 mod process_utils;
 mod reaper;
+mod replay;
+mod store;
 mod worker_executor;
 
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose, Engine as _};
 use chrono::{SecondsFormat, Utc};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use hmac::{Hmac, Mac};
 use screenshots::image::ImageFormat;
 use screenshots::Screen;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -42,7 +44,7 @@ use tokio::net::{TcpListener, TcpStream};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::process::Command as TokioCommand;
 use tokio::signal;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, oneshot, watch, Mutex, Notify};
 use tokio::task::spawn_blocking;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
@@ -59,14 +61,17 @@ use windows_sys::Win32::{
     System::Threading::{GetCurrentProcess, OpenProcessToken},
 };
 
+use devit_common::orchestration::graph;
 use devit_common::orchestration::types::{
-    CapabilityRateLimit, DelegatedTask, OrchestrationStatus, OrchestrationSummary,
-    TaskNotification as OrchestrationTaskNotification, TaskStatus, DEFAULT_TIMEOUT_SECS,
+    CapabilityRateLimit, DelegatedTask, DependencyFailurePolicy, OrchestrationStatus,
+    OrchestrationSummary, QueuedTask, TaskNotification as OrchestrationTaskNotification,
+    TaskStatus, DEFAULT_TIMEOUT_SECS,
 };
 
 use worker_executor::{
-    load_worker_settings, ScreenshotBackend, ScreenshotSettings, TaskMetadata, WorkerConfig,
-    WorkerExecutor, WorkerOutcome, WorkerSettings, WorkerStatus, WorkerTask,
+    load_worker_settings, ScreenshotBackend, ScreenshotSettings, SlotRequest, TaskMetadata,
+    WorkerAttempt, WorkerConfig, WorkerExecutor, WorkerOutcome, WorkerSettings, WorkerStatus,
+    WorkerTask,
 };
 
 type HmacSha256 = Hmac<Sha256>;
@@ -107,11 +112,88 @@ struct Cli {
     /// Shut down automatically after N seconds with no active clients (set 0 to disable)
     #[arg(long)]
     auto_shutdown_after: Option<u64>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Inspect the HMAC-chained journal
+    Journal {
+        #[command(subcommand)]
+        action: JournalCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum JournalCommand {
+    /// Walk the whole journal and report the first broken link
+    Verify {
+        /// Journal file (defaults to DEVITD_JOURNAL, else next to --socket)
+        #[arg(long)]
+        journal: Option<PathBuf>,
+    },
+}
+
+/// Journal location: `DEVITD_JOURNAL`, else next to the socket
+/// (`/tmp/devitd.sock` -> `/tmp/devitd.journal`).
+fn default_journal_path(socket: &Path) -> String {
+    std::env::var("DEVITD_JOURNAL").unwrap_or_else(|_| {
+        daemon_state_path(socket, "journal")
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+/// Task store directory: `DEVITD_TASK_STORE`, else next to the socket
+/// (`/tmp/devitd.sock` -> `/tmp/devitd.tasks`).
+fn default_task_store_path(socket: &Path) -> PathBuf {
+    std::env::var_os("DEVITD_TASK_STORE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| daemon_state_path(socket, "tasks"))
+}
+
+/// Per-daemon state path, so daemons on different sockets never share files.
+fn daemon_state_path(socket: &Path, extension: &str) -> PathBuf {
+    if cfg!(unix) {
+        socket.with_extension(extension)
+    } else {
+        PathBuf::from(format!("devitd.{}", extension))
+    }
+}
+
+/// `devitd journal verify`: print the report, exit 1 on a broken chain.
+fn run_journal_verify(secret: &str, socket: &Path, journal: Option<PathBuf>) -> Result<()> {
+    let path = journal
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|| default_journal_path(socket));
+    let report = journal::JournalVerifier::new(secret.as_bytes())
+        .verify(&path)
+        .with_context(|| format!("cannot read journal {}", path))?;
+
+    match &report.broken {
+        None => {
+            println!(
+                "{}: OK, {} entries (last seq {})",
+                path, report.verified, report.last_seq
+            );
+            Ok(())
+        }
+        Some(broken) => {
+            println!("{}: BROKEN at {}", path, broken);
+            println!(
+                "{} entries verified before the break (last good seq {})",
+                report.verified, report.last_seq
+            );
+            std::process::exit(1);
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 struct Msg {
-    pub msg_type: String, // REGISTER, HEARTBEAT, DELEGATE, NOTIFY, ACK, ERR
+    pub msg_type: String, // REGISTER, HEARTBEAT, DELEGATE, NOTIFY, CANCEL, ACK, ERR
     pub msg_id: String,
     pub from: String,
     pub to: String,
@@ -153,6 +235,20 @@ struct TaskDetails {
     working_dir: Option<String>,
     response_format: Option<String>,
     model: Option<String>,
+    depends_on: Vec<String>,
+    on_dependency_failure: DependencyFailurePolicy,
+    priority: i32,
+}
+
+/// Where a DELEGATE with `depends_on` stands
+enum DependencyGate {
+    Ready,
+    Waiting(Vec<String>),
+    Broken {
+        dependency: String,
+        status: TaskStatus,
+    },
+    Unknown(String),
 }
 
 fn parse_task_details(task: Option<&serde_json::Value>, fallback_id: &str) -> TaskDetails {
@@ -196,6 +292,32 @@ fn parse_task_details(task: Option<&serde_json::Value>, fallback_id: &str) -> Ta
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty());
 
+    let mut depends_on: Vec<String> = Vec::new();
+    for dep in task
+        .and_then(|t| t.get("depends_on"))
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|value| value.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        if !depends_on.iter().any(|known| known == dep) {
+            depends_on.push(dep.to_string());
+        }
+    }
+
+    let on_dependency_failure = DependencyFailurePolicy::parse_opt(
+        task.and_then(|t| t.get("on_dependency_failure"))
+            .and_then(|v| v.as_str()),
+    );
+
+    let priority = task
+        .and_then(|t| t.get("priority"))
+        .and_then(|v| v.as_i64())
+        .map(|p| p.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+        .unwrap_or(0);
+
     TaskDetails {
         goal,
         timeout_secs,
@@ -204,6 +326,9 @@ fn parse_task_details(task: Option<&serde_json::Value>, fallback_id: &str) -> Ta
         working_dir,
         response_format,
         model,
+        depends_on,
+        on_dependency_failure,
+        priority,
     }
 }
 
@@ -211,6 +336,8 @@ struct State {
     clients: HashMap<String, Client>,
     leases: HashMap<String, Lease>,         // task_id -> lease
     expired_leases: HashMap<String, Lease>, // task_id -> lease preserved after timeout
+    // Rehydrated leases of subprocess workers that died with the previous daemon
+    orphaned_leases: HashSet<String>,
     pending_notifications: HashMap<String, Vec<Msg>>, // client_id -> notifications
     pending_approvals: HashMap<String, PendingApproval>, // approval_id -> pending approval
     // Pending ACK markers for notification hooks waiting for confirmation
@@ -232,6 +359,32 @@ struct State {
     expected_worker_version: Option<String>,
     screenshot: ScreenshotControl,
     approver_target: String,
+    replay: replay::ReplayGuard,
+    store: store::StoreWriter,
+    // Subprocess workers currently running, one slot each
+    running_workers: HashMap<String, RunningWorker>, // task_id -> slot
+    // Retrying workers waiting to take their slot back after a backoff
+    slot_reclaims: Vec<(String, oneshot::Sender<()>)>, // task_id -> grant
+    // DELEGATEs waiting for a worker slot
+    queued_tasks: HashMap<String, store::StoredQueuedTask>, // task_id -> entry
+    queue_seq: u64,
+    max_concurrent_workers: Option<usize>,
+    // Evaluates DELEGATE requests and approval grants ([daemon.policy])
+    policy: policy::Policy,
+    // Clients allowed to cancel tasks they did not delegate and answer approvals
+    admins: HashSet<String>,
+    // DELEGATEs held until their depends_on are completed
+    blocked_tasks: HashMap<String, Msg>, // task_id -> original DELEGATE
+    ready_tasks: Vec<String>,            // blocked task_ids to re-evaluate
+    // Wakes the dispatcher of blocked and queued DELEGATEs
+    dispatch_wakeup: Arc<Notify>,
+}
+
+struct RunningWorker {
+    worker: String,
+    cancel: watch::Sender<bool>,
+    // Waiting out a retry backoff: the slot is lent to other tasks
+    backing_off: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -264,6 +417,79 @@ struct PendingApproval {
     requested_at: Instant,
 }
 
+impl Lease {
+    fn to_stored(&self) -> store::StoredLease {
+        store::StoredLease {
+            task_id: self.task_id.clone(),
+            assigned_to: self.assigned_to.clone(),
+            original_from: self.original_from.clone(),
+            deadline_unix: instant_to_unix(self.deadline),
+            return_to: self.return_to.clone(),
+            working_dir: self.working_dir.clone(),
+            response_format: self.response_format.clone(),
+            model: self.model.clone(),
+            model_resolved: self.model_resolved.clone(),
+        }
+    }
+
+    fn from_stored(stored: store::StoredLease) -> Self {
+        Self {
+            task_id: stored.task_id,
+            assigned_to: stored.assigned_to,
+            original_from: stored.original_from,
+            deadline: unix_to_instant(stored.deadline_unix),
+            return_to: stored.return_to,
+            working_dir: stored.working_dir,
+            response_format: stored.response_format,
+            model: stored.model,
+            model_resolved: stored.model_resolved,
+        }
+    }
+}
+
+impl PendingApproval {
+    fn to_stored(&self) -> store::StoredApproval {
+        store::StoredApproval {
+            task_id: self.task_id.clone(),
+            original_msg: self.original_msg.clone(),
+            tool: self.tool.clone(),
+            requested_at_unix: instant_to_unix(self.requested_at),
+        }
+    }
+
+    fn from_stored(stored: store::StoredApproval) -> Self {
+        Self {
+            task_id: stored.task_id,
+            original_msg: stored.original_msg,
+            tool: stored.tool,
+            requested_at: unix_to_instant(stored.requested_at_unix),
+        }
+    }
+}
+
+/// Wall-clock seconds of a monotonic instant (for persistence).
+fn instant_to_unix(at: Instant) -> u64 {
+    let now = Instant::now();
+    if at >= now {
+        now_ts().saturating_add((at - now).as_secs())
+    } else {
+        now_ts().saturating_sub((now - at).as_secs())
+    }
+}
+
+/// Monotonic instant of wall-clock seconds; past times stay in the past so
+/// expired deadlines are reconciled by the next cleanup.
+fn unix_to_instant(ts: u64) -> Instant {
+    let now = Instant::now();
+    let now_unix = now_ts();
+    if ts >= now_unix {
+        now + Duration::from_secs(ts - now_unix)
+    } else {
+        now.checked_sub(Duration::from_secs(now_unix - ts))
+            .unwrap_or(now)
+    }
+}
+
 struct ScreenshotControl {
     enabled: bool,
     backend: ScreenshotBackend,
@@ -515,10 +741,13 @@ impl State {
     fn new(
         secret: String,
         journal_path: &str,
+        store_path: &Path,
         workers: WorkerSettings,
         notify_hook: Option<NotifyHook>,
     ) -> Result<Self> {
         let journal = journal::Journal::open(journal_path, secret.as_bytes())?;
+        let (store, stored) = store::TaskStore::open(store_path)?;
+        let store = store::StoreWriter::spawn(store)?;
         let WorkerSettings {
             configs,
             workspace_root,
@@ -526,8 +755,21 @@ impl State {
             capabilities,
             screenshot,
             approval_target,
+            replay,
+            max_concurrent_workers,
+            policy: policy_settings,
+            admins,
         } = workers;
 
+        let policy =
+            policy::Policy::new(&policy_settings, workspace_root.clone()).unwrap_or_else(|err| {
+                error!(
+                    "Invalid policy rules, every task will need approval: {}",
+                    err
+                );
+                policy::Policy::lockdown(workspace_root.clone())
+            });
+
         let screenshot_control = ScreenshotControl::new(
             screenshot,
             capabilities.screenshot.rate_limit,
@@ -535,12 +777,78 @@ impl State {
             workspace_root.clone(),
         );
 
+        let mut tasks_active = HashMap::new();
+        let mut tasks_completed = HashMap::new();
+        for (task_id, task) in stored.tasks {
+            if task_is_terminal(&task) {
+                tasks_completed.insert(task_id, task);
+            } else {
+                tasks_active.insert(task_id, task);
+            }
+        }
+        let leases: HashMap<String, Lease> = stored
+            .leases
+            .into_iter()
+            .map(|(task_id, lease)| (task_id, Lease::from_stored(lease)))
+            .collect();
+        if !leases.is_empty()
+            || !stored.pending_approvals.is_empty()
+            || !stored.blocked_tasks.is_empty()
+            || !stored.queued_tasks.is_empty()
+        {
+            info!(
+                leases = leases.len(),
+                active_tasks = tasks_active.len(),
+                pending_approvals = stored.pending_approvals.len(),
+                blocked_tasks = stored.blocked_tasks.len(),
+                queued_tasks = stored.queued_tasks.len(),
+                "Rehydrated task store from {}",
+                store_path.display()
+            );
+        }
+        // Subprocess workers died with the previous daemon and nothing will
+        // complete their leases: fail them on the first cleanup rather than
+        // at the deadline.
+        let orphaned_leases: HashSet<String> = leases
+            .values()
+            .filter(|lease| configs.contains_key(&lease.assigned_to))
+            .map(|lease| lease.task_id.clone())
+            .collect();
+        if !orphaned_leases.is_empty() {
+            warn!(
+                tasks = orphaned_leases.len(),
+                "Subprocess workers were lost with the previous daemon; failing their tasks"
+            );
+        }
+        // Dependencies may have settled while the daemon was down, and the
+        // workers that held the slots are gone.
+        let ready_tasks: Vec<String> = stored.blocked_tasks.keys().cloned().collect();
+        let queue_seq = stored
+            .queued_tasks
+            .values()
+            .map(|queued| queued.seq + 1)
+            .max()
+            .unwrap_or(0);
+        let dispatch_wakeup = Arc::new(Notify::new());
+        if !ready_tasks.is_empty() || !stored.queued_tasks.is_empty() {
+            dispatch_wakeup.notify_one();
+        }
+
         Ok(Self {
             clients: HashMap::new(),
-            leases: HashMap::new(),
-            expired_leases: HashMap::new(),
-            pending_notifications: HashMap::new(),
-            pending_approvals: HashMap::new(),
+            leases,
+            expired_leases: stored
+                .expired_leases
+                .into_iter()
+                .map(|(task_id, lease)| (task_id, Lease::from_stored(lease)))
+                .collect(),
+            orphaned_leases,
+            pending_notifications: stored.pending_notifications,
+            pending_approvals: stored
+                .pending_approvals
+                .into_iter()
+                .map(|(id, approval)| (id, PendingApproval::from_stored(approval)))
+                .collect(),
             ack_markers: HashMap::new(),
             #[cfg(unix)]
             ack_sockets: HashMap::new(),
@@ -552,8 +860,8 @@ impl State {
             journal,
             worker_configs: configs,
             workspace_root,
-            tasks_active: HashMap::new(),
-            tasks_completed: HashMap::new(),
+            tasks_active,
+            tasks_completed,
             notify_hook,
             daemon_version: DAEMON_VERSION.to_string(),
             expected_worker_version,
@@ -563,9 +871,328 @@ impl State {
             } else {
                 approval_target
             },
+            replay: replay::ReplayGuard::new(replay),
+            store,
+            running_workers: HashMap::new(),
+            slot_reclaims: Vec::new(),
+            queued_tasks: stored.queued_tasks,
+            queue_seq,
+            max_concurrent_workers,
+            policy,
+            admins: admins.into_iter().collect(),
+            blocked_tasks: stored.blocked_tasks,
+            ready_tasks,
+            dispatch_wakeup,
+        })
+    }
+
+    /// Persist one mutation in the background; the in-memory state stays
+    /// authoritative if the store cannot be written.
+    fn persist(&mut self, record: store::Record) {
+        self.store.record(record);
+    }
+
+    fn persist_notifications(&mut self, client_id: &str) {
+        let msgs = self
+            .pending_notifications
+            .get(client_id)
+            .cloned()
+            .unwrap_or_default();
+        self.persist(store::Record::SetNotifications {
+            client: client_id.to_string(),
+            msgs,
+        });
+    }
+
+    fn insert_lease(&mut self, lease: Lease) {
+        self.persist(store::Record::PutLease {
+            lease: lease.to_stored(),
+        });
+        self.leases.insert(lease.task_id.clone(), lease);
+    }
+
+    /// Remove the lease of `task_id`, live or already expired.
+    fn take_lease(&mut self, task_id: &str) -> Option<Lease> {
+        self.orphaned_leases.remove(task_id);
+        if let Some(lease) = self.leases.remove(task_id) {
+            self.persist(store::Record::DelLease {
+                task_id: task_id.to_string(),
+            });
+            return Some(lease);
+        }
+        let lease = self.expired_leases.remove(task_id)?;
+        self.persist(store::Record::DelExpiredLease {
+            task_id: task_id.to_string(),
+        });
+        Some(lease)
+    }
+
+    /// Remove `task_id` from the active or completed tasks.
+    fn take_task(&mut self, task_id: &str) -> Option<DelegatedTask> {
+        let task = self
+            .tasks_active
+            .remove(task_id)
+            .or_else(|| self.tasks_completed.remove(task_id))?;
+        self.persist(store::Record::DelTask {
+            task_id: task_id.to_string(),
+        });
+        Some(task)
+    }
+
+    fn insert_approval(&mut self, approval_id: String, pending: PendingApproval) {
+        self.persist(store::Record::PutApproval {
+            id: approval_id.clone(),
+            approval: pending.to_stored(),
+        });
+        self.pending_approvals.insert(approval_id, pending);
+    }
+
+    fn take_approval(&mut self, approval_id: &str) -> Option<PendingApproval> {
+        let pending = self.pending_approvals.remove(approval_id)?;
+        self.persist(store::Record::DelApproval {
+            id: approval_id.to_string(),
+        });
+        Some(pending)
+    }
+
+    /// Hold a DELEGATE until its dependencies are completed; the task is
+    /// listed as pending meanwhile.
+    fn hold_task(&mut self, msg: Msg, task: DelegatedTask) {
+        self.persist(store::Record::PutBlocked {
+            task_id: task.id.clone(),
+            msg: msg.clone(),
+        });
+        self.blocked_tasks.insert(task.id.clone(), msg);
+        self.insert_pending_task(task);
+    }
+
+    /// Insert a pending placeholder, keeping the creation date of a task
+    /// that was already held.
+    fn insert_pending_task(&mut self, mut task: DelegatedTask) {
+        if let Some(existing) = self.tasks_active.get(&task.id) {
+            task.created_at = existing.created_at;
+        }
+        self.insert_active_task(task);
+    }
+
+    fn take_blocked(&mut self, task_id: &str) -> Option<Msg> {
+        let msg = self.blocked_tasks.remove(task_id)?;
+        self.persist(store::Record::DelBlocked {
+            task_id: task_id.to_string(),
+        });
+        Some(msg)
+    }
+
+    /// Queue the blocked tasks depending on `finished_id` for the
+    /// dependency dispatcher.
+    fn release_dependents(&mut self, finished_id: &str) {
+        let mut released = false;
+        for (task_id, held) in &self.blocked_tasks {
+            let waits_on_it = parse_task_details(held.payload.get("task"), task_id)
+                .depends_on
+                .iter()
+                .any(|dep| dep == finished_id);
+            if waits_on_it && !self.ready_tasks.contains(task_id) {
+                self.ready_tasks.push(task_id.clone());
+                released = true;
+            }
+        }
+        if released {
+            self.dispatch_wakeup.notify_one();
+        }
+    }
+
+    /// `true` when `worker` may start one more subprocess under its own
+    /// `max_concurrent` and the global cap.
+    fn has_worker_slot(&self, worker: &str) -> bool {
+        let holders = self
+            .running_workers
+            .values()
+            .filter(|running| !running.backing_off);
+        if let Some(limit) = self.max_concurrent_workers {
+            if holders.clone().count() >= limit {
+                return false;
+            }
+        }
+        match self
+            .worker_configs
+            .get(worker)
+            .and_then(|cfg| cfg.max_concurrent)
+        {
+            Some(limit) => holders.filter(|running| running.worker == worker).count() < limit,
+            None => true,
+        }
+    }
+
+    /// Take a slot of `worker` for `task_id`; the receiver cancels it.
+    fn claim_worker_slot(&mut self, task_id: &str, worker: &str) -> watch::Receiver<bool> {
+        let (cancel, cancel_rx) = watch::channel(false);
+        self.running_workers.insert(
+            task_id.to_string(),
+            RunningWorker {
+                worker: worker.to_string(),
+                cancel,
+                backing_off: false,
+            },
+        );
+        cancel_rx
+    }
+
+    fn release_worker_slot(&mut self, task_id: &str) -> Option<RunningWorker> {
+        let running = self.running_workers.remove(task_id)?;
+        if !self.queued_tasks.is_empty() || !self.slot_reclaims.is_empty() {
+            self.dispatch_wakeup.notify_one();
+        }
+        Some(running)
+    }
+
+    /// Handle a slot move of the retrying worker of `task_id`.
+    fn handle_slot_request(&mut self, task_id: &str, request: SlotRequest) {
+        match request {
+            SlotRequest::Release => {
+                if let Some(running) = self.running_workers.get_mut(task_id) {
+                    running.backing_off = true;
+                    if !self.queued_tasks.is_empty() {
+                        self.dispatch_wakeup.notify_one();
+                    }
+                }
+            }
+            SlotRequest::Reclaim(granted) => {
+                self.slot_reclaims.push((task_id.to_string(), granted));
+                self.dispatch_wakeup.notify_one();
+            }
+        }
+    }
+
+    /// Give their slot back to retrying workers, before any queued task.
+    fn grant_slot_reclaims(&mut self) {
+        for (task_id, granted) in std::mem::take(&mut self.slot_reclaims) {
+            // Cancelled during its backoff: dropping `granted` tells it
+            let Some(worker) = self
+                .running_workers
+                .get(&task_id)
+                .map(|running| running.worker.clone())
+            else {
+                continue;
+            };
+            if !self.has_worker_slot(&worker) {
+                self.slot_reclaims.push((task_id, granted));
+                continue;
+            }
+            if let Some(running) = self.running_workers.get_mut(&task_id) {
+                running.backing_off = false;
+            }
+            let _ = granted.send(());
+        }
+    }
+
+    /// `true` while a retrying worker of `worker` waits for a free slot.
+    fn has_slot_reclaim(&self, worker: &str) -> bool {
+        self.slot_reclaims.iter().any(|(task_id, _)| {
+            self.running_workers
+                .get(task_id)
+                .is_some_and(|running| running.worker == worker)
         })
     }
 
+    /// Queue `msg` until a slot of `worker` frees up; returns its position.
+    fn enqueue_task(&mut self, msg: Msg, worker: &str, priority: i32) -> usize {
+        let queued = store::StoredQueuedTask {
+            task_id: msg.msg_id.clone(),
+            msg,
+            worker: worker.to_string(),
+            priority,
+            seq: self.queue_seq,
+            queued_at: Utc::now(),
+        };
+        self.queue_seq += 1;
+        let task_id = queued.task_id.clone();
+        self.persist(store::Record::PutQueued {
+            task: queued.clone(),
+        });
+        self.queued_tasks.insert(task_id.clone(), queued);
+        self.queue_order()
+            .iter()
+            .position(|queued| queued.task_id == task_id)
+            .map(|index| index + 1)
+            .unwrap_or(self.queued_tasks.len())
+    }
+
+    fn take_queued(&mut self, task_id: &str) -> Option<store::StoredQueuedTask> {
+        let queued = self.queued_tasks.remove(task_id)?;
+        self.persist(store::Record::DelQueued {
+            task_id: task_id.to_string(),
+        });
+        Some(queued)
+    }
+
+    /// Queued tasks in dispatch order: highest priority first, then FIFO.
+    fn queue_order(&self) -> Vec<&store::StoredQueuedTask> {
+        let mut order: Vec<_> = self.queued_tasks.values().collect();
+        order.sort_by_key(|queued| (std::cmp::Reverse(queued.priority), queued.seq));
+        order
+    }
+
+    /// Dequeue the first task whose worker has a free slot and claim it.
+    fn next_queued(&mut self) -> Option<(store::StoredQueuedTask, watch::Receiver<bool>)> {
+        let task_id = self
+            .queue_order()
+            .into_iter()
+            .find(|queued| self.has_worker_slot(&queued.worker))
+            .map(|queued| queued.task_id.clone())?;
+        let queued = self.take_queued(&task_id)?;
+        let cancel_rx = self.claim_worker_slot(&task_id, &queued.worker);
+        Some((queued, cancel_rx))
+    }
+
+    /// Client that delegated `task_id`, while the daemon still tracks it.
+    fn task_owner(&self, task_id: &str) -> Option<&str> {
+        if let Some(lease) = self.leases.get(task_id) {
+            return Some(&lease.original_from);
+        }
+        self.blocked_tasks
+            .get(task_id)
+            .or_else(|| self.queued_tasks.get(task_id).map(|queued| &queued.msg))
+            .or_else(|| {
+                self.pending_approvals
+                    .values()
+                    .find(|pending| pending.task_id == task_id)
+                    .map(|pending| &pending.original_msg)
+            })
+            .map(|original| original.from.as_str())
+    }
+
+    fn find_task(&self, task_id: &str) -> Option<&DelegatedTask> {
+        self.tasks_active
+            .get(task_id)
+            .or_else(|| self.tasks_completed.get(task_id))
+    }
+
+    fn dependency_gate(&self, task_id: &str, depends_on: &[String]) -> DependencyGate {
+        let mut waiting = Vec::new();
+        for dep in depends_on {
+            if dep == task_id {
+                return DependencyGate::Unknown(dep.clone());
+            }
+            match self.find_task(dep) {
+                None => return DependencyGate::Unknown(dep.clone()),
+                Some(task) if task.status == TaskStatus::Completed => {}
+                Some(task) if task_is_terminal(task) => {
+                    return DependencyGate::Broken {
+                        dependency: dep.clone(),
+                        status: task.status.clone(),
+                    }
+                }
+                Some(_) => waiting.push(dep.clone()),
+            }
+        }
+        if waiting.is_empty() {
+            DependencyGate::Ready
+        } else {
+            DependencyGate::Waiting(waiting)
+        }
+    }
+
     fn has_live_clients(&self) -> bool {
         self.clients
             .values()
@@ -590,28 +1217,41 @@ impl State {
             .entry(client_id.to_string())
             .or_insert_with(Vec::new)
             .push(msg);
+        self.persist_notifications(client_id);
     }
 
     fn get_notifications(&mut self, client_id: &str) -> Vec<Msg> {
-        self.pending_notifications
+        let notifications = self
+            .pending_notifications
             .remove(client_id)
-            .unwrap_or_default()
+            .unwrap_or_default();
+        if !notifications.is_empty() {
+            self.persist_notifications(client_id);
+        }
+        notifications
     }
 
     fn cleanup_expired(&mut self) -> Vec<HookInvocation> {
         let now = Instant::now();
         let mut hook_invocations = Vec::new();
 
-        // Remove expired leases
+        // Remove expired leases, and those of workers lost in a restart
         let expired_leases: Vec<String> = self
             .leases
             .iter()
-            .filter(|(_, lease)| now > lease.deadline)
+            .filter(|(k, lease)| now > lease.deadline || self.orphaned_leases.contains(*k))
             .map(|(k, _)| k.clone())
             .collect();
 
         for task_id in expired_leases {
+            let orphaned = self.orphaned_leases.remove(&task_id);
             if let Some(lease) = self.leases.remove(&task_id) {
+                self.persist(store::Record::DelLease {
+                    task_id: task_id.clone(),
+                });
+                self.persist(store::Record::PutExpiredLease {
+                    lease: lease.to_stored(),
+                });
                 self.expired_leases.insert(task_id.clone(), lease.clone());
                 warn!(
                     task_id = %task_id,
@@ -625,19 +1265,32 @@ impl State {
                     .unwrap_or_else(|| lease.original_from.clone());
                 let timestamp = Utc::now();
                 let timestamp_rfc3339 = timestamp.to_rfc3339_opts(SecondsFormat::Millis, true);
-                let summary = format!(
-                    "Task lease expired after {}s without completion",
-                    LEASE_TTL.as_secs()
-                );
+                let (reason, summary) = if orphaned {
+                    (
+                        "daemon_restart",
+                        format!(
+                            "Worker {} was lost when the daemon restarted",
+                            lease.assigned_to
+                        ),
+                    )
+                } else {
+                    (
+                        "lease_timeout",
+                        format!(
+                            "Task lease expired after {}s without completion",
+                            LEASE_TTL.as_secs()
+                        ),
+                    )
+                };
                 let detail_payload = serde_json::json!({
-                    "reason": "lease_timeout",
+                    "reason": reason,
                     "timeout_secs": LEASE_TTL.as_secs(),
                     "expired_at": timestamp_rfc3339,
                     "worker": lease.assigned_to,
                 });
                 let metadata = serde_json::json!({
                     "failure": {
-                        "reason": "lease_timeout",
+                        "reason": reason,
                         "timeout_secs": LEASE_TTL.as_secs(),
                         "expired_at": timestamp_rfc3339,
                         "worker": lease.assigned_to,
@@ -655,10 +1308,7 @@ impl State {
                 };
                 let hook_record = notification_record.clone();
 
-                let mut task = self.tasks_active.remove(&task_id);
-                if task.is_none() {
-                    task = self.tasks_completed.remove(&task_id);
-                }
+                let task = self.take_task(&task_id);
 
                 let updated_task = if let Some(mut existing) = task {
                     existing.status = TaskStatus::Failed;
@@ -693,6 +1343,7 @@ impl State {
                         response_format: lease.response_format.clone(),
                         model: lease.model.clone(),
                         model_resolved: lease.model_resolved.clone(),
+                        depends_on: Vec::new(),
                     })
                 };
 
@@ -753,7 +1404,7 @@ impl State {
                         "summary": summary,
                         "details": detail_payload,
                         "metadata": metadata,
-                        "reason": "lease_timeout"
+                        "reason": reason
                     }),
                 );
 
@@ -788,25 +1439,76 @@ impl State {
         for ident in dead_clients {
             warn!("Client timed out: {}", ident);
             self.clients.remove(&ident);
-            self.pending_notifications.remove(&ident);
+            if self.pending_notifications.remove(&ident).is_some() {
+                self.persist_notifications(&ident);
+            }
         }
 
         hook_invocations
     }
 
     fn insert_active_task(&mut self, task: DelegatedTask) {
+        self.persist(store::Record::PutTask {
+            task: Box::new(task.clone()),
+        });
         self.tasks_active.insert(task.id.clone(), task);
     }
 
+    /// Record a failed attempt of a subprocess worker about to be retried:
+    /// the lease is renewed and the attempt joins the task history.
+    fn record_retry(&mut self, task_id: &str, attempt: &WorkerAttempt) {
+        if let Some(mut lease) = self.leases.get(task_id).cloned() {
+            lease.deadline = Instant::now() + LEASE_TTL;
+            self.insert_lease(lease);
+        }
+
+        let summary = format!(
+            "Attempt {} failed, retrying in {}ms: {}",
+            attempt.attempt,
+            attempt.retry_in_ms.unwrap_or_default(),
+            attempt.summary
+        );
+        let attempt_value = serde_json::to_value(attempt).unwrap_or(serde_json::Value::Null);
+        let Some(mut task) = self.tasks_active.get(task_id).cloned() else {
+            return;
+        };
+        task.last_activity = attempt.time_completed;
+        task.notifications.push(OrchestrationTaskNotification {
+            received_at: attempt.time_completed,
+            status: "retrying".to_string(),
+            summary: summary.clone(),
+            details: None,
+            evidence: None,
+            auto_generated: true,
+            metadata: Some(serde_json::json!({ "retry": attempt_value })),
+        });
+        let worker = task.delegated_to.clone();
+        self.insert_active_task(task);
+
+        let _ = self.journal.append(
+            "RETRY",
+            task_id,
+            &worker,
+            "orchestrator",
+            serde_json::json!({
+                "status": "retrying",
+                "summary": summary,
+                "attempt": attempt_value,
+            }),
+        );
+    }
+
     fn finalize_task(&mut self, task: DelegatedTask) {
-        match task.status {
-            TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled => {
-                self.tasks_completed.insert(task.id.clone(), task);
-                self.prune_completed();
-            }
-            _ => {
-                self.tasks_active.insert(task.id.clone(), task);
-            }
+        self.persist(store::Record::PutTask {
+            task: Box::new(task.clone()),
+        });
+        if task_is_terminal(&task) {
+            let task_id = task.id.clone();
+            self.tasks_completed.insert(task_id.clone(), task);
+            self.release_dependents(&task_id);
+            self.prune_completed();
+        } else {
+            self.tasks_active.insert(task.id.clone(), task);
         }
     }
 
@@ -825,6 +1527,7 @@ impl State {
         let overflow = self.tasks_completed.len() - MAX_COMPLETED_TASKS;
         for (task_id, _) in tasks.into_iter().take(overflow) {
             self.tasks_completed.remove(&task_id);
+            self.persist(store::Record::DelTask { task_id });
         }
     }
 
@@ -854,6 +1557,19 @@ impl State {
             .min_by_key(|task| task.created_at)
             .map(|task| task.id.clone());
 
+        let queue = self
+            .queue_order()
+            .into_iter()
+            .enumerate()
+            .map(|(index, queued)| QueuedTask {
+                task_id: queued.task_id.clone(),
+                delegated_to: queued.worker.clone(),
+                priority: queued.priority,
+                position: index + 1,
+                queued_at: queued.queued_at,
+            })
+            .collect();
+
         OrchestrationStatus {
             active_tasks,
             completed_tasks,
@@ -863,6 +1579,7 @@ impl State {
                 total_failed,
                 oldest_active_task,
             },
+            queue,
         }
     }
 }
@@ -884,6 +1601,13 @@ async fn main() -> Result<()> {
         .or_else(|| std::env::var("DEVIT_SECRET").ok())
         .unwrap_or_else(|| DEFAULT_SECRET.to_string());
 
+    if let Some(Command::Journal {
+        action: JournalCommand::Verify { journal },
+    }) = cli.command
+    {
+        return run_journal_verify(&secret, &cli.socket, journal);
+    }
+
     if secret == DEFAULT_SECRET {
         warn!("Using default secret - change DEVIT_SECRET in production!");
     }
@@ -952,15 +1676,17 @@ async fn main() -> Result<()> {
         let _ = std::fs::remove_file(&cli.socket);
         let listener = UnixListener::bind(&cli.socket)?;
         info!("DevIt daemon listening on {}", cli.socket.display());
-        let journal_path = "/tmp/devitd.journal";
+        let journal_path = default_journal_path(&cli.socket);
         let state = Arc::new(Mutex::new(State::new(
             secret,
-            journal_path,
+            &journal_path,
+            &default_task_store_path(&cli.socket),
             worker_settings,
             notify_hook,
         )?));
 
         spawn_signal_handlers(state.clone());
+        spawn_dispatcher(state.clone());
         if let Some(duration) = auto_shutdown {
             spawn_idle_shutdown_task(state.clone(), duration);
         }
@@ -1034,16 +1760,17 @@ async fn main() -> Result<()> {
     #[cfg(not(unix))]
     {
         let addr_str = cli.socket.to_string_lossy().to_string();
-        let journal_path =
-            std::env::var("DEVITD_JOURNAL").unwrap_or_else(|_| "devitd.journal".into());
+        let journal_path = default_journal_path(&cli.socket);
         let state = Arc::new(Mutex::new(State::new(
             secret,
             &journal_path,
+            &default_task_store_path(&cli.socket),
             worker_settings,
             notify_hook,
         )?));
 
         spawn_signal_handlers(state.clone());
+        spawn_dispatcher(state.clone());
         if let Some(duration) = auto_shutdown {
             spawn_idle_shutdown_task(state.clone(), duration);
         }
@@ -1353,6 +2080,46 @@ fn spawn_idle_shutdown_task(state: Arc<Mutex<State>>, idle_after: Duration) {
     });
 }
 
+/// Re-submit held DELEGATEs once the tasks they depend on have settled, and
+/// start queued ones as worker slots free up.
+fn spawn_dispatcher(state: Arc<Mutex<State>>) {
+    tokio::spawn(async move {
+        let wakeup = state.lock().await.dispatch_wakeup.clone();
+        loop {
+            wakeup.notified().await;
+            let ready: Vec<Msg> = {
+                let mut guard = state.lock().await;
+                let task_ids = std::mem::take(&mut guard.ready_tasks);
+                task_ids
+                    .iter()
+                    .filter_map(|task_id| guard.take_blocked(task_id))
+                    .collect()
+            };
+            for msg in ready {
+                let task_id = msg.msg_id.clone();
+                if let Err(err) = handle_delegate(msg, &state).await {
+                    error!(
+                        "Failed to dispatch task {} after its dependencies: {}",
+                        task_id, err
+                    );
+                }
+            }
+
+            state.lock().await.grant_slot_reclaims();
+            loop {
+                let next = state.lock().await.next_queued();
+                let Some((queued, cancel_rx)) = next else {
+                    break;
+                };
+                let task_id = queued.task_id.clone();
+                if let Err(err) = launch_queued(queued, cancel_rx, &state).await {
+                    error!("Failed to dispatch queued task {}: {}", task_id, err);
+                }
+            }
+        }
+    });
+}
+
 fn spawn_signal_handlers(state: Arc<Mutex<State>>) {
     let ctrl_c_state = state.clone();
     tokio::spawn(async move {
@@ -1386,15 +2153,18 @@ async fn shutdown_daemon(state: Arc<Mutex<State>>) {
         return;
     }
 
-    let (clients, leases, active_tasks, approvals) = {
+    let (clients, leases, active_tasks, approvals, flushed) = {
         let guard = state.lock().await;
         (
             guard.clients.len(),
             guard.leases.len(),
             guard.tasks_active.len(),
             guard.pending_approvals.len(),
+            guard.store.flush(),
         )
     };
+    // Records still queued for the task store must reach the disk.
+    let _ = tokio::task::spawn_blocking(move || flushed.recv()).await;
 
     info!(
         connected_clients = clients,
@@ -1524,27 +2294,66 @@ fn create_restricted_named_pipe(name: &str) -> io::Result<NamedPipeServer> {
 async fn handle_message(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<Msg>> {
     debug!("Received message: {} from {}", msg.msg_type, msg.from);
 
-    // Verify HMAC
-    let hmac_check = {
-        let state_guard = state.lock().await;
-        verify_hmac_detailed(&msg, &state_guard.secret)?
-    };
-
-    if !hmac_check.valid {
-        warn!(
-            from = %msg.from,
-            to = %msg.to,
-            msg_type = %msg.msg_type,
-            msg_id = %msg.msg_id,
-            ts = msg.ts,
-            nonce = %msg.nonce,
-            provided_sig = %shorten_sig(&hmac_check.provided),
-            expected_sig = %shorten_sig(&hmac_check.expected),
-            body = %summarize_body(&hmac_check.body),
-            "Invalid HMAC from client"
-        );
-        return Ok(None);
-    }
+    // Verify HMAC, then timestamp window and nonce reuse
+    {
+        let mut state_guard = state.lock().await;
+        let hmac_check = verify_hmac_detailed(&msg, &state_guard.secret)?;
+
+        let rejection = if !hmac_check.valid {
+            warn!(
+                from = %msg.from,
+                to = %msg.to,
+                msg_type = %msg.msg_type,
+                msg_id = %msg.msg_id,
+                ts = msg.ts,
+                nonce = %msg.nonce,
+                provided_sig = %shorten_sig(&hmac_check.provided),
+                expected_sig = %shorten_sig(&hmac_check.expected),
+                body = %summarize_body(&hmac_check.body),
+                "Invalid HMAC from client"
+            );
+            Some((
+                "E_HMAC_INVALID",
+                "invalid_hmac",
+                "message signature does not match".to_string(),
+            ))
+        } else if let Err(rejection) = state_guard.replay.check(&msg.nonce, msg.ts, now_ts()) {
+            let window_secs = state_guard.replay.settings().window_secs;
+            warn!(
+                from = %msg.from,
+                msg_type = %msg.msg_type,
+                msg_id = %msg.msg_id,
+                ts = msg.ts,
+                nonce = %msg.nonce,
+                reason = rejection.reason(),
+                "Rejected message (replay protection)"
+            );
+            Some((
+                rejection.code(),
+                rejection.reason(),
+                rejection.message(window_secs),
+            ))
+        } else {
+            None
+        };
+
+        if let Some((code, reason, message)) = rejection {
+            let _ = state_guard.journal.append(
+                "POLICY",
+                &msg.msg_id,
+                &msg.from,
+                "orchestrator",
+                serde_json::json!({
+                    "decision": "reject",
+                    "reason": reason,
+                    "msg_type": &msg.msg_type,
+                    "ts": msg.ts,
+                    "nonce": &msg.nonce
+                }),
+            );
+            return Ok(Some(build_error_response(&msg, code, &message)));
+        }
+    }
 
     match msg.msg_type.as_str() {
         "REGISTER" => handle_register(msg, state).await,
@@ -1555,6 +2364,7 @@ async fn handle_message(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<Ms
         "POLL" => handle_poll(msg, state).await,
         "STATUS_REQUEST" => handle_status_request(msg, state).await,
         "SCREENSHOT" => handle_screenshot(msg, state).await,
+        "CANCEL" => handle_cancel(msg, state).await,
         _ => {
             warn!("Unknown message type: {}", msg.msg_type);
             Ok(None)
@@ -1731,41 +2541,50 @@ async fn handle_heartbeat(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<
     Ok(None)
 }
 
-async fn handle_delegate(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<Msg>> {
+async fn handle_delegate(mut msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<Msg>> {
     let task_id = msg.msg_id.clone();
     let worker = msg.to.clone();
-    let return_to = msg
-        .payload
-        .get("return_to")
-        .and_then(|v| v.as_str())
-        .map(String::from);
-
-    // Extract tool name for policy evaluation
-    let tool = msg
-        .payload
-        .get("task")
-        .and_then(|t| t.get("action"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("unknown")
-        .to_string();
+    let return_to = delegate_return_to(&msg);
+    let tool = delegate_tool(&msg);
 
     let task_payload = msg.payload.get("task");
-    let task_details = parse_task_details(task_payload, &task_id);
+    let mut task_details = parse_task_details(task_payload, &task_id);
 
     info!("Delegating task {} (tool: {}) to {}", task_id, tool, worker);
 
+    if !task_details.depends_on.is_empty() {
+        if let Some(response) = gate_on_dependencies(
+            &mut msg,
+            &task_id,
+            &worker,
+            return_to.as_deref(),
+            &task_details,
+            state,
+        )
+        .await
+        {
+            return Ok(response);
+        }
+        task_details = parse_task_details(msg.payload.get("task"), &task_id);
+    }
+
     // Evaluate policy
-    let policy_decision = policy::Policy::eval(&tool, &msg.payload);
+    let verdict = {
+        let state_guard = state.lock().await;
+        state_guard.policy.eval(&msg.from, &tool, &msg.payload)
+    };
 
-    match policy_decision {
+    match verdict.action {
         policy::PolicyAction::Deny => {
-            warn!("Task {} denied by policy", task_id);
+            warn!("Task {} denied by policy: {}", task_id, verdict.reason);
             let detail_payload = serde_json::json!({
                 "reason": "policy_denied",
                 "tool": tool,
                 "task_id": task_id,
                 "worker": worker,
-                "goal": task_details.goal
+                "goal": task_details.goal,
+                "policy_reason": verdict.reason,
+                "rule": verdict.rule
             });
             let metadata_payload = serde_json::json!({
                 "failure": {
@@ -1790,7 +2609,11 @@ async fn handle_delegate(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<M
                     serde_json::json!({
                         "decision": "deny",
                         "tool": &tool,
-                        "reason": "policy_forbidden"
+                        "reason": "policy_forbidden",
+                        "actor": &msg.from,
+                        "operation": format!("{:?}", verdict.operation),
+                        "rule": &verdict.rule,
+                        "policy_reason": &verdict.reason
                     }),
                 );
                 let target = return_to.clone().unwrap_or_else(|| msg.from.clone());
@@ -1831,121 +2654,72 @@ async fn handle_delegate(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<M
         }
         policy::PolicyAction::NeedApproval => {
             info!("Task {} requires approval", task_id);
-            return handle_approval_request(msg, &task_id, &tool, state).await;
+            return handle_approval_request(msg, &task_id, &tool, &verdict, state).await;
         }
         policy::PolicyAction::Allow => {
             info!("Task {} allowed by policy", task_id);
         }
     }
 
-    let working_dir = task_details.working_dir.clone();
-    let task_timeout = Some(task_details.timeout_secs);
-
-    let (configured_worker, workspace_root, state_secret) = {
+    let configured = {
         let state_guard = state.lock().await;
-        (
-            state_guard.worker_configs.get(&worker).cloned(),
-            state_guard.workspace_root.clone(),
-            state_guard.secret.clone(),
-        )
+        state_guard.worker_configs.contains_key(&worker)
     };
 
-    if let Some(worker_cfg) = configured_worker {
-        let worker_task = WorkerTask {
-            id: task_id.clone(),
-            goal: task_details.goal.clone(),
-            delegated_to: worker.clone(),
-            working_dir: working_dir.clone(),
-            timeout_secs: task_timeout,
-            return_to: return_to.clone(),
-            response_format: task_details.response_format.clone(),
-            time_queued: Utc::now(),
-            model: task_details.model.clone(),
-            context: task_details.context.clone(),
+    if configured {
+        let cancel_rx = {
+            let mut state_guard = state.lock().await;
+            let queue_ahead = state_guard
+                .queued_tasks
+                .values()
+                .any(|queued| queued.worker == worker)
+                || state_guard.has_slot_reclaim(&worker);
+            if queue_ahead || !state_guard.has_worker_slot(&worker) {
+                queue_task(msg, &task_id, &worker, &task_details, &mut state_guard);
+                return Ok(None);
+            }
+            state_guard.claim_worker_slot(&task_id, &worker)
         };
-
-        if let Err(err) = proceed_with_delegation(
-            msg.clone(),
+        return launch_worker(
+            msg,
             &task_id,
             &worker,
-            return_to.clone(),
+            return_to,
             &tool,
-            false,
-            task_details.clone(),
+            task_details,
+            cancel_rx,
             state,
         )
-        .await
-        {
-            error!(
-                "Failed to record subprocess delegation for task {}: {}",
-                task_id, err
-            );
-            return Ok(None);
-        }
-
-        let state_for_spawn = state.clone();
-        let workspace_clone = workspace_root.clone();
-        let secret_clone = state_secret.clone();
-        tokio::spawn(async move {
-            let executor = WorkerExecutor::new(worker_cfg, workspace_clone);
-            let outcome = match executor.execute_task(&worker_task).await {
-                Ok(outcome) => outcome,
-                Err(err) => WorkerOutcome {
-                    status: WorkerStatus::Failed,
-                    summary: format!("Worker execution error: {}", err),
-                    details: None,
-                    evidence: None,
-                    truncated: false,
-                    original_size: None,
-                    metadata: TaskMetadata::default(),
-                },
-            };
-
-            let artifacts = build_worker_artifacts(&outcome);
-
-            let mut payload = serde_json::Map::new();
-            payload.insert("task_id".into(), serde_json::json!(&worker_task.id));
-            payload.insert("status".into(), serde_json::json!(outcome.status.as_str()));
-            payload.insert("artifacts".into(), artifacts);
-            if let Some(rt) = worker_task.return_to.clone() {
-                payload.insert("return_to".into(), serde_json::json!(rt));
-            }
-
-            let mut notify_msg = Msg {
-                msg_type: "NOTIFY".to_string(),
-                msg_id: worker_task.id.clone(),
-                from: worker_task.delegated_to.clone(),
-                to: "orchestrator".to_string(),
-                ts: now_ts(),
-                nonce: Uuid::new_v4().to_string(),
-                hmac: String::new(),
-                payload: serde_json::Value::Object(payload),
-            };
-
-            if let Err(err) = sign_msg(&mut notify_msg, &secret_clone) {
-                error!(
-                    "Failed to sign worker notification for task {}: {}",
-                    worker_task.id, err
-                );
-                return;
-            }
-
-            if let Err(err) = handle_notify(notify_msg, &state_for_spawn).await {
-                error!(
-                    "Failed to record worker completion for task {}: {}",
-                    worker_task.id, err
-                );
-            }
-        });
-
-        return Ok(None);
+        .await;
     }
 
     // Check if worker is alive in polling mode
     {
-        let state_guard = state.lock().await;
+        let mut state_guard = state.lock().await;
         if !state_guard.is_client_alive(&worker) {
             warn!("Worker {} not available", worker);
+            // A task released by its dependencies would otherwise stay pending.
+            if !task_details.depends_on.is_empty()
+                && state_guard.tasks_active.contains_key(&task_id)
+            {
+                let target = return_to.clone().unwrap_or_else(|| msg.from.clone());
+                let artifacts = serde_json::json!({
+                    "summary": format!("Worker {} not available", worker),
+                    "details": {
+                        "reason": "worker_unavailable",
+                        "worker": worker,
+                        "task_id": task_id
+                    }
+                });
+                record_immediate_failure(
+                    &mut state_guard,
+                    &target,
+                    &task_id,
+                    &worker,
+                    artifacts,
+                    &task_details,
+                );
+            }
             return Ok(None);
         }
     }
@@ -1964,10 +2738,404 @@ async fn handle_delegate(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<M
     .await
 }
 
+/// Hold, reject or settle a DELEGATE carrying `depends_on`.
+///
+/// Returns `None` once every dependency is completed, after adding their
+/// results to the task context; the delegation then proceeds normally.
+async fn gate_on_dependencies(
+    msg: &mut Msg,
+    task_id: &str,
+    worker: &str,
+    return_to: Option<&str>,
+    task_details: &TaskDetails,
+    state: &Arc<Mutex<State>>,
+) -> Option<Option<Msg>> {
+    let mut state_guard = state.lock().await;
+    let target = return_to.unwrap_or(&msg.from).to_string();
+
+    match state_guard.dependency_gate(task_id, &task_details.depends_on) {
+        DependencyGate::Ready => {
+            let upstream = task_details
+                .depends_on
+                .iter()
+                .filter_map(|dep| {
+                    state_guard
+                        .find_task(dep)
+                        .map(|task| (dep.clone(), graph::upstream_entry(task)))
+                })
+                .collect();
+            if let Some(task) = msg
+                .payload
+                .get_mut("task")
+                .and_then(|value| value.as_object_mut())
+            {
+                let context = task.remove("context");
+                task.insert("context".into(), graph::attach_upstream(context, upstream));
+            }
+            None
+        }
+        DependencyGate::Waiting(waiting) => {
+            let first_hold = !state_guard.tasks_active.contains_key(task_id);
+            let placeholder = pending_task(task_id, worker, task_details);
+            state_guard.hold_task(msg.clone(), placeholder);
+            if first_hold {
+                let _ = state_guard.journal.append(
+                    "DELEGATE",
+                    task_id,
+                    &msg.from,
+                    worker,
+                    serde_json::json!({
+                        "held": true,
+                        "depends_on": task_details.depends_on,
+                        "waiting_on": waiting,
+                        "on_dependency_failure": task_details.on_dependency_failure.as_str(),
+                        "return_to": return_to,
+                        "task": msg.payload.get("task"),
+                    }),
+                );
+            }
+            info!(
+                task_id = %task_id,
+                waiting_on = ?waiting,
+                "Task held until its dependencies complete"
+            );
+            Some(None)
+        }
+        DependencyGate::Unknown(dependency) => {
+            let message = format!("unknown dependency {} for task {}", dependency, task_id);
+            // A held task whose dependency disappeared can never run.
+            if state_guard.tasks_active.contains_key(task_id) {
+                let artifacts = serde_json::json!({
+                    "summary": message.clone(),
+                    "details": {
+                        "reason": "unknown_dependency",
+                        "dependency": dependency,
+                        "task_id": task_id
+                    }
+                });
+                record_immediate_failure(
+                    &mut state_guard,
+                    &target,
+                    task_id,
+                    worker,
+                    artifacts,
+                    task_details,
+                );
+            }
+            Some(Some(build_error_response(
+                msg,
+                "E_UNKNOWN_DEPENDENCY",
+                &message,
+            )))
+        }
+        DependencyGate::Broken { dependency, status } => {
+            let policy = task_details.on_dependency_failure;
+            let outcome = policy.outcome();
+            let summary = format!(
+                "Dependency {} ended {}; task {}",
+                dependency,
+                task_status_label(&status),
+                match policy {
+                    DependencyFailurePolicy::Fail => "failed",
+                    DependencyFailurePolicy::Skip => "skipped",
+                }
+            );
+            let dependency_info = serde_json::json!({
+                "reason": "dependency_failed",
+                "dependency": dependency,
+                "dependency_status": task_status_label(&status),
+                "policy": policy.as_str(),
+            });
+            let artifacts = serde_json::json!({
+                "summary": summary.clone(),
+                "details": dependency_info.clone(),
+                "metadata": { "dependency": dependency_info.clone() }
+            });
+            record_immediate_outcome(
+                &mut state_guard,
+                &target,
+                task_id,
+                worker,
+                artifacts,
+                task_details,
+                outcome.clone(),
+            );
+            let _ = state_guard.journal.append(
+                "NOTIFY",
+                task_id,
+                "orchestrator",
+                &target,
+                serde_json::json!({
+                    "status": task_status_label(&outcome),
+                    "summary": summary,
+                    "details": dependency_info,
+                    "reason": "dependency_failed"
+                }),
+            );
+            info!(
+                task_id = %task_id,
+                dependency = %dependency,
+                "Task not dispatched: dependency did not complete"
+            );
+            Some(None)
+        }
+    }
+}
+
+/// Run a subprocess worker in the slot claimed for `task_id`.
+#[allow(clippy::too_many_arguments)]
+async fn launch_worker(
+    msg: Msg,
+    task_id: &str,
+    worker: &str,
+    return_to: Option<String>,
+    tool: &str,
+    task_details: TaskDetails,
+    cancel_rx: watch::Receiver<bool>,
+    state: &Arc<Mutex<State>>,
+) -> Result<Option<Msg>> {
+    let (configured_worker, workspace_root, secret) = {
+        let state_guard = state.lock().await;
+        (
+            state_guard.worker_configs.get(worker).cloned(),
+            state_guard.workspace_root.clone(),
+            state_guard.secret.clone(),
+        )
+    };
+    let Some(worker_cfg) = configured_worker else {
+        state.lock().await.release_worker_slot(task_id);
+        return Ok(None);
+    };
+
+    let worker_task = WorkerTask {
+        id: task_id.to_string(),
+        goal: task_details.goal.clone(),
+        delegated_to: worker.to_string(),
+        working_dir: task_details.working_dir.clone(),
+        timeout_secs: Some(task_details.timeout_secs),
+        return_to: return_to.clone(),
+        response_format: task_details.response_format.clone(),
+        time_queued: Utc::now(),
+        model: task_details.model.clone(),
+        context: task_details.context.clone(),
+    };
+
+    if let Err(err) = proceed_with_delegation(
+        msg.clone(),
+        task_id,
+        worker,
+        return_to.clone(),
+        tool,
+        false,
+        task_details.clone(),
+        state,
+    )
+    .await
+    {
+        error!(
+            "Failed to record subprocess delegation for task {}: {}",
+            task_id, err
+        );
+        state.lock().await.release_worker_slot(task_id);
+        return Ok(None);
+    }
+
+    let (attempts_tx, mut attempts_rx) = mpsc::unbounded_channel::<WorkerAttempt>();
+    let state_for_attempts = state.clone();
+    let retried_task = task_id.to_string();
+    tokio::spawn(async move {
+        while let Some(attempt) = attempts_rx.recv().await {
+            state_for_attempts
+                .lock()
+                .await
+                .record_retry(&retried_task, &attempt);
+        }
+    });
+
+    let (slot_tx, mut slot_rx) = mpsc::unbounded_channel::<SlotRequest>();
+    let state_for_slot = state.clone();
+    let slot_task = task_id.to_string();
+    tokio::spawn(async move {
+        while let Some(request) = slot_rx.recv().await {
+            state_for_slot
+                .lock()
+                .await
+                .handle_slot_request(&slot_task, request);
+        }
+    });
+
+    let state_for_spawn = state.clone();
+    tokio::spawn(async move {
+        let executor = WorkerExecutor::new(worker_cfg, workspace_root)
+            .with_cancel(cancel_rx)
+            .with_attempt_events(attempts_tx)
+            .with_slot(slot_tx);
+        let outcome = match executor.execute_task(&worker_task).await {
+            Ok(outcome) => outcome,
+            Err(err) => WorkerOutcome {
+                status: WorkerStatus::Failed,
+                summary: format!("Worker execution error: {}", err),
+                details: None,
+                evidence: None,
+                truncated: false,
+                original_size: None,
+                metadata: TaskMetadata::default(),
+            },
+        };
+
+        state_for_spawn
+            .lock()
+            .await
+            .release_worker_slot(&worker_task.id);
+        if matches!(outcome.status, WorkerStatus::Cancelled) {
+            // handle_cancel already closed the lease and notified return_to.
+            return;
+        }
+
+        let artifacts = build_worker_artifacts(&outcome);
+
+        let mut payload = serde_json::Map::new();
+        payload.insert("task_id".into(), serde_json::json!(&worker_task.id));
+        payload.insert("status".into(), serde_json::json!(outcome.status.as_str()));
+        payload.insert("artifacts".into(), artifacts);
+        if let Some(rt) = worker_task.return_to.clone() {
+            payload.insert("return_to".into(), serde_json::json!(rt));
+        }
+
+        let mut notify_msg = Msg {
+            msg_type: "NOTIFY".to_string(),
+            msg_id: worker_task.id.clone(),
+            from: worker_task.delegated_to.clone(),
+            to: "orchestrator".to_string(),
+            ts: now_ts(),
+            nonce: Uuid::new_v4().to_string(),
+            hmac: String::new(),
+            payload: serde_json::Value::Object(payload),
+        };
+
+        if let Err(err) = sign_msg(&mut notify_msg, &secret) {
+            error!(
+                "Failed to sign worker notification for task {}: {}",
+                worker_task.id, err
+            );
+            return;
+        }
+
+        if let Err(err) = handle_notify(notify_msg, &state_for_spawn).await {
+            error!(
+                "Failed to record worker completion for task {}: {}",
+                worker_task.id, err
+            );
+        }
+    });
+
+    Ok(None)
+}
+
+/// Park a DELEGATE until a slot of `worker` frees up.
+fn queue_task(
+    msg: Msg,
+    task_id: &str,
+    worker: &str,
+    task_details: &TaskDetails,
+    state: &mut State,
+) {
+    let from = msg.from.clone();
+    let return_to = delegate_return_to(&msg);
+    let task_payload = msg.payload.get("task").cloned();
+    let position = state.enqueue_task(msg, worker, task_details.priority);
+    state.insert_pending_task(pending_task(task_id, worker, task_details));
+    let _ = state.journal.append(
+        "DELEGATE",
+        task_id,
+        &from,
+        worker,
+        serde_json::json!({
+            "queued": true,
+            "priority": task_details.priority,
+            "position": position,
+            "return_to": return_to,
+            "task": task_payload,
+        }),
+    );
+    info!(
+        task_id = %task_id,
+        worker = %worker,
+        position,
+        "Task queued until a worker slot frees up"
+    );
+}
+
+/// Start a task taken off the queue by the dispatcher.
+async fn launch_queued(
+    queued: store::StoredQueuedTask,
+    cancel_rx: watch::Receiver<bool>,
+    state: &Arc<Mutex<State>>,
+) -> Result<Option<Msg>> {
+    let task_details = parse_task_details(queued.msg.payload.get("task"), &queued.task_id);
+    let return_to = delegate_return_to(&queued.msg);
+    let tool = delegate_tool(&queued.msg);
+    info!(
+        "Dispatching queued task {} to {}",
+        queued.task_id, queued.worker
+    );
+    launch_worker(
+        queued.msg,
+        &queued.task_id,
+        &queued.worker,
+        return_to,
+        &tool,
+        task_details,
+        cancel_rx,
+        state,
+    )
+    .await
+}
+
+fn delegate_return_to(msg: &Msg) -> Option<String> {
+    msg.payload
+        .get("return_to")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+}
+
+/// Tool name used for policy evaluation
+fn delegate_tool(msg: &Msg) -> String {
+    msg.payload
+        .get("task")
+        .and_then(|t| t.get("action"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Pending placeholder shown by `status` while a DELEGATE is held back.
+fn pending_task(task_id: &str, worker: &str, task_details: &TaskDetails) -> DelegatedTask {
+    let now = Utc::now();
+    DelegatedTask {
+        id: task_id.to_string(),
+        goal: task_details.goal.clone(),
+        delegated_to: worker.to_string(),
+        created_at: now,
+        timeout_secs: task_details.timeout_secs,
+        status: TaskStatus::Pending,
+        context: task_details.context.clone(),
+        watch_patterns: task_details.watch_patterns.clone(),
+        last_activity: now,
+        notifications: Vec::new(),
+        working_dir: task_details.working_dir.as_ref().map(PathBuf::from),
+        response_format: task_details.response_format.clone(),
+        model: task_details.model.clone(),
+        model_resolved: None,
+        depends_on: task_details.depends_on.clone(),
+    }
+}
+
 async fn handle_approval_request(
     msg: Msg,
     task_id: &str,
     tool: &str,
+    verdict: &policy::PolicyVerdict,
     state: &Arc<Mutex<State>>,
 ) -> Result<Option<Msg>> {
     let approval_id = Uuid::new_v4().to_string();
@@ -1983,7 +3151,7 @@ async fn handle_approval_request(
 
     // Create approval request
     let approval_request =
-        policy::create_approval_request(task_id, tool, &msg.msg_id, &msg.payload);
+        policy::create_approval_request(task_id, tool, &msg.msg_id, &msg.payload, verdict);
 
     let approval_msg = Msg {
         msg_type: "APPROVAL".to_string(),
@@ -1997,6 +3165,7 @@ async fn handle_approval_request(
     };
 
     // Store pending approval
+    let original_from = msg.from.clone();
     let pending = PendingApproval {
         task_id: task_id.to_string(),
         original_msg: msg,
@@ -2006,9 +3175,7 @@ async fn handle_approval_request(
 
     {
         let mut state_guard = state.lock().await;
-        state_guard
-            .pending_approvals
-            .insert(approval_id.clone(), pending);
+        state_guard.insert_approval(approval_id.clone(), pending);
         state_guard.add_notification(&approver, approval_msg);
 
         // Journal the approval request
@@ -2020,7 +3187,11 @@ async fn handle_approval_request(
             serde_json::json!({
                 "decision": "request_approval",
                 "tool": tool,
-                "task_id": task_id
+                "task_id": task_id,
+                "actor": &original_from,
+                "operation": format!("{:?}", verdict.operation),
+                "rule": &verdict.rule,
+                "policy_reason": &verdict.reason
             }),
         );
     }
@@ -2064,6 +3235,7 @@ async fn proceed_with_delegation(
         response_format: task_details.response_format.clone(),
         model: task_details.model.clone(),
         model_resolved: None,
+        depends_on: task_details.depends_on.clone(),
     };
 
     // Create lease
@@ -2093,7 +3265,7 @@ async fn proceed_with_delegation(
 
     {
         let mut state_guard = state.lock().await;
-        state_guard.leases.insert(task_id.to_string(), lease);
+        state_guard.insert_lease(lease);
         state_guard.insert_active_task(delegated_task);
         if notify_worker {
             let task_msg = Msg {
@@ -2272,10 +3444,11 @@ async fn handle_notify(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<Msg
     }
 
     let mut state_guard = state.lock().await;
-    let lease_opt = state_guard
-        .leases
-        .remove(&task_id)
-        .or_else(|| state_guard.expired_leases.remove(&task_id));
+    if state_guard.blocked_tasks.contains_key(&task_id) {
+        let message = format!("task {} is waiting on its dependencies", task_id);
+        return Ok(Some(build_error_response(&msg, "E_TASK_BLOCKED", &message)));
+    }
+    let lease_opt = state_guard.take_lease(&task_id);
 
     if let Some(lease) = lease_opt {
         info!("Task {} completed by {}", task_id, lease.assigned_to);
@@ -2336,10 +3509,7 @@ async fn handle_notify(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<Msg
             metadata: metadata_value.clone(),
         };
 
-        let mut task = state_guard.tasks_active.remove(&task_id);
-        if task.is_none() {
-            task = state_guard.tasks_completed.remove(&task_id);
-        }
+        let task = state_guard.take_task(&task_id);
 
         let updated_task = if let Some(mut existing) = task {
             existing.status = status_enum.clone();
@@ -2380,6 +3550,7 @@ async fn handle_notify(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<Msg
                     .clone()
                     .or_else(|| lease.model.clone()),
                 model_resolved: metadata_model_used.clone(),
+                depends_on: Vec::new(),
             })
         };
 
@@ -2473,10 +3644,7 @@ async fn handle_notify(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<Msg
             metadata: metadata_value.clone(),
         };
 
-        let mut task = state_guard.tasks_active.remove(&task_id);
-        if task.is_none() {
-            task = state_guard.tasks_completed.remove(&task_id);
-        }
+        let task = state_guard.take_task(&task_id);
 
         let updated_task = if let Some(mut existing) = task {
             existing.status = map_status(status_str);
@@ -2499,6 +3667,7 @@ async fn handle_notify(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<Msg
                 response_format: None,
                 model: None,
                 model_resolved: None,
+                depends_on: Vec::new(),
             })
         };
 
@@ -2666,6 +3835,303 @@ async fn handle_screenshot(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option
     }
 }
 
+/// CANCEL: stop a delegated task. Subprocess workers are killed (whole
+/// process group), polling workers get the queued DELEGATE withdrawn or a
+/// CANCEL notification; the lease is closed, the task marked cancelled and
+/// `return_to` notified. Only the delegating client or a `[daemon].admins`
+/// entry may cancel.
+async fn handle_cancel(msg: Msg, state: &Arc<Mutex<State>>) -> Result<Option<Msg>> {
+    let Some(task_id) = msg
+        .payload
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+    else {
+        return Ok(Some(build_error_response(
+            &msg,
+            "E_INVALID_REQUEST",
+            "CANCEL requires payload.task_id",
+        )));
+    };
+    let reason = msg
+        .payload
+        .get("reason")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .unwrap_or_else(|| format!("cancelled by {}", msg.from));
+
+    let mut state_guard = state.lock().await;
+
+    if let Some(task) = state_guard.tasks_completed.get(&task_id) {
+        let message = format!(
+            "task {} already finished ({})",
+            task_id,
+            task_status_label(&task.status)
+        );
+        return Ok(Some(build_error_response(
+            &msg,
+            "E_TASK_FINISHED",
+            &message,
+        )));
+    }
+
+    let known = state_guard.tasks_active.contains_key(&task_id);
+    let owner = state_guard.task_owner(&task_id).map(String::from);
+    if (known || owner.is_some())
+        && owner.as_deref() != Some(msg.from.as_str())
+        && !state_guard.admins.contains(&msg.from)
+    {
+        warn!(
+            task_id = %task_id,
+            by = %msg.from,
+            owner = ?owner,
+            "CANCEL refused: not the delegating client"
+        );
+        let message = format!(
+            "{} may not cancel task {}: only the client that delegated it or a daemon admin can",
+            msg.from, task_id
+        );
+        return Ok(Some(build_error_response(&msg, "E_FORBIDDEN", &message)));
+    }
+
+    let approval_id = state_guard
+        .pending_approvals
+        .iter()
+        .find(|(_, pending)| pending.task_id == task_id)
+        .map(|(id, _)| id.clone());
+    let approval = approval_id.and_then(|id| state_guard.take_approval(&id));
+    let lease = state_guard.take_lease(&task_id);
+    let blocked = state_guard.take_blocked(&task_id);
+    let queued = state_guard.take_queued(&task_id).map(|queued| queued.msg);
+    state_guard.ready_tasks.retain(|id| id != &task_id);
+    let task = state_guard.take_task(&task_id);
+
+    if lease.is_none() && task.is_none() && approval.is_none() {
+        let message = format!("unknown task {}", task_id);
+        return Ok(Some(build_error_response(
+            &msg,
+            "E_TASK_NOT_FOUND",
+            &message,
+        )));
+    }
+
+    let worker = lease
+        .as_ref()
+        .map(|l| l.assigned_to.clone())
+        .or_else(|| task.as_ref().map(|t| t.delegated_to.clone()))
+        .or_else(|| approval.as_ref().map(|a| a.original_msg.to.clone()))
+        .unwrap_or_default();
+    let return_target = lease
+        .as_ref()
+        .map(|l| {
+            l.return_to
+                .clone()
+                .unwrap_or_else(|| l.original_from.clone())
+        })
+        .or_else(|| {
+            approval
+                .as_ref()
+                .map(|a| &a.original_msg)
+                .or(blocked.as_ref())
+                .or(queued.as_ref())
+                .map(|original| {
+                    original
+                        .payload
+                        .get("return_to")
+                        .and_then(|v| v.as_str())
+                        .map(String::from)
+                        .unwrap_or_else(|| original.from.clone())
+                })
+        })
+        .unwrap_or_else(|| msg.from.clone());
+
+    // Subprocess worker: kill it. Polling worker: withdraw the DELEGATE it
+    // has not fetched yet, otherwise ask it to stop.
+    let delivery = if let Some(running) = state_guard.release_worker_slot(&task_id) {
+        let _ = running.cancel.send(true);
+        "killed"
+    } else if approval.is_some() && lease.is_none() {
+        "approval_withdrawn"
+    } else if blocked.is_some() || queued.is_some() {
+        // Still waiting on its dependencies or for a worker slot: nothing
+        // was dispatched.
+        "withdrawn"
+    } else {
+        let queued = state_guard
+            .pending_notifications
+            .get(&worker)
+            .map(|queue| {
+                queue
+                    .iter()
+                    .any(|m| m.msg_type == "DELEGATE" && m.msg_id == task_id)
+            })
+            .unwrap_or(false);
+        if queued {
+            if let Some(queue) = state_guard.pending_notifications.get_mut(&worker) {
+                queue.retain(|m| !(m.msg_type == "DELEGATE" && m.msg_id == task_id));
+                if queue.is_empty() {
+                    state_guard.pending_notifications.remove(&worker);
+                }
+            }
+            state_guard.persist_notifications(&worker);
+            "withdrawn"
+        } else {
+            let cancel_msg = Msg {
+                msg_type: "CANCEL".to_string(),
+                msg_id: Uuid::new_v4().to_string(),
+                from: "orchestrator".to_string(),
+                to: worker.clone(),
+                ts: now_ts(),
+                nonce: Uuid::new_v4().to_string(),
+                hmac: String::new(),
+                payload: serde_json::json!({ "task_id": task_id, "reason": reason }),
+            };
+            state_guard.add_notification(&worker, cancel_msg);
+            "worker_notified"
+        }
+    };
+
+    info!(
+        task_id = %task_id,
+        worker = %worker,
+        by = %msg.from,
+        delivery,
+        "Task cancelled"
+    );
+
+    let timestamp = Utc::now();
+    let timestamp_rfc3339 = timestamp.to_rfc3339_opts(SecondsFormat::Millis, true);
+    let summary = format!("Task cancelled: {}", reason);
+    let metadata = serde_json::json!({
+        "cancel": {
+            "reason": reason,
+            "requested_by": msg.from,
+            "cancelled_at": timestamp_rfc3339,
+            "delivery": delivery,
+            "worker": worker,
+        }
+    });
+    let notification_record = OrchestrationTaskNotification {
+        received_at: timestamp,
+        status: "cancelled".to_string(),
+        summary: summary.clone(),
+        details: None,
+        evidence: None,
+        auto_generated: true,
+        metadata: Some(metadata.clone()),
+    };
+
+    let cancelled_task = match task {
+        Some(mut existing) => {
+            existing.status = TaskStatus::Cancelled;
+            existing.last_activity = timestamp;
+            existing.notifications.push(notification_record);
+            existing
+        }
+        None => {
+            let details = approval
+                .as_ref()
+                .map(|a| parse_task_details(a.original_msg.payload.get("task"), &task_id))
+                .unwrap_or_else(|| parse_task_details(None, &task_id));
+            DelegatedTask {
+                id: task_id.clone(),
+                goal: details.goal,
+                delegated_to: worker.clone(),
+                created_at: timestamp,
+                timeout_secs: details.timeout_secs,
+                status: TaskStatus::Cancelled,
+                context: details.context,
+                watch_patterns: details.watch_patterns,
+                last_activity: timestamp,
+                notifications: vec![notification_record],
+                working_dir: lease
+                    .as_ref()
+                    .and_then(|l| l.working_dir.as_ref())
+                    .or(details.working_dir.as_ref())
+                    .map(PathBuf::from),
+                response_format: details.response_format,
+                model: details.model,
+                model_resolved: lease.as_ref().and_then(|l| l.model_resolved.clone()),
+                depends_on: details.depends_on,
+            }
+        }
+    };
+    state_guard.finalize_task(cancelled_task);
+
+    let mut payload = serde_json::Map::new();
+    payload.insert("task_id".into(), serde_json::json!(task_id));
+    payload.insert("status".into(), serde_json::json!("cancelled"));
+    payload.insert(
+        "artifacts".into(),
+        serde_json::json!({
+            "summary": summary,
+            "reported_at": timestamp_rfc3339,
+        }),
+    );
+    payload.insert("metadata".into(), metadata);
+    payload.insert("return_to".into(), serde_json::json!(return_target));
+    let mut notification = Msg {
+        msg_type: "NOTIFY".to_string(),
+        msg_id: Uuid::new_v4().to_string(),
+        from: worker.clone(),
+        to: return_target.clone(),
+        ts: now_ts(),
+        nonce: Uuid::new_v4().to_string(),
+        hmac: String::new(),
+        payload: serde_json::Value::Object(payload),
+    };
+    if return_target != msg.from {
+        if let Err(err) = sign_msg(&mut notification, &state_guard.secret) {
+            warn!(task_id = %task_id, "Failed to sign cancel notification: {}", err);
+        } else {
+            state_guard.add_notification(&return_target, notification);
+        }
+    }
+
+    let _ = state_guard.journal.append(
+        "CANCEL",
+        &task_id,
+        &msg.from,
+        &worker,
+        serde_json::json!({
+            "reason": reason,
+            "delivery": delivery,
+            "had_lease": lease.is_some(),
+            "return_to": return_target,
+        }),
+    );
+
+    Ok(Some(Msg {
+        msg_type: "ACK".to_string(),
+        msg_id: Uuid::new_v4().to_string(),
+        from: "orchestrator".to_string(),
+        to: msg.from.clone(),
+        ts: now_ts(),
+        nonce: Uuid::new_v4().to_string(),
+        hmac: String::new(),
+        payload: serde_json::json!({
+            "task_id": task_id,
+            "status": "cancelled",
+            "delivery": delivery,
+            "worker": worker,
+        }),
+    }))
+}
+
+fn task_status_label(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Pending => "pending",
+        TaskStatus::InProgress => "in_progress",
+        TaskStatus::Completed => "completed",
+        TaskStatus::Failed => "failed",
+        TaskStatus::Cancelled => "cancelled",
+    }
+}
+
 fn build_error_response(msg: &Msg, code: &str, message: &str) -> Msg {
     Msg {
         msg_type: "ERR".to_string(),
@@ -2690,6 +4156,28 @@ fn record_immediate_failure(
     artifacts: serde_json::Value,
     task_details: &TaskDetails,
 ) {
+    record_immediate_outcome(
+        state,
+        target,
+        task_id,
+        delegated_to,
+        artifacts,
+        task_details,
+        TaskStatus::Failed,
+    );
+}
+
+/// Close a task that never reached a worker and notify `target`.
+fn record_immediate_outcome(
+    state: &mut State,
+    target: &str,
+    task_id: &str,
+    delegated_to: &str,
+    artifacts: serde_json::Value,
+    task_details: &TaskDetails,
+    status: TaskStatus,
+) {
+    let status_label = task_status_label(&status);
     let mut payload = serde_json::Map::new();
     payload.insert(
         "task_id".into(),
@@ -2697,7 +4185,7 @@ fn record_immediate_failure(
     );
     payload.insert(
         "status".into(),
-        serde_json::Value::String("failed".to_string()),
+        serde_json::Value::String(status_label.to_string()),
     );
     payload.insert("artifacts".into(), artifacts.clone());
     payload.insert(
@@ -2731,7 +4219,7 @@ fn record_immediate_failure(
     let metadata_value = parts.metadata.clone();
     let notification_record = OrchestrationTaskNotification {
         received_at: timestamp,
-        status: "failed".to_string(),
+        status: status_label.to_string(),
         summary: parts.summary.clone(),
         details: details_value,
         evidence: None,
@@ -2739,13 +4227,18 @@ fn record_immediate_failure(
         metadata: metadata_value.clone(),
     };
 
+    // Replaces the pending placeholder of a held task, if any.
+    let created_at = state
+        .take_task(task_id)
+        .map(|task| task.created_at)
+        .unwrap_or(timestamp);
     let delegated_task = DelegatedTask {
         id: task_id.to_string(),
         goal: task_details.goal.clone(),
         delegated_to: delegated_to.to_string(),
-        created_at: timestamp,
+        created_at,
         timeout_secs: task_details.timeout_secs,
-        status: TaskStatus::Failed,
+        status,
         context: task_details.context.clone(),
         watch_patterns: task_details.watch_patterns.clone(),
         last_activity: timestamp,
@@ -2757,6 +4250,7 @@ fn record_immediate_failure(
         response_format: task_details.response_format.clone(),
         model: task_details.model.clone(),
         model_resolved: None,
+        depends_on: task_details.depends_on.clone(),
     };
 
     state.finalize_task(delegated_task);
@@ -3083,6 +4577,13 @@ fn extract_notification_parts(artifacts: serde_json::Value) -> NotificationParts
     }
 }
 
+fn task_is_terminal(task: &DelegatedTask) -> bool {
+    matches!(
+        task.status,
+        TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled
+    )
+}
+
 fn map_status(status: &str) -> TaskStatus {
     match status {
         "completed" => TaskStatus::Completed,
@@ -3106,10 +4607,27 @@ async fn handle_approval_decision(msg: Msg, state: &Arc<Mutex<State>>) -> Result
         status, approval_id
     );
 
-    // Find pending approval
+    // Only the approver the request was sent to, or a daemon admin, may answer
     let pending_approval = {
         let mut state_guard = state.lock().await;
-        state_guard.pending_approvals.remove(approval_id)
+        let approver = if state_guard.approver_target.trim().is_empty() {
+            worker_executor::DEFAULT_APPROVER_TARGET
+        } else {
+            state_guard.approver_target.as_str()
+        };
+        if msg.from != approver && !state_guard.admins.contains(&msg.from) {
+            warn!(
+                approval_id = %approval_id,
+                by = %msg.from,
+                "APPROVAL_DECISION refused: not the approver"
+            );
+            let message = format!(
+                "{} may not answer approval {}: only {} or a daemon admin can",
+                msg.from, approval_id, approver
+            );
+            return Ok(Some(build_error_response(&msg, "E_FORBIDDEN", &message)));
+        }
+        state_guard.take_approval(approval_id)
     };
 
     if let Some(pending) = pending_approval {
@@ -3118,6 +4636,27 @@ async fn handle_approval_decision(msg: Msg, state: &Arc<Mutex<State>>) -> Result
         let original_msg = pending.original_msg;
         let original_task_details = parse_task_details(original_msg.payload.get("task"), task_id);
 
+        // A grant only lifts the approval requirement: the task is evaluated
+        // again and stays refused if the policy now denies it.
+        let mut status = status;
+        let mut policy_reason = None;
+        if status == "granted" {
+            let verdict = {
+                let state_guard = state.lock().await;
+                state_guard
+                    .policy
+                    .eval(&original_msg.from, &tool, &original_msg.payload)
+            };
+            if verdict.action == policy::PolicyAction::Deny {
+                warn!(
+                    "Approval granted for task {} but denied by policy: {}",
+                    task_id, verdict.reason
+                );
+                status = "denied";
+                policy_reason = Some(verdict.reason);
+            }
+        }
+
         match status {
             "granted" => {
                 info!("Approval granted for task {}", task_id);
@@ -3163,12 +4702,18 @@ async fn handle_approval_decision(msg: Msg, state: &Arc<Mutex<State>>) -> Result
             }
             "denied" => {
                 warn!("Approval denied for task {}", task_id);
-                let note_text = msg
-                    .payload
-                    .get("note")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty());
+                let denial = if policy_reason.is_some() {
+                    "policy_denied"
+                } else {
+                    "approval_denied"
+                };
+                let note_text = policy_reason.clone().or_else(|| {
+                    msg.payload
+                        .get("note")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                });
                 let summary = note_text
                     .as_ref()
                     .map(|note| format!("Approval denied for task {}: {}", task_id, note))
@@ -3176,10 +4721,7 @@ async fn handle_approval_decision(msg: Msg, state: &Arc<Mutex<State>>) -> Result
                         format!("Approval denied for task {} (tool: {})", task_id, tool)
                     });
                 let mut detail_obj = serde_json::Map::new();
-                detail_obj.insert(
-                    "reason".into(),
-                    serde_json::Value::String("approval_denied".into()),
-                );
+                detail_obj.insert("reason".into(), serde_json::Value::String(denial.into()));
                 detail_obj.insert("tool".into(), serde_json::Value::String(tool.clone()));
                 detail_obj.insert(
                     "task_id".into(),
@@ -3195,10 +4737,7 @@ async fn handle_approval_decision(msg: Msg, state: &Arc<Mutex<State>>) -> Result
                 let detail_payload = serde_json::Value::Object(detail_obj);
 
                 let mut failure_meta = serde_json::Map::new();
-                failure_meta.insert(
-                    "reason".into(),
-                    serde_json::Value::String("approval_denied".into()),
-                );
+                failure_meta.insert("reason".into(), serde_json::Value::String(denial.into()));
                 failure_meta.insert("tool".into(), serde_json::Value::String(tool.clone()));
                 failure_meta.insert(
                     "approver".into(),
@@ -3231,7 +4770,8 @@ async fn handle_approval_decision(msg: Msg, state: &Arc<Mutex<State>>) -> Result
                             "decision": "denied",
                             "tool": &tool,
                             "task_id": task_id,
-                            "note": msg.payload.get("note")
+                            "note": msg.payload.get("note"),
+                            "policy_reason": &policy_reason
                         }),
                     );
 
@@ -1,8 +1,20 @@
 //! Approval policies for DevIt orchestration
 //!
-//! Controls which actions require approval based on declarative policies.
-//! Supports trusted, untrusted, and on_request approval levels.
-
+//! DELEGATE requests are evaluated by the shared [`PolicyEngine`]: the tool
+//! named in `task.action` gives the operation type, the client ident is the
+//! actor and the task paths are confined to the workspace root. The level
+//! granted to clients comes from `[daemon.policy]`; the rules and path
+//! overrides of the shared `[policy]` section of `devit.core.toml` apply too.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use devit_common::policy::{
+    deserialize_approval_level, deserialize_path_overrides, OperationContext, OperationType,
+    PolicyDecision, PolicyEngine, PolicyError, PolicyRuleConfig,
+};
+use devit_common::{ApprovalLevel, SandboxProfile};
+use serde::Deserialize;
 use serde_json::Value;
 
 /// Policy decision for an action
@@ -13,124 +25,194 @@ pub enum PolicyAction {
     NeedApproval, // Action requires explicit approval
 }
 
-/// Policy evaluator
-pub struct Policy;
+/// Policy settings of the daemon (`[daemon.policy]`, merged with the rules
+/// of `[policy]` by [`PolicySettings::from_config`]).
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicySettings {
+    /// Level granted to authenticated clients
+    #[serde(
+        default = "default_daemon_approval",
+        alias = "default_approval",
+        deserialize_with = "deserialize_approval_level"
+    )]
+    pub default_approval_level: ApprovalLevel,
+    #[serde(default)]
+    pub rules: Vec<PolicyRuleConfig>,
+    #[serde(default, deserialize_with = "deserialize_path_overrides")]
+    pub path_overrides: HashMap<PathBuf, ApprovalLevel>,
+}
 
-impl Policy {
-    /// Evaluate policy for a given tool and payload
-    pub fn eval(tool: &str, payload: &Value) -> PolicyAction {
-        match tool {
-            "devit_patch_apply" => eval_patch_apply(payload),
-            "devit_file_read" => eval_file_read(payload),
-            "devit_test_run" => eval_test_run(payload),
-            "devit_snapshot" => PolicyAction::Allow, // Snapshots are safe
-            "devit_journal_append" => PolicyAction::Allow, // Logging is safe
-            _ => PolicyAction::Allow,                // Default allow for unknown tools
+/// Without configuration, clients may read and write the workspace; tests,
+/// commands and agent runs (every plain delegation) need approval.
+fn default_daemon_approval() -> ApprovalLevel {
+    ApprovalLevel::Moderate
+}
+
+impl Default for PolicySettings {
+    fn default() -> Self {
+        Self {
+            default_approval_level: default_daemon_approval(),
+            rules: Vec::new(),
+            path_overrides: HashMap::new(),
         }
     }
 }
 
-/// Evaluate patch application policy
-fn eval_patch_apply(payload: &Value) -> PolicyAction {
-    // Extract path from task payload
-    let path = payload
-        .get("task")
-        .and_then(|t| t.get("path"))
-        .and_then(|p| p.as_str());
-
-    if let Some(path) = path {
-        // Forbidden paths - immediate deny
-        if path.starts_with("/etc")
-            || path.starts_with("/var")
-            || path.starts_with("/home")
-            || path.starts_with("/root")
-            || path.starts_with("/sys")
-            || path.starts_with("/proc")
-        {
-            return PolicyAction::Deny;
+impl PolicySettings {
+    /// Fallback for an invalid policy section: every task needs approval.
+    pub fn lockdown() -> Self {
+        Self {
+            default_approval_level: ApprovalLevel::Untrusted,
+            ..Self::default()
         }
+    }
 
-        // Trusted paths - allow without approval
-        if path.starts_with("/workdir/src")
-            || path.starts_with("/workdir/tests")
-            || path.starts_with("/tmp/devit")
-        {
-            return PolicyAction::Allow;
+    /// Settings from a parsed `devit.core.toml`. The level only comes from
+    /// `[daemon.policy]`: the `default_approval` of `[policy]` is the patch
+    /// pipeline's. Rules of `[policy]` come first, path overrides of
+    /// `[daemon.policy]` win.
+    pub fn from_config(config: &toml::Value) -> Result<Self, toml::de::Error> {
+        let mut settings = match config.get("daemon").and_then(|daemon| daemon.get("policy")) {
+            Some(section) => section.clone().try_into::<Self>()?,
+            None => Self::default(),
+        };
+        if let Some(section) = config.get("policy") {
+            let shared = section.clone().try_into::<Self>()?;
+            settings.rules = shared.rules.into_iter().chain(settings.rules).collect();
+            for (path, level) in shared.path_overrides {
+                settings.path_overrides.entry(path).or_insert(level);
+            }
         }
+        Ok(settings)
     }
+}
+
+/// Outcome of evaluating one DELEGATE
+#[derive(Debug, Clone)]
+pub struct PolicyVerdict {
+    pub action: PolicyAction,
+    pub operation: OperationType,
+    pub reason: String,
+    /// Custom rule that decided, if any
+    pub rule: Option<String>,
+}
 
-    // Default for patch_apply - require approval
-    PolicyAction::NeedApproval
+/// Policy evaluator
+pub struct Policy {
+    engine: PolicyEngine,
+    workspace_root: Option<PathBuf>,
 }
 
-/// Evaluate file read policy
-fn eval_file_read(payload: &Value) -> PolicyAction {
-    let path = payload
-        .get("task")
-        .and_then(|t| t.get("path"))
-        .and_then(|p| p.as_str());
-
-    if let Some(path) = path {
-        // Sensitive files - deny
-        if path.contains("password")
-            || path.contains("secret")
-            || path.contains("key")
-            || path.starts_with("/etc/shadow")
-            || path.starts_with("/root/.ssh")
-        {
-            return PolicyAction::Deny;
+impl Policy {
+    pub fn new(
+        settings: &PolicySettings,
+        workspace_root: Option<PathBuf>,
+    ) -> Result<Self, PolicyError> {
+        let mut engine = PolicyEngine::new(
+            settings.default_approval_level.clone(),
+            SandboxProfile::Strict,
+        );
+        engine.apply_rules(&settings.rules, &settings.path_overrides)?;
+        Ok(Self {
+            engine,
+            workspace_root,
+        })
+    }
+
+    /// Policy of a daemon whose `[policy]` section could not be loaded.
+    pub fn lockdown(workspace_root: Option<PathBuf>) -> Self {
+        Self {
+            engine: PolicyEngine::new(ApprovalLevel::Untrusted, SandboxProfile::Strict),
+            workspace_root,
         }
+    }
 
-        // Check file size limit
-        if let Some(size) = payload
-            .get("task")
-            .and_then(|t| t.get("max_bytes"))
-            .and_then(|s| s.as_u64())
-        {
-            if size > 1_048_576
-            // 1MB limit
-            {
-                return PolicyAction::NeedApproval;
-            }
+    /// Evaluate policy for a given client, tool and payload
+    pub fn eval(&self, actor: &str, tool: &str, payload: &Value) -> PolicyVerdict {
+        let operation = operation_for_tool(tool);
+        let mut metadata = HashMap::new();
+        metadata.insert("tool".to_string(), tool.to_string());
+        if let Some(root) = &self.workspace_root {
+            metadata.insert("sandbox_root".to_string(), root.display().to_string());
+        }
+        let context = OperationContext {
+            actor: actor.to_string(),
+            operation: operation.clone(),
+            paths: task_paths(payload),
+            data_size: payload
+                .get("task")
+                .and_then(|t| t.get("max_bytes"))
+                .and_then(Value::as_u64),
+            estimated_duration: None,
+            metadata,
+        };
+
+        match self.engine.evaluate_operation(&context) {
+            Ok(decision) => verdict(operation, decision),
+            Err(err) => PolicyVerdict {
+                action: PolicyAction::Deny,
+                operation,
+                reason: err.to_string(),
+                rule: None,
+            },
         }
     }
+}
 
-    PolicyAction::Allow
+fn verdict(operation: OperationType, decision: PolicyDecision) -> PolicyVerdict {
+    let action = if !decision.allow {
+        PolicyAction::Deny
+    } else if decision.requires_confirmation {
+        PolicyAction::NeedApproval
+    } else {
+        PolicyAction::Allow
+    };
+    PolicyVerdict {
+        action,
+        operation,
+        reason: decision.reason,
+        rule: decision.matched_rule,
+    }
 }
 
-/// Evaluate test run policy
-fn eval_test_run(payload: &Value) -> PolicyAction {
-    // Check timeout
-    if let Some(timeout) = payload
-        .get("task")
-        .and_then(|t| t.get("timeout_secs"))
-        .and_then(|t| t.as_u64())
-    {
-        if timeout > 120
-        // 2 minute limit
-        {
-            return PolicyAction::NeedApproval;
-        }
+/// Operation performed by a tool; unknown tools (and delegations, which run
+/// an agent) count as process execution.
+fn operation_for_tool(tool: &str) -> OperationType {
+    match tool {
+        "devit_file_read"
+        | "devit_file_read_ext"
+        | "devit_file_list"
+        | "devit_file_list_ext"
+        | "devit_file_search"
+        | "devit_file_search_ext"
+        | "devit_directory_list"
+        | "devit_project_structure"
+        | "devit_project_structure_ext"
+        | "devit_pwd" => OperationType::FileRead,
+        tool if tool.starts_with("devit_git_") => OperationType::FileRead,
+        "devit_patch_apply" | "devit_file_write" => OperationType::FileWrite,
+        "devit_snapshot" | "devit_journal_append" => OperationType::FileCreate,
+        "devit_test_run" => OperationType::TestExecution,
+        "devit_fetch_url" | "devit_search_web" => OperationType::NetworkAccess,
+        _ => OperationType::ProcessExecute,
     }
+}
 
-    // Check if test command contains dangerous operations
-    if let Some(command) = payload
-        .get("task")
-        .and_then(|t| t.get("command"))
-        .and_then(|c| c.as_str())
-    {
-        let dangerous_patterns = [
-            "rm -rf", "sudo", "chmod +x", "curl", "wget", "network", "docker",
-        ];
-
-        for pattern in &dangerous_patterns {
-            if command.contains(pattern) {
-                return PolicyAction::NeedApproval;
-            }
+/// Paths named by the task (`path`, `paths`, `working_dir`)
+fn task_paths(payload: &Value) -> Vec<PathBuf> {
+    let Some(task) = payload.get("task") else {
+        return Vec::new();
+    };
+    let mut paths = Vec::new();
+    for key in ["path", "working_dir"] {
+        if let Some(path) = task.get(key).and_then(Value::as_str) {
+            paths.push(PathBuf::from(path));
         }
     }
-
-    PolicyAction::Allow
+    if let Some(items) = task.get("paths").and_then(Value::as_array) {
+        paths.extend(items.iter().filter_map(Value::as_str).map(PathBuf::from));
+    }
+    paths
 }
 
 /// Create approval request message
@@ -139,6 +221,7 @@ pub fn create_approval_request(
     tool: &str,
     original_msg_id: &str,
     details: &Value,
+    verdict: &PolicyVerdict,
 ) -> Value {
     serde_json::json!({
         "reason": "on_request",
@@ -147,40 +230,21 @@ pub fn create_approval_request(
         "original_msg_id": original_msg_id,
         "details": details,
         "timestamp": chrono::Utc::now().to_rfc3339(),
-        "risk_level": assess_risk_level(tool, details)
+        "risk_level": assess_risk_level(&verdict.operation),
+        "policy": {
+            "operation": format!("{:?}", verdict.operation),
+            "reason": verdict.reason,
+            "rule": verdict.rule,
+        }
     })
 }
 
 /// Assess risk level for approval UI
-fn assess_risk_level(tool: &str, details: &Value) -> &'static str {
-    match tool {
-        "devit_patch_apply" => {
-            let path = details
-                .get("task")
-                .and_then(|t| t.get("path"))
-                .and_then(|p| p.as_str())
-                .unwrap_or("");
-
-            if path.contains("main.rs") || path.contains("lib.rs") {
-                "medium"
-            } else {
-                "low"
-            }
-        }
-        "devit_test_run" => {
-            let timeout = details
-                .get("task")
-                .and_then(|t| t.get("timeout_secs"))
-                .and_then(|t| t.as_u64())
-                .unwrap_or(0);
-
-            if timeout > 60 {
-                "medium"
-            } else {
-                "low"
-            }
-        }
-        _ => "low",
+fn assess_risk_level(operation: &OperationType) -> &'static str {
+    match operation.required_approval() {
+        ApprovalLevel::Untrusted | ApprovalLevel::Ask => "low",
+        ApprovalLevel::Moderate => "medium",
+        ApprovalLevel::Trusted | ApprovalLevel::Privileged { .. } => "high",
     }
 }
 
@@ -189,139 +253,128 @@ mod tests {
     use super::*;
     use serde_json::json;
 
-    #[test]
-    fn test_patch_apply_policy() {
-        // Forbidden path
-        let payload = json!({
-            "task": {
-                "action": "devit_patch_apply",
-                "path": "/etc/hosts"
-            }
-        });
-        assert_eq!(
-            Policy::eval("devit_patch_apply", &payload),
-            PolicyAction::Deny
-        );
-
-        // Trusted path
-        let payload = json!({
-            "task": {
-                "action": "devit_patch_apply",
-                "path": "/workdir/src/main.rs"
-            }
-        });
-        assert_eq!(
-            Policy::eval("devit_patch_apply", &payload),
-            PolicyAction::Allow
-        );
+    fn settings(toml: &str) -> PolicySettings {
+        toml::from_str(toml).unwrap()
+    }
 
-        // Unknown path - needs approval
-        let payload = json!({
-            "task": {
-                "action": "devit_patch_apply",
-                "path": "/unknown/file.rs"
-            }
-        });
-        assert_eq!(
-            Policy::eval("devit_patch_apply", &payload),
-            PolicyAction::NeedApproval
-        );
+    fn eval(policy: &Policy, tool: &str, task: Value) -> PolicyVerdict {
+        policy.eval("client-1", tool, &json!({ "task": task }))
     }
 
     #[test]
-    fn test_file_read_policy() {
-        // Sensitive file
-        let payload = json!({
-            "task": {
-                "action": "devit_file_read",
-                "path": "/etc/shadow"
-            }
-        });
-        assert_eq!(
-            Policy::eval("devit_file_read", &payload),
-            PolicyAction::Deny
-        );
+    fn default_policy_asks_before_running_agents() {
+        let policy = Policy::new(&PolicySettings::default(), Some(PathBuf::from("/work"))).unwrap();
 
-        // Large file
-        let payload = json!({
-            "task": {
-                "action": "devit_file_read",
-                "path": "/workdir/large.txt",
-                "max_bytes": 2000000
-            }
-        });
-        assert_eq!(
-            Policy::eval("devit_file_read", &payload),
-            PolicyAction::NeedApproval
-        );
+        let verdict = eval(&policy, "devit_delegate", json!({"goal": "fix the build"}));
+        assert_eq!(verdict.action, PolicyAction::NeedApproval);
 
-        // Normal file
-        let payload = json!({
-            "task": {
-                "action": "devit_file_read",
-                "path": "/workdir/small.txt"
-            }
-        });
-        assert_eq!(
-            Policy::eval("devit_file_read", &payload),
-            PolicyAction::Allow
+        let verdict = eval(
+            &policy,
+            "devit_patch_apply",
+            json!({"path": "/work/src/main.rs"}),
+        );
+        assert_eq!(verdict.action, PolicyAction::Allow);
+
+        // Paths leaving the workspace are refused whatever the level.
+        let verdict = eval(&policy, "devit_patch_apply", json!({"path": "/etc/hosts"}));
+        assert_eq!(verdict.action, PolicyAction::Deny);
+        let verdict = eval(
+            &policy,
+            "devit_delegate",
+            json!({"working_dir": "../other-project"}),
         );
+        assert_eq!(verdict.action, PolicyAction::Deny);
     }
 
     #[test]
-    fn test_test_run_policy() {
-        // Dangerous command
-        let payload = json!({
-            "task": {
-                "action": "devit_test_run",
-                "command": "rm -rf /"
-            }
-        });
-        assert_eq!(
-            Policy::eval("devit_test_run", &payload),
-            PolicyAction::NeedApproval
+    fn configured_level_and_rules_drive_decisions() {
+        let settings = settings(
+            r#"
+            default_approval = "ask"
+            max_files_moderate = 3
+
+            [[rules]]
+            id = "no-vendor"
+            pattern = "vendor/**"
+            blocking = true
+
+            [[rules]]
+            id = "migrations"
+            pattern = "migrations/**"
+            required_approval = "untrusted"
+            "#,
         );
+        let policy = Policy::new(&settings, Some(PathBuf::from("/work"))).unwrap();
 
-        // Long timeout
-        let payload = json!({
-            "task": {
-                "action": "devit_test_run",
-                "timeout_secs": 300
-            }
-        });
-        assert_eq!(
-            Policy::eval("devit_test_run", &payload),
-            PolicyAction::NeedApproval
+        let verdict = eval(&policy, "devit_file_read", json!({"path": "src/lib.rs"}));
+        assert_eq!(verdict.action, PolicyAction::Allow);
+
+        // Unknown tools are no longer allowed by default.
+        let verdict = eval(&policy, "devit_something_new", json!({}));
+        assert_eq!(verdict.action, PolicyAction::NeedApproval);
+        assert!(verdict.reason.contains("client-1"));
+
+        let verdict = eval(
+            &policy,
+            "devit_file_read",
+            json!({"path": "migrations/001.sql"}),
         );
+        assert_eq!(verdict.action, PolicyAction::NeedApproval);
+        assert_eq!(verdict.rule.as_deref(), Some("migrations"));
 
-        // Safe test
-        let payload = json!({
-            "task": {
-                "action": "devit_test_run",
-                "command": "cargo test",
-                "timeout_secs": 60
-            }
-        });
-        assert_eq!(
-            Policy::eval("devit_test_run", &payload),
-            PolicyAction::Allow
+        let verdict = eval(
+            &policy,
+            "devit_patch_apply",
+            json!({"path": "/work/vendor/lib.rs"}),
         );
+        assert_eq!(verdict.action, PolicyAction::Deny);
+        assert_eq!(verdict.rule.as_deref(), Some("no-vendor"));
     }
 
     #[test]
-    fn test_risk_assessment() {
-        let details = json!({
-            "task": {
-                "path": "/workdir/src/main.rs"
-            }
-        });
-        assert_eq!(assess_risk_level("devit_patch_apply", &details), "medium");
+    fn daemon_level_is_separate_from_the_shared_policy() {
+        let config: toml::Value = toml::from_str(
+            r#"
+            [policy]
+            default_approval = "untrusted"
+
+            [[policy.rules]]
+            id = "no-vendor"
+            pattern = "vendor/**"
+            blocking = true
+
+            [daemon.policy]
+            default_approval = "trusted"
+            "#,
+        )
+        .unwrap();
+        let settings = PolicySettings::from_config(&config).unwrap();
+        assert_eq!(settings.default_approval_level, ApprovalLevel::Trusted);
+        let policy = Policy::new(&settings, Some(PathBuf::from("/work"))).unwrap();
+
+        let verdict = eval(&policy, "devit_delegate", json!({"goal": "fix the build"}));
+        assert_eq!(verdict.action, PolicyAction::Allow);
+        let verdict = eval(&policy, "devit_patch_apply", json!({"path": "vendor/a.rs"}));
+        assert_eq!(verdict.action, PolicyAction::Deny);
+
+        // The shared level alone does not reach the daemon.
+        let config: toml::Value =
+            toml::from_str("[policy]\ndefault_approval = \"trusted\"").unwrap();
+        let settings = PolicySettings::from_config(&config).unwrap();
+        assert_eq!(settings.default_approval_level, ApprovalLevel::Moderate);
+    }
 
-        let details = json!({
-            "task": {
-                "timeout_secs": 90
-            }
-        });
-        assert_eq!(assess_risk_level("devit_test_run", &details), "medium");
+    #[test]
+    fn approval_request_carries_the_verdict() {
+        let policy = Policy::lockdown(None);
+        let payload = json!({"task": {"action": "devit_test_run"}});
+        let verdict = policy.eval("client-1", "devit_test_run", &payload);
+        assert_eq!(verdict.action, PolicyAction::NeedApproval);
+
+        let request =
+            create_approval_request("task-1", "devit_test_run", "msg-1", &payload, &verdict);
+        assert_eq!(request["risk_level"], "high");
+        assert_eq!(request["policy"]["operation"], "TestExecution");
+        assert_eq!(request["policy"]["reason"], verdict.reason);
     }
 }
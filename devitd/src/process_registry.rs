@@ -4,6 +4,7 @@ use std::io;
 use std::path::PathBuf;
 
 use chrono::{DateTime, Utc};
+use devit_common::ResourceUsage;
 use fs2::FileExt;
 use serde::{Deserialize, Serialize};
 
@@ -36,6 +37,12 @@ pub struct ProcessRecord {
     pub exit_code: Option<i32>,
     pub terminated_by_signal: Option<i32>,
     pub auto_kill_at: Option<DateTime<Utc>>,
+    /// cgroup holding the job's limits (Linux), removed once it is reaped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<PathBuf>,
+    /// Final cgroup counters, captured when the cgroup is removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_usage: Option<ResourceUsage>,
 }
 
 /// Registry backing storage.
@@ -7,7 +7,7 @@ use tokio::sync::Mutex;
 use tracing::{debug, error, warn};
 
 use crate::process_registry::{
-    load_registry, save_registry, validate_process, ProcessStatus, Registry,
+    load_registry, save_registry, validate_process, ProcessRecord, ProcessStatus, Registry,
 };
 use crate::process_utils::read_proc_stat;
 
@@ -94,8 +94,13 @@ async fn reaper_loop(registry: Arc<Mutex<Registry>>) {
             }
         }
 
+        // Exited jobs whose cgroup could not be removed yet (or was left by devit_kill)
+        let pending_cgroups = fresh_registry
+            .iter()
+            .any(|(_, record)| record.status == ProcessStatus::Exited && record.cgroup.is_some());
+
         // Apply updates
-        if !updates.is_empty() {
+        if !updates.is_empty() || pending_cgroups {
             let mut registry_guard = registry.lock().await;
 
             // Reload again to avoid race
@@ -107,6 +112,7 @@ async fn reaper_loop(registry: Arc<Mutex<Registry>>) {
                 }
             };
 
+            let mut changed = !updates.is_empty();
             for (pid, update) in updates {
                 if let Some(record) = fresh.processes.get_mut(&pid) {
                     match update {
@@ -121,6 +127,14 @@ async fn reaper_loop(registry: Arc<Mutex<Registry>>) {
                     }
                 }
             }
+            for record in fresh.processes.values_mut() {
+                if record.status == ProcessStatus::Exited {
+                    changed |= release_cgroup(record);
+                }
+            }
+            if !changed {
+                continue;
+            }
 
             if let Err(e) = save_registry(&fresh) {
                 error!("Reaper: failed to save registry: {}", e);
@@ -130,3 +144,35 @@ async fn reaper_loop(registry: Arc<Mutex<Registry>>) {
         }
     }
 }
+
+/// Removes the cgroup of an exited job, keeping its final counters (OOM
+/// kills, throttling) in the record. Returns whether the record changed; a
+/// cgroup still holding processes is retried on the next pass.
+fn release_cgroup(record: &mut ProcessRecord) -> bool {
+    let Some(path) = record.cgroup.clone() else {
+        return false;
+    };
+    #[cfg(target_os = "linux")]
+    {
+        match devit_sandbox::Cgroup::release(&path) {
+            Ok(usage) => {
+                if usage.oom_killed() {
+                    warn!(
+                        "Process {} was OOM-killed ({} kills)",
+                        record.pid, usage.oom_kills
+                    );
+                }
+                record.resource_usage = Some(usage);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                debug!("cgroup {} not released yet: {}", path.display(), e);
+                return false;
+            }
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = path;
+    record.cgroup = None;
+    true
+}
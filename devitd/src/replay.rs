@@ -0,0 +1,222 @@
+//! Anti-replay guard for signed daemon messages
+//!
+//! A message is accepted only if its `ts` lies within `window_secs` of the
+//! daemon clock and its `nonce` has not been seen inside that window. Nonces
+//! older than the window are forgotten since their timestamp alone now gets
+//! them rejected. The cache is bounded: when it is full, the oldest nonce is
+//! evicted and its timestamp becomes a floor below which every message is
+//! refused, so eviction never reopens a replay.
+
+use std::collections::{HashSet, VecDeque};
+
+pub const DEFAULT_WINDOW_SECS: u64 = 120;
+pub const DEFAULT_CACHE_SIZE: usize = 10_000;
+
+/// Replay settings (`[daemon.replay]` in devit.core.toml)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplaySettings {
+    /// Maximum clock skew accepted between client and daemon (seconds)
+    pub window_secs: u64,
+    /// Maximum number of nonces remembered
+    pub cache_size: usize,
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        Self {
+            window_secs: DEFAULT_WINDOW_SECS,
+            cache_size: DEFAULT_CACHE_SIZE,
+        }
+    }
+}
+
+/// Why a message was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayRejection {
+    MissingNonce,
+    StaleTimestamp { skew_secs: u64 },
+    FutureTimestamp { skew_secs: u64 },
+    ReplayedNonce,
+}
+
+impl ReplayRejection {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ReplayRejection::MissingNonce => "E_NONCE_MISSING",
+            ReplayRejection::StaleTimestamp { .. } | ReplayRejection::FutureTimestamp { .. } => {
+                "E_TIMESTAMP_SKEW"
+            }
+            ReplayRejection::ReplayedNonce => "E_NONCE_REPLAYED",
+        }
+    }
+
+    /// Short machine-friendly reason, used in POLICY journal entries
+    pub fn reason(&self) -> &'static str {
+        match self {
+            ReplayRejection::MissingNonce => "missing_nonce",
+            ReplayRejection::StaleTimestamp { .. } => "stale_timestamp",
+            ReplayRejection::FutureTimestamp { .. } => "future_timestamp",
+            ReplayRejection::ReplayedNonce => "replayed_nonce",
+        }
+    }
+
+    pub fn message(&self, window_secs: u64) -> String {
+        match self {
+            ReplayRejection::MissingNonce => "message has no nonce".to_string(),
+            ReplayRejection::StaleTimestamp { skew_secs } => format!(
+                "message timestamp is {}s old (window {}s)",
+                skew_secs, window_secs
+            ),
+            ReplayRejection::FutureTimestamp { skew_secs } => format!(
+                "message timestamp is {}s in the future (window {}s)",
+                skew_secs, window_secs
+            ),
+            ReplayRejection::ReplayedNonce => "nonce already used".to_string(),
+        }
+    }
+}
+
+pub struct ReplayGuard {
+    settings: ReplaySettings,
+    seen: HashSet<String>,
+    /// (ts, nonce) in insertion order
+    order: VecDeque<(u64, String)>,
+    /// Highest timestamp evicted because the cache was full
+    floor_ts: Option<u64>,
+}
+
+impl ReplayGuard {
+    pub fn new(settings: ReplaySettings) -> Self {
+        Self {
+            settings,
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            floor_ts: None,
+        }
+    }
+
+    pub fn settings(&self) -> ReplaySettings {
+        self.settings
+    }
+
+    /// Validate `(nonce, ts)` against the daemon clock `now` and remember the
+    /// nonce on success.
+    pub fn check(&mut self, nonce: &str, ts: u64, now: u64) -> Result<(), ReplayRejection> {
+        if nonce.trim().is_empty() {
+            return Err(ReplayRejection::MissingNonce);
+        }
+        let window = self.settings.window_secs;
+        if ts.saturating_add(window) < now {
+            return Err(ReplayRejection::StaleTimestamp {
+                skew_secs: now - ts,
+            });
+        }
+        if ts > now.saturating_add(window) {
+            return Err(ReplayRejection::FutureTimestamp {
+                skew_secs: ts - now,
+            });
+        }
+
+        self.purge(now);
+
+        if self.floor_ts.is_some_and(|floor| ts <= floor) {
+            return Err(ReplayRejection::StaleTimestamp {
+                skew_secs: now.saturating_sub(ts),
+            });
+        }
+        if self.seen.contains(nonce) {
+            return Err(ReplayRejection::ReplayedNonce);
+        }
+
+        while self.order.len() >= self.settings.cache_size.max(1) {
+            if let Some((evicted_ts, evicted)) = self.order.pop_front() {
+                self.seen.remove(&evicted);
+                self.floor_ts = Some(self.floor_ts.map_or(evicted_ts, |f| f.max(evicted_ts)));
+            }
+        }
+        self.seen.insert(nonce.to_string());
+        self.order.push_back((ts, nonce.to_string()));
+        Ok(())
+    }
+
+    /// Forget nonces whose timestamp fell out of the window.
+    fn purge(&mut self, now: u64) {
+        let window = self.settings.window_secs;
+        while let Some((ts, _)) = self.order.front() {
+            if ts.saturating_add(window) >= now {
+                break;
+            }
+            if let Some((_, nonce)) = self.order.pop_front() {
+                self.seen.remove(&nonce);
+            }
+        }
+        if self
+            .floor_ts
+            .is_some_and(|floor| floor.saturating_add(window) < now)
+        {
+            self.floor_ts = None;
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.order.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(window_secs: u64, cache_size: usize) -> ReplayGuard {
+        ReplayGuard::new(ReplaySettings {
+            window_secs,
+            cache_size,
+        })
+    }
+
+    #[test]
+    fn rejects_reused_nonce_and_skewed_timestamps() {
+        let mut guard = guard(60, 100);
+        assert_eq!(guard.check("n1", 1_000, 1_000), Ok(()));
+        assert_eq!(
+            guard.check("n1", 1_000, 1_001),
+            Err(ReplayRejection::ReplayedNonce)
+        );
+        assert_eq!(
+            guard.check("n2", 900, 1_000),
+            Err(ReplayRejection::StaleTimestamp { skew_secs: 100 })
+        );
+        assert_eq!(
+            guard.check("n3", 1_100, 1_000),
+            Err(ReplayRejection::FutureTimestamp { skew_secs: 100 })
+        );
+        assert_eq!(
+            guard.check("", 1_000, 1_000),
+            Err(ReplayRejection::MissingNonce)
+        );
+    }
+
+    #[test]
+    fn expired_nonces_are_forgotten() {
+        let mut guard = guard(60, 100);
+        guard.check("n1", 1_000, 1_000).unwrap();
+        guard.check("n2", 1_050, 1_070).unwrap();
+        assert_eq!(guard.len(), 1);
+    }
+
+    #[test]
+    fn eviction_raises_a_timestamp_floor() {
+        let mut guard = guard(60, 2);
+        guard.check("a", 1_000, 1_000).unwrap();
+        guard.check("b", 1_001, 1_001).unwrap();
+        guard.check("c", 1_002, 1_002).unwrap();
+        assert_eq!(guard.len(), 2);
+        // "a" was evicted but cannot be replayed.
+        assert!(matches!(
+            guard.check("a", 1_000, 1_003),
+            Err(ReplayRejection::StaleTimestamp { .. })
+        ));
+        assert_eq!(guard.check("d", 1_003, 1_003), Ok(()));
+    }
+}
@@ -0,0 +1,485 @@
+//! Durable task store for devitd
+//!
+//! Leases, delegated tasks, pending approvals, queued notifications and the
+//! DELEGATEs held on their dependencies or waiting for a worker slot are
+//! kept in a directory holding `snapshot.json` and an append-only
+//! `records.log` (one JSON [`Record`] per line). On open, the snapshot is
+//! loaded and the log replayed on top of it; a torn last line (crash during
+//! a write) is ignored. Once the log grows past [`COMPACT_AFTER`] records the
+//! current state is written to a new snapshot (temp file + rename) and the
+//! log is truncated.
+//!
+//! The daemon appends through a [`StoreWriter`]: records are handed to a
+//! dedicated thread, which writes whatever accumulated since its last pass
+//! and syncs once, so the async state lock is never held across disk I/O.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use devit_common::orchestration::types::DelegatedTask;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use tracing::warn;
+
+use crate::Msg;
+
+const SNAPSHOT_FILE: &str = "snapshot.json";
+const LOG_FILE: &str = "records.log";
+const COMPACT_AFTER: usize = 512;
+
+/// Lease with its deadline expressed in wall-clock seconds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StoredLease {
+    pub task_id: String,
+    pub assigned_to: String,
+    pub original_from: String,
+    pub deadline_unix: u64,
+    pub return_to: Option<String>,
+    pub working_dir: Option<String>,
+    pub response_format: Option<String>,
+    pub model: Option<String>,
+    pub model_resolved: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredApproval {
+    pub task_id: String,
+    pub original_msg: Msg,
+    pub tool: String,
+    pub requested_at_unix: u64,
+}
+
+/// DELEGATE waiting for a free slot of its worker
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredQueuedTask {
+    pub task_id: String,
+    pub msg: Msg,
+    pub worker: String,
+    pub priority: i32,
+    /// Arrival order, breaks ties between equal priorities
+    pub seq: u64,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// Everything that must survive a daemon restart
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct StoredState {
+    pub leases: HashMap<String, StoredLease>,
+    pub expired_leases: HashMap<String, StoredLease>,
+    pub tasks: HashMap<String, DelegatedTask>,
+    pub pending_approvals: HashMap<String, StoredApproval>,
+    pub pending_notifications: HashMap<String, Vec<Msg>>,
+    /// DELEGATEs held until their dependencies are completed
+    pub blocked_tasks: HashMap<String, Msg>,
+    /// DELEGATEs waiting for a worker slot
+    pub queued_tasks: HashMap<String, StoredQueuedTask>,
+}
+
+/// One mutation of [`StoredState`]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Record {
+    PutLease {
+        lease: StoredLease,
+    },
+    DelLease {
+        task_id: String,
+    },
+    PutExpiredLease {
+        lease: StoredLease,
+    },
+    DelExpiredLease {
+        task_id: String,
+    },
+    PutTask {
+        task: Box<DelegatedTask>,
+    },
+    DelTask {
+        task_id: String,
+    },
+    PutApproval {
+        id: String,
+        approval: StoredApproval,
+    },
+    DelApproval {
+        id: String,
+    },
+    /// Replaces the queue of `client` (an empty queue removes it)
+    SetNotifications {
+        client: String,
+        msgs: Vec<Msg>,
+    },
+    PutBlocked {
+        task_id: String,
+        msg: Msg,
+    },
+    DelBlocked {
+        task_id: String,
+    },
+    PutQueued {
+        task: StoredQueuedTask,
+    },
+    DelQueued {
+        task_id: String,
+    },
+}
+
+impl StoredState {
+    fn apply(&mut self, record: Record) {
+        match record {
+            Record::PutLease { lease } => {
+                self.leases.insert(lease.task_id.clone(), lease);
+            }
+            Record::DelLease { task_id } => {
+                self.leases.remove(&task_id);
+            }
+            Record::PutExpiredLease { lease } => {
+                self.expired_leases.insert(lease.task_id.clone(), lease);
+            }
+            Record::DelExpiredLease { task_id } => {
+                self.expired_leases.remove(&task_id);
+            }
+            Record::PutTask { task } => {
+                self.tasks.insert(task.id.clone(), *task);
+            }
+            Record::DelTask { task_id } => {
+                self.tasks.remove(&task_id);
+            }
+            Record::PutApproval { id, approval } => {
+                self.pending_approvals.insert(id, approval);
+            }
+            Record::DelApproval { id } => {
+                self.pending_approvals.remove(&id);
+            }
+            Record::SetNotifications { client, msgs } => {
+                if msgs.is_empty() {
+                    self.pending_notifications.remove(&client);
+                } else {
+                    self.pending_notifications.insert(client, msgs);
+                }
+            }
+            Record::PutBlocked { task_id, msg } => {
+                self.blocked_tasks.insert(task_id, msg);
+            }
+            Record::DelBlocked { task_id } => {
+                self.blocked_tasks.remove(&task_id);
+            }
+            Record::PutQueued { task } => {
+                self.queued_tasks.insert(task.task_id.clone(), task);
+            }
+            Record::DelQueued { task_id } => {
+                self.queued_tasks.remove(&task_id);
+            }
+        }
+    }
+}
+
+pub struct TaskStore {
+    dir: PathBuf,
+    log: File,
+    state: StoredState,
+    log_records: usize,
+}
+
+impl TaskStore {
+    /// Open (or create) the store in `dir` and return it with the state
+    /// rebuilt from the snapshot and the log.
+    pub fn open(dir: &Path) -> Result<(Self, StoredState)> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("cannot create task store {}", dir.display()))?;
+
+        let snapshot_path = dir.join(SNAPSHOT_FILE);
+        let mut state = if snapshot_path.exists() {
+            let raw = fs::read_to_string(&snapshot_path)?;
+            serde_json::from_str(&raw)
+                .with_context(|| format!("corrupted task snapshot {}", snapshot_path.display()))?
+        } else {
+            StoredState::default()
+        };
+
+        let log_path = dir.join(LOG_FILE);
+        let mut log_records = 0;
+        if log_path.exists() {
+            let lines: Vec<String> = BufReader::new(File::open(&log_path)?)
+                .lines()
+                .collect::<Result<_, _>>()?;
+            let last = lines.len().saturating_sub(1);
+            for (index, line) in lines.iter().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Record>(line) {
+                    Ok(record) => {
+                        state.apply(record);
+                        log_records += 1;
+                    }
+                    Err(err) if index == last => {
+                        warn!(
+                            "Ignoring torn last record in {}: {}",
+                            log_path.display(),
+                            err
+                        );
+                    }
+                    Err(err) => {
+                        return Err(err).with_context(|| {
+                            format!(
+                                "corrupted task log {} line {}",
+                                log_path.display(),
+                                index + 1
+                            )
+                        });
+                    }
+                }
+            }
+        }
+
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)?;
+        let mut store = Self {
+            dir: dir.to_path_buf(),
+            log,
+            state: state.clone(),
+            log_records,
+        };
+        // Start from a clean log so a torn tail is never appended to.
+        store.compact()?;
+        Ok((store, state))
+    }
+
+    /// Append `records` with a single sync, compacting when the log grows
+    /// too long.
+    pub fn record_batch(&mut self, records: Vec<Record>) -> Result<()> {
+        let mut lines = String::new();
+        for record in &records {
+            lines.push_str(&serde_json::to_string(record)?);
+            lines.push('\n');
+        }
+        self.log.write_all(lines.as_bytes())?;
+        self.log.sync_data()?;
+        self.log_records += records.len();
+        for record in records {
+            self.state.apply(record);
+        }
+
+        if self.log_records >= COMPACT_AFTER {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Write the current state to a fresh snapshot and truncate the log.
+    pub fn compact(&mut self) -> Result<()> {
+        let snapshot_path = self.dir.join(SNAPSHOT_FILE);
+        let tmp_path = self.dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+        {
+            let mut tmp = File::create(&tmp_path)?;
+            tmp.write_all(serde_json::to_string(&self.state)?.as_bytes())?;
+            tmp.sync_all()?;
+        }
+        fs::rename(&tmp_path, &snapshot_path)?;
+        // The rename must be durable before the log it replaces is emptied.
+        File::open(&self.dir)?.sync_all()?;
+
+        self.log = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(self.dir.join(LOG_FILE))?;
+        self.log.sync_all()?;
+        self.log_records = 0;
+        Ok(())
+    }
+}
+
+enum WriterCommand {
+    Record(Box<Record>),
+    Flush(mpsc::Sender<()>),
+}
+
+/// Appends records to a [`TaskStore`] from a dedicated thread.
+pub struct StoreWriter {
+    tx: Option<mpsc::Sender<WriterCommand>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StoreWriter {
+    pub fn spawn(mut store: TaskStore) -> Result<Self> {
+        let (tx, rx) = mpsc::channel::<WriterCommand>();
+        let thread = std::thread::Builder::new()
+            .name("devitd-store".to_string())
+            .spawn(move || {
+                while let Ok(first) = rx.recv() {
+                    let mut records = Vec::new();
+                    let mut flushed = Vec::new();
+                    for command in std::iter::once(first).chain(rx.try_iter()) {
+                        match command {
+                            WriterCommand::Record(record) => records.push(*record),
+                            WriterCommand::Flush(done) => flushed.push(done),
+                        }
+                    }
+                    if !records.is_empty() {
+                        if let Err(err) = store.record_batch(records) {
+                            warn!("Failed to persist task store records: {}", err);
+                        }
+                    }
+                    for done in flushed {
+                        let _ = done.send(());
+                    }
+                }
+            })?;
+        Ok(Self {
+            tx: Some(tx),
+            thread: Some(thread),
+        })
+    }
+
+    /// Queue `record`; never blocks on disk I/O.
+    pub fn record(&self, record: Record) {
+        if let Some(tx) = &self.tx {
+            if tx.send(WriterCommand::Record(Box::new(record))).is_err() {
+                warn!("Task store writer is gone; record dropped");
+            }
+        }
+    }
+
+    /// Ask for everything queued so far to be synced; the receiver fires
+    /// once it is on disk.
+    pub fn flush(&self) -> mpsc::Receiver<()> {
+        let (done, flushed) = mpsc::channel();
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(WriterCommand::Flush(done));
+        }
+        flushed
+    }
+}
+
+impl Drop for StoreWriter {
+    fn drop(&mut self) {
+        // Closing the channel lets the thread drain the queue and exit.
+        self.tx.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use devit_common::orchestration::types::TaskStatus;
+
+    fn lease(task_id: &str) -> StoredLease {
+        StoredLease {
+            task_id: task_id.to_string(),
+            assigned_to: "codex".to_string(),
+            original_from: "claude".to_string(),
+            deadline_unix: 42,
+            return_to: None,
+            working_dir: None,
+            response_format: None,
+            model: None,
+            model_resolved: None,
+        }
+    }
+
+    fn task(task_id: &str) -> DelegatedTask {
+        let now = Utc::now();
+        DelegatedTask {
+            id: task_id.to_string(),
+            goal: "goal".to_string(),
+            delegated_to: "codex".to_string(),
+            created_at: now,
+            timeout_secs: 60,
+            status: TaskStatus::Pending,
+            context: None,
+            watch_patterns: Vec::new(),
+            last_activity: now,
+            notifications: Vec::new(),
+            working_dir: None,
+            response_format: None,
+            model: None,
+            model_resolved: None,
+            depends_on: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn state_survives_reopen() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        {
+            let (mut store, state) = TaskStore::open(dir.path())?;
+            assert!(state.leases.is_empty());
+            store.record_batch(vec![Record::PutLease { lease: lease("t1") }])?;
+            store.record_batch(vec![
+                Record::PutLease { lease: lease("t2") },
+                Record::DelLease {
+                    task_id: "t2".into(),
+                },
+                Record::PutTask {
+                    task: Box::new(task("t1")),
+                },
+            ])?;
+        }
+
+        let (_, state) = TaskStore::open(dir.path())?;
+        assert_eq!(state.leases.len(), 1);
+        assert_eq!(state.leases.get("t1"), Some(&lease("t1")));
+        assert!(state.tasks.contains_key("t1"));
+        Ok(())
+    }
+
+    #[test]
+    fn torn_last_record_is_ignored_and_compaction_keeps_state() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        {
+            let (mut store, _) = TaskStore::open(dir.path())?;
+            for i in 0..(COMPACT_AFTER + 3) {
+                store.record_batch(vec![Record::PutLease {
+                    lease: lease(&format!("t{}", i)),
+                }])?;
+            }
+        }
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(LOG_FILE))?;
+        log.write_all(b"{\"op\":\"del_lease\",\"task_")?;
+
+        let (_, state) = TaskStore::open(dir.path())?;
+        assert_eq!(state.leases.len(), COMPACT_AFTER + 3);
+        Ok(())
+    }
+
+    #[test]
+    fn writer_batches_records_in_order() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (store, _) = TaskStore::open(dir.path())?;
+        let writer = StoreWriter::spawn(store)?;
+        for i in 0..10 {
+            writer.record(Record::PutLease {
+                lease: lease(&format!("t{}", i)),
+            });
+        }
+        writer.record(Record::DelLease {
+            task_id: "t3".into(),
+        });
+        writer.flush().recv()?;
+        // Dropping the writer drains what is still queued
+        writer.record(Record::DelLease {
+            task_id: "t4".into(),
+        });
+        drop(writer);
+
+        let (_, state) = TaskStore::open(dir.path())?;
+        assert_eq!(state.leases.len(), 8);
+        assert!(!state.leases.contains_key("t3") && !state.leases.contains_key("t4"));
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 use std::env;
 use std::ffi::OsString;
 use std::fs;
+use std::future::Future;
 use std::path::{Component, Path, PathBuf};
 use std::process::{Command as StdCommand, Stdio};
 use std::time::Duration;
@@ -14,18 +15,31 @@ use serde_json::{json, Value};
 use strip_ansi_escapes::strip;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
 use tokio::process::{ChildStdin, ChildStdout, Command as TokioCommand};
-use tokio::task;
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::time;
-use tracing::{debug, info, warn};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use devit_common::orchestration::{CapabilityRateLimit, OrchestrationCapabilities};
+use devit_common::{IsolationMode, SandboxPlan, SandboxProfile};
 
+use crate::policy::PolicySettings;
+use crate::replay::ReplaySettings;
 use crate::DAEMON_VERSION;
 
 /// Default timeout for worker execution (seconds)
 const DEFAULT_TIMEOUT_SECS: u64 = 300;
 const LOG_SNIPPET_LIMIT: usize = 2048;
+/// Failures retried when a retry policy lists no `retry_on` pattern. A
+/// worker timeout is not among them: the same task would most likely time
+/// out again.
+const DEFAULT_RETRY_ON: &[&str] = &[
+    "429",
+    "rate limit",
+    "rate_limit",
+    "too many requests",
+    "overloaded",
+];
 pub const DEFAULT_APPROVER_TARGET: &str = "client:approver";
 
 /// Worker configuration loaded from TOML.
@@ -52,12 +66,100 @@ pub struct WorkerConfig {
     pub default_model: Option<String>,
     #[serde(default)]
     pub allowed_models: Option<Vec<String>>,
+    /// Namespace isolation for the worker subprocess (off | auto | required)
+    #[serde(default)]
+    pub isolation: IsolationMode,
+    /// Profile used to build the isolation plan. Workers usually need to reach
+    /// their LLM API, hence the permissive default.
+    #[serde(default = "default_sandbox_profile")]
+    pub sandbox_profile: SandboxProfile,
+    /// Subprocesses of this worker allowed to run at once; further tasks
+    /// are queued (unlimited when unset)
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+    /// Retry failed executions with exponential backoff
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+}
+
+/// `[workers.<name>.retry]`: how failed executions are retried.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetryPolicy {
+    /// Total attempts, the first one included
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Case-insensitive substrings looked up in the failure summary, stderr
+    /// and exit reason; [`DEFAULT_RETRY_ON`] when empty
+    #[serde(default)]
+    pub retry_on: Vec<String>,
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    1_000
+}
+
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+fn default_max_backoff_ms() -> u64 {
+    60_000
+}
+
+impl RetryPolicy {
+    /// `true` when the failure matches one of the `retry_on` patterns.
+    fn should_retry(&self, outcome: &WorkerOutcome) -> bool {
+        let mut haystack = format!("{}\n{}", outcome.summary, outcome.metadata.exit_reason);
+        if let Some(stderr) = outcome
+            .details
+            .as_ref()
+            .and_then(|details| details.get("stderr"))
+            .and_then(|value| value.as_str())
+        {
+            haystack.push('\n');
+            haystack.push_str(stderr);
+        }
+        let haystack = haystack.to_lowercase();
+
+        if self.retry_on.is_empty() {
+            DEFAULT_RETRY_ON
+                .iter()
+                .any(|pattern| haystack.contains(pattern))
+        } else {
+            self.retry_on
+                .iter()
+                .any(|pattern| haystack.contains(&pattern.to_lowercase()))
+        }
+    }
+
+    /// Delay before the attempt following `attempt` (1-based).
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = self
+            .backoff_multiplier
+            .powi(attempt.saturating_sub(1).min(i32::MAX as u32) as i32);
+        let delay = (self.initial_backoff_ms as f64 * factor).min(self.max_backoff_ms as f64);
+        Duration::from_millis(delay as u64)
+    }
 }
 
 fn default_timeout_secs() -> u64 {
     DEFAULT_TIMEOUT_SECS
 }
 
+fn default_sandbox_profile() -> SandboxProfile {
+    SandboxProfile::Permissive
+}
+
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum WorkerType {
@@ -118,6 +220,7 @@ pub struct WorkerOutcome {
 pub enum WorkerStatus {
     Completed,
     Failed,
+    Cancelled,
 }
 
 impl WorkerStatus {
@@ -125,6 +228,7 @@ impl WorkerStatus {
         match self {
             WorkerStatus::Completed => "completed",
             WorkerStatus::Failed => "failed",
+            WorkerStatus::Cancelled => "cancelled",
         }
     }
 }
@@ -146,6 +250,38 @@ pub struct TaskMetadata {
     pub tokens_output: Option<u64>,
     pub tokens_reasoning: Option<u64>,
     pub cost_estimate_usd: Option<f64>,
+    /// Every execution of the task when the worker has a retry policy
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<WorkerAttempt>,
+}
+
+/// One execution of a worker retried under its [`RetryPolicy`]
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerAttempt {
+    pub attempt: u32,
+    pub time_started: DateTime<Utc>,
+    pub time_completed: DateTime<Utc>,
+    pub status: String,
+    pub exit_code: i32,
+    pub exit_reason: String,
+    pub summary: String,
+    /// Backoff before the next attempt, when there is one
+    pub retry_in_ms: Option<u64>,
+}
+
+impl WorkerAttempt {
+    fn new(attempt: u32, outcome: &WorkerOutcome, retry_in: Option<Duration>) -> Self {
+        Self {
+            attempt,
+            time_started: outcome.metadata.time_started,
+            time_completed: outcome.metadata.time_completed,
+            status: outcome.status.as_str().to_string(),
+            exit_code: outcome.metadata.exit_code,
+            exit_reason: outcome.metadata.exit_reason.clone(),
+            summary: truncate_plain(&outcome.summary, LOG_SNIPPET_LIMIT),
+            retry_in_ms: retry_in.map(|delay| delay.as_millis() as u64),
+        }
+    }
 }
 
 impl Default for TaskMetadata {
@@ -167,6 +303,7 @@ impl Default for TaskMetadata {
             tokens_output: None,
             tokens_reasoning: None,
             cost_estimate_usd: None,
+            attempts: Vec::new(),
         }
     }
 }
@@ -201,6 +338,7 @@ impl TaskMetadata {
             tokens_output: telemetry.tokens_output,
             tokens_reasoning: telemetry.tokens_reasoning,
             cost_estimate_usd: telemetry.cost_estimate_usd,
+            attempts: Vec::new(),
         }
     }
 }
@@ -214,6 +352,13 @@ pub struct WorkerSettings {
     pub capabilities: OrchestrationCapabilities,
     pub screenshot: ScreenshotSettings,
     pub approval_target: String,
+    pub replay: ReplaySettings,
+    /// Cap on worker subprocesses running at once, all workers included
+    pub max_concurrent_workers: Option<usize>,
+    /// `[daemon.policy]` and `[policy]` rules applied to DELEGATE requests
+    pub policy: PolicySettings,
+    /// `[daemon].admins`: clients allowed to cancel any task
+    pub admins: Vec<String>,
 }
 
 impl Default for WorkerSettings {
@@ -225,6 +370,10 @@ impl Default for WorkerSettings {
             capabilities: OrchestrationCapabilities::default(),
             screenshot: ScreenshotSettings::default(),
             approval_target: DEFAULT_APPROVER_TARGET.to_string(),
+            replay: ReplaySettings::default(),
+            max_concurrent_workers: None,
+            policy: PolicySettings::default(),
+            admins: Vec::new(),
         }
     }
 }
@@ -410,6 +559,44 @@ pub fn load_worker_settings(config_path: Option<&Path>) -> WorkerSettings {
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
         .unwrap_or_else(|| DEFAULT_APPROVER_TARGET.to_string());
+    let replay = parsed_value
+        .as_ref()
+        .map(parse_replay_settings)
+        .unwrap_or_default();
+    let max_concurrent_workers = parsed_value
+        .as_ref()
+        .and_then(|value| value.get("daemon"))
+        .and_then(|daemon| daemon.get("max_concurrent_workers"))
+        .and_then(|value| value.as_integer())
+        .and_then(|limit| {
+            if limit > 0 {
+                Some(limit as usize)
+            } else {
+                warn!(
+                    "Ignoring non-positive daemon.max_concurrent_workers ({})",
+                    limit
+                );
+                None
+            }
+        });
+    let policy = parsed_value
+        .as_ref()
+        .map(parse_policy_settings)
+        .unwrap_or_default();
+    let admins = parsed_value
+        .as_ref()
+        .and_then(|value| value.get("daemon"))
+        .and_then(|daemon| daemon.get("admins"))
+        .and_then(|value| value.as_array())
+        .map(|idents| {
+            idents
+                .iter()
+                .filter_map(|ident| ident.as_str())
+                .map(|ident| ident.trim().to_string())
+                .filter(|ident| !ident.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
 
     WorkerSettings {
         configs,
@@ -418,9 +605,53 @@ pub fn load_worker_settings(config_path: Option<&Path>) -> WorkerSettings {
         capabilities,
         screenshot,
         approval_target,
+        replay,
+        max_concurrent_workers,
+        policy,
+        admins,
+    }
+}
+
+/// An invalid policy section must not silently loosen the daemon: it is
+/// replaced by the lockdown settings, where every task needs approval.
+fn parse_policy_settings(value: &toml::Value) -> PolicySettings {
+    match PolicySettings::from_config(value) {
+        Ok(settings) => settings,
+        Err(err) => {
+            error!(
+                "Invalid [policy] or [daemon.policy] section, every task will need approval: {}",
+                err
+            );
+            PolicySettings::lockdown()
+        }
     }
 }
 
+fn parse_replay_settings(value: &toml::Value) -> ReplaySettings {
+    let mut settings = ReplaySettings::default();
+    let Some(table) = value.get("daemon").and_then(|daemon| daemon.get("replay")) else {
+        return settings;
+    };
+    if let Some(window) = table.get("window_secs").and_then(|v| v.as_integer()) {
+        if window > 0 {
+            settings.window_secs = window as u64;
+        } else {
+            warn!(
+                "Ignoring non-positive daemon.replay.window_secs ({})",
+                window
+            );
+        }
+    }
+    if let Some(size) = table.get("cache_size").and_then(|v| v.as_integer()) {
+        if size > 0 {
+            settings.cache_size = size as usize;
+        } else {
+            warn!("Ignoring non-positive daemon.replay.cache_size ({})", size);
+        }
+    }
+    settings
+}
+
 fn resolve_workspace_root(config_path: &Path, raw: &str) -> Option<PathBuf> {
     let expanded = expand_home(raw);
     let path = PathBuf::from(expanded);
@@ -470,6 +701,26 @@ fn validate_worker_config(name: &str, config: &WorkerConfig) -> Result<()> {
         }
     }
 
+    if config.max_concurrent == Some(0) {
+        bail!("max_concurrent must be greater than zero");
+    }
+
+    if let Some(retry) = &config.retry {
+        if retry.max_attempts == 0 {
+            bail!("retry.max_attempts must be greater than zero");
+        }
+        if !retry.backoff_multiplier.is_finite() || retry.backoff_multiplier < 1.0 {
+            bail!("retry.backoff_multiplier must be at least 1.0");
+        }
+        if retry
+            .retry_on
+            .iter()
+            .any(|pattern| pattern.trim().is_empty())
+        {
+            bail!("retry.retry_on patterns must not be empty");
+        }
+    }
+
     match config.worker_type {
         WorkerType::Cli => {}
         WorkerType::Mcp => {
@@ -647,6 +898,29 @@ fn default_screenshot_dir(workspace_root: Option<&Path>) -> PathBuf {
     }
 }
 
+/// Make the worker lead its own process group so cancellation can reach
+/// every process it spawned.
+fn spawn_as_group_leader(command: &mut StdCommand) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+fn kill_process_group(pid: Option<u32>) {
+    #[cfg(unix)]
+    if let Some(pid) = pid {
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
 fn fallback_screenshot_dir() -> PathBuf {
     #[cfg(windows)]
     {
@@ -661,6 +935,24 @@ fn fallback_screenshot_dir() -> PathBuf {
 pub struct WorkerExecutor {
     config: WorkerConfig,
     workspace_root: Option<PathBuf>,
+    cancel: Option<watch::Receiver<bool>>,
+    attempt_events: Option<mpsc::UnboundedSender<WorkerAttempt>>,
+    slot: Option<mpsc::UnboundedSender<SlotRequest>>,
+}
+
+/// Worker slot moves of a task waiting out a retry backoff.
+#[derive(Debug)]
+pub enum SlotRequest {
+    /// The backoff starts: the slot may serve other tasks meanwhile
+    Release,
+    /// The backoff is over: answered once the slot is held again
+    Reclaim(oneshot::Sender<()>),
+}
+
+/// Result of waiting on a worker that may be cancelled.
+enum Supervised<T> {
+    Done(T),
+    Cancelled,
 }
 
 impl WorkerExecutor {
@@ -668,10 +960,180 @@ impl WorkerExecutor {
         Self {
             config,
             workspace_root,
+            cancel: None,
+            attempt_events: None,
+            slot: None,
         }
     }
 
+    /// Abort the worker (and its whole process group) once `true` is sent.
+    pub fn with_cancel(mut self, cancel: watch::Receiver<bool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Report each failed attempt that is about to be retried.
+    pub fn with_attempt_events(mut self, events: mpsc::UnboundedSender<WorkerAttempt>) -> Self {
+        self.attempt_events = Some(events);
+        self
+    }
+
+    /// Give the worker slot back during retry backoffs through `requests`.
+    pub fn with_slot(mut self, requests: mpsc::UnboundedSender<SlotRequest>) -> Self {
+        self.slot = Some(requests);
+        self
+    }
+
+    /// Wait out a retry backoff, holding no worker slot meanwhile when the
+    /// executor has one.
+    async fn wait_backoff(&self, backoff: Duration) -> Result<Supervised<()>> {
+        let Some(slot) = &self.slot else {
+            return self.supervise(time::sleep(backoff), None, None).await;
+        };
+        let _ = slot.send(SlotRequest::Release);
+        if let Supervised::Cancelled = self.supervise(time::sleep(backoff), None, None).await? {
+            return Ok(Supervised::Cancelled);
+        }
+        let (granted_tx, granted_rx) = oneshot::channel();
+        if slot.send(SlotRequest::Reclaim(granted_tx)).is_err() {
+            return Ok(Supervised::Done(()));
+        }
+        match self.supervise(granted_rx, None, None).await? {
+            Supervised::Done(Ok(())) => Ok(Supervised::Done(())),
+            // The slot went away with the task
+            Supervised::Done(Err(_)) | Supervised::Cancelled => Ok(Supervised::Cancelled),
+        }
+    }
+
+    /// Wait for `work`, killing the worker process group on timeout or
+    /// cancellation.
+    async fn supervise<T>(
+        &self,
+        work: impl Future<Output = T>,
+        pid: Option<u32>,
+        timeout: Option<Duration>,
+    ) -> Result<Supervised<T>> {
+        let mut cancel = self.cancel.clone();
+        let cancelled = async {
+            let signalled = match cancel.as_mut() {
+                Some(rx) => rx.wait_for(|flag| *flag).await.is_ok(),
+                None => false,
+            };
+            if !signalled {
+                std::future::pending::<()>().await;
+            }
+        };
+        let deadline = async {
+            match timeout {
+                Some(duration) => time::sleep(duration).await,
+                None => std::future::pending::<()>().await,
+            }
+        };
+
+        tokio::select! {
+            value = work => Ok(Supervised::Done(value)),
+            _ = cancelled => {
+                kill_process_group(pid);
+                Ok(Supervised::Cancelled)
+            }
+            _ = deadline => {
+                kill_process_group(pid);
+                Err(anyhow!(
+                    "worker timed out after {}s",
+                    timeout.unwrap_or_default().as_secs()
+                ))
+            }
+        }
+    }
+
+    fn cancelled_outcome(
+        &self,
+        requested_model: Option<String>,
+        resolved_model: Option<String>,
+    ) -> (WorkerOutcome, ExecutionTelemetry) {
+        let outcome = WorkerOutcome {
+            status: WorkerStatus::Cancelled,
+            summary: format!("Worker '{}' cancelled", self.config.binary),
+            details: None,
+            evidence: None,
+            truncated: false,
+            original_size: None,
+            metadata: TaskMetadata::default(),
+        };
+        let telemetry = ExecutionTelemetry {
+            exit_code: Some(-1),
+            exit_reason: Some("cancelled".to_string()),
+            model_requested: requested_model,
+            model_used: resolved_model,
+            ..ExecutionTelemetry::default()
+        };
+        (outcome, telemetry)
+    }
+
+    /// Run `task`, retrying failures allowed by the worker retry policy.
     pub async fn execute_task(&self, task: &WorkerTask) -> Result<WorkerOutcome> {
+        let Some(policy) = self.config.retry.as_ref() else {
+            return self.execute_attempt(task).await;
+        };
+
+        let mut attempts = Vec::new();
+        let mut attempt = 1;
+        loop {
+            let mut outcome = self.execute_attempt(task).await?;
+            let retry = outcome.status == WorkerStatus::Failed
+                && attempt < policy.max_attempts
+                && policy.should_retry(&outcome);
+            let backoff = retry.then(|| policy.backoff(attempt));
+            let record = WorkerAttempt::new(attempt, &outcome, backoff);
+
+            let Some(backoff) = backoff else {
+                attempts.push(record);
+                if let Some(first) = attempts.first() {
+                    let metadata = &mut outcome.metadata;
+                    metadata.time_started = first.time_started;
+                    metadata.duration_execution_ms = (metadata.time_completed
+                        - metadata.time_started)
+                        .num_milliseconds()
+                        .max(0) as u64;
+                }
+                outcome.metadata.attempts = attempts;
+                return Ok(outcome);
+            };
+
+            warn!(
+                worker = task.delegated_to,
+                task_id = task.id,
+                attempt,
+                backoff_ms = backoff.as_millis() as u64,
+                "Worker attempt failed, retrying: {}",
+                outcome.summary
+            );
+            if let Some(events) = &self.attempt_events {
+                let _ = events.send(record.clone());
+            }
+            attempts.push(record);
+
+            if let Supervised::Cancelled = self.wait_backoff(backoff).await? {
+                let (mut cancelled, telemetry) = self.cancelled_outcome(
+                    outcome.metadata.model_requested.clone(),
+                    outcome.metadata.model_used.clone(),
+                );
+                let now = Utc::now();
+                cancelled.metadata = TaskMetadata::new(
+                    task.time_queued,
+                    attempts[0].time_started,
+                    now,
+                    &self.config.worker_type,
+                    telemetry,
+                );
+                cancelled.metadata.attempts = attempts;
+                return Ok(cancelled);
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn execute_attempt(&self, task: &WorkerTask) -> Result<WorkerOutcome> {
         let time_started = Utc::now();
         let result = match self.config.worker_type {
             WorkerType::Cli => self.execute_cli(task).await,
@@ -816,6 +1278,12 @@ impl WorkerExecutor {
             command.env("DEVIT_MODEL_USED", model);
         }
 
+        self.apply_isolation(&mut command, current_dir.as_deref())?;
+        spawn_as_group_leader(&mut command);
+        command.stdin(Stdio::null());
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+
         info!(
             worker = task.delegated_to,
             task_id = task.id,
@@ -828,13 +1296,27 @@ impl WorkerExecutor {
 
         let timeout = self.effective_timeout(task);
         let binary_display = self.config.binary.clone();
-        let join_result = time::timeout(timeout, task::spawn_blocking(move || command.output()))
-            .await
-            .map_err(|_| anyhow!("worker timed out after {}s", timeout.as_secs()))?;
-
-        let output = join_result
-            .context("failed to join worker thread")?
+        let mut command = TokioCommand::from(command);
+        command.kill_on_drop(true);
+        let child = command
+            .spawn()
             .context("failed to execute worker process")?;
+        let pid = child.id();
+
+        let output = match self
+            .supervise(child.wait_with_output(), pid, Some(timeout))
+            .await?
+        {
+            Supervised::Done(output) => output.context("failed to execute worker process")?,
+            Supervised::Cancelled => {
+                info!(
+                    worker = task.delegated_to,
+                    task_id = task.id,
+                    "Worker cancelled"
+                );
+                return Ok(self.cancelled_outcome(requested_model, resolved_model));
+            }
+        };
 
         let stdout_text = decode_and_strip(&output.stdout);
         let stderr_text = decode_and_strip(&output.stderr);
@@ -941,6 +1423,7 @@ impl WorkerExecutor {
         command.stdin(Stdio::piped());
         command.stdout(Stdio::piped());
         command.stderr(Stdio::inherit());
+        command.kill_on_drop(true);
 
         let mut current_dir = None;
         if let Some(ref dir) = self.config.working_dir {
@@ -969,6 +1452,9 @@ impl WorkerExecutor {
             command.env("DEVIT_MODEL_USED", model);
         }
 
+        self.apply_isolation(command.as_std_mut(), current_dir.as_deref())?;
+        spawn_as_group_leader(command.as_std_mut());
+
         info!(
             worker = task.delegated_to,
             task_id = task.id,
@@ -1001,10 +1487,27 @@ impl WorkerExecutor {
                 return Err(err);
             }
         };
-        let rpc_response = session
-            .call_delegate(&self.config, task, resolved_model.as_deref())
-            .await
-            .context("MCP delegate call failed")?;
+        // The session enforces its own timeout; only cancellation is watched here.
+        let rpc_response = match self
+            .supervise(
+                session.call_delegate(&self.config, task, resolved_model.as_deref()),
+                child.id(),
+                None,
+            )
+            .await?
+        {
+            Supervised::Done(response) => response.context("MCP delegate call failed")?,
+            Supervised::Cancelled => {
+                info!(
+                    worker = task.delegated_to,
+                    task_id = task.id,
+                    "MCP worker cancelled"
+                );
+                let _ = child.start_kill();
+                let _ = child.wait().await;
+                return Ok(self.cancelled_outcome(requested_model, resolved_model));
+            }
+        };
 
         let mut telemetry = ExecutionTelemetry {
             exit_code: Some(0),
@@ -1086,6 +1589,43 @@ impl WorkerExecutor {
         Ok((outcome, telemetry))
     }
 
+    /// Confine the worker subprocess according to `isolation`. The workspace
+    /// (or daemon cwd) is writable, the worker binary directory read-only.
+    fn apply_isolation(&self, command: &mut StdCommand, current_dir: Option<&Path>) -> Result<()> {
+        if self.config.isolation == IsolationMode::Off {
+            return Ok(());
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            use devit_sandbox::NamespaceSandbox;
+
+            if NamespaceSandbox::is_supported() {
+                let root = match current_dir {
+                    Some(dir) => dir.to_path_buf(),
+                    None => env::current_dir().context("cannot resolve daemon working dir")?,
+                };
+                let mut plan =
+                    SandboxPlan::plan_for_test(root, self.config.sandbox_profile.clone());
+                if let Some(bin_dir) = resolve_binary_dir(&self.config.binary) {
+                    plan.bind_ro.push(bin_dir);
+                }
+                return NamespaceSandbox::new(plan).confine(command);
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = (command, current_dir);
+
+        if self.config.isolation == IsolationMode::Required {
+            bail!("namespace isolation required but unavailable on this host");
+        }
+        warn!(
+            binary = %self.config.binary,
+            "Worker isolation unavailable, spawning without namespaces"
+        );
+        Ok(())
+    }
+
     fn prepare_args(
         &self,
         task: &WorkerTask,
@@ -1377,6 +1917,25 @@ impl WorkerExecutor {
     }
 }
 
+/// Directory holding the worker binary, resolved through `PATH` when needed.
+#[cfg(target_os = "linux")]
+fn resolve_binary_dir(binary: &str) -> Option<PathBuf> {
+    let candidate = Path::new(binary);
+    if candidate.components().count() > 1 {
+        return candidate
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.parent().map(Path::to_path_buf));
+    }
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(binary))
+            .find(|path| path.is_file())
+            .and_then(|p| p.canonicalize().ok())
+            .and_then(|p| p.parent().map(Path::to_path_buf))
+    })
+}
+
 fn decode_and_strip(bytes: &[u8]) -> String {
     if bytes.is_empty() {
         return String::new();
@@ -2019,4 +2578,184 @@ mod tests {
         assert!(metadata.model_requested.is_none());
         assert!(metadata.model_used.is_none());
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancel_kills_cli_worker_process_group() {
+        let config: WorkerConfig = serde_json::from_value(serde_json::json!({
+            "type": "cli",
+            "binary": "sh",
+            "args": ["-c", "sleep 30 & wait"],
+            "timeout_secs": 60
+        }))
+        .expect("worker config");
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        let executor = WorkerExecutor::new(config, None).with_cancel(cancel_rx);
+        let task = WorkerTask {
+            id: "task-cancel".to_string(),
+            goal: "never finishes".to_string(),
+            delegated_to: "sleeper".to_string(),
+            working_dir: None,
+            timeout_secs: None,
+            return_to: None,
+            response_format: None,
+            time_queued: Utc::now(),
+            model: None,
+            context: None,
+        };
+
+        tokio::spawn(async move {
+            time::sleep(Duration::from_millis(200)).await;
+            let _ = cancel_tx.send(true);
+        });
+
+        let started = std::time::Instant::now();
+        let outcome = executor.execute_task(&task).await.expect("outcome");
+        assert_eq!(outcome.status, WorkerStatus::Cancelled);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    fn retry_task(id: &str) -> WorkerTask {
+        WorkerTask {
+            id: id.to_string(),
+            goal: "flaky".to_string(),
+            delegated_to: "flaky".to_string(),
+            working_dir: None,
+            timeout_secs: None,
+            return_to: None,
+            response_format: None,
+            time_queued: Utc::now(),
+            model: None,
+            context: None,
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn rate_limited_worker_is_retried_until_it_succeeds() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let marker = dir.path().join("attempted");
+        let script = format!(
+            "if [ -e {0} ]; then echo done; else touch {0}; echo 'HTTP 429 Too Many Requests' >&2; exit 1; fi",
+            marker.display()
+        );
+        let config: WorkerConfig = serde_json::from_value(serde_json::json!({
+            "type": "cli",
+            "binary": "sh",
+            "args": ["-c", script],
+            "parse_mode": "text",
+            "retry": {"max_attempts": 3, "initial_backoff_ms": 10}
+        }))
+        .expect("worker config");
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let executor = WorkerExecutor::new(config, None).with_attempt_events(events_tx);
+
+        let outcome = executor
+            .execute_task(&retry_task("task-retry"))
+            .await
+            .expect("outcome");
+        assert_eq!(outcome.status, WorkerStatus::Completed);
+        let attempts = &outcome.metadata.attempts;
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].status, "failed");
+        assert_eq!(attempts[0].retry_in_ms, Some(10));
+        assert_eq!(attempts[1].status, "completed");
+        assert_eq!(outcome.metadata.time_started, attempts[0].time_started);
+
+        let event = events_rx.try_recv().expect("retry event");
+        assert_eq!(event.attempt, 1);
+        assert!(events_rx.try_recv().is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn retries_stop_at_max_attempts_and_skip_unmatched_failures() {
+        let config: WorkerConfig = serde_json::from_value(serde_json::json!({
+            "type": "cli",
+            "binary": "sh",
+            "args": ["-c", "echo 'upstream overloaded' >&2; exit 1"],
+            "retry": {"max_attempts": 3, "initial_backoff_ms": 5, "max_backoff_ms": 8}
+        }))
+        .expect("worker config");
+        let outcome = WorkerExecutor::new(config, None)
+            .execute_task(&retry_task("task-exhausted"))
+            .await
+            .expect("outcome");
+        assert_eq!(outcome.status, WorkerStatus::Failed);
+        let delays: Vec<_> = outcome
+            .metadata
+            .attempts
+            .iter()
+            .map(|attempt| attempt.retry_in_ms)
+            .collect();
+        assert_eq!(delays, vec![Some(5), Some(8), None]);
+
+        let config: WorkerConfig = serde_json::from_value(serde_json::json!({
+            "type": "cli",
+            "binary": "sh",
+            "args": ["-c", "echo 'syntax error' >&2; exit 2"],
+            "retry": {"max_attempts": 3, "initial_backoff_ms": 5, "retry_on": ["rate limit"]}
+        }))
+        .expect("worker config");
+        let outcome = WorkerExecutor::new(config, None)
+            .execute_task(&retry_task("task-fatal"))
+            .await
+            .expect("outcome");
+        assert_eq!(outcome.status, WorkerStatus::Failed);
+        assert_eq!(outcome.metadata.attempts.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timeouts_are_not_retried_by_default() {
+        let config: WorkerConfig = serde_json::from_value(serde_json::json!({
+            "type": "cli",
+            "binary": "sh",
+            "args": ["-c", "sleep 5"],
+            "timeout_secs": 1,
+            "retry": {"max_attempts": 3, "initial_backoff_ms": 5}
+        }))
+        .expect("worker config");
+        let outcome = WorkerExecutor::new(config, None)
+            .execute_task(&retry_task("task-timeout"))
+            .await
+            .expect("outcome");
+        assert_eq!(outcome.status, WorkerStatus::Failed);
+        assert_eq!(outcome.metadata.attempts.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn backoff_releases_then_reclaims_the_slot() {
+        let config: WorkerConfig = serde_json::from_value(serde_json::json!({
+            "type": "cli",
+            "binary": "sh",
+            "args": ["-c", "echo 'rate limit' >&2; exit 1"],
+            "retry": {"max_attempts": 2, "initial_backoff_ms": 5}
+        }))
+        .expect("worker config");
+        let (slot_tx, mut slot_rx) = mpsc::unbounded_channel();
+        let slot = tokio::spawn(async move {
+            let mut moves = Vec::new();
+            while let Some(request) = slot_rx.recv().await {
+                match request {
+                    SlotRequest::Release => moves.push("release"),
+                    SlotRequest::Reclaim(granted) => {
+                        moves.push("reclaim");
+                        granted.send(()).expect("executor waits for the slot");
+                    }
+                }
+            }
+            moves
+        });
+
+        let outcome = WorkerExecutor::new(config, None)
+            .with_slot(slot_tx)
+            .execute_task(&retry_task("task-slot"))
+            .await
+            .expect("outcome");
+        assert_eq!(outcome.metadata.attempts.len(), 2);
+        assert_eq!(slot.await.expect("slot task"), vec!["release", "reclaim"]);
+    }
 }
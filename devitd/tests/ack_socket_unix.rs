@@ -55,13 +55,17 @@ async fn ack_socket_fallbacks_to_marker_and_preserves_status() {
     fs::write(&hook_path, script).expect("write hook script");
     let _ = std::fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755));
 
-    // Spawn daemon
+    // Spawn daemon; test clients delegate without an approver
+    let config = out_dir.join("devit.core.toml");
+    fs::write(&config, "[daemon.policy]\ndefault_approval = \"trusted\"\n").expect("write config");
     let devitd_path = find_devitd_binary();
     let mut child = Command::new(&devitd_path)
         .arg("--socket")
         .arg(&sock)
         .arg("--secret")
         .arg(secret)
+        .arg("--config")
+        .arg(&config)
         .arg("--debug")
         .env("DEVIT_NOTIFY_HOOK", &hook_path)
         .stdin(Stdio::null())
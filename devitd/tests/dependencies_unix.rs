@@ -0,0 +1,188 @@
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use devitd_client::{DevitClient, Msg};
+use tokio::process::{Child, Command};
+use tokio::time::sleep;
+
+fn find_devitd_binary() -> PathBuf {
+    let exe = std::env::current_exe().expect("current_exe");
+    // target/debug/deps/<test-bin>
+    let target_dir = exe
+        .parent()
+        .and_then(|p| p.parent())
+        .expect("target debug dir");
+    let candidate = target_dir.join("devitd");
+    if candidate.is_file() {
+        return candidate;
+    }
+    target_dir
+        .parent()
+        .map(|p| p.join("debug").join("devitd"))
+        .unwrap_or(candidate)
+}
+
+async fn spawn_daemon(sock: &str, secret: &str) -> Child {
+    let _ = std::fs::remove_file(sock);
+    // Test clients delegate without an approver.
+    let config = format!("{}.toml", sock);
+    std::fs::write(&config, "[daemon.policy]\ndefault_approval = \"trusted\"\n")
+        .expect("write config");
+    let child = Command::new(find_devitd_binary())
+        .arg("--socket")
+        .arg(sock)
+        .arg("--secret")
+        .arg(secret)
+        .arg("--config")
+        .arg(&config)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn devitd");
+
+    for _ in 0..50 {
+        if DevitClient::connect(sock, "probe", secret).await.is_ok() {
+            return child;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    panic!("daemon did not come up on {}", sock);
+}
+
+async fn task_status(client: &DevitClient, task_id: &str) -> Option<serde_json::Value> {
+    let status = client
+        .status_snapshot()
+        .await
+        .expect("status")
+        .expect("status response")
+        .payload;
+    ["active_tasks", "completed_tasks"]
+        .iter()
+        .filter_map(|key| status.get(*key).and_then(|v| v.as_array()))
+        .flatten()
+        .find(|task| task.get("id").and_then(|id| id.as_str()) == Some(task_id))
+        .cloned()
+}
+
+async fn poll_delegate(worker: &DevitClient) -> Option<Msg> {
+    for _ in 0..20 {
+        if let Some(msg) = worker.poll().await.expect("poll") {
+            if DevitClient::is_delegate(&msg) {
+                return Some(msg);
+            }
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    None
+}
+
+#[tokio::test]
+async fn dependent_task_waits_for_upstream_and_receives_its_result() {
+    let secret = "test-secret";
+    let base = format!(
+        "/tmp/devitd-deptest-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_millis()
+    );
+    let sock = format!("{}.sock", base);
+
+    let mut daemon = spawn_daemon(&sock, secret).await;
+    let client = DevitClient::connect(&sock, "client-a", secret)
+        .await
+        .expect("connect client-a");
+    let worker = DevitClient::connect(&sock, "worker-a", secret)
+        .await
+        .expect("connect worker-a");
+
+    let analyse = client
+        .delegate(
+            "worker-a",
+            serde_json::json!({"goal": "analyse"}),
+            "client-a",
+        )
+        .await
+        .expect("delegate analyse");
+    let refactor = client
+        .delegate(
+            "worker-a",
+            serde_json::json!({"goal": "refactor", "depends_on": [analyse]}),
+            "client-a",
+        )
+        .await
+        .expect("delegate refactor");
+    let review = client
+        .delegate(
+            "worker-a",
+            serde_json::json!({
+                "goal": "review",
+                "depends_on": [refactor],
+                "on_dependency_failure": "skip"
+            }),
+            "client-a",
+        )
+        .await
+        .expect("delegate review");
+
+    let held = task_status(&client, &refactor)
+        .await
+        .expect("refactor task");
+    assert_eq!(held["status"], "pending", "{}", held);
+    assert_eq!(held["depends_on"][0], analyse.as_str());
+
+    // Only the upstream task reaches the worker.
+    let first = poll_delegate(&worker).await.expect("analyse delegate");
+    assert_eq!(first.msg_id, analyse);
+    assert!(worker.poll().await.expect("poll").is_none());
+
+    worker
+        .notify(
+            "orchestrator",
+            &analyse,
+            "completed",
+            serde_json::json!({
+                "summary": "analysis done",
+                "details": {"hotspots": ["parser.rs"]}
+            }),
+            Some("client-a"),
+        )
+        .await
+        .expect("notify analyse");
+
+    let released = poll_delegate(&worker).await.expect("refactor delegate");
+    assert_eq!(released.msg_id, refactor);
+    let upstream = &released.payload["task"]["context"]["upstream"][analyse.as_str()];
+    assert_eq!(upstream["summary"], "analysis done");
+    assert_eq!(upstream["details"]["hotspots"][0], "parser.rs");
+
+    worker
+        .notify(
+            "orchestrator",
+            &refactor,
+            "failed",
+            serde_json::json!({"summary": "tests broke"}),
+            Some("client-a"),
+        )
+        .await
+        .expect("notify refactor");
+
+    let mut skipped = None;
+    for _ in 0..20 {
+        skipped = task_status(&client, &review).await;
+        if skipped.as_ref().map(|t| t["status"] != "pending") == Some(true) {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    let skipped = skipped.expect("review task");
+    assert_eq!(skipped["status"], "cancelled", "{}", skipped);
+
+    let _ = daemon.kill().await;
+    let _ = std::fs::remove_file(&sock);
+    let _ = std::fs::remove_file(format!("{}.toml", sock));
+    let _ = std::fs::remove_file(format!("{}.journal", base));
+    let _ = std::fs::remove_dir_all(Path::new(&format!("{}.tasks", base)));
+}
@@ -0,0 +1,123 @@
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use devitd_client::DevitClient;
+use tokio::process::{Child, Command};
+use tokio::time::sleep;
+
+fn find_devitd_binary() -> PathBuf {
+    let exe = std::env::current_exe().expect("current_exe");
+    // target/debug/deps/<test-bin>
+    let target_dir = exe
+        .parent()
+        .and_then(|p| p.parent())
+        .expect("target debug dir");
+    let candidate = target_dir.join("devitd");
+    if candidate.is_file() {
+        return candidate;
+    }
+    target_dir
+        .parent()
+        .map(|p| p.join("debug").join("devitd"))
+        .unwrap_or(candidate)
+}
+
+async fn spawn_daemon(sock: &str, secret: &str) -> Child {
+    let _ = std::fs::remove_file(sock);
+    // Test clients delegate without an approver.
+    let config = format!("{}.toml", sock);
+    std::fs::write(&config, "[daemon.policy]\ndefault_approval = \"trusted\"\n")
+        .expect("write config");
+    let child = Command::new(find_devitd_binary())
+        .arg("--socket")
+        .arg(sock)
+        .arg("--secret")
+        .arg(secret)
+        .arg("--config")
+        .arg(&config)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn devitd");
+
+    for _ in 0..50 {
+        if DevitClient::connect(sock, "probe", secret).await.is_ok() {
+            return child;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    panic!("daemon did not come up on {}", sock);
+}
+
+#[tokio::test]
+async fn delegated_task_survives_daemon_restart() {
+    let secret = "test-secret";
+    let base = format!(
+        "/tmp/devitd-storetest-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_millis()
+    );
+    let sock = format!("{}.sock", base);
+
+    let mut daemon = spawn_daemon(&sock, secret).await;
+    let client = DevitClient::connect(&sock, "client-a", secret)
+        .await
+        .expect("connect client-a");
+    let _worker = DevitClient::connect(&sock, "worker-a", secret)
+        .await
+        .expect("connect worker-a");
+    let task_id = client
+        .delegate(
+            "worker-a",
+            serde_json::json!({"goal": "survive restart", "timeout": 600}),
+            "client-a",
+        )
+        .await
+        .expect("delegate task");
+    // Round-trip so the DELEGATE is processed before the crash.
+    client.status_snapshot().await.expect("status");
+
+    // Crash, not a graceful shutdown.
+    daemon.kill().await.expect("kill daemon");
+    let _ = daemon.wait().await;
+
+    let mut daemon = spawn_daemon(&sock, secret).await;
+    let client = DevitClient::connect(&sock, "client-a", secret)
+        .await
+        .expect("reconnect client-a");
+    let status = client
+        .status_snapshot()
+        .await
+        .expect("status")
+        .expect("status response")
+        .payload;
+    let active: Vec<&str> = status
+        .get("active_tasks")
+        .and_then(|v| v.as_array())
+        .map(|tasks| {
+            tasks
+                .iter()
+                .filter_map(|t| t.get("id").and_then(|id| id.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    assert!(active.contains(&task_id.as_str()), "status: {}", status);
+
+    // The DELEGATE queued for the worker was rehydrated too.
+    let worker = DevitClient::connect(&sock, "worker-a", secret)
+        .await
+        .expect("connect worker-a");
+    let delegated = worker.poll().await.expect("poll").expect("queued delegate");
+    assert!(DevitClient::is_delegate(&delegated));
+    assert_eq!(delegated.msg_id, task_id);
+
+    let _ = daemon.kill().await;
+    let _ = std::fs::remove_file(&sock);
+    let _ = std::fs::remove_file(format!("{}.toml", sock));
+    let _ = std::fs::remove_file(format!("{}.journal", base));
+    let _ = std::fs::remove_dir_all(Path::new(&format!("{}.tasks", base)));
+}
@@ -0,0 +1,170 @@
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use devitd_client::DevitClient;
+use tokio::process::{Child, Command};
+use tokio::time::sleep;
+
+fn find_devitd_binary() -> PathBuf {
+    let exe = std::env::current_exe().expect("current_exe");
+    // target/debug/deps/<test-bin>
+    let target_dir = exe
+        .parent()
+        .and_then(|p| p.parent())
+        .expect("target debug dir");
+    let candidate = target_dir.join("devitd");
+    if candidate.is_file() {
+        return candidate;
+    }
+    target_dir
+        .parent()
+        .map(|p| p.join("debug").join("devitd"))
+        .unwrap_or(candidate)
+}
+
+async fn spawn_daemon(sock: &str, secret: &str, config: &Path) -> Child {
+    let _ = std::fs::remove_file(sock);
+    let child = Command::new(find_devitd_binary())
+        .arg("--socket")
+        .arg(sock)
+        .arg("--secret")
+        .arg(secret)
+        .arg("--config")
+        .arg(config)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn devitd");
+
+    for _ in 0..50 {
+        if DevitClient::connect(sock, "probe", secret).await.is_ok() {
+            return child;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    panic!("daemon did not come up on {}", sock);
+}
+
+async fn status(client: &DevitClient) -> serde_json::Value {
+    client
+        .status_snapshot()
+        .await
+        .expect("status")
+        .expect("status response")
+        .payload
+}
+
+fn queued_ids(status: &serde_json::Value) -> Vec<String> {
+    status
+        .get("queue")
+        .and_then(|v| v.as_array())
+        .map(|queue| {
+            queue
+                .iter()
+                .filter_map(|entry| entry.get("task_id").and_then(|id| id.as_str()))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn saturated_worker_queues_tasks_by_priority() {
+    let secret = "test-secret";
+    let base = format!(
+        "/tmp/devitd-pooltest-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_millis()
+    );
+    let sock = format!("{}.sock", base);
+    let config = PathBuf::from(format!("{}.toml", base));
+    std::fs::write(
+        &config,
+        r#"
+[workers.sleeper]
+type = "cli"
+binary = "sh"
+args = ["-c", "sleep 5"]
+timeout_secs = 30
+max_concurrent = 1
+
+[daemon.policy]
+default_approval = "trusted"
+"#,
+    )
+    .expect("write config");
+
+    let mut daemon = spawn_daemon(&sock, secret, &config).await;
+    let client = DevitClient::connect(&sock, "client-a", secret)
+        .await
+        .expect("connect client-a");
+
+    let mut ids = Vec::new();
+    for (goal, priority) in [("first", 0), ("second", 0), ("urgent", 5)] {
+        let task_id = client
+            .delegate(
+                "sleeper",
+                serde_json::json!({"goal": goal, "priority": priority}),
+                "client-a",
+            )
+            .await
+            .expect("delegate");
+        ids.push(task_id);
+    }
+    let (first, second, urgent) = (&ids[0], &ids[1], &ids[2]);
+
+    // One slot: the first task runs, the others wait with the urgent one
+    // ahead despite arriving last.
+    let snapshot = status(&client).await;
+    assert_eq!(queued_ids(&snapshot), vec![urgent.clone(), second.clone()]);
+    assert_eq!(snapshot["queue"][0]["position"], 1);
+    assert_eq!(snapshot["queue"][0]["priority"], 5);
+
+    // Only the delegating client may cancel
+    let intruder = DevitClient::connect(&sock, "client-b", secret)
+        .await
+        .expect("connect client-b");
+    let refused = intruder.cancel(second, None).await;
+    assert!(
+        refused
+            .as_ref()
+            .is_err_and(|err| err.to_string().contains("may not cancel")),
+        "{:?}",
+        refused
+    );
+
+    let cancelled = client
+        .cancel(second, Some("not needed"))
+        .await
+        .expect("cancel queued task");
+    assert_eq!(cancelled.delivery, "withdrawn");
+    assert_eq!(queued_ids(&status(&client).await), vec![urgent.clone()]);
+
+    // Once the first worker exits, the urgent task takes its slot.
+    let mut drained = false;
+    for _ in 0..100 {
+        let snapshot = status(&client).await;
+        if queued_ids(&snapshot).is_empty() {
+            drained = true;
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert!(drained, "queue never drained");
+    let snapshot = status(&client).await;
+    let finished_first = snapshot["completed_tasks"]
+        .as_array()
+        .map(|tasks| tasks.iter().any(|task| task["id"] == first.as_str()))
+        .unwrap_or(false);
+    assert!(finished_first, "status: {}", snapshot);
+
+    let _ = daemon.kill().await;
+    let _ = std::fs::remove_file(&sock);
+    let _ = std::fs::remove_file(&config);
+    let _ = std::fs::remove_file(format!("{}.journal", base));
+    let _ = std::fs::remove_dir_all(Path::new(&format!("{}.tasks", base)));
+}